@@ -15,7 +15,7 @@ use {
     },
     grug::{
         Addr, AuthCtx, AuthMode, AuthResponse, Coins, Hash160, Inner, JsonDeExt, Message,
-        MutableCtx, Order, Response, StdResult, Storage, Tx,
+        MutableCtx, Op, Order, Response, StdResult, Storage, Tx,
     },
 };
 
@@ -111,6 +111,11 @@ pub fn execute(ctx: MutableCtx, msg: ExecuteMsg) -> anyhow::Result<Response> {
             key,
             key_hash,
         } => register_user(ctx, username, key, key_hash),
+        ExecuteMsg::UpdateKey {
+            username,
+            key_hash,
+            key,
+        } => update_key(ctx, username, key_hash, key),
         ExecuteMsg::RegisterAccount { params } => register_account(ctx, params),
         ExecuteMsg::ConfigureSafe { updates } => configure_safe(ctx, updates),
     }
@@ -241,12 +246,84 @@ fn onboard_new_user(
     )
 }
 
+fn update_key(
+    ctx: MutableCtx,
+    username: Username,
+    key_hash: Hash160,
+    key: Op<Key>,
+) -> anyhow::Result<Response> {
+    // Only a single signature account owned by the user can update the user's
+    // keys. Since the account has authenticated the transaction, this means
+    // the transaction was signed by one of the user's existing keys.
+    //
+    // Safes are excluded, despite being associated with each of its members,
+    // because a Safe's transactions are authorized by its members collectively,
+    // not by the user alone.
+    match ACCOUNTS.may_load(ctx.storage, ctx.sender)? {
+        Some(Account {
+            params: AccountParams::Spot(params) | AccountParams::Margin(params),
+            ..
+        }) if params.owner == username => {},
+        _ => bail!("sender isn't a single signature account owned by user `{username}`"),
+    }
+
+    match key {
+        Op::Insert(key) => {
+            ensure!(
+                !KEYS_BY_USER.has(ctx.storage, (&username, key_hash)),
+                "key hash {key_hash} is already associated with user `{username}`"
+            );
+
+            // If the key exists (already used by another user), they must match.
+            KEYS.update(ctx.storage, key_hash, |maybe_key| {
+                if let Some(existing_key) = maybe_key {
+                    ensure!(key == existing_key, "reusing an existing key but mismatch");
+                }
+                Ok(Some(key))
+            })?;
+
+            KEYS_BY_USER.insert(ctx.storage, (&username, key_hash))?;
+            USERS_BY_KEY.insert(ctx.storage, (key_hash, &username))?;
+        },
+        Op::Delete => {
+            ensure!(
+                KEYS_BY_USER.has(ctx.storage, (&username, key_hash)),
+                "key hash {key_hash} isn't associated with user `{username}`"
+            );
+
+            KEYS_BY_USER.remove(ctx.storage, (&username, key_hash));
+            USERS_BY_KEY.remove(ctx.storage, (key_hash, &username));
+
+            ensure!(
+                KEYS_BY_USER
+                    .prefix(&username)
+                    .keys(ctx.storage, None, None, Order::Ascending)
+                    .next()
+                    .is_some(),
+                "can't remove the last key of user `{username}`"
+            );
+
+            // Delete the key itself, if no other user is using it.
+            if USERS_BY_KEY
+                .prefix(key_hash)
+                .keys(ctx.storage, None, None, Order::Ascending)
+                .next()
+                .is_none()
+            {
+                KEYS.remove(ctx.storage, key_hash);
+            }
+        },
+    }
+
+    Ok(Response::new())
+}
+
 fn register_account(ctx: MutableCtx, params: AccountParams) -> anyhow::Result<Response> {
     // Basic validations of the account.
     // - For single signature accounts (spot and margin), one can only register
     //   accounts for themself. They cannot register account for another user.
     // - For multisig accounts (Safe), ensure voting threshold isn't greater
-    //   than total voting power.
+    //   than total voting power, and allowances are valid.
     match &params {
         AccountParams::Spot(params) | AccountParams::Margin(params) => {
             ensure!(
@@ -255,10 +332,7 @@ fn register_account(ctx: MutableCtx, params: AccountParams) -> anyhow::Result<Re
             );
         },
         AccountParams::Safe(params) => {
-            ensure!(
-                params.threshold.into_inner() <= params.total_power(),
-                "threshold can't be greater than total power"
-            );
+            validate_safe_params(params, ctx.contract)?;
         },
     }
 
@@ -298,6 +372,29 @@ fn register_account(ctx: MutableCtx, params: AccountParams) -> anyhow::Result<Re
     )?))
 }
 
+fn validate_safe_params(params: &multi::Params, factory: Addr) -> anyhow::Result<()> {
+    ensure!(
+        params.threshold.into_inner() <= params.total_power(),
+        "threshold can't be greater than total power"
+    );
+
+    for (member, allowance) in &params.allowances {
+        ensure!(
+            params.members.contains_key(member),
+            "user `{member}` isn't a member and can't be given an allowance"
+        );
+
+        // Executing the account factory without a proposal would allow a member
+        // to change the Safe's params on their own.
+        ensure!(
+            !allowance.targets.contains(&factory),
+            "allowance can't target the account factory"
+        );
+    }
+
+    Ok(())
+}
+
 fn configure_safe(ctx: MutableCtx, updates: multi::ParamUpdates) -> anyhow::Result<Response> {
     for member in updates.members.add().keys() {
         ACCOUNTS_BY_USER.insert(ctx.storage, (member, ctx.sender))?;
@@ -314,10 +411,7 @@ fn configure_safe(ctx: MutableCtx, updates: multi::ParamUpdates) -> anyhow::Resu
             AccountParams::Safe(params) => {
                 params.apply_updates(updates);
 
-                ensure!(
-                    params.threshold.into_inner() <= params.total_power(),
-                    "threshold can't be greater than total power"
-                );
+                validate_safe_params(params, ctx.contract)?;
             },
             _ => bail!("account isn't a Safe"),
         }
@@ -0,0 +1,131 @@
+use {
+    crate::{query_collateral_price, query_health},
+    anyhow::{anyhow, ensure},
+    dango_auth::authenticate_tx,
+    dango_types::{
+        account::{
+            margin::{Config, ExecuteMsg},
+            InstantiateMsg,
+        },
+        config::{ACCOUNT_FACTORY_KEY, MARGIN_KEY},
+        lending,
+    },
+    grug::{
+        Addr, AuthCtx, AuthResponse, Coin, Coins, Denom, IsZero, Message, MultiplyFraction,
+        MutableCtx, Number, NumberConst, Response, StdResult, Tx, Udec128, Uint128,
+    },
+    std::cmp,
+};
+
+#[cfg_attr(not(feature = "library"), grug::export)]
+pub fn instantiate(ctx: MutableCtx, _msg: InstantiateMsg) -> anyhow::Result<Response> {
+    let account_factory: Addr = ctx.querier.query_app_config(ACCOUNT_FACTORY_KEY)?;
+
+    // Only the account factory can create new accounts.
+    ensure!(
+        ctx.sender == account_factory,
+        "you don't have the right, O you don't have the right"
+    );
+
+    Ok(Response::new())
+}
+
+#[cfg_attr(not(feature = "library"), grug::export)]
+pub fn authenticate(ctx: AuthCtx, tx: Tx) -> anyhow::Result<AuthResponse> {
+    authenticate_tx(ctx, tx, None, None)?;
+
+    // Request a backrun, in which we make sure the account is still healthy
+    // after the transaction's messages have been executed.
+    Ok(AuthResponse::new().request_backrun(true))
+}
+
+#[cfg_attr(not(feature = "library"), grug::export)]
+pub fn backrun(ctx: AuthCtx, _tx: Tx) -> anyhow::Result<Response> {
+    let cfg: Config = ctx.querier.query_app_config(MARGIN_KEY)?;
+    let health = query_health(&ctx.querier, &cfg, ctx.contract, &Coins::new())?;
+
+    ensure!(
+        health.is_healthy(),
+        "account is undercollateralized! collateral value: {}, debt value: {}",
+        health.collateral_value,
+        health.debt_value
+    );
+
+    Ok(Response::new())
+}
+
+#[cfg_attr(not(feature = "library"), grug::export)]
+pub fn receive(_ctx: MutableCtx) -> StdResult<Response> {
+    // Do nothing, accept all transfers.
+    Ok(Response::new())
+}
+
+#[cfg_attr(not(feature = "library"), grug::export)]
+pub fn execute(ctx: MutableCtx, msg: ExecuteMsg) -> anyhow::Result<Response> {
+    match msg {
+        ExecuteMsg::Liquidate { collateral_denom } => liquidate(ctx, collateral_denom),
+    }
+}
+
+fn liquidate(ctx: MutableCtx, collateral_denom: Denom) -> anyhow::Result<Response> {
+    let cfg: Config = ctx.querier.query_app_config(MARGIN_KEY)?;
+
+    // The coins sent by the liquidator are meant for repaying the debts, so
+    // they don't count as the account's collaterals.
+    let health = query_health(&ctx.querier, &cfg, ctx.contract, &ctx.funds)?;
+
+    ensure!(
+        !health.is_healthy(),
+        "account is healthy and can't be liquidated! collateral value: {}, debt value: {}",
+        health.collateral_value,
+        health.debt_value
+    );
+
+    // Repay as much of the debts as possible with the coins sent, and refund
+    // the rest to the liquidator.
+    let mut repayments = Coins::new();
+    let mut refunds = Coins::new();
+    let mut repaid_value = Uint128::ZERO;
+
+    for coin in ctx.funds {
+        let repaid = cmp::min(coin.amount, health.debts.amount_of(&coin.denom));
+
+        if repaid.is_non_zero() {
+            let price = query_collateral_price(&ctx.querier, &cfg, &coin.denom)?;
+
+            repaid_value.checked_add_assign(repaid.checked_mul_dec_floor(price)?)?;
+            repayments.insert(Coin::new(coin.denom.clone(), repaid)?)?;
+        }
+
+        refunds.insert(Coin::new(coin.denom, coin.amount - repaid)?)?;
+    }
+
+    ensure!(repayments.is_non_empty(), "no debt is repaid");
+
+    // In return, the liquidator receives the chosen collateral, of a value
+    // equal to that of the repaid debts plus the liquidation bonus.
+    let price = query_collateral_price(&ctx.querier, &cfg, &collateral_denom)?;
+    let reward = repaid_value
+        .checked_mul_dec_floor(Udec128::ONE.checked_add(cfg.liquidation_bonus)?)?
+        .checked_div_dec_floor(price)?;
+    let available = health.collaterals.amount_of(&collateral_denom);
+
+    ensure!(
+        reward <= available,
+        "insufficient collateral! required: {reward}, available: {available}"
+    );
+
+    refunds.insert(Coin::new(collateral_denom, reward)?)?;
+
+    Ok(Response::new()
+        .add_message(Message::execute(
+            cfg.lending,
+            &lending::ExecuteMsg::Repay {},
+            repayments,
+        )?)
+        .may_add_message(if refunds.is_non_empty() {
+            Some(Message::transfer(ctx.sender, refunds)?)
+        } else {
+            None
+        }))
+}
@@ -0,0 +1,126 @@
+use {
+    anyhow::{anyhow, bail, ensure},
+    dango_types::{
+        account::margin::{CollateralParams, Config, Health},
+        amm::{QueryPoolRequest, QueryTwapRequest},
+        lending::QueryDebtRequest,
+    },
+    grug::{
+        Addr, Coin, Coins, Denom, IsZero, MultiplyFraction, Number, NumberConst, QuerierWrapper,
+        Udec128, Uint128,
+    },
+};
+
+/// Compute the health of a margin account.
+///
+/// The coins in `excluded` are deducted from the account's balances before
+/// valuing its collaterals. This is used during liquidations, where the coins
+/// sent by the liquidator have already been credited to the account, but
+/// shouldn't count as collateral.
+pub fn query_health(
+    querier: &QuerierWrapper,
+    cfg: &Config,
+    account: Addr,
+    excluded: &Coins,
+) -> anyhow::Result<Health> {
+    let debts = querier.query_wasm_smart(cfg.lending, QueryDebtRequest { account })?;
+
+    let mut collaterals = Coins::new();
+    let mut collateral_value = Uint128::ZERO;
+
+    for (denom, params) in &cfg.collaterals {
+        let balance = querier
+            .query_balance(account, denom.clone())?
+            .checked_sub(excluded.amount_of(denom))?;
+
+        if balance.is_zero() {
+            continue;
+        }
+
+        let price = query_price(querier, cfg, denom, params)?;
+        let value = balance
+            .checked_mul_dec_floor(price)?
+            .checked_mul_dec_floor(params.collateral_factor)?;
+
+        collaterals.insert(Coin::new(denom.clone(), balance)?)?;
+        collateral_value.checked_add_assign(value)?;
+    }
+
+    let mut debt_value = Uint128::ZERO;
+
+    for coin in &debts {
+        let price = query_collateral_price(querier, cfg, coin.denom)?;
+        let value = coin.amount.checked_mul_dec_ceil(price)?;
+
+        debt_value.checked_add_assign(value)?;
+    }
+
+    let health_factor = if debt_value.is_zero() {
+        None
+    } else {
+        Some(Udec128::checked_from_ratio(collateral_value, debt_value)?)
+    };
+
+    Ok(Health {
+        collaterals,
+        debts,
+        collateral_value,
+        debt_value,
+        health_factor,
+    })
+}
+
+/// Query the price of a denom listed in the margin config, in units of the
+/// quote denom.
+pub fn query_collateral_price(
+    querier: &QuerierWrapper,
+    cfg: &Config,
+    denom: &Denom,
+) -> anyhow::Result<Udec128> {
+    let params = cfg
+        .collaterals
+        .get(denom)
+        .ok_or_else(|| anyhow!("denom `{denom}` isn't supported by margin accounts"))?;
+
+    query_price(querier, cfg, denom, params)
+}
+
+// ---------------------------------- helpers ----------------------------------
+
+/// Query the price of a denom, in units of the quote denom.
+///
+/// The price is the time-weighted average price of the AMM pool designated as
+/// the denom's price source, such that it can't be manipulated within a single
+/// block.
+fn query_price(
+    querier: &QuerierWrapper,
+    cfg: &Config,
+    denom: &Denom,
+    params: &CollateralParams,
+) -> anyhow::Result<Udec128> {
+    let Some(pool_id) = params.price_source else {
+        ensure!(
+            *denom == cfg.quote_denom,
+            "denom `{denom}` doesn't have a price source"
+        );
+
+        return Ok(Udec128::ONE);
+    };
+
+    let pool = querier.query_wasm_smart(cfg.amm, QueryPoolRequest { pool_id })?;
+    let twap = querier.query_wasm_smart(cfg.amm, QueryTwapRequest {
+        pool_id,
+        window: cfg.twap_window,
+    })?;
+
+    // The TWAP gives the price of the pool's second coin in units of the
+    // first coin, and vice versa.
+    match pool.denoms() {
+        (first, second) if first == *denom && second == cfg.quote_denom => Ok(twap.inverse_price),
+        (first, second) if first == cfg.quote_denom && second == *denom => Ok(twap.price),
+        _ => bail!(
+            "pool {pool_id} isn't between `{denom}` and quote denom `{}`",
+            cfg.quote_denom
+        ),
+    }
+}
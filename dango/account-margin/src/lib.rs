@@ -0,0 +1,5 @@
+mod execute;
+mod health;
+mod query;
+
+pub use {execute::*, health::*, query::*};
@@ -0,0 +1,34 @@
+use {
+    crate::query_health,
+    dango_auth::NEXT_SEQUENCE,
+    dango_types::{
+        account::margin::{Config, Health, QueryMsg},
+        config::MARGIN_KEY,
+    },
+    grug::{Coins, ImmutableCtx, Json, JsonSerExt, StdResult, Storage},
+};
+
+#[cfg_attr(not(feature = "library"), grug::export)]
+pub fn query(ctx: ImmutableCtx, msg: QueryMsg) -> anyhow::Result<Json> {
+    match msg {
+        QueryMsg::Sequence {} => {
+            let res = query_sequence(ctx.storage)?;
+            res.to_json_value()
+        },
+        QueryMsg::Health {} => {
+            let res = query_account_health(ctx)?;
+            res.to_json_value()
+        },
+    }
+    .map_err(Into::into)
+}
+
+fn query_sequence(storage: &dyn Storage) -> StdResult<u32> {
+    NEXT_SEQUENCE.current(storage)
+}
+
+fn query_account_health(ctx: ImmutableCtx) -> anyhow::Result<Health> {
+    let cfg: Config = ctx.querier.query_app_config(MARGIN_KEY)?;
+
+    query_health(&ctx.querier, &cfg, ctx.contract, &Coins::new())
+}
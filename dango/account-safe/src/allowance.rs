@@ -0,0 +1,115 @@
+use {
+    crate::ALLOWANCE_USAGES,
+    anyhow::{anyhow, ensure},
+    dango_types::{
+        account::multi::{Allowance, AllowanceUsage, CurrentAllowance, Params},
+        account_factory::{QueryAccountRequest, Username},
+        config::ACCOUNT_FACTORY_KEY,
+    },
+    grug::{
+        Addr, Coins, Inner, Number, NumberConst, QuerierWrapper, StdResult, Storage, Timestamp,
+        Uint128,
+    },
+};
+
+/// Query the Safe's parameters from the account factory.
+pub fn query_params(querier: &QuerierWrapper, safe: Addr) -> StdResult<Params> {
+    let factory = querier.query_app_config(ACCOUNT_FACTORY_KEY)?;
+
+    let params = querier
+        .query_wasm_smart(factory, QueryAccountRequest { address: safe })?
+        .params
+        .as_safe();
+
+    Ok(params)
+}
+
+/// Find a member's usage of their allowance in the current period.
+///
+/// If the previous period has elapsed, or the member hasn't used the allowance
+/// yet, the usage is zero.
+pub fn query_usage(
+    storage: &dyn Storage,
+    now: Timestamp,
+    member: &Username,
+    allowance: &Allowance,
+) -> StdResult<Option<AllowanceUsage>> {
+    let usage = ALLOWANCE_USAGES
+        .may_load(storage, member)?
+        .filter(|usage| now < usage.period_start + allowance.period.into_inner());
+
+    Ok(usage)
+}
+
+/// Find a member's allowance, and how much of it is left in the current period.
+pub fn query_current_allowance(
+    storage: &dyn Storage,
+    now: Timestamp,
+    member: &Username,
+    allowance: Allowance,
+) -> StdResult<CurrentAllowance> {
+    let usage = query_usage(storage, now, member, &allowance)?;
+    let used = usage
+        .as_ref()
+        .map(|usage| usage.used)
+        .unwrap_or(Uint128::ZERO);
+    let resets_at = usage.map(|usage| usage.period_start + allowance.period.into_inner());
+    let remaining = allowance.amount.into_inner().saturating_sub(used);
+
+    Ok(CurrentAllowance {
+        allowance,
+        used,
+        remaining,
+        resets_at,
+    })
+}
+
+/// Deduct the coins sent by a member without a proposal from their allowance.
+///
+/// `spends` consists of the recipients of transfers or the contracts being
+/// executed, and the coins sent to each of them.
+pub fn spend_allowance(
+    storage: &mut dyn Storage,
+    now: Timestamp,
+    params: &Params,
+    member: &Username,
+    spends: Vec<(Addr, Coins)>,
+) -> anyhow::Result<()> {
+    let allowance = params
+        .allowances
+        .get(member)
+        .ok_or_else(|| anyhow!("user `{member}` doesn't have an allowance in this Safe"))?;
+
+    let mut usage = query_usage(storage, now, member, allowance)?.unwrap_or(AllowanceUsage {
+        period_start: now,
+        used: Uint128::ZERO,
+    });
+
+    for (target, coins) in spends {
+        ensure!(
+            allowance.targets.contains(&target),
+            "{target} isn't an allowed target of user `{member}`'s allowance"
+        );
+
+        for coin in coins {
+            ensure!(
+                coin.denom == allowance.denom,
+                "denom `{}` isn't covered by user `{member}`'s allowance",
+                coin.denom
+            );
+
+            usage.used.checked_add_assign(coin.amount)?;
+        }
+    }
+
+    ensure!(
+        usage.used <= allowance.amount.into_inner(),
+        "allowance exceeded! used: {}, limit: {}",
+        usage.used,
+        allowance.amount.into_inner()
+    );
+
+    ALLOWANCE_USAGES.save(storage, member, &usage)?;
+
+    Ok(())
+}
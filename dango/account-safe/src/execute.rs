@@ -1,5 +1,7 @@
 use {
-    crate::{NEXT_PROPOSAL_ID, PROPOSALS, VOTES},
+    crate::{
+        query_params, spend_allowance, INVALIDATED_BEFORE, NEXT_PROPOSAL_ID, PROPOSALS, VOTES,
+    },
     anyhow::{bail, ensure},
     dango_auth::authenticate_tx,
     dango_types::{
@@ -7,12 +9,13 @@ use {
             multi::{ExecuteMsg, Proposal, ProposalId, Status, Vote},
             InstantiateMsg,
         },
-        account_factory::{QueryAccountRequest, Username},
+        account_factory::{self, QueryAccountRequest, Username},
         auth::Metadata,
         config::ACCOUNT_FACTORY_KEY,
     },
     grug::{
-        Addr, AuthCtx, AuthResponse, Inner, JsonDeExt, Message, MutableCtx, Response, StdResult, Tx,
+        Addr, AuthCtx, AuthResponse, Inner, JsonDeExt, Message, MutableCtx, QuerierWrapper,
+        Response, StdResult, Storage, Tx,
     },
 };
 
@@ -33,24 +36,74 @@ pub fn instantiate(ctx: MutableCtx, _msg: InstantiateMsg) -> anyhow::Result<Resp
 pub fn authenticate(ctx: AuthCtx, tx: Tx) -> anyhow::Result<AuthResponse> {
     let metadata: Metadata = tx.data.clone().deserialize_json()?;
 
-    // The only type of transaction a Safe account is allowed to emit is to
-    // execute itself. Everything else needs to be done through proposals.
-    // Additionally, if the action is proposing or voting, the proposer/voter's
-    // username must match the transaction signer's username.
+    // Generally, the only type of transaction a Safe account is allowed to emit
+    // is to execute itself. Everything else needs to be done through proposals.
+    // Additionally, if the action is proposing, voting, or cancelling, the
+    // proposer/voter's username must match the transaction signer's username.
+    //
+    // The exception is transfers and executions within the signer's allowance,
+    // which are collected here and deducted from the allowance below.
+    let mut spends = Vec::new();
+
     for msg in &tx.msgs {
         match msg {
             Message::Execute { contract, msg, .. } if contract == ctx.contract => {
-                if let ExecuteMsg::Vote { voter, .. } = msg.clone().deserialize_json()? {
-                    ensure!(
-                        voter == metadata.username,
-                        "can't vote with a different username"
-                    );
+                match msg.clone().deserialize_json()? {
+                    ExecuteMsg::Propose { proposer, .. } => {
+                        ensure!(
+                            proposer == metadata.username,
+                            "can't propose with a different username"
+                        );
+                    },
+                    ExecuteMsg::Vote { voter, .. } => {
+                        ensure!(
+                            voter == metadata.username,
+                            "can't vote with a different username"
+                        );
+                    },
+                    ExecuteMsg::Cancel {
+                        proposer: Some(proposer),
+                        ..
+                    } => {
+                        ensure!(
+                            proposer == metadata.username,
+                            "can't cancel with a different username"
+                        );
+                    },
+                    // Cancelling without a proposer is only possible through
+                    // another proposal, which doesn't go through authentication.
+                    ExecuteMsg::Cancel { proposer: None, .. } => {
+                        bail!("a proposal can only be cancelled by its proposer or through a vote");
+                    },
+                    _ => (),
                 }
             },
-            _ => bail!("a Safe account can only execute itself"),
+            Message::Transfer { to, coins } => {
+                spends.push((*to, coins.clone()));
+            },
+            Message::Execute {
+                contract, funds, ..
+            } => {
+                spends.push((*contract, funds.clone()));
+            },
+            _ => bail!(
+                "a Safe account can only execute itself, or transfer or execute within allowances"
+            ),
         }
     }
 
+    if !spends.is_empty() {
+        let params = query_params(&ctx.querier, ctx.contract)?;
+
+        spend_allowance(
+            ctx.storage,
+            ctx.block.timestamp,
+            &params,
+            &metadata.username,
+            spends,
+        )?;
+    }
+
     authenticate_tx(ctx, tx, None, Some(metadata))?;
 
     Ok(AuthResponse::new().request_backrun(false))
@@ -63,12 +116,25 @@ pub fn receive(_ctx: MutableCtx) -> StdResult<Response> {
 
 #[cfg_attr(not(feature = "library"), grug::export)]
 pub fn execute(ctx: MutableCtx, msg: ExecuteMsg) -> anyhow::Result<Response> {
+    // Proposing, voting, cancelling, and pruning must be done by the Safe
+    // executing itself, such that the proposer or voter is checked against the
+    // transaction's signer during authentication, and cancelling without a
+    // proposer can only happen through a passed proposal. Executing a passed
+    // proposal is permissionless.
+    if !matches!(msg, ExecuteMsg::Execute { .. }) {
+        ensure!(
+            ctx.sender == ctx.contract,
+            "only the Safe itself can propose, vote, cancel, or prune"
+        );
+    }
+
     match msg {
         ExecuteMsg::Propose {
+            proposer,
             title,
             description,
             messages,
-        } => propose(ctx, title, description, messages),
+        } => propose(ctx, proposer, title, description, messages),
         ExecuteMsg::Vote {
             proposal_id,
             voter,
@@ -76,11 +142,17 @@ pub fn execute(ctx: MutableCtx, msg: ExecuteMsg) -> anyhow::Result<Response> {
             execute,
         } => do_vote(ctx, proposal_id, voter, vote, execute),
         ExecuteMsg::Execute { proposal_id } => execute_proposal(ctx, proposal_id),
+        ExecuteMsg::Cancel {
+            proposal_id,
+            proposer,
+        } => cancel(ctx, proposal_id, proposer),
+        ExecuteMsg::Prune { proposal_id } => prune(ctx, proposal_id),
     }
 }
 
 fn propose(
     ctx: MutableCtx,
+    proposer: Username,
     title: String,
     description: Option<String>,
     messages: Vec<Message>,
@@ -94,7 +166,7 @@ fn propose(
     //
     // As such, we always use the params _at the time of the proposal's creation_
     // for tallying the proposal. Changes made to the Safe's params _after_ the
-    // proposal's creation has no effect on it.
+    // proposal's creation invalidate it, instead of affecting its tally.
     let params = ctx
         .querier
         .query_wasm_smart(factory, QueryAccountRequest {
@@ -103,7 +175,11 @@ fn propose(
         .params
         .as_safe();
 
+    // Ensure the proposer is a member.
+    params.power_of(&proposer)?;
+
     let proposal = Proposal {
+        proposer: Some(proposer),
         title,
         description,
         messages,
@@ -134,11 +210,17 @@ fn do_vote(
 ) -> anyhow::Result<Response> {
     let mut proposal = PROPOSALS.load(ctx.storage, proposal_id)?;
 
-    // Ensure the voter hasn't already voted.
-    // Unlike Cosmos SDK's x/gov module, we don't allow changing votes.
-    // Whereas DAO voters sometimes change votes, this rarely happens for multisigs.
     ensure!(
-        !VOTES.has(ctx.storage, (proposal_id, &voter)),
+        !is_invalidated(ctx.storage, proposal_id)?,
+        "proposal is invalidated by a change of the Safe's params"
+    );
+
+    // The voter may change their vote during the voting period, but casting
+    // the same vote again is meaningless.
+    let previous_vote = VOTES.may_load(ctx.storage, (proposal_id, &voter))?;
+
+    ensure!(
+        previous_vote != Some(vote),
         "user `{voter}` has already voted in this proposal"
     );
 
@@ -153,13 +235,26 @@ fn do_vote(
             // Ensure voting period hasn't ended yet.
             ensure!(ctx.block.timestamp < *until, "voting period already ended");
 
+            let power = params.power_of(&voter)?;
+
+            // If the voter is changing their vote, retract the previous vote.
+            match previous_vote {
+                Some(Vote::Yes) => {
+                    *yes -= power;
+                },
+                Some(Vote::No) => {
+                    *no -= power;
+                },
+                None => (),
+            }
+
             // Update the vote count.
             match vote {
                 Vote::Yes => {
-                    *yes += params.power_of(&voter)?;
+                    *yes += power;
                 },
                 Vote::No => {
-                    *no += params.power_of(&voter)?;
+                    *no += power;
                 },
             }
 
@@ -213,6 +308,9 @@ fn do_vote(
         vec![]
     };
 
+    // If the proposal changes the Safe's params, invalidate other proposals.
+    invalidate_if_configuring(ctx.storage, &ctx.querier, &msgs)?;
+
     // Save the vote.
     VOTES.save(ctx.storage, (proposal_id, &voter), &vote)?;
 
@@ -225,6 +323,11 @@ fn do_vote(
 fn execute_proposal(ctx: MutableCtx, proposal_id: ProposalId) -> anyhow::Result<Response> {
     let mut proposal = PROPOSALS.load(ctx.storage, proposal_id)?;
 
+    ensure!(
+        !is_invalidated(ctx.storage, proposal_id)?,
+        "proposal is invalidated by a change of the Safe's params"
+    );
+
     // The propsal can only be executed if passed, and timelock has elapsed.
     let msgs = match proposal.status {
         Status::Passed { execute_after } if ctx.block.timestamp > execute_after => {
@@ -234,12 +337,114 @@ fn execute_proposal(ctx: MutableCtx, proposal_id: ProposalId) -> anyhow::Result<
         _ => bail!("proposal isn't passed or timelock hasn't elapsed"),
     };
 
+    // If the proposal changes the Safe's params, invalidate other proposals.
+    invalidate_if_configuring(ctx.storage, &ctx.querier, &msgs)?;
+
     // Save the updated proposal.
     PROPOSALS.save(ctx.storage, proposal_id, &proposal)?;
 
     Ok(Response::new().add_messages(msgs))
 }
 
+fn cancel(
+    ctx: MutableCtx,
+    proposal_id: ProposalId,
+    proposer: Option<Username>,
+) -> anyhow::Result<Response> {
+    let proposal = PROPOSALS.load(ctx.storage, proposal_id)?;
+
+    // If a proposer is provided, it must be the proposal's proposer. Otherwise,
+    // the cancellation comes from another proposal that has passed.
+    //
+    // Proposals created before proposers were recorded don't have one, so they
+    // can only be cancelled through another proposal.
+    if let Some(proposer) = proposer {
+        ensure!(
+            proposal.proposer.as_ref() == Some(&proposer),
+            "only the proposer can cancel a proposal without a vote"
+        );
+    }
+
+    ensure!(
+        proposal.status != Status::Executed,
+        "proposal is already executed and can't be cancelled"
+    );
+
+    delete_proposal(ctx.storage, proposal_id);
+
+    Ok(Response::new())
+}
+
+fn prune(ctx: MutableCtx, proposal_id: ProposalId) -> anyhow::Result<Response> {
+    let proposal = PROPOSALS.load(ctx.storage, proposal_id)?;
+
+    let prunable = match proposal.status {
+        Status::Failed => true,
+        // The voting period has ended without the proposal receiving enough
+        // votes to pass, so it's effectively failed.
+        Status::Voting { until, .. } if ctx.block.timestamp >= until => true,
+        // Executed proposals are kept as a record of the Safe's actions.
+        Status::Executed => false,
+        _ => is_invalidated(ctx.storage, proposal_id)?,
+    };
+
+    ensure!(
+        prunable,
+        "only failed or invalidated proposals can be pruned"
+    );
+
+    delete_proposal(ctx.storage, proposal_id);
+
+    Ok(Response::new())
+}
+
+/// Return whether a proposal has been invalidated by a change of the Safe's
+/// params made after its creation.
+///
+/// Note that this doesn't consider the proposal's status. An executed proposal
+/// isn't affected by later changes of the params.
+pub(crate) fn is_invalidated(storage: &dyn Storage, proposal_id: ProposalId) -> StdResult<bool> {
+    Ok(INVALIDATED_BEFORE
+        .may_load(storage)?
+        .is_some_and(|before| proposal_id < before))
+}
+
+/// If the messages to be executed include changing the Safe's params via the
+/// account factory, invalidate all proposals created so far.
+fn invalidate_if_configuring(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    msgs: &[Message],
+) -> anyhow::Result<()> {
+    for msg in msgs {
+        let Message::Execute { contract, msg, .. } = msg else {
+            continue;
+        };
+
+        let Ok(account_factory::ExecuteMsg::ConfigureSafe { .. }) = msg.clone().deserialize_json()
+        else {
+            continue;
+        };
+
+        let factory: Addr = querier.query_app_config(ACCOUNT_FACTORY_KEY)?;
+
+        if *contract == factory {
+            let next_proposal_id = NEXT_PROPOSAL_ID.current(storage)?;
+
+            INVALIDATED_BEFORE.save(storage, &next_proposal_id)?;
+
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn delete_proposal(storage: &mut dyn Storage, proposal_id: ProposalId) {
+    PROPOSALS.remove(storage, proposal_id);
+    VOTES.prefix(proposal_id).clear(storage, None, None);
+}
+
 // ----------------------------------- tests -----------------------------------
 
 #[cfg(test)]
@@ -248,13 +453,14 @@ mod tests {
         super::*,
         dango_account_factory::ACCOUNTS_BY_USER,
         dango_types::{
-            account::multi::{self, Params},
-            account_factory::{self, Account, AccountParams},
+            account::multi::{self, ParamUpdates, Params},
+            account_factory::{Account, AccountParams},
             config::ACCOUNT_FACTORY_KEY,
+            ChangeSet,
         },
         grug::{
-            btree_map, Addr, AuthMode, Coins, Duration, GenericResult, Hash, Json, JsonSerExt,
-            MockContext, MockQuerier, NonZero, ResultExt, Timestamp, MOCK_BLOCK,
+            btree_map, btree_set, Addr, AuthMode, Coins, Duration, GenericResult, Hash, Json,
+            JsonSerExt, MockContext, MockQuerier, NonZero, ResultExt, Timestamp, MOCK_BLOCK,
         },
         std::{collections::BTreeMap, str::FromStr},
         test_case::test_case,
@@ -329,6 +535,7 @@ mod tests {
                     // reach the signature verification step.
                     key_hash: Hash::ZERO,
                     sequence: 0,
+                    unordered: None,
                 }
                 .to_json_value()
                 .unwrap(),
@@ -341,16 +548,18 @@ mod tests {
         }
 
         // A member sends a tx, but it's doing something other than executing
-        // the Safe itself. Should fail.
+        // the Safe itself, transferring, or executing other contracts. Should
+        // fail.
         {
             let res = authenticate(ctx.as_auth(), Tx {
                 sender: SAFE,
                 gas_limit: 1_000_000,
-                msgs: vec![Message::transfer(Addr::mock(123), Coins::new()).unwrap()],
+                msgs: vec![Message::upload(b"code".to_vec())],
                 data: Metadata {
                     username: member1,
                     key_hash: Hash::ZERO,
                     sequence: 0,
+                    unordered: None,
                 }
                 .to_json_value()
                 .unwrap(),
@@ -383,6 +592,7 @@ mod tests {
                     username: member3,
                     key_hash: Hash::ZERO,
                     sequence: 0,
+                    unordered: None,
                 }
                 .to_json_value()
                 .unwrap(),
@@ -411,6 +621,7 @@ mod tests {
             voting_period: NonZero::new(Duration::from_seconds(100)).unwrap(),
             threshold: NonZero::new(2).unwrap(),
             timelock: None,
+            allowances: btree_map! {},
         };
 
         // Need to make a clone of `params` so it can be moved into the closure.
@@ -440,7 +651,14 @@ mod tests {
 
         // Create the 1st proposal.
         {
-            propose(ctx.as_mutable(), "first".to_string(), None, vec![]).unwrap();
+            propose(
+                ctx.as_mutable(),
+                m1.clone(),
+                "first".to_string(),
+                None,
+                vec![],
+            )
+            .unwrap();
 
             let proposal = PROPOSALS.load(&ctx.storage, 1).unwrap();
 
@@ -475,7 +693,14 @@ mod tests {
 
         // Create the 2nd proposal. It should use the updated params.
         {
-            propose(ctx.as_mutable(), "second".to_string(), None, vec![]).unwrap();
+            propose(
+                ctx.as_mutable(),
+                m1.clone(),
+                "second".to_string(),
+                None,
+                vec![],
+            )
+            .unwrap();
 
             let proposal = PROPOSALS.load(&ctx.storage, 2).unwrap();
 
@@ -495,6 +720,7 @@ mod tests {
                 voting_period: NonZero::new(Duration::from_seconds(100)).unwrap(),
                 threshold: NonZero::new(2).unwrap(),
                 timelock: None,
+                allowances: btree_map! {},
             },
             until: Timestamp::from_seconds(200),
             yes: 0,
@@ -542,6 +768,7 @@ mod tests {
         // Save the proposal.
         PROPOSALS
             .save(&mut ctx.storage, proposal_id, &Proposal {
+                proposer: Some(Username::from_str("member1").unwrap()),
                 title: "title".to_string(),
                 description: None,
                 messages: vec![],
@@ -563,7 +790,7 @@ mod tests {
             Username::from_str("member1").unwrap() => Vote::Yes,
         },
         Username::from_str("member1").unwrap(),
-        Vote::No,
+        Vote::Yes,
         false,
         None,
         |result| result.is_err_and(|err| {
@@ -573,6 +800,18 @@ mod tests {
         |_| true;
         "voting twice"
     )]
+    #[test_case(
+        btree_map! {
+            Username::from_str("member1").unwrap() => Vote::Yes,
+        },
+        Username::from_str("member1").unwrap(),
+        Vote::No,
+        false,
+        None,
+        |result| result.is_ok_and(|res| res.submsgs.is_empty()),
+        |proposal| matches!(proposal.status, Status::Voting { yes: 0, no: 1, .. });
+        "changing vote"
+    )]
     #[test_case(
         btree_map! {},
         Username::from_str("jake").unwrap(),
@@ -714,6 +953,7 @@ mod tests {
         // Save the proposal.
         PROPOSALS
             .save(&mut ctx.storage, proposal_id, &Proposal {
+                proposer: Some(Username::from_str("member1").unwrap()),
                 title: "title".to_string(),
                 description: None,
                 messages: vec![Message::transfer(
@@ -727,6 +967,7 @@ mod tests {
                         voting_period: NonZero::new(Duration::from_seconds(100)).unwrap(),
                         threshold: NonZero::new(2).unwrap(),
                         timelock: timelock.map(|d| NonZero::new(d).unwrap()),
+                        allowances: btree_map! {},
                     },
                     until: Timestamp::from_seconds(200),
                     yes: previous_yes_votes,
@@ -764,6 +1005,7 @@ mod tests {
                 voting_period: NonZero::new(Duration::from_seconds(100)).unwrap(),
                 threshold: NonZero::new(1).unwrap(),
                 timelock: None,
+                allowances: btree_map! {},
             },
             until: Timestamp::from_seconds(100),
             yes: 0,
@@ -806,6 +1048,7 @@ mod tests {
 
         PROPOSALS
             .save(&mut ctx.storage, proposal_id, &Proposal {
+                proposer: Some(Username::from_str("member1").unwrap()),
                 title: "title".to_string(),
                 description: None,
                 messages: vec![],
@@ -815,4 +1058,338 @@ mod tests {
 
         execute_proposal(ctx.as_mutable(), proposal_id).should_match(expect);
     }
+
+    #[test_case(
+        Status::Voting {
+            params: Params {
+                members: btree_map! {},
+                voting_period: NonZero::new(Duration::from_seconds(100)).unwrap(),
+                threshold: NonZero::new(1).unwrap(),
+                timelock: None,
+                allowances: btree_map! {},
+            },
+            until: Timestamp::from_seconds(100),
+            yes: 0,
+            no: 0,
+        },
+        Some(Username::from_str("member1").unwrap()),
+        GenericResult::Ok(Response::new());
+        "proposal still voting, cancelled by proposer"
+    )]
+    #[test_case(
+        Status::Passed {
+            execute_after: Timestamp::from_seconds(100),
+        },
+        Some(Username::from_str("member2").unwrap()),
+        GenericResult::Err("only the proposer can cancel a proposal without a vote".to_string());
+        "proposal passed, cancelled by someone else"
+    )]
+    #[test_case(
+        Status::Passed {
+            execute_after: Timestamp::from_seconds(100),
+        },
+        None,
+        GenericResult::Ok(Response::new());
+        "proposal passed, cancelled by vote"
+    )]
+    #[test_case(
+        Status::Executed,
+        Some(Username::from_str("member1").unwrap()),
+        GenericResult::Err("proposal is already executed and can't be cancelled".to_string());
+        "proposal already executed"
+    )]
+    fn cancelling(status: Status, proposer: Option<Username>, expect: GenericResult<Response>) {
+        let mut ctx = MockContext::new()
+            .with_sender(SAFE)
+            .with_funds(Coins::new());
+
+        let member1 = Username::from_str("member1").unwrap();
+        let proposal_id = 123;
+
+        PROPOSALS
+            .save(&mut ctx.storage, proposal_id, &Proposal {
+                proposer: Some(member1.clone()),
+                title: "title".to_string(),
+                description: None,
+                messages: vec![],
+                status,
+            })
+            .unwrap();
+
+        VOTES
+            .save(&mut ctx.storage, (proposal_id, &member1), &Vote::Yes)
+            .unwrap();
+
+        let cancelled = expect.is_ok();
+
+        cancel(ctx.as_mutable(), proposal_id, proposer).should_match(expect);
+
+        // If cancelled, the proposal and its votes should have been deleted.
+        assert_eq!(PROPOSALS.has(&ctx.storage, proposal_id), !cancelled);
+        assert_eq!(VOTES.has(&ctx.storage, (proposal_id, &member1)), !cancelled);
+    }
+
+    #[test]
+    fn cancelling_proposal_without_proposer() {
+        let mut ctx = MockContext::new()
+            .with_sender(SAFE)
+            .with_funds(Coins::new());
+
+        let member1 = Username::from_str("member1").unwrap();
+        let proposal_id = 123;
+
+        // A proposal created before proposers were recorded.
+        PROPOSALS
+            .save(&mut ctx.storage, proposal_id, &Proposal {
+                proposer: None,
+                title: "title".to_string(),
+                description: None,
+                messages: vec![],
+                status: Status::Passed {
+                    execute_after: Timestamp::from_seconds(100),
+                },
+            })
+            .unwrap();
+
+        // No member can cancel it without a vote.
+        cancel(ctx.as_mutable(), proposal_id, Some(member1))
+            .should_fail_with_error("only the proposer can cancel a proposal without a vote");
+
+        // It can be cancelled through another proposal.
+        cancel(ctx.as_mutable(), proposal_id, None).should_succeed();
+
+        assert!(!PROPOSALS.has(&ctx.storage, proposal_id));
+    }
+
+    #[test_case(
+        ExecuteMsg::Propose {
+            proposer: Username::from_str("member1").unwrap(),
+            title: "title".to_string(),
+            description: None,
+            messages: vec![],
+        };
+        "proposing"
+    )]
+    #[test_case(
+        ExecuteMsg::Vote {
+            proposal_id: 123,
+            voter: Username::from_str("member1").unwrap(),
+            vote: Vote::Yes,
+            execute: false,
+        };
+        "voting"
+    )]
+    #[test_case(
+        ExecuteMsg::Cancel {
+            proposal_id: 123,
+            proposer: Some(Username::from_str("member1").unwrap()),
+        };
+        "cancelling with proposer"
+    )]
+    #[test_case(
+        ExecuteMsg::Cancel {
+            proposal_id: 123,
+            proposer: None,
+        };
+        "cancelling without proposer"
+    )]
+    #[test_case(
+        ExecuteMsg::Prune { proposal_id: 123 };
+        "pruning"
+    )]
+    fn non_member_cannot_execute(msg: ExecuteMsg) {
+        let mut ctx = MockContext::new()
+            .with_contract(SAFE)
+            .with_sender(Addr::mock(123))
+            .with_funds(Coins::new());
+
+        let member1 = Username::from_str("member1").unwrap();
+        let proposal_id = 123;
+
+        PROPOSALS
+            .save(&mut ctx.storage, proposal_id, &Proposal {
+                proposer: Some(member1),
+                title: "title".to_string(),
+                description: None,
+                messages: vec![],
+                status: Status::Failed,
+            })
+            .unwrap();
+
+        execute(ctx.as_mutable(), msg)
+            .should_fail_with_error("only the Safe itself can propose, vote, cancel, or prune");
+
+        // The proposal should be left intact.
+        assert!(PROPOSALS.has(&ctx.storage, proposal_id));
+    }
+
+    #[test_case(
+        Status::Failed,
+        false,
+        GenericResult::Ok(Response::new());
+        "proposal failed"
+    )]
+    #[test_case(
+        Status::Voting {
+            params: Params {
+                members: btree_map! {},
+                voting_period: NonZero::new(Duration::from_seconds(100)).unwrap(),
+                threshold: NonZero::new(1).unwrap(),
+                timelock: None,
+                allowances: btree_map! {},
+            },
+            until: Timestamp::from_seconds(100),
+            yes: 0,
+            no: 0,
+        },
+        false,
+        GenericResult::Ok(Response::new());
+        "voting period ended"
+    )]
+    #[test_case(
+        Status::Voting {
+            params: Params {
+                members: btree_map! {},
+                voting_period: NonZero::new(Duration::from_seconds(100)).unwrap(),
+                threshold: NonZero::new(1).unwrap(),
+                timelock: None,
+                allowances: btree_map! {},
+            },
+            until: Timestamp::from_seconds(300),
+            yes: 0,
+            no: 0,
+        },
+        false,
+        GenericResult::Err("only failed or invalidated proposals can be pruned".to_string());
+        "proposal still voting"
+    )]
+    #[test_case(
+        Status::Passed {
+            execute_after: Timestamp::from_seconds(100),
+        },
+        false,
+        GenericResult::Err("only failed or invalidated proposals can be pruned".to_string());
+        "proposal passed"
+    )]
+    #[test_case(
+        Status::Passed {
+            execute_after: Timestamp::from_seconds(100),
+        },
+        true,
+        GenericResult::Ok(Response::new());
+        "proposal passed but invalidated"
+    )]
+    #[test_case(
+        Status::Executed,
+        true,
+        GenericResult::Err("only failed or invalidated proposals can be pruned".to_string());
+        "proposal executed"
+    )]
+    fn pruning(status: Status, invalidated: bool, expect: GenericResult<Response>) {
+        let mut ctx = MockContext::new()
+            .with_block_timestamp(Timestamp::from_seconds(200))
+            .with_sender(SAFE)
+            .with_funds(Coins::new());
+
+        let proposal_id = 123;
+
+        PROPOSALS
+            .save(&mut ctx.storage, proposal_id, &Proposal {
+                proposer: Some(Username::from_str("member1").unwrap()),
+                title: "title".to_string(),
+                description: None,
+                messages: vec![],
+                status,
+            })
+            .unwrap();
+
+        if invalidated {
+            INVALIDATED_BEFORE
+                .save(&mut ctx.storage, &(proposal_id + 1))
+                .unwrap();
+        }
+
+        prune(ctx.as_mutable(), proposal_id).should_match(expect);
+    }
+
+    #[test]
+    fn invalidating_proposals() {
+        let member1 = Username::from_str("member1").unwrap();
+
+        let querier = MockQuerier::new()
+            .with_app_config(ACCOUNT_FACTORY_KEY, ACCOUNT_FACTORY)
+            .unwrap();
+
+        let mut ctx = MockContext::new()
+            .with_querier(querier)
+            .with_block_timestamp(Timestamp::from_seconds(200))
+            .with_sender(SAFE)
+            .with_funds(Coins::new());
+
+        // Proposal 1 changes the Safe's params, and has passed.
+        // Proposal 2 is created before proposal 1 is executed, and is still in
+        // its voting period.
+        NEXT_PROPOSAL_ID.increment(&mut ctx.storage).unwrap();
+        NEXT_PROPOSAL_ID.increment(&mut ctx.storage).unwrap();
+
+        PROPOSALS
+            .save(&mut ctx.storage, 1, &Proposal {
+                proposer: Some(member1.clone()),
+                title: "configure".to_string(),
+                description: None,
+                messages: vec![Message::execute(
+                    ACCOUNT_FACTORY,
+                    &account_factory::ExecuteMsg::ConfigureSafe {
+                        updates: ParamUpdates {
+                            members: ChangeSet::new(btree_map! {}, btree_set! {}).unwrap(),
+                            voting_period: None,
+                            threshold: Some(NonZero::new(1).unwrap()),
+                            allowances: btree_map! {},
+                        },
+                    },
+                    Coins::new(),
+                )
+                .unwrap()],
+                status: Status::Passed {
+                    execute_after: Timestamp::from_seconds(100),
+                },
+            })
+            .unwrap();
+
+        PROPOSALS
+            .save(&mut ctx.storage, 2, &Proposal {
+                proposer: Some(member1.clone()),
+                title: "title".to_string(),
+                description: None,
+                messages: vec![],
+                status: Status::Voting {
+                    params: Params {
+                        members: btree_map! {
+                            member1.clone() => NonZero::new(1).unwrap(),
+                        },
+                        voting_period: NonZero::new(Duration::from_seconds(100)).unwrap(),
+                        threshold: NonZero::new(2).unwrap(),
+                        timelock: None,
+                        allowances: btree_map! {},
+                    },
+                    until: Timestamp::from_seconds(300),
+                    yes: 0,
+                    no: 0,
+                },
+            })
+            .unwrap();
+
+        // Execute proposal 1. It should invalidate proposal 2.
+        execute_proposal(ctx.as_mutable(), 1).should_succeed_and(|res| res.submsgs.len() == 1);
+
+        // Proposal 2 can no longer be voted on.
+        do_vote(ctx.as_mutable(), 2, member1, Vote::Yes, false)
+            .should_fail_with_error("proposal is invalidated by a change of the Safe's params");
+
+        // Proposal 2 can be pruned, but the executed proposal 1 can't.
+        prune(ctx.as_mutable(), 2).should_succeed();
+
+        prune(ctx.as_mutable(), 1)
+            .should_fail_with_error("only failed or invalidated proposals can be pruned");
+    }
 }
@@ -1,5 +1,6 @@
+mod allowance;
 mod execute;
 mod query;
 mod state;
 
-pub use {execute::*, query::*, state::*};
+pub use {allowance::*, execute::*, query::*, state::*};
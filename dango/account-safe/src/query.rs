@@ -1,8 +1,8 @@
 use {
-    crate::{PROPOSALS, VOTES},
+    crate::{is_invalidated, query_current_allowance, query_params, PROPOSALS, VOTES},
     dango_auth::NEXT_SEQUENCE,
     dango_types::{
-        account::multi::{Proposal, ProposalId, QueryMsg, Status, Vote},
+        account::multi::{CurrentAllowance, Proposal, ProposalId, QueryMsg, Status, Vote},
         account_factory::Username,
     },
     grug::{Bound, ImmutableCtx, Json, JsonSerExt, Order, StdResult, Storage},
@@ -37,6 +37,14 @@ pub fn query(ctx: ImmutableCtx, msg: QueryMsg) -> StdResult<Json> {
             let res = query_votes(ctx.storage, proposal_id)?;
             res.to_json_value()
         },
+        QueryMsg::Allowance { member } => {
+            let res = query_allowance(ctx, member)?;
+            res.to_json_value()
+        },
+        QueryMsg::Allowances {} => {
+            let res = query_allowances(ctx)?;
+            res.to_json_value()
+        },
     }
 }
 
@@ -47,13 +55,7 @@ fn query_sequence(storage: &dyn Storage) -> StdResult<u32> {
 fn query_proposal(ctx: ImmutableCtx, proposal_id: ProposalId) -> StdResult<Proposal> {
     let mut proposal = PROPOSALS.load(ctx.storage, proposal_id)?;
 
-    // If the proposal is in "voting" state, but voting period has already
-    // finished, it means not enough vote is received. The proposal fails.
-    if let Status::Voting { until, .. } = &proposal.status {
-        if ctx.block.timestamp > *until {
-            proposal.status = Status::Failed;
-        }
-    }
+    update_status(&ctx, proposal_id, &mut proposal)?;
 
     Ok(proposal)
 }
@@ -72,17 +74,39 @@ fn query_proposals(
         .map(|res| {
             let (proposal_id, mut proposal) = res?;
 
-            if let Status::Voting { until, .. } = &proposal.status {
-                if ctx.block.timestamp > *until {
-                    proposal.status = Status::Failed;
-                }
-            }
+            update_status(&ctx, proposal_id, &mut proposal)?;
 
             Ok((proposal_id, proposal))
         })
         .collect()
 }
 
+/// Mark the proposal as failed if it can no longer pass or be executed, but
+/// its status isn't yet updated in storage.
+fn update_status(
+    ctx: &ImmutableCtx,
+    proposal_id: ProposalId,
+    proposal: &mut Proposal,
+) -> StdResult<()> {
+    match &proposal.status {
+        // If the proposal is in "voting" state, but voting period has already
+        // finished, it means not enough vote is received. The proposal fails.
+        Status::Voting { until, .. } if ctx.block.timestamp > *until => {
+            proposal.status = Status::Failed;
+        },
+        // If the Safe's params have changed since the proposal's creation, and
+        // it hasn't been executed, the proposal fails.
+        Status::Voting { .. } | Status::Passed { .. }
+            if is_invalidated(ctx.storage, proposal_id)? =>
+        {
+            proposal.status = Status::Failed;
+        },
+        _ => (),
+    }
+
+    Ok(())
+}
+
 fn query_vote(
     storage: &dyn Storage,
     proposal_id: ProposalId,
@@ -101,6 +125,33 @@ fn query_votes(
         .collect()
 }
 
+fn query_allowance(ctx: ImmutableCtx, member: Username) -> StdResult<Option<CurrentAllowance>> {
+    let mut params = query_params(&ctx.querier, ctx.contract)?;
+
+    params
+        .allowances
+        .remove(&member)
+        .map(|allowance| {
+            query_current_allowance(ctx.storage, ctx.block.timestamp, &member, allowance)
+        })
+        .transpose()
+}
+
+fn query_allowances(ctx: ImmutableCtx) -> StdResult<BTreeMap<Username, CurrentAllowance>> {
+    let params = query_params(&ctx.querier, ctx.contract)?;
+
+    params
+        .allowances
+        .into_iter()
+        .map(|(member, allowance)| {
+            let current =
+                query_current_allowance(ctx.storage, ctx.block.timestamp, &member, allowance)?;
+
+            Ok((member, current))
+        })
+        .collect()
+}
+
 // ----------------------------------- tests -----------------------------------
 
 #[cfg(test)]
@@ -119,6 +170,7 @@ mod tests {
         let proposal_id = 123;
 
         let proposal = Proposal {
+            proposer: Some(Username::from_str("a").unwrap()),
             title: "title".to_string(),
             description: None,
             messages: vec![],
@@ -132,6 +184,7 @@ mod tests {
                     voting_period: NonZero::new(Timestamp::from_seconds(100)).unwrap(),
                     threshold: NonZero::new(2).unwrap(),
                     timelock: None,
+                    allowances: btree_map! {},
                 },
                 until: Timestamp::from_seconds(100),
                 yes: 1,
@@ -1,15 +1,21 @@
 use {
     dango_types::{
-        account::multi::{Proposal, ProposalId, Vote},
+        account::multi::{AllowanceUsage, Proposal, ProposalId, Vote},
         account_factory::Username,
     },
-    grug::{Counter, Map, Serde},
+    grug::{Counter, Item, Map, Serde},
 };
 
 pub const NEXT_PROPOSAL_ID: Counter<ProposalId> = Counter::new("next_proposal_id", 1, 1);
 
+/// Proposals with IDs smaller than this were created before the most recent
+/// change of the Safe's params. Unless already executed, they are invalid.
+pub const INVALIDATED_BEFORE: Item<ProposalId> = Item::new("invalidated_before");
+
 // Note: Have to use serde codec for this, because `Proposal` contains `Message`
 // which contains `serde_json::Value` which doesn't implement Borsh traits.
 pub const PROPOSALS: Map<ProposalId, Proposal, Serde> = Map::new("proposal");
 
 pub const VOTES: Map<(ProposalId, &Username), Vote> = Map::new("vote");
+
+pub const ALLOWANCE_USAGES: Map<&Username, AllowanceUsage> = Map::new("allowance_usage");
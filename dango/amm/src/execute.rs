@@ -1,15 +1,20 @@
 use {
-    crate::{perform_swap, PoolExt, PoolInit, CONFIG, NEXT_POOL_ID, POOLS},
-    anyhow::{anyhow, ensure},
+    crate::{
+        initialize_observations, perform_reverse_swap, perform_swap, ramp_amplification,
+        stop_ramp_amplification, update_observations, PoolExt, PoolInit, CONFIG, NEXT_POOL_ID,
+        POOLS,
+    },
+    anyhow::{anyhow, bail, ensure},
     dango_types::{
         amm::{
-            ConcentratedPool, ExecuteMsg, InstantiateMsg, Pool, PoolId, PoolParams, XykPool,
-            MINIMUM_LIQUIDITY, NAMESPACE, SUB_NAMESPACE_POOL,
+            ConcentratedPool, ExecuteMsg, InstantiateMsg, Pool, PoolId, PoolParams, StablePool,
+            XykPool, MINIMUM_LIQUIDITY, NAMESPACE, SUB_NAMESPACE_POOL,
         },
         bank,
     },
     grug::{
-        Coins, Denom, Inner, Message, MutableCtx, Number, Response, StdResult, Uint128, UniqueVec,
+        Coin, Coins, Denom, Inner, IsZero, Message, MutableCtx, Number, Response, StdResult,
+        Timestamp, Uint128, UniqueVec,
     },
 };
 
@@ -28,11 +33,20 @@ pub fn execute(ctx: MutableCtx, msg: ExecuteMsg) -> anyhow::Result<Response> {
             route,
             minimum_output,
         } => swap(ctx, route, minimum_output),
+        ExecuteMsg::SwapExactOutput { route, output } => swap_exact_output(ctx, route, output),
         ExecuteMsg::ProvideLiquidity {
             pool_id,
             minimum_output,
         } => provide_liquidity(ctx, pool_id, minimum_output),
         ExecuteMsg::WithdrawLiquidity { pool_id } => withdraw_liquidity(ctx, pool_id),
+        ExecuteMsg::RampAmplification {
+            pool_id,
+            amplification,
+            end_time,
+        } => ramp_stable_pool_amplification(ctx, pool_id, amplification, end_time),
+        ExecuteMsg::StopRampAmplification { pool_id } => {
+            stop_ramp_stable_pool_amplification(ctx, pool_id)
+        },
     }
 }
 
@@ -56,13 +70,19 @@ fn create_pool(ctx: MutableCtx, params: PoolParams) -> anyhow::Result<Response>
 
     let (shares_to_mint, pool) = match params {
         PoolParams::Xyk(params) => {
-            let xyk = XykPool::initialize(liquidity.try_into()?, params)?;
+            let xyk = XykPool::initialize(liquidity.try_into()?, params, ctx.block.timestamp)?;
             (xyk.shares, Pool::Xyk(xyk))
         },
         PoolParams::Concentracted(params) => {
-            let concentrated = ConcentratedPool::initialize(liquidity.try_into()?, params)?;
+            let concentrated =
+                ConcentratedPool::initialize(liquidity.try_into()?, params, ctx.block.timestamp)?;
             (concentrated.shares, Pool::Concentrated(concentrated))
         },
+        PoolParams::Stable(params) => {
+            let stable =
+                StablePool::initialize(liquidity.try_into()?, params, ctx.block.timestamp)?;
+            (stable.shares, Pool::Stable(stable))
+        },
     };
 
     // A minimum amount of liquidity tokens is to be withheld by the contract,
@@ -79,6 +99,8 @@ fn create_pool(ctx: MutableCtx, params: PoolParams) -> anyhow::Result<Response>
 
     POOLS.save(ctx.storage, pool_id, &pool)?;
 
+    initialize_observations(ctx.storage, pool_id, ctx.block.timestamp)?;
+
     let cfg = ctx.querier.query_config()?;
     let denom = denom_of(pool_id)?;
 
@@ -123,8 +145,13 @@ fn swap(
         .map(|&pool_id| POOLS.load(ctx.storage, pool_id))
         .collect::<StdResult<Vec<_>>>()?;
 
+    // Record the pools' prices before they're changed by the swap.
+    for (pool_id, pool) in route.inner().iter().zip(&pools) {
+        update_observations(ctx.storage, *pool_id, pool, ctx.block.timestamp)?;
+    }
+
     // Perform the swap in each pool.
-    let outcome = perform_swap(&cfg, input, pools.iter_mut())?;
+    let outcome = perform_swap(&cfg, input, pools.iter_mut(), ctx.block.timestamp)?;
 
     if let Some(minimum_output) = minimum_output {
         ensure!(
@@ -147,6 +174,75 @@ fn swap(
         .add_message(Message::transfer(cfg.fee_recipient, outcome.protocol_fee)?))
 }
 
+fn swap_exact_output(
+    ctx: MutableCtx,
+    route: UniqueVec<PoolId>,
+    output: Coin,
+) -> anyhow::Result<Response> {
+    let cfg = CONFIG.load(ctx.storage)?;
+    let maximum_input = ctx.funds.into_one_coin()?;
+    let mut pools = route
+        .inner()
+        .iter()
+        .map(|&pool_id| POOLS.load(ctx.storage, pool_id))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // Record the pools' prices before they're changed by the swap.
+    for (pool_id, pool) in route.inner().iter().zip(&pools) {
+        update_observations(ctx.storage, *pool_id, pool, ctx.block.timestamp)?;
+    }
+
+    // Compute the input needed for the output.
+    let input = perform_reverse_swap(&cfg, output.clone(), pools.iter(), ctx.block.timestamp)?;
+
+    ensure!(
+        input.denom == maximum_input.denom,
+        "incorrect input denom! expecting: {}, got: {}",
+        input.denom,
+        maximum_input.denom
+    );
+
+    ensure!(
+        input.amount <= maximum_input.amount,
+        "excessive swap input: {} > {}",
+        input.amount,
+        maximum_input.amount
+    );
+
+    let refund = Coin {
+        denom: maximum_input.denom,
+        amount: maximum_input.amount.checked_sub(input.amount)?,
+    };
+
+    // Perform the swap in each pool.
+    let outcome = perform_swap(&cfg, input, pools.iter_mut(), ctx.block.timestamp)?;
+
+    // This should always hold, since the reverse swap rounds the input up.
+    ensure!(
+        outcome.output.amount >= output.amount,
+        "insufficient swap output: {} < {}",
+        outcome.output.amount,
+        output.amount
+    );
+
+    // Save the updated pool states.
+    for (pool_id, pool) in route.inner().iter().zip(pools) {
+        POOLS.save(ctx.storage, *pool_id, &pool)?;
+    }
+
+    // 1. Transfer the protocol fee to the fee collector.
+    // 2. Transfer the output to the trader.
+    // 3. Refund the unused input to the trader, if any.
+    Ok(Response::new()
+        .add_message(Message::transfer(ctx.sender, outcome.output)?)
+        .add_message(Message::transfer(cfg.fee_recipient, outcome.protocol_fee)?)
+        .may_add_message(if refund.amount.is_non_zero() {
+            Some(Message::transfer(ctx.sender, refund)?)
+        } else {
+            None
+        }))
+}
+
 fn provide_liquidity(
     mut ctx: MutableCtx,
     pool_id: PoolId,
@@ -159,9 +255,15 @@ fn provide_liquidity(
     // Sender must not send any other funds than what goes into the pool.
     ensure!(ctx.funds.is_empty(), "unexpected funds: {}", ctx.funds);
 
+    // Record the pool's prices before they're changed by the provision.
+    update_observations(ctx.storage, pool_id, &pool, ctx.block.timestamp)?;
+
     let shares_to_mint = match &mut pool {
-        Pool::Xyk(xyk) => xyk.provide_liquidity(deposit)?,
-        Pool::Concentrated(concentrated) => concentrated.provide_liquidity(deposit)?,
+        Pool::Xyk(xyk) => xyk.provide_liquidity(deposit, ctx.block.timestamp)?,
+        Pool::Concentrated(concentrated) => {
+            concentrated.provide_liquidity(deposit, ctx.block.timestamp)?
+        },
+        Pool::Stable(stable) => stable.provide_liquidity(deposit, ctx.block.timestamp)?,
     };
 
     POOLS.save(ctx.storage, pool_id, &pool)?;
@@ -203,9 +305,13 @@ fn withdraw_liquidity(ctx: MutableCtx, pool_id: PoolId) -> anyhow::Result<Respon
     let mut pool = POOLS.load(ctx.storage, pool_id)?;
     let shares_to_burn = coin_to_burn.amount;
 
+    // Record the pool's prices before they're changed by the withdrawal.
+    update_observations(ctx.storage, pool_id, &pool, ctx.block.timestamp)?;
+
     let refunds = match &mut pool {
         Pool::Xyk(xyk) => xyk.withdraw_liquidity(shares_to_burn)?,
         Pool::Concentrated(concentrated) => concentrated.withdraw_liquidity(shares_to_burn)?,
+        Pool::Stable(stable) => stable.withdraw_liquidity(shares_to_burn)?,
     };
 
     POOLS.save(ctx.storage, pool_id, &pool)?;
@@ -225,6 +331,52 @@ fn withdraw_liquidity(ctx: MutableCtx, pool_id: PoolId) -> anyhow::Result<Respon
         .add_message(Message::transfer(ctx.sender, refunds)?))
 }
 
+fn ramp_stable_pool_amplification(
+    ctx: MutableCtx,
+    pool_id: PoolId,
+    amplification: Uint128,
+    end_time: Timestamp,
+) -> anyhow::Result<Response> {
+    let cfg = ctx.querier.query_config()?;
+
+    ensure!(
+        ctx.sender == cfg.owner,
+        "only the chain owner can ramp amplification"
+    );
+
+    let Pool::Stable(mut pool) = POOLS.load(ctx.storage, pool_id)? else {
+        bail!("pool {pool_id} is not a stable pool");
+    };
+
+    ramp_amplification(&mut pool, amplification, end_time, ctx.block.timestamp)?;
+
+    POOLS.save(ctx.storage, pool_id, &Pool::Stable(pool))?;
+
+    Ok(Response::new())
+}
+
+fn stop_ramp_stable_pool_amplification(
+    ctx: MutableCtx,
+    pool_id: PoolId,
+) -> anyhow::Result<Response> {
+    let cfg = ctx.querier.query_config()?;
+
+    ensure!(
+        ctx.sender == cfg.owner,
+        "only the chain owner can stop ramping amplification"
+    );
+
+    let Pool::Stable(mut pool) = POOLS.load(ctx.storage, pool_id)? else {
+        bail!("pool {pool_id} is not a stable pool");
+    };
+
+    stop_ramp_amplification(&mut pool, ctx.block.timestamp)?;
+
+    POOLS.save(ctx.storage, pool_id, &Pool::Stable(pool))?;
+
+    Ok(Response::new())
+}
+
 /// Returns the LP token denom of the given pool.
 #[inline]
 fn denom_of(pool_id: PoolId) -> StdResult<Denom> {
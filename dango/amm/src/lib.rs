@@ -1,7 +1,8 @@
 mod execute;
+mod oracle;
 mod pools;
 mod query;
 mod state;
 mod swap;
 
-pub use {execute::*, pools::*, query::*, state::*, swap::*};
+pub use {execute::*, oracle::*, pools::*, query::*, state::*, swap::*};
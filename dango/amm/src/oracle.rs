@@ -0,0 +1,284 @@
+use {
+    crate::{PoolExt, OBSERVATIONS, OBSERVATION_COUNTS, POOLS},
+    anyhow::{bail, ensure},
+    dango_types::amm::{Observation, Pool, PoolId, Twap, MAX_OBSERVATIONS},
+    grug::{
+        Duration, Fraction, IsZero, MultiplyRatio, Number, NumberConst, PrevNumber, StdResult,
+        Storage, Timestamp, Udec256, Uint256,
+    },
+};
+
+/// Record the first price observation of a newly created pool, with zero
+/// cumulative prices.
+pub fn initialize_observations(
+    storage: &mut dyn Storage,
+    pool_id: PoolId,
+    block_time: Timestamp,
+) -> StdResult<()> {
+    OBSERVATIONS.save(storage, (pool_id, 0), &Observation {
+        timestamp: block_time,
+        cumulative_price: Udec256::ZERO,
+        cumulative_inverse_price: Udec256::ZERO,
+    })?;
+
+    OBSERVATION_COUNTS.save(storage, pool_id, &1)
+}
+
+/// Accumulate the pool's prices until the current block time, and record them
+/// as a new price observation.
+///
+/// This must be called _before_ the pool's state is changed by a swap or a
+/// liquidity provision or withdrawal, such that the prices accumulated are the
+/// ones that have held since the last observation. This way, a change to the
+/// pool's state only affects the prices accumulated in later blocks, so the
+/// time-weighted average price can't be manipulated within a single block.
+pub fn update_observations(
+    storage: &mut dyn Storage,
+    pool_id: PoolId,
+    pool: &Pool,
+    block_time: Timestamp,
+) -> anyhow::Result<()> {
+    // Pools created before the oracle was introduced don't have observations.
+    // Start recording them from now on.
+    let Some((count, latest)) = load_latest(storage, pool_id)? else {
+        return Ok(initialize_observations(storage, pool_id, block_time)?);
+    };
+
+    // The prices have already been accumulated until this block.
+    if latest.timestamp >= block_time {
+        return Ok(());
+    }
+
+    let observation = accumulate(&latest, pool, block_time)?;
+
+    OBSERVATIONS.save(storage, (pool_id, slot_of(count)), &observation)?;
+    OBSERVATION_COUNTS.save(storage, pool_id, &(count + 1))?;
+
+    Ok(())
+}
+
+/// Compute the time-weighted average prices of a pool over the given period
+/// of time until the current block time.
+pub fn query_twap(
+    storage: &dyn Storage,
+    block_time: Timestamp,
+    pool_id: PoolId,
+    window: Duration,
+) -> anyhow::Result<Twap> {
+    ensure!(window.is_non_zero(), "TWAP window must be non-zero");
+
+    ensure!(
+        window <= block_time,
+        "TWAP window too long! {} > {} nanoseconds",
+        window.into_nanos(),
+        block_time.into_nanos()
+    );
+
+    let pool = POOLS.load(storage, pool_id)?;
+    let start = observation_at(storage, pool_id, &pool, block_time - window)?;
+    let end = observation_at(storage, pool_id, &pool, block_time)?;
+    let elapsed = Udec256::new(window.into_nanos());
+
+    Ok(Twap {
+        price: end
+            .cumulative_price
+            .checked_sub(start.cumulative_price)?
+            .checked_div(elapsed)?
+            .checked_into_prev()?,
+        inverse_price: end
+            .cumulative_inverse_price
+            .checked_sub(start.cumulative_inverse_price)?
+            .checked_div(elapsed)?
+            .checked_into_prev()?,
+    })
+}
+
+// ---------------------------------- helpers ----------------------------------
+
+/// Compute the cumulative prices of a pool at the given time, which must not
+/// be earlier than the oldest observation kept in the ring buffer.
+fn observation_at(
+    storage: &dyn Storage,
+    pool_id: PoolId,
+    pool: &Pool,
+    time: Timestamp,
+) -> anyhow::Result<Observation> {
+    let Some((count, latest)) = load_latest(storage, pool_id)? else {
+        bail!("pool {pool_id} doesn't have any price observation yet");
+    };
+
+    // If the time is after the latest observation, the pool's price has held
+    // since then, so we extrapolate using the pool's current state.
+    if time >= latest.timestamp {
+        return accumulate(&latest, pool, time);
+    }
+
+    // Otherwise, binary search for the latest observation that isn't after
+    // the time. Between two adjacent observations, the price is constant, so
+    // the cumulative prices are linearly interpolated.
+    let mut low = count.saturating_sub(MAX_OBSERVATIONS as u64);
+    let mut high = count - 1;
+
+    let oldest = OBSERVATIONS.load(storage, (pool_id, slot_of(low)))?;
+
+    if time < oldest.timestamp {
+        bail!(
+            "TWAP window too long! oldest observation is at {} nanoseconds, requested {}",
+            oldest.timestamp.into_nanos(),
+            time.into_nanos()
+        );
+    }
+
+    // Invariant: observation `low` is not after the time, while observation
+    // `high` is after it.
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+
+        if OBSERVATIONS
+            .load(storage, (pool_id, slot_of(mid)))?
+            .timestamp
+            <= time
+        {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    let before = OBSERVATIONS.load(storage, (pool_id, slot_of(low)))?;
+    let after = OBSERVATIONS.load(storage, (pool_id, slot_of(high)))?;
+
+    let elapsed = Uint256::new_from_u128((time - before.timestamp).into_nanos());
+    let interval = Uint256::new_from_u128((after.timestamp - before.timestamp).into_nanos());
+
+    Ok(Observation {
+        timestamp: time,
+        cumulative_price: interpolate(
+            before.cumulative_price,
+            after.cumulative_price,
+            elapsed,
+            interval,
+        )?,
+        cumulative_inverse_price: interpolate(
+            before.cumulative_inverse_price,
+            after.cumulative_inverse_price,
+            elapsed,
+            interval,
+        )?,
+    })
+}
+
+/// Load the number of observations of a pool, and the latest one, if any.
+fn load_latest(storage: &dyn Storage, pool_id: PoolId) -> StdResult<Option<(u64, Observation)>> {
+    let Some(count) = OBSERVATION_COUNTS.may_load(storage, pool_id)? else {
+        return Ok(None);
+    };
+
+    let latest = OBSERVATIONS.load(storage, (pool_id, slot_of(count - 1)))?;
+
+    Ok(Some((count, latest)))
+}
+
+/// Accumulate the pool's current prices from the given observation until the
+/// given time.
+fn accumulate(
+    observation: &Observation,
+    pool: &Pool,
+    time: Timestamp,
+) -> anyhow::Result<Observation> {
+    let price = match pool {
+        Pool::Xyk(xyk) => xyk.spot_price(time)?,
+        Pool::Concentrated(concentrated) => concentrated.spot_price(time)?,
+        Pool::Stable(stable) => stable.spot_price(time)?,
+    };
+    let inverse_price = price.checked_inv()?;
+    let elapsed = Udec256::new((time - observation.timestamp).into_nanos());
+
+    Ok(Observation {
+        timestamp: time,
+        cumulative_price: observation
+            .cumulative_price
+            .checked_add(price.checked_mul(elapsed)?)?,
+        cumulative_inverse_price: observation
+            .cumulative_inverse_price
+            .checked_add(inverse_price.checked_mul(elapsed)?)?,
+    })
+}
+
+fn interpolate(
+    before: Udec256,
+    after: Udec256,
+    elapsed: Uint256,
+    interval: Uint256,
+) -> anyhow::Result<Udec256> {
+    let increase = after
+        .numerator()
+        .checked_sub(*before.numerator())?
+        .checked_multiply_ratio_floor(elapsed, interval)?;
+
+    Ok(Udec256::raw(before.numerator().checked_add(increase)?))
+}
+
+/// Returns the slot in the ring buffer of the observation of the given index.
+#[inline]
+fn slot_of(index: u64) -> u32 {
+    (index % MAX_OBSERVATIONS as u64) as u32
+}
+
+// ----------------------------------- tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::PoolInit,
+        dango_types::amm::{FeeRate, StableParams, StablePool},
+        grug::{Coin, CoinPair, MockStorage, Udec128, Uint128},
+    };
+
+    const POOL_ID: PoolId = 1;
+
+    fn mock_pool() -> Pool {
+        Pool::Stable(
+            StablePool::initialize(
+                CoinPair::new(
+                    Coin::new("uusdc", 1_000_000).unwrap(),
+                    Coin::new("uusdt", 1_000_000).unwrap(),
+                )
+                .unwrap(),
+                StableParams {
+                    amplification: Uint128::new(100),
+                    liquidity_fee_rate: FeeRate::new_unchecked(Udec128::new_bps(30)),
+                },
+                Timestamp::from_seconds(0),
+            )
+            .unwrap(),
+        )
+    }
+
+    /// Pools created before the oracle was introduced don't have observations.
+    /// The first update should start recording them, instead of failing.
+    #[test]
+    fn updating_pool_without_observations_works() {
+        let mut storage = MockStorage::new();
+        let pool = mock_pool();
+
+        assert!(observation_at(&storage, POOL_ID, &pool, Timestamp::from_seconds(10)).is_err());
+
+        update_observations(&mut storage, POOL_ID, &pool, Timestamp::from_seconds(10)).unwrap();
+
+        assert_eq!(OBSERVATION_COUNTS.load(&storage, POOL_ID).unwrap(), 1);
+        assert_eq!(
+            OBSERVATIONS.load(&storage, (POOL_ID, 0)).unwrap(),
+            Observation {
+                timestamp: Timestamp::from_seconds(10),
+                cumulative_price: Udec256::ZERO,
+                cumulative_inverse_price: Udec256::ZERO,
+            }
+        );
+
+        update_observations(&mut storage, POOL_ID, &pool, Timestamp::from_seconds(20)).unwrap();
+
+        assert_eq!(OBSERVATION_COUNTS.load(&storage, POOL_ID).unwrap(), 2);
+    }
+}
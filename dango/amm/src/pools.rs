@@ -1,5 +1,6 @@
 mod concentrated;
+mod stable;
 mod traits;
 mod xyk;
 
-pub use traits::*;
+pub use {stable::*, traits::*};
@@ -1,28 +1,884 @@
+//! Concentrated liquidity pools, ported from Curve's two-coin cryptoswap pool.
+//!
+//! The math here follows Curve's implementation closely, including the order
+//! of operations, such that the results can be compared against it. Numbers
+//! are represented as 256-bit unsigned integers with 18 decimal places, as in
+//! Curve, and token amounts are multiplied by [`PRECISION_MULTIPLIER`] before
+//! going into the math.
+//!
+//! See the whitepaper for the derivations:
+//! > https://classic.curve.fi/files/crypto-pools-paper.pdf
+
 use {
+    crate::{PoolExt, PoolInit},
+    anyhow::{bail, ensure},
     dango_types::amm::{ConcentratedParams, ConcentratedPool},
-    grug::{Coin, CoinPair, Uint128},
+    grug::{
+        Coin, CoinPair, Inner, IsZero, MathResult, MultiplyFraction, MultiplyRatio, NextNumber,
+        Number, NumberConst, PrevNumber, Timestamp, Udec128, Udec256, Uint128, Uint256,
+    },
+    std::cmp::max,
 };
 
-use super::{PoolExt, PoolInit};
+/// Fixed-point numbers in the math have 18 decimal places.
+const PRECISION: Uint256 = Uint256::new_from_u128(1_000_000_000_000_000_000);
+
+/// Token amounts are multiplied by this factor before going into the math, so
+/// that the Newton's methods have enough precision even for tokens with few
+/// decimal places.
+const PRECISION_MULTIPLIER: Uint256 = Uint256::new_from_u128(1_000_000_000_000);
+
+/// Number of coins in the pool.
+const N: Uint256 = Uint256::new_from_u128(2);
+
+/// The amplification coefficient is multiplied by this factor, in addition to
+/// `N ^ N`.
+const A_MULTIPLIER: Uint256 = Uint256::new_from_u128(10_000);
+
+const MIN_AMPLIFICATION: Uint128 = Uint128::new(4_000);
+
+const MAX_AMPLIFICATION: Uint128 = Uint128::new(4_000_000_000);
+
+const MIN_GAMMA: Udec128 = Udec128::raw(Uint128::new(10_000_000_000));
+
+const MAX_GAMMA: Udec128 = Udec128::raw(Uint128::new(20_000_000_000_000_000));
+
+/// Maximum half time of the price oracle: one week.
+const MAX_MA_HALF_TIME: u128 = 7 * 24 * 60 * 60 * 1_000_000_000;
+
+/// A fee rate of 0.001% added to the fee of liquidity provisions, so that
+/// depositing and immediately withdrawing can't profit from rounding errors.
+const NOISE_FEE: Uint256 = Uint256::new_from_u128(10_000_000_000_000);
+
+/// Precision at which the series expansion in [`halfpow`] is truncated.
+const EXP_PRECISION: Uint256 = Uint256::new_from_u128(10_000_000_000);
+
+/// A swap updates the last price only if both the input and output amounts
+/// are above this, so that the price is reasonably precise.
+const MIN_PRICE_AMOUNT: Uint128 = Uint128::new(100_000);
+
+const MAX_ITERATIONS: usize = 255;
+
+/// Number of iterations for finding the liquidity fee of a reverse swap, which
+/// converges very quickly, since the fee rate barely depends on the swap size.
+const REVERSE_SWAP_FEE_ITERATIONS: usize = 3;
 
 impl PoolInit for ConcentratedPool {
     type Params = ConcentratedParams;
 
-    fn initialize(_liquidity: CoinPair, _params: ConcentratedParams) -> anyhow::Result<Self> {
-        todo!()
+    fn initialize(
+        liquidity: CoinPair,
+        params: ConcentratedParams,
+        block_time: Timestamp,
+    ) -> anyhow::Result<Self> {
+        validate_params(&params)?;
+
+        // The initial price is such that the pool is balanced.
+        let price_scale =
+            Udec128::checked_from_ratio(*liquidity.first().amount, *liquidity.second().amount)?;
+
+        ensure!(
+            price_scale.is_non_zero(),
+            "initial liquidity is too imbalanced"
+        );
+
+        let xp = scaled_balances(&liquidity, dec_to_raw(price_scale))?;
+        let invariant = newton_d(&params, xp)?;
+
+        // Similar to the XYK pool, the initial amount of shares is the
+        // geometric mean of the deposit, up to rounding.
+        let shares = xcp(invariant, dec_to_raw(price_scale))?
+            .checked_div(PRECISION_MULTIPLIER)?
+            .checked_into_prev()?;
+
+        Ok(Self {
+            params,
+            liquidity,
+            shares,
+            invariant,
+            price_scale,
+            price_oracle: price_scale,
+            last_price: price_scale,
+            last_price_timestamp: block_time,
+            xcp_profit: Udec128::ONE,
+            virtual_price: Udec128::ONE,
+            not_adjusted: false,
+        })
     }
 }
 
 impl PoolExt for ConcentratedPool {
-    fn swap(&mut self, _input: Coin) -> anyhow::Result<(Coin, Coin)> {
-        todo!()
+    fn swap(&mut self, input: Coin, block_time: Timestamp) -> anyhow::Result<(Coin, Coin)> {
+        // Index of the offer coin (`i`) and the ask coin (`j`).
+        let (i, j) = if input.denom == *self.liquidity.first().denom {
+            (0, 1)
+        } else if input.denom == *self.liquidity.second().denom {
+            (1, 0)
+        } else {
+            bail!(
+                "invalid input denom! must be {}|{}, got: {}",
+                self.liquidity.first().denom,
+                self.liquidity.second().denom,
+                input.denom
+            );
+        };
+
+        let price_scale = dec_to_raw(self.price_scale);
+
+        let mut balances = [
+            *self.liquidity.first().amount,
+            *self.liquidity.second().amount,
+        ];
+        balances[i].checked_add_assign(input.amount)?;
+
+        // Compute swap output.
+        let mut xp = [
+            scale(balances[0], 0, price_scale)?,
+            scale(balances[1], 1, price_scale)?,
+        ];
+
+        let y = newton_y(&self.params, xp, self.invariant, j)?;
+        let dy = xp[j].checked_sub(y)?;
+
+        xp[j] = y;
+
+        // Round the output down by one, in favor of the pool.
+        let mut output = unscale(dy.checked_sub(Uint256::ONE)?, j, price_scale)?;
+
+        // Compute liquidity fee. (Note: use ceil rounding.)
+        let fee_rate = raw_to_dec(dynamic_fee(&self.params, xp)?)?;
+        let liquidity_fee = output.checked_mul_dec_ceil(fee_rate)?;
+
+        // Deduct liquidity fee from the output.
+        output.checked_sub_assign(liquidity_fee)?;
+
+        // Update pool state. The liquidity fee stays in the pool.
+        balances[j].checked_sub_assign(output)?;
+        xp[j] = scale(balances[j], j, price_scale)?;
+
+        {
+            let (first, second) = self.liquidity.as_mut();
+            *first.amount = balances[0];
+            *second.amount = balances[1];
+        }
+
+        let last_price = if input.amount > MIN_PRICE_AMOUNT && output > MIN_PRICE_AMOUNT {
+            if i == 0 {
+                Some(Udec128::checked_from_ratio(input.amount, output)?)
+            } else {
+                Some(Udec128::checked_from_ratio(output, input.amount)?)
+            }
+        } else {
+            None
+        };
+
+        tweak_price(self, xp, last_price, None, block_time)?;
+
+        let ask_denom = if j == 0 {
+            self.liquidity.first().denom.clone()
+        } else {
+            self.liquidity.second().denom.clone()
+        };
+
+        Ok((
+            Coin {
+                denom: ask_denom.clone(),
+                amount: output,
+            },
+            Coin {
+                denom: ask_denom,
+                amount: liquidity_fee,
+            },
+        ))
+    }
+
+    fn reverse_swap(&self, output: Coin, _block_time: Timestamp) -> anyhow::Result<Coin> {
+        // Index of the offer coin (`i`) and the ask coin (`j`).
+        let (i, j) = if output.denom == *self.liquidity.second().denom {
+            (0, 1)
+        } else if output.denom == *self.liquidity.first().denom {
+            (1, 0)
+        } else {
+            bail!(
+                "invalid output denom! must be {}|{}, got: {}",
+                self.liquidity.first().denom,
+                self.liquidity.second().denom,
+                output.denom
+            );
+        };
+
+        let price_scale = dec_to_raw(self.price_scale);
+        let xp = scaled_balances(&self.liquidity, price_scale)?;
+
+        // Compute the balances after a swap that outputs the given amount
+        // before liquidity fee. This is the inverse of `swap`, rounded up.
+        let balances_after = |output_before_fee: Uint128| -> anyhow::Result<[Uint256; 2]> {
+            let mut xp = xp;
+            xp[j].checked_sub_assign(
+                scale_ceil(output_before_fee, j, price_scale)?.checked_add(Uint256::ONE)?,
+            )?;
+            xp[i] = newton_y(&self.params, xp, self.invariant, i)?;
+            Ok(xp)
+        };
+
+        // The liquidity fee rate depends on the balances after the swap, which
+        // in turn depend on the fee. Starting from the output without fee,
+        // iterate until the fee is found. Add one to the result to account for
+        // rounding errors in the fee rate. (Note: use ceil rounding.)
+        let mut output_before_fee = output.amount;
+
+        for _ in 0..REVERSE_SWAP_FEE_ITERATIONS {
+            let fee_rate = raw_to_dec(dynamic_fee(
+                &self.params,
+                balances_after(output_before_fee)?,
+            )?)?;
+            output_before_fee = output
+                .amount
+                .checked_div_dec_ceil(Udec128::ONE.checked_sub(fee_rate)?)?
+                .checked_add(Uint128::ONE)?;
+        }
+
+        // Compute swap input. Add one to account for the tolerance of Newton's
+        // method.
+        let dx = balances_after(output_before_fee)?[i].checked_sub(xp[i])?;
+        let input = unscale_ceil(dx, i, price_scale)?.checked_add(Uint128::ONE)?;
+
+        let offer_denom = if i == 0 {
+            self.liquidity.first().denom.clone()
+        } else {
+            self.liquidity.second().denom.clone()
+        };
+
+        Ok(Coin {
+            denom: offer_denom,
+            amount: input,
+        })
+    }
+
+    fn provide_liquidity(
+        &mut self,
+        deposit: CoinPair,
+        block_time: Timestamp,
+    ) -> anyhow::Result<Uint128> {
+        let price_scale = dec_to_raw(self.price_scale);
+        let xp_before = scaled_balances(&self.liquidity, price_scale)?;
+
+        self.liquidity.merge(deposit)?;
+
+        let xp = scaled_balances(&self.liquidity, price_scale)?;
+        let amounts = [
+            xp[0].checked_sub(xp_before[0])?,
+            xp[1].checked_sub(xp_before[1])?,
+        ];
+
+        let invariant_before = self.invariant;
+        let invariant_after = newton_d(&self.params, xp)?;
+
+        // Shares are minted proportionally to the growth of the invariant.
+        let shares_before = self.shares.into_next();
+        let shares_to_mint = shares_before
+            .checked_mul(invariant_after)?
+            .checked_div(invariant_before)?
+            .checked_sub(shares_before)?;
+
+        ensure!(shares_to_mint.is_non_zero(), "deposit is too small");
+
+        // Charge a fee for the imbalanced part of the deposit, which is
+        // equivalent to a swap. (Note: use ceil rounding.)
+        let fee = token_fee(&self.params, amounts, xp)?
+            .checked_mul(shares_to_mint)?
+            .checked_div(PRECISION)?
+            .checked_add(Uint256::ONE)?;
+
+        let shares_to_mint = shares_to_mint.checked_sub(fee)?.checked_into_prev()?;
+
+        self.shares.checked_add_assign(shares_to_mint)?;
+
+        tweak_price(self, xp, None, Some(invariant_after), block_time)?;
+
+        Ok(shares_to_mint)
+    }
+
+    // The pool doesn't have a closed-form marginal price, so we use the price
+    // at which the last swap was executed, same as what the price oracle does.
+    fn spot_price(&self, _block_time: Timestamp) -> anyhow::Result<Udec256> {
+        Ok(self.last_price.into_next())
+    }
+
+    fn withdraw_liquidity(&mut self, shares_to_burn: Uint128) -> anyhow::Result<CoinPair> {
+        let shares_before = self.shares;
+
+        self.shares = shares_before.checked_sub(shares_to_burn)?;
+
+        // The invariant decreases proportionally, so the virtual price stays
+        // the same, up to rounding in favor of the pool.
+        self.invariant.checked_sub_assign(
+            self.invariant
+                .checked_mul(shares_to_burn.into_next())?
+                .checked_div(shares_before.into_next())?,
+        )?;
+
+        Ok(self.liquidity.split(shares_to_burn, shares_before)?)
+    }
+}
+
+// ---------------------------------- helpers ----------------------------------
+
+fn validate_params(params: &ConcentratedParams) -> anyhow::Result<()> {
+    ensure!(
+        (MIN_AMPLIFICATION..=MAX_AMPLIFICATION).contains(&params.amplification),
+        "amplification must be between {MIN_AMPLIFICATION} and {MAX_AMPLIFICATION}, got: {}",
+        params.amplification
+    );
+
+    ensure!(
+        (MIN_GAMMA..=MAX_GAMMA).contains(&params.gamma),
+        "gamma must be between {MIN_GAMMA} and {MAX_GAMMA}, got: {}",
+        params.gamma
+    );
+
+    ensure!(
+        params.mid_fee.inner() <= params.out_fee.inner(),
+        "mid fee can't be greater than out fee: {} > {}",
+        params.mid_fee.inner(),
+        params.out_fee.inner()
+    );
+
+    ensure!(
+        params.fee_gamma.is_non_zero() && params.fee_gamma <= Udec128::ONE,
+        "fee gamma must be greater than zero and no greater than one, got: {}",
+        params.fee_gamma
+    );
+
+    ensure!(
+        params.allowed_extra_profit <= Udec128::ONE,
+        "allowed extra profit can't be greater than one, got: {}",
+        params.allowed_extra_profit
+    );
+
+    ensure!(
+        params.adjustment_step <= Udec128::ONE,
+        "adjustment step can't be greater than one, got: {}",
+        params.adjustment_step
+    );
+
+    ensure!(
+        params.ma_half_time.into_nanos() > 0
+            && params.ma_half_time.into_nanos() <= MAX_MA_HALF_TIME,
+        "moving average half time must be greater than zero and no greater than one week"
+    );
+
+    Ok(())
+}
+
+/// Update the price oracle, the virtual price, and the invariant after the
+/// balances have changed, and repeg the pool if it has made enough profit.
+///
+/// `xp` are the new balances, scaled by the current price scale. The last
+/// price is computed from the balances if not given. The invariant is computed
+/// if not given.
+fn tweak_price(
+    pool: &mut ConcentratedPool,
+    xp: [Uint256; 2],
+    last_price: Option<Udec128>,
+    invariant: Option<Uint256>,
+    block_time: Timestamp,
+) -> anyhow::Result<()> {
+    let price_scale = dec_to_raw(pool.price_scale);
+    let mut price_oracle = dec_to_raw(pool.price_oracle);
+
+    // Update the price oracle, which is the exponential moving average of the
+    // last prices. Note that it uses the last price _before_ this action, so
+    // that it can't be manipulated within a single block.
+    if pool.last_price_timestamp < block_time {
+        let elapsed = block_time.into_nanos() - pool.last_price_timestamp.into_nanos();
+        let alpha = halfpow(
+            Uint256::new_from_u128(elapsed)
+                .checked_mul(PRECISION)?
+                .checked_div(Uint256::new_from_u128(
+                    pool.params.ma_half_time.into_nanos(),
+                ))?,
+        )?;
+
+        price_oracle = dec_to_raw(pool.last_price)
+            .checked_mul(PRECISION.checked_sub(alpha)?)?
+            .checked_add(price_oracle.checked_mul(alpha)?)?
+            .checked_div(PRECISION)?;
+
+        pool.price_oracle = raw_to_dec(price_oracle)?;
+        pool.last_price_timestamp = block_time;
+    }
+
+    let invariant_unadjusted = match invariant {
+        Some(invariant) => invariant,
+        None => newton_d(&pool.params, xp)?,
+    };
+
+    pool.last_price = match last_price {
+        Some(last_price) => last_price,
+        None => {
+            // Compute the marginal price by simulating a tiny swap.
+            let dx = xp[0].checked_div(Uint256::new_from_u128(1_000_000))?;
+            let y = newton_y(
+                &pool.params,
+                [xp[0].checked_add(dx)?, xp[1]],
+                invariant_unadjusted,
+                1,
+            )?;
+
+            raw_to_dec(
+                price_scale
+                    .checked_mul(dx)?
+                    .checked_div(xp[1].checked_sub(y)?)?,
+            )?
+        },
+    };
+
+    // Update the profit, without taking repegging into account.
+    let supply = pool.shares.into_next().checked_mul(PRECISION_MULTIPLIER)?;
+    let old_virtual_price = dec_to_raw(pool.virtual_price);
+    let virtual_price = PRECISION
+        .checked_mul(xcp(invariant_unadjusted, price_scale)?)?
+        .checked_div(supply)?;
+    let xcp_profit = dec_to_raw(pool.xcp_profit)
+        .checked_mul(virtual_price)?
+        .checked_div(old_virtual_price)?;
+
+    ensure!(
+        virtual_price >= old_virtual_price,
+        "loss! virtual price decreased from {} to {}",
+        pool.virtual_price,
+        raw_to_dec(virtual_price)?
+    );
+
+    pool.xcp_profit = raw_to_dec(xcp_profit)?;
+
+    // Relative distance between the price oracle and the price scale.
+    let norm = abs_diff(
+        price_oracle
+            .checked_mul(PRECISION)?
+            .checked_div(price_scale)?,
+        PRECISION,
+    );
+    let adjustment_step = max(
+        dec_to_raw(pool.params.adjustment_step),
+        norm.checked_div(Uint256::new_from_u128(5))?,
+    );
+
+    // The pool repegs only if it has made more than twice the profit needed,
+    // i.e. `virtual_price - 1 > (xcp_profit - 1) / 2 + allowed_extra_profit`,
+    // so that half of the profit goes to liquidity providers.
+    if !pool.not_adjusted
+        && norm > adjustment_step
+        && virtual_price.checked_mul(N)?
+            > xcp_profit
+                .checked_add(dec_to_raw(pool.params.allowed_extra_profit).checked_mul(N)?)?
+                .checked_add(PRECISION)?
+    {
+        pool.not_adjusted = true;
+    }
+
+    if pool.not_adjusted {
+        if norm > adjustment_step {
+            // Move the price scale towards the price oracle.
+            let new_price_scale = price_scale
+                .checked_mul(norm.checked_sub(adjustment_step)?)?
+                .checked_add(adjustment_step.checked_mul(price_oracle)?)?
+                .checked_div(norm)?;
+
+            let xp = [
+                xp[0],
+                xp[1]
+                    .checked_mul(new_price_scale)?
+                    .checked_div(price_scale)?,
+            ];
+
+            let new_invariant = newton_d(&pool.params, xp)?;
+            let new_virtual_price = PRECISION
+                .checked_mul(xcp(new_invariant, new_price_scale)?)?
+                .checked_div(supply)?;
+
+            // Only repeg if the loss in virtual price leaves enough profit.
+            if new_virtual_price > PRECISION
+                && new_virtual_price.checked_mul(N)? > xcp_profit.checked_add(PRECISION)?
+            {
+                pool.price_scale = raw_to_dec(new_price_scale)?;
+                pool.invariant = new_invariant;
+                pool.virtual_price = raw_to_dec(new_virtual_price)?;
+
+                return Ok(());
+            }
+        }
+
+        pool.not_adjusted = false;
     }
 
-    fn provide_liquidity(&mut self, _deposit: CoinPair) -> anyhow::Result<Uint128> {
-        todo!()
+    pool.invariant = invariant_unadjusted;
+    pool.virtual_price = raw_to_dec(virtual_price)?;
+
+    Ok(())
+}
+
+/// Compute the invariant `D` given the scaled balances, using Newton's method.
+fn newton_d(params: &ConcentratedParams, xp: [Uint256; 2]) -> anyhow::Result<Uint256> {
+    let [x0, x1] = sort_desc(xp);
+
+    ensure!(
+        x1.checked_mul(PRECISION)?.checked_div(x0)? >= Uint256::new_from_u128(100_000_000_000_000),
+        "unsafe values! pool is too imbalanced"
+    );
+
+    let sum = x0.checked_add(x1)?;
+    let mut d = N.checked_mul(x0.checked_mul(x1)?.checked_sqrt()?)?;
+
+    for _ in 0..MAX_ITERATIONS {
+        let d_prev = d;
+
+        let k0 = PRECISION
+            .checked_mul(N)?
+            .checked_mul(N)?
+            .checked_mul(x0)?
+            .checked_div(d)?
+            .checked_mul(x1)?
+            .checked_div(d)?;
+        let (g1k0, mul1) = g1k0_and_mul1(params, d, k0)?;
+        let mul2 = PRECISION
+            .checked_mul(N)?
+            .checked_mul(N)?
+            .checked_mul(k0)?
+            .checked_div(g1k0)?;
+
+        let neg_fprime = sum
+            .checked_add(sum.checked_mul(mul2)?.checked_div(PRECISION)?)?
+            .checked_add(mul1.checked_mul(N)?.checked_div(k0)?)?
+            .checked_sub(mul2.checked_mul(d)?.checked_div(PRECISION)?)?;
+
+        let d_plus = d
+            .checked_mul(neg_fprime.checked_add(sum)?)?
+            .checked_div(neg_fprime)?;
+        let mut d_minus = d.checked_mul(d)?.checked_div(neg_fprime)?;
+
+        let term = d
+            .checked_mul(mul1.checked_div(neg_fprime)?)?
+            .checked_div(PRECISION)?
+            .checked_mul(abs_diff(PRECISION, k0))?
+            .checked_div(k0)?;
+
+        if PRECISION > k0 {
+            d_minus.checked_add_assign(term)?;
+        } else {
+            d_minus.checked_sub_assign(term)?;
+        }
+
+        d = if d_plus > d_minus {
+            d_plus - d_minus
+        } else {
+            (d_minus - d_plus).checked_div(N)?
+        };
+
+        if abs_diff(d, d_prev).checked_mul(Uint256::new_from_u128(100_000_000_000_000))?
+            < max(Uint256::new_from_u128(10_000_000_000_000_000), d)
+        {
+            for x in [x0, x1] {
+                ensure_safe_fraction(x, d)?;
+            }
+
+            return Ok(d);
+        }
     }
 
-    fn withdraw_liquidity(&mut self, _shares_to_burn: Uint128) -> anyhow::Result<CoinPair> {
-        todo!()
+    bail!("newton's method for D didn't converge");
+}
+
+/// Compute the scaled balance of the `i`-th coin given the scaled balance of
+/// the other coin and the invariant `D`, using Newton's method.
+fn newton_y(
+    params: &ConcentratedParams,
+    xp: [Uint256; 2],
+    d: Uint256,
+    i: usize,
+) -> anyhow::Result<Uint256> {
+    let x = xp[1 - i];
+
+    ensure_safe_fraction(x, d)?;
+
+    let mut y = d
+        .checked_div(N)?
+        .checked_mul(d)?
+        .checked_div(x.checked_mul(N)?)?;
+    let k0_i = PRECISION.checked_mul(x)?.checked_mul(N)?.checked_div(d)?;
+    let convergence_limit = max(
+        max(x, d).checked_div(Uint256::new_from_u128(100_000_000_000_000))?,
+        Uint256::new_from_u128(100),
+    );
+
+    for _ in 0..MAX_ITERATIONS {
+        let y_prev = y;
+
+        let k0 = k0_i.checked_mul(y)?.checked_mul(N)?.checked_div(d)?;
+        let sum = x.checked_add(y)?;
+        let (g1k0, mul1) = g1k0_and_mul1(params, d, k0)?;
+        let mul2 = PRECISION.checked_add(
+            PRECISION
+                .checked_mul(N)?
+                .checked_mul(k0)?
+                .checked_div(g1k0)?,
+        )?;
+
+        let yfprime = PRECISION
+            .checked_mul(y)?
+            .checked_add(sum.checked_mul(mul2)?)?
+            .checked_add(mul1)?;
+        let dyfprime = d.checked_mul(mul2)?;
+
+        // The step would overshoot below zero. Halve `y` instead.
+        if yfprime < dyfprime {
+            y = y_prev.checked_div(N)?;
+            continue;
+        }
+
+        let yfprime = yfprime - dyfprime;
+        let fprime = yfprime.checked_div(y)?;
+
+        let mut y_minus = mul1.checked_div(fprime)?;
+        let y_plus = yfprime
+            .checked_add(PRECISION.checked_mul(d)?)?
+            .checked_div(fprime)?
+            .checked_add(y_minus.checked_mul(PRECISION)?.checked_div(k0)?)?;
+        y_minus.checked_add_assign(PRECISION.checked_mul(sum)?.checked_div(fprime)?)?;
+
+        y = if y_plus < y_minus {
+            y_prev.checked_div(N)?
+        } else {
+            y_plus - y_minus
+        };
+
+        if abs_diff(y, y_prev)
+            < max(
+                convergence_limit,
+                y.checked_div(Uint256::new_from_u128(100_000_000_000_000))?,
+            )
+        {
+            ensure_safe_fraction(y, d)?;
+
+            return Ok(y);
+        }
+    }
+
+    bail!("newton's method for y didn't converge");
+}
+
+/// Compute `|gamma + 1 - K0| + 1`, and `D / (A * N ^ N) * g1k0 ^ 2 / gamma ^ 2`,
+/// which are common to both Newton's methods.
+fn g1k0_and_mul1(
+    params: &ConcentratedParams,
+    d: Uint256,
+    k0: Uint256,
+) -> anyhow::Result<(Uint256, Uint256)> {
+    let gamma = dec_to_raw(params.gamma);
+    let g1k0 = abs_diff(gamma.checked_add(PRECISION)?, k0).checked_add(Uint256::ONE)?;
+    let mul1 = PRECISION
+        .checked_mul(d)?
+        .checked_div(gamma)?
+        .checked_mul(g1k0)?
+        .checked_div(gamma)?
+        .checked_mul(g1k0)?
+        .checked_mul(A_MULTIPLIER)?
+        .checked_div(params.amplification.into_next())?;
+
+    Ok((g1k0, mul1))
+}
+
+/// Compute the liquidity fee rate given the scaled balances. It's `mid_fee`
+/// when the pool is balanced, and approaches `out_fee` as it's imbalanced.
+fn dynamic_fee(params: &ConcentratedParams, xp: [Uint256; 2]) -> anyhow::Result<Uint256> {
+    let fee_gamma = dec_to_raw(params.fee_gamma);
+    let sum = xp[0].checked_add(xp[1])?;
+    let k = PRECISION
+        .checked_mul(N)?
+        .checked_mul(N)?
+        .checked_mul(xp[0])?
+        .checked_div(sum)?
+        .checked_mul(xp[1])?
+        .checked_div(sum)?;
+    let f = fee_gamma
+        .checked_mul(PRECISION)?
+        .checked_div(fee_gamma.checked_add(PRECISION)?.checked_sub(k)?)?;
+
+    Ok(dec_to_raw(*params.mid_fee.inner())
+        .checked_mul(f)?
+        .checked_add(dec_to_raw(*params.out_fee.inner()).checked_mul(PRECISION.checked_sub(f)?)?)?
+        .checked_div(PRECISION)?)
+}
+
+/// Compute the fee rate for providing liquidity, which is charged on the part
+/// of the deposit that is imbalanced, as if it's swapped.
+fn token_fee(
+    params: &ConcentratedParams,
+    amounts: [Uint256; 2],
+    xp: [Uint256; 2],
+) -> anyhow::Result<Uint256> {
+    let fee = dynamic_fee(params, xp)?.checked_div(N)?;
+    let sum = amounts[0].checked_add(amounts[1])?;
+    let avg = sum.checked_div(N)?;
+    let diff = abs_diff(amounts[0], avg).checked_add(abs_diff(amounts[1], avg))?;
+
+    Ok(fee
+        .checked_mul(diff)?
+        .checked_div(sum)?
+        .checked_add(NOISE_FEE)?)
+}
+
+/// Compute `0.5 ^ power`, where `power` has 18 decimal places, using the
+/// binomial series for the fractional part.
+fn halfpow(power: Uint256) -> anyhow::Result<Uint256> {
+    let int_pow = power.checked_div(PRECISION)?;
+    let frac_pow = power.checked_sub(int_pow.checked_mul(PRECISION)?)?;
+
+    if int_pow > Uint256::new_from_u128(59) {
+        return Ok(Uint256::ZERO);
     }
+
+    let result =
+        PRECISION.checked_div(N.checked_pow(int_pow.checked_into_prev()?.into_inner() as u32)?)?;
+
+    if frac_pow.is_zero() {
+        return Ok(result);
+    }
+
+    let x = PRECISION.checked_div(N)?;
+    let mut term = PRECISION;
+    let mut sum = PRECISION;
+    let mut neg = false;
+
+    for i in 1..=MAX_ITERATIONS {
+        let k = Uint256::new_from_u128(i as u128).checked_mul(PRECISION)?;
+        let mut c = k.checked_sub(PRECISION)?;
+
+        if frac_pow > c {
+            c = frac_pow - c;
+            neg = !neg;
+        } else {
+            c -= frac_pow;
+        }
+
+        term = term
+            .checked_mul(c.checked_mul(x)?.checked_div(PRECISION)?)?
+            .checked_div(k)?;
+
+        if neg {
+            sum.checked_sub_assign(term)?;
+        } else {
+            sum.checked_add_assign(term)?;
+        }
+
+        if term < EXP_PRECISION {
+            return Ok(result.checked_mul(sum)?.checked_div(PRECISION)?);
+        }
+    }
+
+    bail!("halfpow didn't converge");
+}
+
+/// Compute the value of the liquidity, as the geometric mean of the balances
+/// of a balanced pool with the given invariant and price scale.
+fn xcp(d: Uint256, price_scale: Uint256) -> anyhow::Result<Uint256> {
+    let x0 = d.checked_div(N)?;
+    let x1 = d
+        .checked_mul(PRECISION)?
+        .checked_div(N.checked_mul(price_scale)?)?;
+
+    Ok(x0.checked_mul(x1)?.checked_sqrt()?)
+}
+
+/// Scale the balances of the pool by the precision multiplier, and the second
+/// balance additionally by the price scale, such that they are denominated in
+/// the first coin.
+fn scaled_balances(liquidity: &CoinPair, price_scale: Uint256) -> MathResult<[Uint256; 2]> {
+    Ok([
+        scale(*liquidity.first().amount, 0, price_scale)?,
+        scale(*liquidity.second().amount, 1, price_scale)?,
+    ])
+}
+
+fn scale(amount: Uint128, i: usize, price_scale: Uint256) -> MathResult<Uint256> {
+    let scaled = amount.into_next().checked_mul(PRECISION_MULTIPLIER)?;
+
+    if i == 0 {
+        Ok(scaled)
+    } else {
+        scaled.checked_mul(price_scale)?.checked_div(PRECISION)
+    }
+}
+
+fn unscale(scaled: Uint256, i: usize, price_scale: Uint256) -> MathResult<Uint128> {
+    let scaled = if i == 0 {
+        scaled
+    } else {
+        scaled.checked_mul(PRECISION)?.checked_div(price_scale)?
+    };
+
+    scaled
+        .checked_div(PRECISION_MULTIPLIER)?
+        .checked_into_prev()
+}
+
+fn scale_ceil(amount: Uint128, i: usize, price_scale: Uint256) -> MathResult<Uint256> {
+    let scaled = amount.into_next().checked_mul(PRECISION_MULTIPLIER)?;
+
+    if i == 0 {
+        Ok(scaled)
+    } else {
+        scaled.checked_multiply_ratio_ceil(price_scale, PRECISION)
+    }
+}
+
+fn unscale_ceil(scaled: Uint256, i: usize, price_scale: Uint256) -> MathResult<Uint128> {
+    let scaled = if i == 0 {
+        scaled
+    } else {
+        scaled.checked_multiply_ratio_ceil(PRECISION, price_scale)?
+    };
+
+    scaled
+        .checked_multiply_ratio_ceil(Uint256::ONE, PRECISION_MULTIPLIER)?
+        .checked_into_prev()
+}
+
+/// Ensure a scaled balance is within a reasonable range relative to the
+/// invariant, outside of which the Newton's methods are imprecise.
+fn ensure_safe_fraction(x: Uint256, d: Uint256) -> anyhow::Result<()> {
+    let frac = x.checked_mul(PRECISION)?.checked_div(d)?;
+
+    ensure!(
+        frac >= Uint256::new_from_u128(10_000_000_000_000_000)
+            && frac <= Uint256::new_from_u128(100_000_000_000_000_000_000),
+        "unsafe values! pool is too imbalanced"
+    );
+
+    Ok(())
+}
+
+fn sort_desc([a, b]: [Uint256; 2]) -> [Uint256; 2] {
+    if a >= b {
+        [a, b]
+    } else {
+        [b, a]
+    }
+}
+
+fn abs_diff(a: Uint256, b: Uint256) -> Uint256 {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+fn dec_to_raw(dec: Udec128) -> Uint256 {
+    dec.numerator().into_next()
+}
+
+fn raw_to_dec(raw: Uint256) -> MathResult<Udec128> {
+    Ok(Udec128::raw(raw.checked_into_prev()?))
 }
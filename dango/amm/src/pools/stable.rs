@@ -0,0 +1,567 @@
+//! Stableswap pools, ported from Curve's two-coin StableSwap pool.
+//!
+//! The invariant and balances are computed with Newton's method over 256-bit
+//! unsigned integers, such that the intermediate products don't overflow and
+//! the results are precise to the last unit of token amounts.
+//!
+//! See the whitepaper for the derivations:
+//! > https://classic.curve.fi/files/stableswap-paper.pdf
+
+use {
+    crate::{PoolExt, PoolInit},
+    anyhow::{bail, ensure},
+    dango_types::amm::{AmplificationRamp, StableParams, StablePool},
+    grug::{
+        Coin, CoinPair, Duration, Inner, IsZero, MultiplyFraction, MultiplyRatio, NextNumber,
+        Number, NumberConst, PrevNumber, Timestamp, Udec128, Udec256, Uint128, Uint256,
+    },
+};
+
+/// Number of coins in the pool.
+const N: Uint256 = Uint256::new_from_u128(2);
+
+const MIN_AMPLIFICATION: Uint128 = Uint128::new(1);
+
+const MAX_AMPLIFICATION: Uint128 = Uint128::new(1_000_000);
+
+/// A single ramp can multiply or divide the amplification coefficient by at
+/// most this factor.
+const MAX_AMPLIFICATION_CHANGE: Uint128 = Uint128::new(10);
+
+/// A ramp must last at least one day, so that liquidity providers have time to
+/// react to it.
+const MIN_RAMP_DURATION: Duration = Duration::from_seconds(24 * 60 * 60);
+
+const MAX_ITERATIONS: usize = 255;
+
+impl PoolInit for StablePool {
+    type Params = StableParams;
+
+    fn initialize(
+        liquidity: CoinPair,
+        params: StableParams,
+        _block_time: Timestamp,
+    ) -> anyhow::Result<Self> {
+        validate_amplification(params.amplification)?;
+
+        // The initial shares equal the invariant, i.e. the total value of the
+        // liquidity when the pool is balanced.
+        let shares = newton_d(params.amplification, balances(&liquidity))?.checked_into_prev()?;
+
+        Ok(Self {
+            params,
+            liquidity,
+            shares,
+            ramp: None,
+        })
+    }
+}
+
+impl PoolExt for StablePool {
+    fn swap(&mut self, input: Coin, block_time: Timestamp) -> anyhow::Result<(Coin, Coin)> {
+        let amplification = current_amplification(self, block_time)?;
+        let d = newton_d(amplification, balances(&self.liquidity))?;
+
+        let (offer, ask) = if input.denom == *self.liquidity.first().denom {
+            self.liquidity.as_mut()
+        } else if input.denom == *self.liquidity.second().denom {
+            self.liquidity.as_mut_rev()
+        } else {
+            bail!(
+                "invalid input denom! must be {}|{}, got: {}",
+                self.liquidity.first().denom,
+                self.liquidity.second().denom,
+                input.denom
+            );
+        };
+
+        // Compute the ask balance after the swap, such that the invariant is
+        // unchanged. Subtract one from the output in favor of the pool, in case
+        // of rounding errors in Newton's method.
+        let ask_after = newton_y(
+            amplification,
+            offer.amount.checked_add(input.amount)?.into_next(),
+            d,
+        )?;
+        let mut output = ask
+            .amount
+            .into_next()
+            .saturating_sub(ask_after)
+            .saturating_sub(Uint256::ONE)
+            .checked_into_prev()?;
+
+        // Compute liquidity fee. (Note: use ceil rounding.)
+        let liquidity_fee = output.checked_mul_dec_ceil(*self.params.liquidity_fee_rate.inner())?;
+
+        // Deduct liquidity fee from the output.
+        output.checked_sub_assign(liquidity_fee)?;
+
+        // Update pool state.
+        offer.amount.checked_add_assign(input.amount)?;
+        ask.amount.checked_sub_assign(output)?;
+
+        Ok((
+            Coin {
+                denom: ask.denom.clone(),
+                amount: output,
+            },
+            Coin {
+                denom: ask.denom.clone(),
+                amount: liquidity_fee,
+            },
+        ))
+    }
+
+    fn reverse_swap(&self, output: Coin, block_time: Timestamp) -> anyhow::Result<Coin> {
+        let (offer, ask) = if output.denom == *self.liquidity.first().denom {
+            self.liquidity.as_ref_rev()
+        } else if output.denom == *self.liquidity.second().denom {
+            self.liquidity.as_ref()
+        } else {
+            bail!(
+                "invalid output denom! must be {}|{}, got: {}",
+                self.liquidity.first().denom,
+                self.liquidity.second().denom,
+                output.denom
+            );
+        };
+
+        // Compute the output before liquidity fee, such that deducting the fee
+        // from it leaves the given output. (Note: use ceil rounding.)
+        let output_before_fee = output.amount.checked_div_dec_ceil(
+            Udec128::ONE.checked_sub(*self.params.liquidity_fee_rate.inner())?,
+        )?;
+
+        // Add one to the output, same as it's subtracted in `swap`.
+        let ask_reduction = output_before_fee.checked_add(Uint128::ONE)?;
+
+        ensure!(
+            ask_reduction < *ask.amount,
+            "insufficient liquidity: {} < {}",
+            ask.amount,
+            ask_reduction
+        );
+
+        let amplification = current_amplification(self, block_time)?;
+        let d = newton_d(amplification, balances(&self.liquidity))?;
+
+        // Compute the offer balance after the swap, such that the invariant is
+        // unchanged. Add one to the input in favor of the pool, in case of
+        // rounding errors in Newton's method.
+        let offer_after = newton_y(
+            amplification,
+            ask.amount.checked_sub(ask_reduction)?.into_next(),
+            d,
+        )?;
+        let input = offer_after
+            .checked_sub(offer.amount.into_next())?
+            .checked_add(Uint256::ONE)?
+            .checked_into_prev()?;
+
+        Ok(Coin {
+            denom: offer.denom.clone(),
+            amount: input,
+        })
+    }
+
+    fn provide_liquidity(
+        &mut self,
+        deposit: CoinPair,
+        block_time: Timestamp,
+    ) -> anyhow::Result<Uint128> {
+        let amplification = current_amplification(self, block_time)?;
+
+        let old_balances = balances(&self.liquidity);
+        let d_before = newton_d(amplification, old_balances)?;
+
+        self.liquidity.merge(deposit)?;
+
+        let new_balances = balances(&self.liquidity);
+        let d_after = newton_d(amplification, new_balances)?;
+
+        ensure!(
+            d_after > d_before,
+            "liquidity provision doesn't increase the invariant"
+        );
+
+        // Charge liquidity fee on the part of the deposit that makes the pool
+        // imbalanced, i.e. its difference from a deposit proportional to the
+        // pool's balances, which is effectively a swap. Following Curve, for
+        // two coins, the fee rate is half of that of a swap.
+        let fee_rate = self
+            .params
+            .liquidity_fee_rate
+            .inner()
+            .into_next()
+            .checked_div(Udec256::new(2))?;
+
+        let mut balances_after_fee = new_balances;
+
+        for (i, balance) in balances_after_fee.iter_mut().enumerate() {
+            let ideal_balance = d_after
+                .checked_mul(old_balances[i])?
+                .checked_div(d_before)?;
+            let fee = abs_diff(ideal_balance, new_balances[i]).checked_mul_dec_ceil(fee_rate)?;
+
+            balance.checked_sub_assign(fee)?;
+        }
+
+        // The fee stays in the pool, but doesn't count towards the shares to
+        // be minted.
+        let d_after_fee = newton_d(amplification, balances_after_fee)?;
+
+        let shares_to_mint = self
+            .shares
+            .into_next()
+            .checked_multiply_ratio_floor(d_after_fee.checked_sub(d_before)?, d_before)?
+            .checked_into_prev()?;
+
+        self.shares.checked_add_assign(shares_to_mint)?;
+
+        Ok(shares_to_mint)
+    }
+
+    fn spot_price(&self, block_time: Timestamp) -> anyhow::Result<Udec256> {
+        let amplification = current_amplification(self, block_time)?;
+        let [x0, x1] = balances(&self.liquidity);
+        let d = newton_d(amplification, [x0, x1])?;
+
+        // The price is the ratio between the partial derivatives of the
+        // invariant with regard to the two balances, which, for two coins,
+        // simplifies to:
+        //
+        // price = (A * N^N * N^N * x0 * x1 + D^3 / x1) / (A * N^N * N^N * x0 * x1 + D^3 / x0)
+        let ann = amplification.into_next().checked_mul(N)?.checked_mul(N)?;
+        let common = ann
+            .checked_mul(N)?
+            .checked_mul(N)?
+            .checked_mul(x0)?
+            .checked_mul(x1)?;
+        let d2 = d.checked_mul(d)?;
+
+        Ok(Udec256::checked_from_ratio(
+            common.checked_add(d2.checked_div(x1)?.checked_mul(d)?)?,
+            common.checked_add(d2.checked_div(x0)?.checked_mul(d)?)?,
+        )?)
+    }
+
+    fn withdraw_liquidity(&mut self, shares_to_burn: Uint128) -> anyhow::Result<CoinPair> {
+        let shares_before = self.shares;
+
+        self.shares = shares_before.checked_sub(shares_to_burn)?;
+
+        Ok(self.liquidity.split(shares_to_burn, shares_before)?)
+    }
+}
+
+/// Start linearly changing the amplification coefficient of a stable pool,
+/// from its current value to the given one, until the given end time.
+pub fn ramp_amplification(
+    pool: &mut StablePool,
+    amplification: Uint128,
+    end_time: Timestamp,
+    block_time: Timestamp,
+) -> anyhow::Result<()> {
+    ensure!(
+        pool.ramp
+            .as_ref()
+            .map_or(true, |ramp| block_time >= ramp.end_time),
+        "amplification is already being ramped"
+    );
+
+    ensure!(
+        end_time >= block_time + MIN_RAMP_DURATION,
+        "ramp is too short! must last at least {} seconds",
+        MIN_RAMP_DURATION.into_seconds()
+    );
+
+    validate_amplification(amplification)?;
+
+    let initial_amplification = current_amplification(pool, block_time)?;
+
+    ensure!(
+        amplification <= initial_amplification.checked_mul(MAX_AMPLIFICATION_CHANGE)?
+            && initial_amplification <= amplification.checked_mul(MAX_AMPLIFICATION_CHANGE)?,
+        "amplification change is too big! must be within a factor of {} from {}, got: {}",
+        MAX_AMPLIFICATION_CHANGE,
+        initial_amplification,
+        amplification
+    );
+
+    pool.params.amplification = amplification;
+    pool.ramp = Some(AmplificationRamp {
+        initial_amplification,
+        start_time: block_time,
+        end_time,
+    });
+
+    Ok(())
+}
+
+/// Stop the ongoing amplification ramp of a stable pool, keeping the
+/// amplification coefficient at its current value.
+pub fn stop_ramp_amplification(pool: &mut StablePool, block_time: Timestamp) -> anyhow::Result<()> {
+    ensure!(
+        pool.ramp
+            .as_ref()
+            .is_some_and(|ramp| block_time < ramp.end_time),
+        "amplification isn't being ramped"
+    );
+
+    pool.params.amplification = current_amplification(pool, block_time)?;
+    pool.ramp = None;
+
+    Ok(())
+}
+
+// ---------------------------------- helpers ----------------------------------
+
+fn validate_amplification(amplification: Uint128) -> anyhow::Result<()> {
+    ensure!(
+        (MIN_AMPLIFICATION..=MAX_AMPLIFICATION).contains(&amplification),
+        "amplification must be within [{}, {}], got: {}",
+        MIN_AMPLIFICATION,
+        MAX_AMPLIFICATION,
+        amplification
+    );
+
+    Ok(())
+}
+
+/// Compute the amplification coefficient at the given time, interpolating
+/// linearly if it's being ramped.
+fn current_amplification(pool: &StablePool, block_time: Timestamp) -> anyhow::Result<Uint128> {
+    let Some(ramp) = &pool.ramp else {
+        return Ok(pool.params.amplification);
+    };
+
+    if block_time >= ramp.end_time {
+        return Ok(pool.params.amplification);
+    }
+
+    let elapsed = Uint128::new((block_time - ramp.start_time).into_nanos());
+    let duration = Uint128::new((ramp.end_time - ramp.start_time).into_nanos());
+
+    let initial = ramp.initial_amplification;
+    let future = pool.params.amplification;
+
+    if future > initial {
+        Ok(initial.checked_add(
+            future
+                .checked_sub(initial)?
+                .checked_multiply_ratio_floor(elapsed, duration)?,
+        )?)
+    } else {
+        Ok(initial.checked_sub(
+            initial
+                .checked_sub(future)?
+                .checked_multiply_ratio_floor(elapsed, duration)?,
+        )?)
+    }
+}
+
+/// Compute the invariant `D` from the balances, which satisfies:
+///
+/// ```plain
+/// A * N^N * sum(x) + D = A * N^N * D + D^(N+1) / (N^N * prod(x))
+/// ```
+fn newton_d(amplification: Uint128, [x0, x1]: [Uint256; 2]) -> anyhow::Result<Uint256> {
+    ensure!(
+        x0.is_non_zero() && x1.is_non_zero(),
+        "pool balances must be non-zero"
+    );
+
+    let ann = amplification.into_next().checked_mul(N)?.checked_mul(N)?;
+    let sum = x0.checked_add(x1)?;
+    let mut d = sum;
+
+    for _ in 0..MAX_ITERATIONS {
+        let d_p = d
+            .checked_mul(d)?
+            .checked_div(x0.checked_mul(N)?)?
+            .checked_mul(d)?
+            .checked_div(x1.checked_mul(N)?)?;
+        let d_prev = d;
+
+        d = ann
+            .checked_mul(sum)?
+            .checked_add(d_p.checked_mul(N)?)?
+            .checked_mul(d)?
+            .checked_div(
+                ann.checked_sub(Uint256::ONE)?
+                    .checked_mul(d)?
+                    .checked_add(N.checked_add(Uint256::ONE)?.checked_mul(d_p)?)?,
+            )?;
+
+        if abs_diff(d, d_prev) <= Uint256::ONE {
+            return Ok(d);
+        }
+    }
+
+    bail!("newton's method for D didn't converge");
+}
+
+/// Given the balance of one coin and the invariant `D`, compute the balance of
+/// the other coin.
+fn newton_y(amplification: Uint128, x: Uint256, d: Uint256) -> anyhow::Result<Uint256> {
+    let ann = amplification.into_next().checked_mul(N)?.checked_mul(N)?;
+    let c = d
+        .checked_mul(d)?
+        .checked_div(x.checked_mul(N)?)?
+        .checked_mul(d)?
+        .checked_div(ann.checked_mul(N)?)?;
+    let b = x.checked_add(d.checked_div(ann)?)?;
+    let mut y = d;
+
+    for _ in 0..MAX_ITERATIONS {
+        let y_prev = y;
+
+        y = y
+            .checked_mul(y)?
+            .checked_add(c)?
+            .checked_div(y.checked_mul(N)?.checked_add(b)?.checked_sub(d)?)?;
+
+        if abs_diff(y, y_prev) <= Uint256::ONE {
+            return Ok(y);
+        }
+    }
+
+    bail!("newton's method for y didn't converge");
+}
+
+fn balances(liquidity: &CoinPair) -> [Uint256; 2] {
+    [
+        liquidity.first().amount.into_next(),
+        liquidity.second().amount.into_next(),
+    ]
+}
+
+fn abs_diff(a: Uint256, b: Uint256) -> Uint256 {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+// ----------------------------------- tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use {super::*, dango_types::amm::FeeRate, proptest::prelude::*};
+
+    fn mock_pool(balance1: u128, balance2: u128, amplification: u128, fee_bps: u128) -> StablePool {
+        StablePool::initialize(
+            CoinPair::new(
+                Coin::new("uusdc", balance1).unwrap(),
+                Coin::new("uusdt", balance2).unwrap(),
+            )
+            .unwrap(),
+            StableParams {
+                amplification: Uint128::new(amplification),
+                liquidity_fee_rate: FeeRate::new_unchecked(Udec128::new_bps(fee_bps)),
+            },
+            Timestamp::from_seconds(0),
+        )
+        .unwrap()
+    }
+
+    fn invariant(pool: &StablePool) -> Uint256 {
+        newton_d(pool.params.amplification, balances(&pool.liquidity)).unwrap()
+    }
+
+    proptest! {
+        /// Swaps never decrease the invariant, so liquidity providers never
+        /// lose value to traders.
+        #[test]
+        fn swap_never_decreases_invariant(
+            balance1 in 1_000_000..1_000_000_000_000_000_u128,
+            balance2 in 1_000_000..1_000_000_000_000_000_u128,
+            amplification in 1..10_000_u128,
+            fee_bps in 0..100_u128,
+            input_ratio in 1..1_000_u128,
+        ) {
+            prop_assume!(balance1 <= balance2 * 10 && balance2 <= balance1 * 10);
+
+            let mut pool = mock_pool(balance1, balance2, amplification, fee_bps);
+            let d_before = invariant(&pool);
+
+            let input = Coin::new("uusdc", balance1 * input_ratio / 1_000).unwrap();
+            pool.swap(input, Timestamp::from_seconds(0)).unwrap();
+
+            prop_assert!(invariant(&pool) >= d_before);
+        }
+
+        /// Swapping a coin and then swapping the output back never returns
+        /// more than the initial input.
+        #[test]
+        fn swap_round_trip_never_profits(
+            balance1 in 1_000_000..1_000_000_000_000_000_u128,
+            balance2 in 1_000_000..1_000_000_000_000_000_u128,
+            amplification in 1..10_000_u128,
+            fee_bps in 0..100_u128,
+            input_ratio in 1..1_000_u128,
+        ) {
+            prop_assume!(balance1 <= balance2 * 10 && balance2 <= balance1 * 10);
+
+            let mut pool = mock_pool(balance1, balance2, amplification, fee_bps);
+            let input = Coin::new("uusdc", balance1 * input_ratio / 1_000).unwrap();
+
+            let (output, _) = pool.swap(input.clone(), Timestamp::from_seconds(0)).unwrap();
+            let (round_trip, _) = pool.swap(output, Timestamp::from_seconds(0)).unwrap();
+
+            prop_assert!(round_trip.amount <= input.amount);
+        }
+
+        /// Swapping the input computed by a reverse swap outputs at least the
+        /// requested amount.
+        #[test]
+        fn reverse_swap_outputs_at_least_requested(
+            balance1 in 1_000_000..1_000_000_000_000_000_u128,
+            balance2 in 1_000_000..1_000_000_000_000_000_u128,
+            amplification in 1..10_000_u128,
+            fee_bps in 0..100_u128,
+            output_ratio in 1..500_u128,
+        ) {
+            prop_assume!(balance1 <= balance2 * 10 && balance2 <= balance1 * 10);
+
+            let mut pool = mock_pool(balance1, balance2, amplification, fee_bps);
+            let output = Coin::new("uusdt", balance2 * output_ratio / 1_000).unwrap();
+
+            let input = pool.reverse_swap(output.clone(), Timestamp::from_seconds(0)).unwrap();
+            let (actual_output, _) = pool.swap(input, Timestamp::from_seconds(0)).unwrap();
+
+            prop_assert!(actual_output.amount >= output.amount);
+        }
+
+        /// Liquidity provisions never decrease the invariant per share, so they
+        /// never dilute existing liquidity providers.
+        #[test]
+        fn liquidity_provision_never_dilutes(
+            balance1 in 1_000_000..1_000_000_000_000_000_u128,
+            balance2 in 1_000_000..1_000_000_000_000_000_u128,
+            amplification in 1..10_000_u128,
+            fee_bps in 0..100_u128,
+            deposit1 in 0..100_000_000_000_000_u128,
+            deposit2 in 1..100_000_000_000_000_u128,
+        ) {
+            prop_assume!(balance1 <= balance2 * 10 && balance2 <= balance1 * 10);
+
+            let mut pool = mock_pool(balance1, balance2, amplification, fee_bps);
+            let d_before = invariant(&pool);
+            let shares_before = pool.shares.into_next();
+
+            let deposit = CoinPair::new(
+                Coin::new("uusdc", deposit1).unwrap(),
+                Coin::new("uusdt", deposit2).unwrap(),
+            )
+            .unwrap();
+            pool.provide_liquidity(deposit, Timestamp::from_seconds(0)).unwrap();
+
+            let d_after = invariant(&pool);
+            let shares_after = pool.shares.into_next();
+
+            prop_assert!(d_after * shares_before >= d_before * shares_after);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use grug::{Coin, CoinPair, Uint128};
+use grug::{Coin, CoinPair, Timestamp, Udec256, Uint128};
 
 // Note: this trait is not object-safe, because of:
 // - it has an associated type;
@@ -7,7 +7,11 @@ use grug::{Coin, CoinPair, Uint128};
 pub trait PoolInit: Sized {
     type Params;
 
-    fn initialize(liquidity: CoinPair, params: Self::Params) -> anyhow::Result<Self>;
+    fn initialize(
+        liquidity: CoinPair,
+        params: Self::Params,
+        block_time: Timestamp,
+    ) -> anyhow::Result<Self>;
 }
 
 pub trait PoolExt {
@@ -19,11 +23,31 @@ pub trait PoolExt {
     ///
     /// We don't actually use the liquidity fee amount in contract logics.
     /// We just output it in events for data logging purpose.
-    fn swap(&mut self, input: Coin) -> anyhow::Result<(Coin, Coin)>;
+    ///
+    /// The block time is used by pools that keep track of a price oracle.
+    fn swap(&mut self, input: Coin, block_time: Timestamp) -> anyhow::Result<(Coin, Coin)>;
+
+    /// Compute the input needed for a swap to output the given coin, liquidity
+    /// fee deducted. Performing a swap with this input outputs at least this
+    /// coin.
+    ///
+    /// This doesn't change the pool's state.
+    fn reverse_swap(&self, output: Coin, block_time: Timestamp) -> anyhow::Result<Coin>;
 
     /// Provide liquidity to the pool.
     /// Returns the amount of liquidity tokens to be minted.
-    fn provide_liquidity(&mut self, deposit: CoinPair) -> anyhow::Result<Uint128>;
+    fn provide_liquidity(
+        &mut self,
+        deposit: CoinPair,
+        block_time: Timestamp,
+    ) -> anyhow::Result<Uint128>;
+
+    /// Return the pool's current price of the second coin, in units of the
+    /// first coin.
+    ///
+    /// This is the marginal price, i.e. the price of an infinitesimally small
+    /// swap, liquidity fee not included.
+    fn spot_price(&self, block_time: Timestamp) -> anyhow::Result<Udec256>;
 
     /// Withdraw liquidity from the pool.
     /// Returns the amount of liquidity to be refunded to the user.
@@ -1,17 +1,21 @@
 use {
     crate::{PoolExt, PoolInit},
-    anyhow::bail,
+    anyhow::{bail, ensure},
     dango_types::amm::{XykParams, XykPool},
     grug::{
-        Coin, CoinPair, Inner, MultiplyFraction, MultiplyRatio, NextNumber, Number, PrevNumber,
-        Uint128,
+        Coin, CoinPair, Inner, MultiplyFraction, MultiplyRatio, NextNumber, Number, NumberConst,
+        PrevNumber, Timestamp, Udec128, Udec256, Uint128,
     },
 };
 
 impl PoolInit for XykPool {
     type Params = XykParams;
 
-    fn initialize(liquidity: CoinPair, params: XykParams) -> anyhow::Result<Self> {
+    fn initialize(
+        liquidity: CoinPair,
+        params: XykParams,
+        _block_time: Timestamp,
+    ) -> anyhow::Result<Self> {
         let shares = liquidity
             .first()
             .amount
@@ -28,7 +32,7 @@ impl PoolInit for XykPool {
 }
 
 impl PoolExt for XykPool {
-    fn swap(&mut self, input: Coin) -> anyhow::Result<(Coin, Coin)> {
+    fn swap(&mut self, input: Coin, _block_time: Timestamp) -> anyhow::Result<(Coin, Coin)> {
         let (offer, ask) = if input.denom == *self.liquidity.first().denom {
             self.liquidity.as_mut()
         } else if input.denom == *self.liquidity.second().denom {
@@ -75,8 +79,56 @@ impl PoolExt for XykPool {
         ))
     }
 
+    fn reverse_swap(&self, output: Coin, _block_time: Timestamp) -> anyhow::Result<Coin> {
+        let (offer, ask) = if output.denom == *self.liquidity.first().denom {
+            self.liquidity.as_ref_rev()
+        } else if output.denom == *self.liquidity.second().denom {
+            self.liquidity.as_ref()
+        } else {
+            bail!(
+                "invalid output denom! must be {}|{}, got: {}",
+                self.liquidity.first().denom,
+                self.liquidity.second().denom,
+                output.denom
+            );
+        };
+
+        // Compute the output before liquidity fee, such that deducting the fee
+        // from it leaves the given output. (Note: use ceil rounding.)
+        //
+        // output = output_before_fee * (1 - fee_rate)
+        let output_before_fee = output.amount.checked_div_dec_ceil(
+            Udec128::ONE.checked_sub(*self.params.liquidity_fee_rate.inner())?,
+        )?;
+
+        ensure!(
+            output_before_fee < *ask.amount,
+            "insufficient liquidity: {} < {}",
+            ask.amount,
+            output_before_fee
+        );
+
+        // Compute swap input. (Note: use ceil rounding.)
+        //
+        // ask_pool * offer_pool = (ask_pool - output) * (offer_pool + input)
+        // input = offer_pool * output / (ask_pool - output)
+        let input = offer.amount.checked_multiply_ratio_ceil(
+            output_before_fee,
+            ask.amount.checked_sub(output_before_fee)?,
+        )?;
+
+        Ok(Coin {
+            denom: offer.denom.clone(),
+            amount: input,
+        })
+    }
+
     // See `liquidity-providion.md` in docs for the math used here.
-    fn provide_liquidity(&mut self, deposit: CoinPair) -> anyhow::Result<Uint128> {
+    fn provide_liquidity(
+        &mut self,
+        deposit: CoinPair,
+        _block_time: Timestamp,
+    ) -> anyhow::Result<Uint128> {
         let pool1 = self.liquidity.first().amount.into_next();
         let pool2 = self.liquidity.second().amount.into_next();
 
@@ -100,6 +152,13 @@ impl PoolExt for XykPool {
         Ok(shares_after - shares_before)
     }
 
+    fn spot_price(&self, _block_time: Timestamp) -> anyhow::Result<Udec256> {
+        Ok(Udec256::checked_from_ratio(
+            self.liquidity.first().amount.into_next(),
+            self.liquidity.second().amount.into_next(),
+        )?)
+    }
+
     fn withdraw_liquidity(&mut self, shares_to_burn: Uint128) -> anyhow::Result<CoinPair> {
         let shares_before = self.shares;
 
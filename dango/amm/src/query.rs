@@ -1,7 +1,10 @@
 use {
-    crate::{perform_swap, CONFIG, POOLS},
-    dango_types::amm::{Config, Pool, PoolId, QueryMsg, SwapOutcome},
-    grug::{Bound, Coin, ImmutableCtx, Json, JsonSerExt, Order, StdResult, Storage, UniqueVec},
+    crate::{find_best_route, perform_reverse_swap, perform_swap, query_twap, CONFIG, POOLS},
+    dango_types::amm::{Config, Pool, PoolId, QueryMsg, RouteOutcome, SwapOutcome},
+    grug::{
+        Bound, Coin, Denom, ImmutableCtx, Json, JsonSerExt, Order, StdResult, Storage, Timestamp,
+        UniqueVec,
+    },
     std::collections::BTreeMap,
 };
 
@@ -23,7 +26,19 @@ pub fn query(ctx: ImmutableCtx, msg: QueryMsg) -> anyhow::Result<Json> {
             res.to_json_value()
         },
         QueryMsg::Simulate { input, route } => {
-            let res = query_simulte(ctx.storage, input, route)?;
+            let res = query_simulte(ctx.storage, ctx.block.timestamp, input, route)?;
+            res.to_json_value()
+        },
+        QueryMsg::SimulateExactOutput { output, route } => {
+            let res = query_simulate_exact_output(ctx.storage, ctx.block.timestamp, output, route)?;
+            res.to_json_value()
+        },
+        QueryMsg::BestRoute { input, ask_denom } => {
+            let res = query_best_route(ctx.storage, ctx.block.timestamp, input, ask_denom)?;
+            res.to_json_value()
+        },
+        QueryMsg::Twap { pool_id, window } => {
+            let res = query_twap(ctx.storage, ctx.block.timestamp, pool_id, window)?;
             res.to_json_value()
         },
     }
@@ -54,6 +69,7 @@ fn query_pools(
 
 fn query_simulte(
     storage: &dyn Storage,
+    block_time: Timestamp,
     input: Coin,
     route: UniqueVec<PoolId>,
 ) -> anyhow::Result<SwapOutcome> {
@@ -63,5 +79,34 @@ fn query_simulte(
         .map(|pool_id| POOLS.load(storage, pool_id))
         .collect::<StdResult<Vec<_>>>()?;
 
-    perform_swap(&cfg, input, pools.iter_mut())
+    perform_swap(&cfg, input, pools.iter_mut(), block_time)
+}
+
+fn query_simulate_exact_output(
+    storage: &dyn Storage,
+    block_time: Timestamp,
+    output: Coin,
+    route: UniqueVec<PoolId>,
+) -> anyhow::Result<Coin> {
+    let cfg = CONFIG.load(storage)?;
+    let pools = route
+        .into_iter()
+        .map(|pool_id| POOLS.load(storage, pool_id))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    perform_reverse_swap(&cfg, output, pools.iter(), block_time)
+}
+
+fn query_best_route(
+    storage: &dyn Storage,
+    block_time: Timestamp,
+    input: Coin,
+    ask_denom: Denom,
+) -> anyhow::Result<RouteOutcome> {
+    let cfg = CONFIG.load(storage)?;
+    let pools = POOLS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<BTreeMap<_, _>>>()?;
+
+    find_best_route(&cfg, input, &ask_denom, &pools, block_time)
 }
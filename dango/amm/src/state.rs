@@ -1,5 +1,5 @@
 use {
-    dango_types::amm::{Config, Pool, PoolId},
+    dango_types::amm::{Config, Observation, Pool, PoolId},
     grug::{Counter, Item, Map},
 };
 
@@ -8,3 +8,11 @@ pub const CONFIG: Item<Config> = Item::new("config");
 pub const NEXT_POOL_ID: Counter<PoolId> = Counter::new("next_pool_id", 1, 1);
 
 pub const POOLS: Map<PoolId, Pool> = Map::new("pool");
+
+/// Price observations of each pool, indexed by pool ID and slot in the pool's
+/// ring buffer.
+pub const OBSERVATIONS: Map<(PoolId, u32), Observation> = Map::new("observation");
+
+/// The total number of price observations ever made of each pool, including
+/// the ones that have since been overwritten in the ring buffer.
+pub const OBSERVATION_COUNTS: Map<PoolId, u64> = Map::new("observation_count");
@@ -1,14 +1,24 @@
 use {
     crate::PoolExt,
-    dango_types::amm::{Config, Pool, SwapOutcome},
-    grug::{Coin, Coins, Inner, MultiplyFraction, Number},
+    anyhow::anyhow,
+    dango_types::amm::{Config, Pool, PoolId, RouteOutcome, SwapOutcome, MAX_ROUTE_LENGTH},
+    grug::{
+        Coin, Coins, Denom, Inner, MultiplyFraction, Number, NumberConst, Timestamp, Udec128,
+        UniqueVec,
+    },
+    std::collections::BTreeMap,
 };
 
 // Note: this function assumes the swap route doesn't contain any loop, meaning
 // the same pool must not appear twice in the `pools` iterator.
 // The caller should make sure of this by using a `UniqueVec` when taking in
 // the swap route.
-pub fn perform_swap<'a, I>(cfg: &Config, mut input: Coin, pools: I) -> anyhow::Result<SwapOutcome>
+pub fn perform_swap<'a, I>(
+    cfg: &Config,
+    mut input: Coin,
+    pools: I,
+    block_time: Timestamp,
+) -> anyhow::Result<SwapOutcome>
 where
     I: Iterator<Item = &'a mut Pool>,
 {
@@ -17,8 +27,9 @@ where
     // Iterate through the pools and perform swaps.
     for pool in pools {
         let (output, liquidity_fee) = match pool {
-            Pool::Xyk(xyk) => xyk.swap(input)?,
-            Pool::Concentrated(concentrated) => concentrated.swap(input)?,
+            Pool::Xyk(xyk) => xyk.swap(input, block_time)?,
+            Pool::Concentrated(concentrated) => concentrated.swap(input, block_time)?,
+            Pool::Stable(stable) => stable.swap(input, block_time)?,
         };
 
         // The output of this pool is the input for the next pool.
@@ -48,3 +59,126 @@ where
         liquidity_fees,
     })
 }
+
+/// Compute the input needed for a swap through the given pools to output the
+/// given coin, protocol fee deducted. The pools are not changed.
+pub fn perform_reverse_swap<'a, I>(
+    cfg: &Config,
+    output: Coin,
+    pools: I,
+    block_time: Timestamp,
+) -> anyhow::Result<Coin>
+where
+    I: DoubleEndedIterator<Item = &'a Pool>,
+{
+    // Compute the output before protocol fee, such that deducting the fee
+    // from it leaves the given output. (Note: use ceil rounding.)
+    let mut output = Coin {
+        amount: output
+            .amount
+            .checked_div_dec_ceil(Udec128::ONE.checked_sub(*cfg.protocol_fee_rate.inner())?)?,
+        denom: output.denom,
+    };
+
+    // Iterate through the pools backwards. The input of each pool is the
+    // output of the previous pool.
+    for pool in pools.rev() {
+        output = match pool {
+            Pool::Xyk(xyk) => xyk.reverse_swap(output, block_time)?,
+            Pool::Concentrated(concentrated) => concentrated.reverse_swap(output, block_time)?,
+            Pool::Stable(stable) => stable.reverse_swap(output, block_time)?,
+        };
+    }
+
+    // This is the input of the first pool.
+    Ok(output)
+}
+
+/// Find the route through the given pools, of no more than `MAX_ROUTE_LENGTH`
+/// pools, that gives the most output when swapping the given coin for the
+/// given denom. The pools are not changed.
+pub fn find_best_route(
+    cfg: &Config,
+    input: Coin,
+    ask_denom: &Denom,
+    pools: &BTreeMap<PoolId, Pool>,
+    block_time: Timestamp,
+) -> anyhow::Result<RouteOutcome> {
+    let mut routes = vec![];
+
+    find_routes(
+        pools,
+        ask_denom,
+        &mut vec![input.denom.clone()],
+        &mut vec![],
+        &mut routes,
+    );
+
+    let mut best: Option<RouteOutcome> = None;
+
+    for route in routes {
+        let mut route_pools = route
+            .iter()
+            .map(|pool_id| pools[pool_id].clone())
+            .collect::<Vec<_>>();
+
+        // Skip the routes through which the swap fails, e.g. because a pool
+        // doesn't have enough liquidity.
+        let Ok(outcome) = perform_swap(cfg, input.clone(), route_pools.iter_mut(), block_time)
+        else {
+            continue;
+        };
+
+        if best.as_ref().map_or(true, |best| {
+            outcome.output.amount > best.outcome.output.amount
+        }) {
+            best = Some(RouteOutcome {
+                route: UniqueVec::new_unchecked(route),
+                outcome,
+            });
+        }
+    }
+
+    best.ok_or_else(|| anyhow!("no route found from {} to {}", input.denom, ask_denom))
+}
+
+// Find all routes from the last of the visited denoms to the ask denom, that
+// don't visit any denom twice, and therefore don't contain any loop.
+fn find_routes(
+    pools: &BTreeMap<PoolId, Pool>,
+    ask_denom: &Denom,
+    visited: &mut Vec<Denom>,
+    route: &mut Vec<PoolId>,
+    routes: &mut Vec<Vec<PoolId>>,
+) {
+    // The last visited denom is the one we're currently holding.
+    let offer_denom = visited.last().unwrap().clone();
+
+    for (pool_id, pool) in pools {
+        let (denom1, denom2) = pool.denoms();
+
+        let next_denom = if denom1 == offer_denom {
+            denom2
+        } else if denom2 == offer_denom {
+            denom1
+        } else {
+            continue;
+        };
+
+        if visited.contains(&next_denom) {
+            continue;
+        }
+
+        route.push(*pool_id);
+
+        if next_denom == *ask_denom {
+            routes.push(route.clone());
+        } else if route.len() < MAX_ROUTE_LENGTH {
+            visited.push(next_denom);
+            find_routes(pools, ask_denom, visited, route, routes);
+            visited.pop();
+        }
+
+        route.pop();
+    }
+}
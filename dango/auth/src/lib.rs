@@ -3,11 +3,16 @@ use {
     base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine},
     dango_account_factory::{ACCOUNTS_BY_USER, KEYS, KEYS_BY_USER},
     dango_types::{
-        auth::{ClientData, Credential, Key, Metadata, SignDoc},
+        auth::{
+            ClientData, Credential, Key, MessageType, Metadata, SessionInfo, SessionSignDoc,
+            SignDoc, Unordered,
+        },
         config::ACCOUNT_FACTORY_KEY,
     },
     grug::{
-        Addr, AuthCtx, AuthMode, BorshDeExt, Counter, HashExt, JsonDeExt, JsonSerExt, Query, Tx,
+        Addr, Api, AuthCtx, AuthMode, BorshDeExt, Coins, Counter, Duration, Hash256, HashExt,
+        JsonDeExt, JsonSerExt, Map, Message, Order, PrefixBound, Query, Set, StdResult, Storage,
+        Timestamp, Tx,
     },
 };
 
@@ -17,6 +22,28 @@ use {
 /// same storage slot.
 pub const NEXT_SEQUENCE: Counter<u32> = Counter::new("sequence", 0, 1);
 
+/// The maximum period of time from the current block until an unordered
+/// transaction's timeout.
+///
+/// This bounds how long the nonces of unordered transactions are stored.
+pub const MAX_UNORDERED_TIMEOUT: Duration = Duration::from_seconds(60 * 60);
+
+/// Nonces used by unordered transactions that haven't timed out.
+pub const NONCES: Set<u64> = Set::new("nonce");
+
+/// Nonces used by unordered transactions that haven't timed out, indexed by
+/// the transactions' timeouts. Used for pruning nonces that have timed out.
+pub const NONCES_BY_TIMEOUT: Set<(Timestamp, u64)> = Set::new("nonce__timeout");
+
+/// Coins sent by each session key that has a spend limit, indexed by the hash
+/// of the session's sign doc.
+pub const SESSION_SPENDINGS: Map<Hash256, Coins> = Map::new("session_spending");
+
+/// Hashes of the sessions that have spendings recorded, indexed by the
+/// sessions' expiration times. Used for pruning spendings of expired sessions.
+pub const SESSION_SPENDINGS_BY_EXPIRY: Set<(Timestamp, Hash256)> =
+    Set::new("session_spending__expiry");
+
 /// Authenticate a transaction.
 ///
 /// This logic is shared across all three account types.
@@ -42,9 +69,6 @@ pub fn authenticate_tx(
         tx.data.deserialize_json()?
     };
 
-    // Increment the sequence.
-    let (sequence, _) = NEXT_SEQUENCE.increment(ctx.storage)?;
-
     // Query the account factory. We need to do three things:
     // - ensure the `tx.sender` is associated with the username;
     // - ensure the `key_hash` is associated wit the username;
@@ -76,7 +100,9 @@ pub fn authenticate_tx(
         );
 
         // Similarly, if the key hash is associated with the username, it must
-        // be present in the `KEYS_BY_USER` set.
+        // be present in the `KEYS_BY_USER` set. A key removed from the user
+        // using `UpdateKey` is deleted from this set, so transactions signed
+        // by it are rejected from then on.
         ensure!(
             res2.as_wasm_raw().is_some_and(|bytes| bytes.is_empty()),
             "key hash {} isn't associated with user `{}`",
@@ -92,78 +118,98 @@ pub fn authenticate_tx(
 
     // Compute the sign bytes.
     let sign_bytes = SignDoc {
-        messages: tx.msgs,
-        chain_id: ctx.chain_id,
+        messages: tx.msgs.clone(),
+        chain_id: ctx.chain_id.clone(),
         sequence: metadata.sequence,
+        unordered: metadata.unordered.clone(),
     }
     .to_json_vec()?
     .hash256();
 
-    // Verify sequence.
-    match ctx.mode {
-        // For `CheckTx`, we only make sure the tx's sequence is no smaller than
-        // the stored sequence. This allows the account to broadcast multiple
-        // txs for the same block.
-        AuthMode::Check => {
-            ensure!(
-                metadata.sequence >= sequence,
-                "sequence is too old: expecting at least {}, found {}",
-                sequence,
-                metadata.sequence
-            );
-        },
-        // For `FinalizeBlock`, we make sure the tx's sequence matches exactly
-        // the stored sequence.
-        AuthMode::Finalize => {
-            ensure!(
-                metadata.sequence == sequence,
-                "incorrect sequence: expecting {}, got {}",
-                sequence,
-                metadata.sequence
-            );
-        },
-        // No need to verify sequence in simulation mode.
-        AuthMode::Simulate => (),
+    // Verify sequence, or nonce and timeout if the transaction is unordered.
+    if let Some(unordered) = &metadata.unordered {
+        match ctx.mode {
+            AuthMode::Check | AuthMode::Finalize => {
+                verify_nonce(ctx.storage, ctx.block.timestamp, unordered)?;
+            },
+            // No need to verify nonce in simulation mode.
+            AuthMode::Simulate => (),
+        }
+    } else {
+        // Increment the sequence.
+        let (sequence, _) = NEXT_SEQUENCE.increment(ctx.storage)?;
+
+        match ctx.mode {
+            // For `CheckTx`, we only make sure the tx's sequence is no smaller
+            // than the stored sequence. This allows the account to broadcast
+            // multiple txs for the same block.
+            AuthMode::Check => {
+                ensure!(
+                    metadata.sequence >= sequence,
+                    "sequence is too old: expecting at least {}, found {}",
+                    sequence,
+                    metadata.sequence
+                );
+            },
+            // For `FinalizeBlock`, we make sure the tx's sequence matches
+            // exactly the stored sequence.
+            AuthMode::Finalize => {
+                ensure!(
+                    metadata.sequence == sequence,
+                    "incorrect sequence: expecting {}, got {}",
+                    sequence,
+                    metadata.sequence
+                );
+            },
+            // No need to verify sequence in simulation mode.
+            AuthMode::Simulate => (),
+        }
     }
 
     // Verify signature.
     match ctx.mode {
-        AuthMode::Check | AuthMode::Finalize => match (key, tx.credential.deserialize_json()?) {
-            (Key::Secp256r1(pk), Credential::Passkey(cred)) => {
-                // Generate the raw bytes that the Passkey should have signed.
-                // See: <https://github.com/j0nl1/demo-passkey/blob/main/wasm/lib.rs#L59-L99>
-                let signed_hash = {
-                    let client_data = ClientData {
-                        ty: "webauthn.get".to_string(),
-                        challenge: URL_SAFE_NO_PAD.encode(sign_bytes),
-                        origin: cred.origin,
-                        cross_origin: cred.cross_origin,
-                    };
-                    let client_data_raw = client_data.to_json_vec()?;
-                    let client_data_hash = ctx.api.sha2_256(&client_data_raw);
-
-                    let signed_data = [
-                        cred.authenticator_data.as_ref(),
-                        client_data_hash.as_slice(),
-                    ]
-                    .concat();
-
-                    // Note we use the FFI `sha2_256` method instead of `hash256`
-                    // from `HashExt`, because we may change the hash function
-                    // used in `HashExt` (we're exploring BLAKE3 over SHA-256).
-                    // Passkey always signs over SHA-256 digests.
-                    ctx.api.sha2_256(&signed_data)
-                };
+        AuthMode::Check | AuthMode::Finalize => match tx.credential.deserialize_json()? {
+            // The transaction is signed by a session key. Verify the chain of
+            // signatures: the user's key authorizing the session key, and the
+            // session key signing the transaction. Then, ensure the messages
+            // are within the session's scope.
+            Credential::Session(session) => {
+                ensure!(
+                    ctx.block.timestamp < session.session_info.expire_at,
+                    "session expired at {} nanoseconds",
+                    session.session_info.expire_at.into_nanos()
+                );
 
-                ctx.api.secp256r1_verify(&signed_hash, &cred.sig, &pk)?;
-            },
-            (Key::Secp256k1(pk), Credential::Secp256k1(sig)) => {
-                ctx.api.secp256k1_verify(&sign_bytes, &sig, &pk)?;
+                let session_sign_bytes = SessionSignDoc {
+                    username: metadata.username,
+                    sender: ctx.contract,
+                    chain_id: ctx.chain_id,
+                    session_info: session.session_info.clone(),
+                }
+                .to_json_vec()?
+                .hash256();
+
+                verify_signature(ctx.api, key, *session.authorization, session_sign_bytes)?;
+
+                verify_signature(
+                    ctx.api,
+                    session.session_info.session_key,
+                    *session.session_signature,
+                    sign_bytes,
+                )?;
+
+                enforce_session_scope(
+                    ctx.storage,
+                    ctx.block.timestamp,
+                    factory,
+                    &session.session_info,
+                    session_sign_bytes,
+                    &tx.msgs,
+                )?;
             },
-            (Key::Ed25519(pk), Credential::Ed25519(sig)) => {
-                ctx.api.ed25519_verify(&sign_bytes, &sig, &pk)?;
+            credential => {
+                verify_signature(ctx.api, key, credential, sign_bytes)?;
             },
-            _ => bail!("key and credential types don't match!"),
         },
         // No need to verify signature in simulation mode.
         AuthMode::Simulate => (),
@@ -171,3 +217,210 @@ pub fn authenticate_tx(
 
     Ok(())
 }
+
+/// Ensure an unordered transaction hasn't timed out, and its nonce hasn't been
+/// used by another unordered transaction that hasn't timed out. Then, record
+/// the nonce until the transaction's timeout.
+///
+/// Nonces of transactions that have timed out are pruned here, so the number
+/// of nonces stored is bounded by the number of unordered transactions the
+/// account can send within `MAX_UNORDERED_TIMEOUT`.
+fn verify_nonce(
+    storage: &mut dyn Storage,
+    block_time: Timestamp,
+    unordered: &Unordered,
+) -> anyhow::Result<()> {
+    ensure!(
+        unordered.timeout > block_time,
+        "transaction timed out at {} nanoseconds",
+        unordered.timeout.into_nanos()
+    );
+
+    ensure!(
+        unordered.timeout <= block_time + MAX_UNORDERED_TIMEOUT,
+        "timeout too far in the future! {} > {} nanoseconds",
+        unordered.timeout.into_nanos(),
+        (block_time + MAX_UNORDERED_TIMEOUT).into_nanos()
+    );
+
+    // Prune the nonces that have timed out. Once a transaction has timed out,
+    // it can no longer be included in a block, so its nonce can be reused.
+    let timed_out = NONCES_BY_TIMEOUT
+        .prefix_range(
+            storage,
+            None,
+            Some(PrefixBound::Inclusive(block_time)),
+            Order::Ascending,
+        )
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (timeout, nonce) in timed_out {
+        NONCES_BY_TIMEOUT.remove(storage, (timeout, nonce));
+        NONCES.remove(storage, nonce);
+    }
+
+    ensure!(
+        !NONCES.has(storage, unordered.nonce),
+        "nonce {} has already been used",
+        unordered.nonce
+    );
+
+    NONCES.insert(storage, unordered.nonce)?;
+    NONCES_BY_TIMEOUT.insert(storage, (unordered.timeout, unordered.nonce))?;
+
+    Ok(())
+}
+
+/// Verify a signature of the given sign bytes by the given key.
+fn verify_signature(
+    api: &dyn Api,
+    key: Key,
+    credential: Credential,
+    sign_bytes: Hash256,
+) -> anyhow::Result<()> {
+    match (key, credential) {
+        (Key::Secp256r1(pk), Credential::Passkey(cred)) => {
+            // Generate the raw bytes that the Passkey should have signed.
+            // See: <https://github.com/j0nl1/demo-passkey/blob/main/wasm/lib.rs#L59-L99>
+            let signed_hash = {
+                let client_data = ClientData {
+                    ty: "webauthn.get".to_string(),
+                    challenge: URL_SAFE_NO_PAD.encode(sign_bytes),
+                    origin: cred.origin,
+                    cross_origin: cred.cross_origin,
+                };
+                let client_data_raw = client_data.to_json_vec()?;
+                let client_data_hash = api.sha2_256(&client_data_raw);
+
+                let signed_data = [
+                    cred.authenticator_data.as_ref(),
+                    client_data_hash.as_slice(),
+                ]
+                .concat();
+
+                // Note we use the FFI `sha2_256` method instead of `hash256`
+                // from `HashExt`, because we may change the hash function
+                // used in `HashExt` (we're exploring BLAKE3 over SHA-256).
+                // Passkey always signs over SHA-256 digests.
+                api.sha2_256(&signed_data)
+            };
+
+            api.secp256r1_verify(&signed_hash, &cred.sig, &pk)?;
+        },
+        (Key::Secp256k1(pk), Credential::Secp256k1(sig)) => {
+            api.secp256k1_verify(&sign_bytes, &sig, &pk)?;
+        },
+        (Key::Ed25519(pk), Credential::Ed25519(sig)) => {
+            api.ed25519_verify(&sign_bytes, &sig, &pk)?;
+        },
+        (_, Credential::Session(_)) => bail!("session credentials can't be nested"),
+        _ => bail!("key and credential types don't match!"),
+    }
+
+    Ok(())
+}
+
+/// Ensure the messages are within the scope of the session, and record the
+/// coins they send towards the session's spend limit.
+///
+/// Note that the spending is recorded during authentication, so it counts
+/// towards the limit even if the messages fail to execute.
+///
+/// Spendings of sessions that have expired are pruned here. Once a session
+/// has expired, it can no longer be used, so its spending is no longer needed.
+fn enforce_session_scope(
+    storage: &mut dyn Storage,
+    block_time: Timestamp,
+    factory: Addr,
+    session_info: &SessionInfo,
+    session_hash: Hash256,
+    msgs: &[Message],
+) -> anyhow::Result<()> {
+    let expired = SESSION_SPENDINGS_BY_EXPIRY
+        .prefix_range(
+            storage,
+            None,
+            Some(PrefixBound::Inclusive(block_time)),
+            Order::Ascending,
+        )
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (expire_at, hash) in expired {
+        SESSION_SPENDINGS_BY_EXPIRY.remove(storage, (expire_at, hash));
+        SESSION_SPENDINGS.remove(storage, hash);
+    }
+
+    let mut spending = Coins::new();
+
+    for msg in msgs {
+        let ty = MessageType::from(msg);
+
+        if let Some(allowed) = &session_info.allowed_message_types {
+            ensure!(
+                allowed.contains(&ty),
+                "message type {ty:?} isn't allowed by the session"
+            );
+        }
+
+        let (target, funds) = match msg {
+            Message::Transfer { to, coins } => (Some(to), Some(coins)),
+            Message::Execute {
+                contract, funds, ..
+            } => (Some(contract), Some(funds)),
+            Message::Instantiate { funds, .. } => (None, Some(funds)),
+            Message::Migrate { contract, .. } => (Some(contract), None),
+            Message::Configure { .. } | Message::Upload { .. } => (None, None),
+        };
+
+        // Session keys can't send messages to the account factory, regardless
+        // of the allowed targets. Otherwise, a session key could register
+        // itself as one of the user's keys, outliving the session.
+        ensure!(
+            target != Some(&factory),
+            "session keys can't send messages to the account factory"
+        );
+
+        if let Some(allowed) = &session_info.allowed_targets {
+            ensure!(
+                target.is_some_and(|target| allowed.contains(target)),
+                "message target isn't allowed by the session"
+            );
+        }
+
+        if let Some(funds) = funds {
+            for coin in funds.clone() {
+                spending.insert(coin)?;
+            }
+        }
+    }
+
+    if let Some(limit) = &session_info.spend_limit {
+        if !SESSION_SPENDINGS.has(storage, session_hash) {
+            SESSION_SPENDINGS_BY_EXPIRY.insert(storage, (session_info.expire_at, session_hash))?;
+        }
+
+        SESSION_SPENDINGS.update(storage, session_hash, |maybe_spent| {
+            let mut spent = maybe_spent.unwrap_or_default();
+
+            for coin in spending {
+                spent.insert(coin)?;
+            }
+
+            for coin in &spent {
+                let allowed = limit.amount_of(coin.denom);
+
+                ensure!(
+                    *coin.amount <= allowed,
+                    "session spend limit exceeded for denom `{}`: {} > {}",
+                    coin.denom,
+                    coin.amount,
+                    allowed
+                );
+            }
+
+            Ok(Some(spent))
+        })?;
+    }
+
+    Ok(())
+}
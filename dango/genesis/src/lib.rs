@@ -1,17 +1,18 @@
 use {
     anyhow::anyhow,
     dango_types::{
+        account::margin::{self, CollateralParams},
         account_factory::{self, AccountType, NewUserSalt, Username},
         amm::{self, FeeRate},
         auth::Key,
         bank,
-        config::{ACCOUNT_FACTORY_KEY, IBC_TRANSFER_KEY},
-        mock_ibc_transfer, taxman, token_factory,
+        config::{ACCOUNT_FACTORY_KEY, IBC_TRANSFER_KEY, MARGIN_KEY},
+        lending, mock_ibc_transfer, taxman, token_factory,
     },
     grug::{
-        btree_map, btree_set, Addr, Binary, Coin, Coins, Config, Denom, GenesisState, Hash160,
-        Hash256, HashExt, JsonSerExt, Message, NonZero, Part, Permission, Permissions, StdResult,
-        Udec128, Uint128, GENESIS_SENDER,
+        btree_map, btree_set, Addr, Binary, Coin, Coins, Config, Denom, Duration, GasCosts,
+        GenesisState, Hash160, Hash256, HashExt, JsonSerExt, Message, NonZero, Part, Permission,
+        Permissions, StdResult, Udec128, Uint128, WasmLimits, GENESIS_SENDER,
     },
     serde::Serialize,
     std::{collections::BTreeMap, error::Error, fs, io, path::Path, str::FromStr},
@@ -28,6 +29,7 @@ pub struct Contracts {
     pub bank: Addr,
     pub fee_recipient: Addr,
     pub ibc_transfer: Addr,
+    pub lending: Addr,
     pub owner: Addr,
     pub taxman: Addr,
     pub token_factory: Addr,
@@ -37,10 +39,12 @@ pub struct Contracts {
 pub struct Codes<T> {
     pub account_factory: T,
     pub account_spot: T,
+    pub account_margin: T,
     pub account_safe: T,
     pub amm: T,
     pub bank: T,
     pub ibc_transfer: T,
+    pub lending: T,
     pub taxman: T,
     pub token_factory: T,
 }
@@ -54,20 +58,24 @@ pub struct GenesisUser {
 pub fn read_wasm_files(artifacts_dir: &Path) -> io::Result<Codes<Vec<u8>>> {
     let account_factory = fs::read(artifacts_dir.join("dango_account_factory.wasm"))?;
     let account_spot = fs::read(artifacts_dir.join("dango_account_spot.wasm"))?;
+    let account_margin = fs::read(artifacts_dir.join("dango_account_margin.wasm"))?;
     let account_safe = fs::read(artifacts_dir.join("dango_account_safe.wasm"))?;
     let amm = fs::read(artifacts_dir.join("dango_amm.wasm"))?;
     let bank = fs::read(artifacts_dir.join("dango_bank.wasm"))?;
     let ibc_transfer = fs::read(artifacts_dir.join("dango_ibc_transfer.wasm"))?;
+    let lending = fs::read(artifacts_dir.join("dango_lending.wasm"))?;
     let taxman = fs::read(artifacts_dir.join("dango_taxman.wasm"))?;
     let token_factory = fs::read(artifacts_dir.join("dango_token_factory.wasm"))?;
 
     Ok(Codes {
         account_factory,
         account_spot,
+        account_margin,
         account_safe,
         amm,
         bank,
         ibc_transfer,
+        lending,
         taxman,
         token_factory,
     })
@@ -94,10 +102,12 @@ where
     // Upload all the codes and compute code hashes.
     let account_factory_code_hash = upload(&mut msgs, codes.account_factory);
     let account_spot_code_hash = upload(&mut msgs, codes.account_spot);
+    let account_margin_code_hash = upload(&mut msgs, codes.account_margin);
     let account_safe_code_hash = upload(&mut msgs, codes.account_safe);
     let amm_code_hash = upload(&mut msgs, codes.amm);
     let bank_code_hash = upload(&mut msgs, codes.bank);
     let ibc_transfer_code_hash = upload(&mut msgs, codes.ibc_transfer);
+    let lending_code_hash = upload(&mut msgs, codes.lending);
     let taxman_code_hash = upload(&mut msgs, codes.taxman);
     let token_factory_code_hash = upload(&mut msgs, codes.token_factory);
 
@@ -116,6 +126,7 @@ where
         &account_factory::InstantiateMsg {
             code_hashes: btree_map! {
                 AccountType::Spot => account_spot_code_hash,
+                AccountType::Margin => account_margin_code_hash,
                 AccountType::Safe => account_safe_code_hash,
             },
             keys,
@@ -184,6 +195,14 @@ where
         "amm",
     )?;
 
+    // Instantiate the lending pool contract.
+    let lending = instantiate(
+        &mut msgs,
+        lending_code_hash,
+        &lending::InstantiateMsg {},
+        "lending",
+    )?;
+
     // Create the `balances` map needed for instantiating bank.
     let balances = genesis_users
         .into_iter()
@@ -224,7 +243,7 @@ where
         &taxman::InstantiateMsg {
             config: taxman::Config {
                 fee_recipient,
-                fee_denom,
+                fee_denom: fee_denom.clone(),
                 fee_rate,
             },
         },
@@ -237,6 +256,7 @@ where
         bank,
         fee_recipient,
         ibc_transfer,
+        lending,
         owner,
         taxman,
         token_factory,
@@ -253,17 +273,36 @@ where
         taxman,
         cronjobs: BTreeMap::new(),
         permissions,
+        wasm_limits: WasmLimits::default(),
     };
 
     let app_configs = btree_map! {
         ACCOUNT_FACTORY_KEY.to_string() => account_factory.to_json_value()?,
         IBC_TRANSFER_KEY.to_string() => ibc_transfer.to_json_value()?,
+        MARGIN_KEY.to_string() => margin::Config {
+            lending,
+            amm,
+            quote_denom: fee_denom.clone(),
+            twap_window: Duration::from_seconds(30 * 60), // 30 minutes
+            // Initially, only the fee denom is accepted as collateral. More
+            // denoms can be added by the chain owner once AMM pools between
+            // them and the fee denom have been created.
+            collaterals: btree_map! {
+                fee_denom => CollateralParams {
+                    price_source: None,
+                    collateral_factor: Udec128::new_percent(90),
+                },
+            },
+            liquidation_bonus: Udec128::new_percent(5),
+        }
+        .to_json_value()?,
     };
 
     let genesis_state = GenesisState {
         config,
         msgs,
         app_configs,
+        gas_costs: GasCosts::default(),
     };
 
     Ok((genesis_state, contracts, addresses))
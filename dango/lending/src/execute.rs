@@ -0,0 +1,140 @@
+use {
+    crate::{DEBTS, DEPOSITS},
+    anyhow::{anyhow, ensure},
+    dango_account_factory::ACCOUNTS,
+    dango_types::{
+        account_factory::{Account, AccountParams},
+        config::ACCOUNT_FACTORY_KEY,
+        lending::{ExecuteMsg, InstantiateMsg},
+    },
+    grug::{BorshDeExt, Coin, Coins, IsZero, Message, MutableCtx, Response, StdResult},
+    std::cmp,
+};
+
+#[cfg_attr(not(feature = "library"), grug::export)]
+pub fn instantiate(_ctx: MutableCtx, _msg: InstantiateMsg) -> StdResult<Response> {
+    Ok(Response::new())
+}
+
+#[cfg_attr(not(feature = "library"), grug::export)]
+pub fn receive(ctx: MutableCtx) -> StdResult<Response> {
+    // Coins sent to this contract become liquidity that margin accounts can
+    // borrow. Record them as the sender's deposit, which they can withdraw.
+    //
+    // Note: for now, depositors don't earn any interest.
+    DEPOSITS.update(ctx.storage, ctx.sender, |maybe_deposits| -> StdResult<_> {
+        let mut deposits = maybe_deposits.unwrap_or_default();
+
+        for coin in ctx.funds {
+            deposits.insert(coin)?;
+        }
+
+        Ok(Some(deposits))
+    })?;
+
+    Ok(Response::new())
+}
+
+#[cfg_attr(not(feature = "library"), grug::export)]
+pub fn execute(ctx: MutableCtx, msg: ExecuteMsg) -> anyhow::Result<Response> {
+    match msg {
+        ExecuteMsg::Borrow { coins } => borrow(ctx, coins),
+        ExecuteMsg::Repay {} => repay(ctx),
+        ExecuteMsg::Withdraw { coins } => withdraw(ctx, coins),
+    }
+}
+
+fn borrow(ctx: MutableCtx, coins: Coins) -> anyhow::Result<Response> {
+    ensure!(coins.is_non_empty(), "can't borrow zero coins");
+    ensure!(ctx.funds.is_empty(), "unexpected funds: {}", ctx.funds);
+
+    // Only margin accounts can borrow. We find the sender's account type by
+    // raw querying the account factory.
+    let account_factory = ctx.querier.query_app_config(ACCOUNT_FACTORY_KEY)?;
+    let account: Account = ctx
+        .querier
+        .query_wasm_raw(account_factory, ACCOUNTS.path(ctx.sender))?
+        .ok_or_else(|| anyhow!("sender {} isn't an account", ctx.sender))?
+        .deserialize_borsh()?;
+
+    ensure!(
+        matches!(account.params, AccountParams::Margin(_)),
+        "only margin accounts can borrow"
+    );
+
+    // Record the debts.
+    DEBTS.update(ctx.storage, ctx.sender, |maybe_debts| -> StdResult<_> {
+        let mut debts = maybe_debts.unwrap_or_default();
+
+        for coin in coins.clone() {
+            debts.insert(coin)?;
+        }
+
+        Ok(Some(debts))
+    })?;
+
+    Ok(Response::new().add_message(Message::transfer(ctx.sender, coins)?))
+}
+
+fn repay(ctx: MutableCtx) -> anyhow::Result<Response> {
+    let mut debts = DEBTS.may_load(ctx.storage, ctx.sender)?.unwrap_or_default();
+    let mut refunds = Coins::new();
+
+    // For each coin sent, repay as much of the debt of that denom as possible,
+    // and refund the rest.
+    for coin in ctx.funds {
+        let debt = debts.amount_of(&coin.denom);
+        let repaid = cmp::min(coin.amount, debt);
+
+        if repaid.is_non_zero() {
+            debts.deduct(Coin::new(coin.denom.clone(), repaid)?)?;
+        }
+
+        refunds.insert(Coin::new(coin.denom, coin.amount - repaid)?)?;
+    }
+
+    if debts.is_empty() {
+        DEBTS.remove(ctx.storage, ctx.sender);
+    } else {
+        DEBTS.save(ctx.storage, ctx.sender, &debts)?;
+    }
+
+    Ok(Response::new().may_add_message(if refunds.is_non_empty() {
+        Some(Message::transfer(ctx.sender, refunds)?)
+    } else {
+        None
+    }))
+}
+
+fn withdraw(ctx: MutableCtx, coins: Coins) -> anyhow::Result<Response> {
+    ensure!(coins.is_non_empty(), "can't withdraw zero coins");
+    ensure!(ctx.funds.is_empty(), "unexpected funds: {}", ctx.funds);
+
+    let mut deposits = DEPOSITS
+        .may_load(ctx.storage, ctx.sender)?
+        .unwrap_or_default();
+
+    for coin in coins.clone() {
+        let deposit = deposits.amount_of(&coin.denom);
+
+        ensure!(
+            coin.amount <= deposit,
+            "insufficient deposit for denom `{}`: {} < {}",
+            coin.denom,
+            deposit,
+            coin.amount
+        );
+
+        deposits.deduct(coin)?;
+    }
+
+    if deposits.is_empty() {
+        DEPOSITS.remove(ctx.storage, ctx.sender);
+    } else {
+        DEPOSITS.save(ctx.storage, ctx.sender, &deposits)?;
+    }
+
+    // If the coins have been lent out, the contract doesn't have enough
+    // balance, and the transfer fails.
+    Ok(Response::new().add_message(Message::transfer(ctx.sender, coins)?))
+}
@@ -0,0 +1,62 @@
+use {
+    crate::{DEBTS, DEPOSITS},
+    dango_types::lending::QueryMsg,
+    grug::{Addr, Bound, Coins, ImmutableCtx, Json, JsonSerExt, Order, StdResult, Storage},
+    std::collections::BTreeMap,
+};
+
+const DEFAULT_PAGE_LIMIT: u32 = 30;
+
+#[cfg_attr(not(feature = "library"), grug::export)]
+pub fn query(ctx: ImmutableCtx, msg: QueryMsg) -> StdResult<Json> {
+    match msg {
+        QueryMsg::Debt { account } => query_debt(ctx.storage, account)?.to_json_value(),
+        QueryMsg::Debts { start_after, limit } => {
+            query_debts(ctx.storage, start_after, limit)?.to_json_value()
+        },
+        QueryMsg::Deposit { account } => query_deposit(ctx.storage, account)?.to_json_value(),
+        QueryMsg::Deposits { start_after, limit } => {
+            query_deposits(ctx.storage, start_after, limit)?.to_json_value()
+        },
+    }
+}
+
+fn query_debt(storage: &dyn Storage, account: Addr) -> StdResult<Coins> {
+    DEBTS
+        .may_load(storage, account)
+        .map(|maybe_debts| maybe_debts.unwrap_or_default())
+}
+
+fn query_debts(
+    storage: &dyn Storage,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<BTreeMap<Addr, Coins>> {
+    let start = start_after.map(Bound::Exclusive);
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT) as usize;
+
+    DEBTS
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect()
+}
+
+fn query_deposit(storage: &dyn Storage, account: Addr) -> StdResult<Coins> {
+    DEPOSITS
+        .may_load(storage, account)
+        .map(|maybe_deposits| maybe_deposits.unwrap_or_default())
+}
+
+fn query_deposits(
+    storage: &dyn Storage,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<BTreeMap<Addr, Coins>> {
+    let start = start_after.map(Bound::Exclusive);
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT) as usize;
+
+    DEPOSITS
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect()
+}
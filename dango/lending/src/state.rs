@@ -0,0 +1,7 @@
+use grug::{Addr, Coins, Map};
+
+/// Outstanding debts of each margin account.
+pub const DEBTS: Map<Addr, Coins> = Map::new("debt");
+
+/// Coins deposited by each account, which they can withdraw.
+pub const DEPOSITS: Map<Addr, Coins> = Map::new("deposit");
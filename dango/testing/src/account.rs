@@ -1,7 +1,10 @@
 use {
     dango_types::{
         account_factory::{NewUserSalt, Username},
-        auth::{Credential, Key, Metadata, SignDoc},
+        auth::{
+            Credential, Key, Metadata, SessionCredential, SessionInfo, SessionSignDoc, SignDoc,
+            Unordered,
+        },
     },
     grug::{
         Addr, Addressable, Defined, Hash160, Hash256, HashExt, Json, JsonSerExt, MaybeDefined,
@@ -100,6 +103,65 @@ impl TestAccount<Undefined<Addr>> {
     }
 }
 
+impl TestAccount {
+    /// Generate a new random key pair for the same username and address, such
+    /// as for testing key rotation.
+    ///
+    /// The new key must be associated with the username in the account factory
+    /// before it can be used to sign transactions.
+    pub fn new_key(&self) -> StdResult<TestAccount> {
+        let new = TestAccount::new_random(&self.username.to_string())?;
+
+        Ok(TestAccount {
+            username: new.username,
+            key: new.key,
+            key_hash: new.key_hash,
+            sequence: self.sequence,
+            sk: new.sk,
+            address: Defined::new(self.address()),
+        })
+    }
+
+    /// Sign an unordered transaction, which is protected from replay by the
+    /// given nonce and timeout instead of the sequence number.
+    ///
+    /// The internally tracked sequence isn't incremented.
+    pub fn sign_unordered_transaction(
+        &self,
+        msgs: Vec<Message>,
+        chain_id: &str,
+        gas_limit: u64,
+        unordered: Unordered,
+    ) -> StdResult<Tx> {
+        let sign_bytes = SignDoc {
+            messages: msgs.clone(),
+            chain_id: chain_id.to_string(),
+            sequence: 0,
+            unordered: Some(unordered.clone()),
+        }
+        .to_json_vec()?;
+
+        let signature: Signature = self.sk.sign(&sign_bytes);
+
+        let data = Metadata {
+            username: self.username.clone(),
+            key_hash: self.key_hash,
+            sequence: 0,
+            unordered: Some(unordered),
+        };
+
+        let credential = Credential::Secp256k1(signature.to_bytes().to_vec().try_into()?);
+
+        Ok(Tx {
+            sender: self.address(),
+            gas_limit,
+            msgs,
+            data: data.to_json_value()?,
+            credential: credential.to_json_value()?,
+        })
+    }
+}
+
 impl<T> TestAccount<T>
 where
     T: MaybeDefined<Inner = Addr>,
@@ -114,6 +176,7 @@ where
             messages: msgs.clone(),
             chain_id: chain_id.to_string(),
             sequence,
+            unordered: None,
         }
         .to_json_vec()?;
 
@@ -125,12 +188,37 @@ where
             username: self.username.clone(),
             key_hash: self.key_hash,
             sequence,
+            unordered: None,
         };
 
         let credential = Credential::Secp256k1(signature.to_bytes().to_vec().try_into()?);
 
         Ok((data, credential))
     }
+
+    /// Sign a session's info with this account's key, authorizing the session
+    /// key to sign transactions on behalf of this user from the given account,
+    /// within the session's scope.
+    pub fn sign_session(
+        &self,
+        session_info: &SessionInfo,
+        sender: Addr,
+        chain_id: &str,
+    ) -> StdResult<Credential> {
+        let sign_bytes = SessionSignDoc {
+            username: self.username.clone(),
+            sender,
+            chain_id: chain_id.to_string(),
+            session_info: session_info.clone(),
+        }
+        .to_json_vec()?;
+
+        let signature: Signature = self.sk.sign(&sign_bytes);
+
+        Ok(Credential::Secp256k1(
+            signature.to_bytes().to_vec().try_into()?,
+        ))
+    }
 }
 
 impl Addressable for TestAccount<Defined<Addr>> {
@@ -202,7 +290,7 @@ impl Signer for Factory {
 pub struct Safe<'a> {
     address: Addr,
     signer: Option<&'a TestAccount>,
-    sequence: u32,
+    pub sequence: u32,
 }
 
 impl<'a> Safe<'a> {
@@ -252,3 +340,134 @@ impl<'a> Signer for Safe<'a> {
         })
     }
 }
+
+// ---------------------------------- margin -----------------------------------
+
+pub struct Margin<'a> {
+    address: Addr,
+    signer: Option<&'a TestAccount>,
+    sequence: u32,
+}
+
+impl<'a> Margin<'a> {
+    pub fn new(address: Addr) -> Self {
+        Self {
+            address,
+            signer: None,
+            sequence: 0,
+        }
+    }
+}
+
+impl<'a> Margin<'a> {
+    pub fn with_signer(&mut self, signer: &'a TestAccount) -> &mut Self {
+        self.signer = Some(signer);
+        self
+    }
+}
+
+impl<'a> Addressable for Margin<'a> {
+    fn address(&self) -> Addr {
+        self.address
+    }
+}
+
+impl<'a> Signer for Margin<'a> {
+    fn sign_transaction(
+        &mut self,
+        msgs: Vec<Message>,
+        chain_id: &str,
+        gas_limit: u64,
+    ) -> StdResult<Tx> {
+        let (data, credential) = self
+            .signer
+            .expect("[Margin]: signer not set")
+            .sign_transaction_with_sequence(msgs.clone(), chain_id, self.sequence)?;
+
+        // Increment the internally tracked sequence.
+        self.sequence += 1;
+
+        Ok(Tx {
+            sender: self.address,
+            gas_limit,
+            msgs,
+            data: data.to_json_value()?,
+            credential: credential.to_json_value()?,
+        })
+    }
+}
+
+// ---------------------------------- session ----------------------------------
+
+/// A signer that signs transactions for a user's account using a session key.
+pub struct Session<'a> {
+    user: &'a TestAccount,
+    session_key: TestAccount,
+    session_info: SessionInfo,
+    pub sequence: u32,
+}
+
+impl<'a> Session<'a> {
+    /// Create a new session for the user. The session key must be the key in
+    /// the session info.
+    pub fn new(user: &'a TestAccount, session_key: TestAccount, session_info: SessionInfo) -> Self {
+        Self {
+            user,
+            session_key,
+            session_info,
+            sequence: user.sequence,
+        }
+    }
+}
+
+impl<'a> Addressable for Session<'a> {
+    fn address(&self) -> Addr {
+        self.user.address()
+    }
+}
+
+impl<'a> Signer for Session<'a> {
+    fn sign_transaction(
+        &mut self,
+        msgs: Vec<Message>,
+        chain_id: &str,
+        gas_limit: u64,
+    ) -> StdResult<Tx> {
+        let sender = self.user.address();
+        let authorization = self
+            .user
+            .sign_session(&self.session_info, sender, chain_id)?;
+
+        let (_, session_signature) = self.session_key.sign_transaction_with_sequence(
+            msgs.clone(),
+            chain_id,
+            self.sequence,
+        )?;
+
+        // The transaction is sent on behalf of the user, so the metadata
+        // identifies the user's key, rather than the session key.
+        let data = Metadata {
+            username: self.user.username.clone(),
+            key_hash: self.user.key_hash,
+            sequence: self.sequence,
+            unordered: None,
+        };
+
+        let credential = Credential::Session(SessionCredential {
+            session_info: self.session_info.clone(),
+            authorization: Box::new(authorization),
+            session_signature: Box::new(session_signature),
+        });
+
+        // Increment the internally tracked sequence.
+        self.sequence += 1;
+
+        Ok(Tx {
+            sender: self.user.address(),
+            gas_limit,
+            msgs,
+            data: data.to_json_value()?,
+            credential: credential.to_json_value()?,
+        })
+    }
+}
@@ -98,6 +98,14 @@ pub fn setup_test() -> anyhow::Result<(TestSuite, Accounts, Codes<ContractWrappe
         .with_query(Box::new(dango_account_spot::query))
         .build();
 
+    let account_margin = ContractBuilder::new(Box::new(dango_account_margin::instantiate))
+        .with_authenticate(Box::new(dango_account_margin::authenticate))
+        .with_backrun(Box::new(dango_account_margin::backrun))
+        .with_receive(Box::new(dango_account_margin::receive))
+        .with_execute(Box::new(dango_account_margin::execute))
+        .with_query(Box::new(dango_account_margin::query))
+        .build();
+
     let account_safe = ContractBuilder::new(Box::new(dango_account_safe::instantiate))
         .with_authenticate(Box::new(dango_account_safe::authenticate))
         .with_receive(Box::new(dango_account_safe::receive))
@@ -120,6 +128,12 @@ pub fn setup_test() -> anyhow::Result<(TestSuite, Accounts, Codes<ContractWrappe
         .with_execute(Box::new(dango_ibc_transfer::execute))
         .build();
 
+    let lending = ContractBuilder::new(Box::new(dango_lending::instantiate))
+        .with_execute(Box::new(dango_lending::execute))
+        .with_receive(Box::new(dango_lending::receive))
+        .with_query(Box::new(dango_lending::query))
+        .build();
+
     let taxman = ContractBuilder::new(Box::new(dango_taxman::instantiate))
         .with_execute(Box::new(dango_taxman::execute))
         .with_query(Box::new(dango_taxman::query))
@@ -135,10 +149,12 @@ pub fn setup_test() -> anyhow::Result<(TestSuite, Accounts, Codes<ContractWrappe
     let codes = Codes {
         account_factory,
         account_spot,
+        account_margin,
         account_safe,
         amm,
         bank,
         ibc_transfer,
+        lending,
         taxman,
         token_factory,
     };
@@ -1,11 +1,14 @@
 use {
     dango_testing::setup_test,
     dango_types::amm::{
-        self, ExecuteMsg, FeeRate, Pool, PoolParams, QueryPoolRequest, QueryPoolsRequest,
-        XykParams, XykPool, MINIMUM_LIQUIDITY,
+        self, AmplificationRamp, ConcentratedParams, ConcentratedPool, ExecuteMsg, FeeRate, Pool,
+        PoolId, PoolParams, QueryBestRouteRequest, QueryPoolRequest, QueryPoolsRequest,
+        QuerySimulateExactOutputRequest, QuerySimulateRequest, QueryTwapRequest, RouteOutcome,
+        StableParams, StablePool, XykParams, XykPool, MINIMUM_LIQUIDITY,
     },
     grug::{
-        btree_map, Coin, CoinPair, Coins, Denom, Message, ResultExt, Udec128, Uint128, UniqueVec,
+        btree_map, Addr, Coin, CoinPair, Coins, Denom, Duration, Fraction, Message, NumberConst,
+        ResultExt, TestSuite, Udec128, Uint128, UniqueVec,
     },
     std::{str::FromStr, sync::LazyLock},
 };
@@ -428,3 +431,804 @@ fn amm() {
             shares: Uint128::new(1_311_793_604_756),
         }));
 }
+
+#[test]
+fn concentrated_pool() {
+    let (mut suite, mut accounts, _, contracts) = setup_test().unwrap();
+
+    // ----------------------------- Pool creation -----------------------------
+
+    // Create a concentrated pool and an XYK pool, both with the same ATOM-USDC
+    // liquidity, at a price of 10 USDC per ATOM.
+    suite
+        .send_messages(&mut accounts.relayer, vec![
+            // pool 1: concentrated
+            Message::execute(
+                contracts.amm,
+                &ExecuteMsg::CreatePool(PoolParams::Concentracted(concentrated_params())),
+                Coins::new_unchecked(btree_map! {
+                    ATOM.clone() => Uint128::new(100_000_000_000),
+                    // liquidity + pool creation fee
+                    USDC.clone() => Uint128::new(1_000_010_000_000),
+                }),
+            )
+            .unwrap(),
+            // pool 2: XYK
+            Message::execute(
+                contracts.amm,
+                &ExecuteMsg::CreatePool(PoolParams::Xyk(XykParams {
+                    liquidity_fee_rate: FeeRate::new_unchecked(Udec128::new_bps(26)),
+                })),
+                Coins::new_unchecked(btree_map! {
+                    ATOM.clone() => Uint128::new(100_000_000_000),
+                    // liquidity + pool creation fee
+                    USDC.clone() => Uint128::new(1_000_010_000_000),
+                }),
+            )
+            .unwrap(),
+        ])
+        .unwrap()
+        .result
+        .should_succeed();
+
+    // The pool is balanced at the initial price, so the initial shares are the
+    // geometric mean of the deposit, same as the XYK pool:
+    // floor(sqrt(100,000,000,000 * 1,000,000,000,000)) = 316,227,766,016
+    let pool = query_concentrated_pool(&suite, contracts.amm, 1);
+
+    assert_eq!(pool.shares, Uint128::new(316_227_766_016));
+    assert_eq!(pool.price_scale, Udec128::new_percent(10));
+    assert_eq!(pool.price_oracle, Udec128::new_percent(10));
+    assert_eq!(pool.virtual_price, Udec128::ONE);
+    assert_eq!(pool.xcp_profit, Udec128::ONE);
+
+    // The creator receives the shares minus the minimum liquidity, and the
+    // fee collector the pool creation fees.
+    suite
+        .query_balance(&accounts.relayer, LP_1.clone())
+        .should_succeed_and_equal(Uint128::new(316_227_765_016));
+    suite
+        .query_balance(&accounts.fee_recipient, USDC.clone())
+        .should_succeed_and_equal(Uint128::new(20_000_000));
+
+    // Invalid parameters are rejected.
+    suite
+        .send_message(
+            &mut accounts.relayer,
+            Message::execute(
+                contracts.amm,
+                &ExecuteMsg::CreatePool(PoolParams::Concentracted(ConcentratedParams {
+                    mid_fee: FeeRate::new_unchecked(Udec128::new_bps(50)),
+                    ..concentrated_params()
+                })),
+                Coins::new_unchecked(btree_map! {
+                    ATOM.clone() => Uint128::new(100_000_000_000),
+                    USDC.clone() => Uint128::new(1_000_010_000_000),
+                }),
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .result
+        .should_fail_with_error("mid fee can't be greater than out fee");
+
+    // --------------------------------- Swap ----------------------------------
+
+    let input = Coin::new(USDC.clone(), Uint128::new(1_000_000_000)).unwrap();
+
+    let outcome = suite
+        .query_wasm_smart(contracts.amm, QuerySimulateRequest {
+            input: input.clone(),
+            route: UniqueVec::new_unchecked(vec![1]),
+        })
+        .unwrap();
+    let xyk_outcome = suite
+        .query_wasm_smart(contracts.amm, QuerySimulateRequest {
+            input: input.clone(),
+            route: UniqueVec::new_unchecked(vec![2]),
+        })
+        .unwrap();
+
+    // With the liquidity concentrated around the current price, the trader
+    // gets a better price than from the XYK pool with the same liquidity and a
+    // similar fee rate, but no better than the spot price.
+    assert!(outcome.output.amount > xyk_outcome.output.amount);
+    assert!(outcome.output.amount < Uint128::new(100_000_000));
+
+    suite
+        .execute(
+            &mut accounts.owner,
+            contracts.amm,
+            &ExecuteMsg::Swap {
+                route: UniqueVec::new_unchecked(vec![1]),
+                minimum_output: None,
+            },
+            input,
+        )
+        .unwrap();
+
+    // Check the trader has received the simulated output, and the fee
+    // collector the protocol fee.
+    suite
+        .query_balance(&accounts.owner, ATOM.clone())
+        .should_succeed_and_equal(outcome.output.amount);
+    suite
+        .query_balance(&accounts.fee_recipient, ATOM.clone())
+        .should_succeed_and_equal(outcome.protocol_fee.amount);
+
+    // Check the pool state. The liquidity fee stays in the pool, so the
+    // virtual price has increased.
+    let pool = query_concentrated_pool(&suite, contracts.amm, 1);
+
+    assert_eq!(
+        pool.liquidity,
+        CoinPair::new_unchecked(
+            Coin {
+                denom: ATOM.clone(),
+                amount: Uint128::new(100_000_000_000)
+                    - outcome.output.amount
+                    - outcome.protocol_fee.amount,
+            },
+            Coin {
+                denom: USDC.clone(),
+                amount: Uint128::new(1_001_000_000_000),
+            },
+        )
+    );
+    assert!(pool.virtual_price > Udec128::ONE);
+    assert_eq!(pool.xcp_profit, pool.virtual_price);
+
+    // The last price is that of the swap, which is lower than the initial
+    // price of USDC, since USDC was sold.
+    assert!(pool.last_price < Udec128::new_percent(10));
+
+    // Swapping the output back returns less than the original input.
+    suite
+        .query_wasm_smart(contracts.amm, QuerySimulateRequest {
+            input: Coin::new(ATOM.clone(), outcome.output.amount).unwrap(),
+            route: UniqueVec::new_unchecked(vec![1]),
+        })
+        .should_succeed_and(|outcome| outcome.output.amount < Uint128::new(1_000_000_000));
+
+    // ------------------------------ Price oracle -----------------------------
+
+    // Make another swap in a later block. The price oracle moves towards the
+    // last price before this swap.
+    let last_price = pool.last_price;
+
+    suite
+        .execute(
+            &mut accounts.owner,
+            contracts.amm,
+            &ExecuteMsg::Swap {
+                route: UniqueVec::new_unchecked(vec![1]),
+                minimum_output: None,
+            },
+            Coin::new(USDC.clone(), Uint128::new(1_000)).unwrap(),
+        )
+        .unwrap();
+
+    let pool = query_concentrated_pool(&suite, contracts.amm, 1);
+
+    assert!(pool.price_oracle < Udec128::new_percent(10));
+    assert!(pool.price_oracle > last_price);
+
+    // -------------------- Liquidity provision and withdrawal -----------------
+
+    // Provide liquidity proportional to the pool, then withdraw it right away.
+    // The liquidity provider should not profit.
+    let atom_before = suite
+        .query_balance(&accounts.relayer, ATOM.clone())
+        .unwrap();
+    let usdc_before = suite
+        .query_balance(&accounts.relayer, USDC.clone())
+        .unwrap();
+
+    suite
+        .execute(
+            &mut accounts.relayer,
+            contracts.amm,
+            &ExecuteMsg::ProvideLiquidity {
+                pool_id: 1,
+                minimum_output: None,
+            },
+            Coins::new_unchecked(btree_map! {
+                ATOM.clone() => *pool.liquidity.first().amount / Uint128::new(100),
+                USDC.clone() => *pool.liquidity.second().amount / Uint128::new(100),
+            }),
+        )
+        .unwrap();
+
+    let shares_minted = query_concentrated_pool(&suite, contracts.amm, 1).shares - pool.shares;
+
+    suite
+        .query_balance(&accounts.relayer, LP_1.clone())
+        .should_succeed_and_equal(Uint128::new(316_227_765_016) + shares_minted);
+
+    suite
+        .execute(
+            &mut accounts.relayer,
+            contracts.amm,
+            &ExecuteMsg::WithdrawLiquidity { pool_id: 1 },
+            Coin::new(LP_1.clone(), shares_minted).unwrap(),
+        )
+        .unwrap();
+
+    suite
+        .query_balance(&accounts.relayer, ATOM.clone())
+        .should_succeed_and(|atom_after| atom_after < atom_before);
+    suite
+        .query_balance(&accounts.relayer, USDC.clone())
+        .should_succeed_and(|usdc_after| usdc_after < usdc_before);
+
+    // ------------------------------- Repegging -------------------------------
+
+    // Make a big swap, which moves the price far from the price scale. After
+    // a few more blocks with swaps, the price oracle follows the price, and
+    // the pool has made enough profit to move the price scale towards it.
+    suite
+        .execute(
+            &mut accounts.owner,
+            contracts.amm,
+            &ExecuteMsg::Swap {
+                route: UniqueVec::new_unchecked(vec![1]),
+                minimum_output: None,
+            },
+            Coin::new(USDC.clone(), Uint128::new(50_000_000_000)).unwrap(),
+        )
+        .unwrap();
+
+    for _ in 0..3 {
+        suite
+            .execute(
+                &mut accounts.owner,
+                contracts.amm,
+                &ExecuteMsg::Swap {
+                    route: UniqueVec::new_unchecked(vec![1]),
+                    minimum_output: None,
+                },
+                Coin::new(USDC.clone(), Uint128::new(1_000_000)).unwrap(),
+            )
+            .unwrap();
+    }
+
+    let pool = query_concentrated_pool(&suite, contracts.amm, 1);
+
+    assert!(pool.price_scale < Udec128::new_percent(10));
+    assert!(pool.price_scale > pool.price_oracle);
+
+    // Repegging spends part of the profit, but never more than half of it.
+    assert!(pool.virtual_price > Udec128::ONE);
+    assert!(pool.xcp_profit > pool.virtual_price);
+}
+
+#[test]
+fn routing() {
+    let (mut suite, mut accounts, _, contracts) = setup_test().unwrap();
+
+    // Create three pools, all at the price 1 ATOM = 10 OSMO = 10 USDC. The
+    // OSMO-USDC pool has much less liquidity than the other two.
+    suite
+        .send_messages(
+            &mut accounts.relayer,
+            [
+                // pool 1: ATOM-OSMO
+                btree_map! {
+                    ATOM.clone() => Uint128::new(100_000_000_000),
+                    OSMO.clone() => Uint128::new(1_000_000_000_000),
+                    // pool creation fee
+                    USDC.clone() => Uint128::new(10_000_000),
+                },
+                // pool 2: ATOM-USDC
+                btree_map! {
+                    ATOM.clone() => Uint128::new(100_000_000_000),
+                    // liquidity + pool creation fee
+                    USDC.clone() => Uint128::new(1_000_010_000_000),
+                },
+                // pool 3: OSMO-USDC
+                btree_map! {
+                    OSMO.clone() => Uint128::new(1_000_000_000),
+                    // liquidity + pool creation fee
+                    USDC.clone() => Uint128::new(1_010_000_000),
+                },
+            ]
+            .into_iter()
+            .map(|funds| {
+                Message::execute(
+                    contracts.amm,
+                    &ExecuteMsg::CreatePool(PoolParams::Xyk(XykParams {
+                        liquidity_fee_rate: FeeRate::new_unchecked(Udec128::new_bps(20)),
+                    })),
+                    Coins::new_unchecked(funds),
+                )
+                .unwrap()
+            })
+            .collect(),
+        )
+        .unwrap()
+        .result
+        .should_succeed();
+
+    // ------------------------------ Best route -------------------------------
+
+    let input = Coin::new(USDC.clone(), Uint128::new(100_000_000)).unwrap();
+
+    let direct = suite
+        .query_wasm_smart(contracts.amm, QuerySimulateRequest {
+            input: input.clone(),
+            route: UniqueVec::new_unchecked(vec![3]),
+        })
+        .unwrap();
+    let indirect = suite
+        .query_wasm_smart(contracts.amm, QuerySimulateRequest {
+            input: input.clone(),
+            route: UniqueVec::new_unchecked(vec![2, 1]),
+        })
+        .unwrap();
+
+    // Despite charging the liquidity fee twice, the route through ATOM gives
+    // more output, thanks to the deeper liquidity.
+    assert!(indirect.output.amount > direct.output.amount);
+
+    suite
+        .query_wasm_smart(contracts.amm, QueryBestRouteRequest {
+            input: input.clone(),
+            ask_denom: OSMO.clone(),
+        })
+        .should_succeed_and_equal(RouteOutcome {
+            route: UniqueVec::new_unchecked(vec![2, 1]),
+            outcome: indirect,
+        });
+
+    // There is no route to a denom that no pool has.
+    suite
+        .query_wasm_smart(contracts.amm, QueryBestRouteRequest {
+            input,
+            ask_denom: Denom::from_str("ufoo").unwrap(),
+        })
+        .should_fail_with_error("no route found");
+
+    // --------------------------- Exact output swap ---------------------------
+
+    let output = Coin::new(OSMO.clone(), Uint128::new(50_000_000)).unwrap();
+    let route = UniqueVec::new_unchecked(vec![2, 1]);
+
+    let input = suite
+        .query_wasm_smart(contracts.amm, QuerySimulateExactOutputRequest {
+            output: output.clone(),
+            route: route.clone(),
+        })
+        .unwrap();
+
+    assert_eq!(input.denom, *USDC);
+
+    // Sending less than the input needed fails.
+    suite
+        .send_message(
+            &mut accounts.owner,
+            Message::execute(
+                contracts.amm,
+                &ExecuteMsg::SwapExactOutput {
+                    route: route.clone(),
+                    output: output.clone(),
+                },
+                Coin::new(USDC.clone(), input.amount - Uint128::ONE).unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .result
+        .should_fail_with_error("excessive swap input");
+
+    // Send more than the input needed. The excess is refunded.
+    let usdc_before = suite.query_balance(&accounts.owner, USDC.clone()).unwrap();
+
+    suite
+        .execute(
+            &mut accounts.owner,
+            contracts.amm,
+            &ExecuteMsg::SwapExactOutput {
+                route,
+                output: output.clone(),
+            },
+            Coin::new(USDC.clone(), input.amount + Uint128::new(1_000_000)).unwrap(),
+        )
+        .unwrap();
+
+    suite
+        .query_balance(&accounts.owner, USDC.clone())
+        .should_succeed_and_equal(usdc_before - input.amount);
+    suite
+        .query_balance(&accounts.owner, OSMO.clone())
+        .should_succeed_and(|osmo| osmo >= output.amount);
+}
+
+#[test]
+fn stable_pool() {
+    let (mut suite, mut accounts, _, contracts) = setup_test().unwrap();
+
+    // ----------------------------- Pool creation -----------------------------
+
+    // Create a stable pool and an XYK pool, both with the same OSMO-USDC
+    // liquidity. For the purpose of this test, we pretend OSMO is a stablecoin
+    // pegged to USDC.
+    suite
+        .send_messages(&mut accounts.relayer, vec![
+            // pool 1: stable
+            Message::execute(
+                contracts.amm,
+                &ExecuteMsg::CreatePool(PoolParams::Stable(StableParams {
+                    amplification: Uint128::new(100),
+                    liquidity_fee_rate: FeeRate::new_unchecked(Udec128::new_bps(4)),
+                })),
+                Coins::new_unchecked(btree_map! {
+                    OSMO.clone() => Uint128::new(100_000_000_000),
+                    // liquidity + pool creation fee
+                    USDC.clone() => Uint128::new(100_010_000_000),
+                }),
+            )
+            .unwrap(),
+            // pool 2: XYK
+            Message::execute(
+                contracts.amm,
+                &ExecuteMsg::CreatePool(PoolParams::Xyk(XykParams {
+                    liquidity_fee_rate: FeeRate::new_unchecked(Udec128::new_bps(4)),
+                })),
+                Coins::new_unchecked(btree_map! {
+                    OSMO.clone() => Uint128::new(100_000_000_000),
+                    // liquidity + pool creation fee
+                    USDC.clone() => Uint128::new(100_010_000_000),
+                }),
+            )
+            .unwrap(),
+        ])
+        .unwrap()
+        .result
+        .should_succeed();
+
+    // The pool is balanced, so the initial shares, which equal the invariant,
+    // are the sum of the deposit.
+    let pool = query_stable_pool(&suite, contracts.amm, 1);
+
+    assert_eq!(pool.shares, Uint128::new(200_000_000_000));
+    assert_eq!(pool.ramp, None);
+
+    suite
+        .query_balance(&accounts.relayer, LP_1.clone())
+        .should_succeed_and_equal(Uint128::new(200_000_000_000) - MINIMUM_LIQUIDITY);
+
+    // --------------------------------- Swap ----------------------------------
+
+    let input = Coin::new(USDC.clone(), Uint128::new(1_000_000_000)).unwrap();
+
+    let outcome = suite
+        .query_wasm_smart(contracts.amm, QuerySimulateRequest {
+            input: input.clone(),
+            route: UniqueVec::new_unchecked(vec![1]),
+        })
+        .unwrap();
+    let xyk_outcome = suite
+        .query_wasm_smart(contracts.amm, QuerySimulateRequest {
+            input: input.clone(),
+            route: UniqueVec::new_unchecked(vec![2]),
+        })
+        .unwrap();
+
+    // The stable pool's price stays close to 1:1, so the trader loses much
+    // less to slippage than in the XYK pool with the same liquidity and fee
+    // rate, but still can't get more than the input.
+    assert!(outcome.output.amount > xyk_outcome.output.amount);
+    assert!(outcome.output.amount < input.amount);
+
+    // -------------------------- Amplification ramp ---------------------------
+
+    // Only the chain owner can ramp the amplification.
+    suite
+        .send_message(
+            &mut accounts.relayer,
+            Message::execute(
+                contracts.amm,
+                &ExecuteMsg::RampAmplification {
+                    pool_id: 1,
+                    amplification: Uint128::new(1_000),
+                    end_time: suite.block.timestamp + Duration::from_seconds(2 * 24 * 60 * 60),
+                },
+                Coins::new(),
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .result
+        .should_fail_with_error("only the chain owner can ramp amplification");
+
+    // The ramp must not be too short.
+    suite
+        .send_message(
+            &mut accounts.owner,
+            Message::execute(
+                contracts.amm,
+                &ExecuteMsg::RampAmplification {
+                    pool_id: 1,
+                    amplification: Uint128::new(1_000),
+                    end_time: suite.block.timestamp + Duration::from_seconds(60 * 60),
+                },
+                Coins::new(),
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .result
+        .should_fail_with_error("ramp is too short");
+
+    // The amplification must not change too much at once.
+    suite
+        .send_message(
+            &mut accounts.owner,
+            Message::execute(
+                contracts.amm,
+                &ExecuteMsg::RampAmplification {
+                    pool_id: 1,
+                    amplification: Uint128::new(10_000),
+                    end_time: suite.block.timestamp + Duration::from_seconds(2 * 24 * 60 * 60),
+                },
+                Coins::new(),
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .result
+        .should_fail_with_error("amplification change is too big");
+
+    // Ramp the amplification from 100 to 1,000 over two days, starting from
+    // the next block.
+    let start_time = suite.block.timestamp + suite.block_time;
+    let end_time = start_time + Duration::from_seconds(2 * 24 * 60 * 60);
+
+    suite
+        .execute(
+            &mut accounts.owner,
+            contracts.amm,
+            &ExecuteMsg::RampAmplification {
+                pool_id: 1,
+                amplification: Uint128::new(1_000),
+                end_time,
+            },
+            Coins::new(),
+        )
+        .unwrap();
+
+    let pool = query_stable_pool(&suite, contracts.amm, 1);
+
+    assert_eq!(pool.params.amplification, Uint128::new(1_000));
+    assert_eq!(
+        pool.ramp,
+        Some(AmplificationRamp {
+            initial_amplification: Uint128::new(100),
+            start_time,
+            end_time,
+        })
+    );
+
+    // Another ramp can't start while one is ongoing.
+    suite
+        .send_message(
+            &mut accounts.owner,
+            Message::execute(
+                contracts.amm,
+                &ExecuteMsg::RampAmplification {
+                    pool_id: 1,
+                    amplification: Uint128::new(500),
+                    end_time,
+                },
+                Coins::new(),
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .result
+        .should_fail_with_error("amplification is already being ramped");
+
+    // Stop the ramp halfway. The amplification stays at the halfway value:
+    // 100 + (1,000 - 100) / 2 = 550
+    suite.block_time = start_time + Duration::from_seconds(24 * 60 * 60) - suite.block.timestamp;
+
+    suite
+        .execute(
+            &mut accounts.owner,
+            contracts.amm,
+            &ExecuteMsg::StopRampAmplification { pool_id: 1 },
+            Coins::new(),
+        )
+        .unwrap();
+
+    suite.block_time = Duration::from_millis(250);
+
+    let pool = query_stable_pool(&suite, contracts.amm, 1);
+
+    assert_eq!(pool.params.amplification, Uint128::new(550));
+    assert_eq!(pool.ramp, None);
+
+    // With a higher amplification, the price is flatter around the balance
+    // point, so the same swap outputs more.
+    let amplified_outcome = suite
+        .query_wasm_smart(contracts.amm, QuerySimulateRequest {
+            input: input.clone(),
+            route: UniqueVec::new_unchecked(vec![1]),
+        })
+        .unwrap();
+
+    assert!(amplified_outcome.output.amount > outcome.output.amount);
+
+    suite
+        .execute(
+            &mut accounts.owner,
+            contracts.amm,
+            &ExecuteMsg::Swap {
+                route: UniqueVec::new_unchecked(vec![1]),
+                minimum_output: None,
+            },
+            input,
+        )
+        .unwrap();
+
+    suite
+        .query_balance(&accounts.owner, OSMO.clone())
+        .should_succeed_and_equal(amplified_outcome.output.amount);
+}
+
+#[test]
+fn twap() {
+    let (mut suite, mut accounts, _, contracts) = setup_test().unwrap();
+
+    // Create an ATOM-USDC pool at a price of 10 USDC per ATOM, i.e. 0.1 ATOM
+    // per USDC.
+    suite
+        .execute(
+            &mut accounts.relayer,
+            contracts.amm,
+            &ExecuteMsg::CreatePool(PoolParams::Xyk(XykParams {
+                liquidity_fee_rate: FeeRate::new_unchecked(Udec128::new_bps(20)),
+            })),
+            Coins::new_unchecked(btree_map! {
+                ATOM.clone() => Uint128::new(100_000_000_000),
+                // liquidity + pool creation fee
+                USDC.clone() => Uint128::new(1_000_010_000_000),
+            }),
+        )
+        .unwrap();
+
+    let creation_time = suite.block.timestamp;
+
+    // Swap in the block 100 seconds after the pool's creation.
+    suite.block_time = Duration::from_seconds(100);
+
+    suite
+        .execute(
+            &mut accounts.owner,
+            contracts.amm,
+            &ExecuteMsg::Swap {
+                route: UniqueVec::new_unchecked(vec![1]),
+                minimum_output: None,
+            },
+            Coin::new(USDC.clone(), Uint128::new(1_000_000_000)).unwrap(),
+        )
+        .unwrap();
+
+    // The swap doesn't affect the TWAP in the block it's performed in, so the
+    // TWAP over the time since the pool's creation is the initial price.
+    suite
+        .query_wasm_smart(contracts.amm, QueryTwapRequest {
+            pool_id: 1,
+            window: suite.block.timestamp - creation_time,
+        })
+        .should_succeed_and(|twap| {
+            twap.price == Udec128::new_percent(10) && twap.inverse_price == Udec128::new(10)
+        });
+
+    // The swap has made USDC cheaper.
+    let price_after_swap = match suite
+        .query_wasm_smart(contracts.amm, QueryPoolRequest { pool_id: 1 })
+        .unwrap()
+    {
+        Pool::Xyk(pool) => Udec128::checked_from_ratio(
+            *pool.liquidity.first().amount,
+            *pool.liquidity.second().amount,
+        )
+        .unwrap(),
+        pool => panic!("expecting an XYK pool, got: {pool:?}"),
+    };
+
+    assert!(price_after_swap < Udec128::new_percent(10));
+
+    // Make another block 100 seconds later.
+    suite.make_empty_block().unwrap();
+
+    // The TWAP over the last 100 seconds is the price after the swap.
+    suite
+        .query_wasm_smart(contracts.amm, QueryTwapRequest {
+            pool_id: 1,
+            window: Duration::from_seconds(100),
+        })
+        .should_succeed_and(|twap| {
+            twap.price == price_after_swap
+                && twap.inverse_price == price_after_swap.checked_inv().unwrap()
+        });
+
+    // The TWAP since the pool's creation is between the two prices.
+    suite
+        .query_wasm_smart(contracts.amm, QueryTwapRequest {
+            pool_id: 1,
+            window: Duration::from_seconds(200),
+        })
+        .should_succeed_and(|twap| {
+            twap.price > price_after_swap && twap.price < Udec128::new_percent(10)
+        });
+
+    // The TWAP over a window that starts between two observations is linearly
+    // interpolated. The price was 0.1 for the first 50 of the last 150 seconds,
+    // so the TWAP is lower than over the last 200 seconds.
+    let twap_200 = suite
+        .query_wasm_smart(contracts.amm, QueryTwapRequest {
+            pool_id: 1,
+            window: Duration::from_seconds(200),
+        })
+        .unwrap();
+    let twap_150 = suite
+        .query_wasm_smart(contracts.amm, QueryTwapRequest {
+            pool_id: 1,
+            window: Duration::from_seconds(150),
+        })
+        .unwrap();
+
+    assert!(twap_150.price > price_after_swap && twap_150.price < twap_200.price);
+
+    // The TWAP can't be queried over a window that starts before the pool's
+    // creation.
+    suite
+        .query_wasm_smart(contracts.amm, QueryTwapRequest {
+            pool_id: 1,
+            window: Duration::from_millis(200_200),
+        })
+        .should_fail_with_error("TWAP window too long");
+
+    // The TWAP window must be non-zero.
+    suite
+        .query_wasm_smart(contracts.amm, QueryTwapRequest {
+            pool_id: 1,
+            window: Duration::from_seconds(0),
+        })
+        .should_fail_with_error("TWAP window must be non-zero");
+}
+
+fn concentrated_params() -> ConcentratedParams {
+    ConcentratedParams {
+        amplification: Uint128::new(400_000),
+        gamma: Udec128::from_str("0.000145").unwrap(),
+        mid_fee: FeeRate::new_unchecked(Udec128::new_bps(26)),
+        out_fee: FeeRate::new_unchecked(Udec128::new_bps(45)),
+        fee_gamma: Udec128::from_str("0.00023").unwrap(),
+        allowed_extra_profit: Udec128::from_str("0.000002").unwrap(),
+        adjustment_step: Udec128::from_str("0.000146").unwrap(),
+        ma_half_time: Duration::from_seconds(1),
+    }
+}
+
+fn query_concentrated_pool(suite: &TestSuite, amm: Addr, pool_id: PoolId) -> ConcentratedPool {
+    match suite
+        .query_wasm_smart(amm, QueryPoolRequest { pool_id })
+        .unwrap()
+    {
+        Pool::Concentrated(pool) => pool,
+        pool => panic!("expecting a concentrated pool, got: {pool:?}"),
+    }
+}
+
+fn query_stable_pool(suite: &TestSuite, amm: Addr, pool_id: PoolId) -> StablePool {
+    match suite
+        .query_wasm_smart(amm, QueryPoolRequest { pool_id })
+        .unwrap()
+    {
+        Pool::Stable(pool) => pool,
+        pool => panic!("expecting a stable pool, got: {pool:?}"),
+    }
+}
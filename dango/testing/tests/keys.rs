@@ -0,0 +1,191 @@
+use {
+    dango_testing::{setup_test, Factory, TestAccount},
+    dango_types::{account_factory, mock_ibc_transfer},
+    grug::{btree_map, Addressable, Coins, HashExt, Message, Op, ResultExt, Uint128},
+};
+
+#[test]
+fn key_rotation() -> anyhow::Result<()> {
+    let (mut suite, mut accounts, codes, contracts) = setup_test()?;
+
+    // Onboard a user.
+    let mut user = TestAccount::new_random("user")?.predict_address(
+        contracts.account_factory,
+        codes.account_spot.to_bytes().hash256(),
+        true,
+    )?;
+
+    suite.execute(
+        &mut accounts.relayer,
+        contracts.ibc_transfer,
+        &mock_ibc_transfer::ExecuteMsg::ReceiveTransfer {
+            recipient: user.address(),
+        },
+        Coins::one("uusdc", 100_000_000)?,
+    )?;
+
+    suite.execute(
+        &mut Factory::new(contracts.account_factory),
+        contracts.account_factory,
+        &account_factory::ExecuteMsg::RegisterUser {
+            username: user.username.clone(),
+            key: user.key,
+            key_hash: user.key_hash,
+        },
+        Coins::new(),
+    )?;
+
+    // The user's only key can't be removed.
+    suite
+        .send_message(
+            &mut user,
+            Message::execute(
+                contracts.account_factory,
+                &account_factory::ExecuteMsg::UpdateKey {
+                    username: user.username.clone(),
+                    key_hash: user.key_hash,
+                    key: Op::Delete,
+                },
+                Coins::new(),
+            )?,
+        )?
+        .result
+        .should_fail_with_error("can't remove the last key of user `user`");
+
+    // Generate a new key for the user.
+    let mut new_user = user.new_key()?;
+
+    // Someone else can't add keys to the user.
+    suite
+        .send_message(
+            &mut accounts.owner,
+            Message::execute(
+                contracts.account_factory,
+                &account_factory::ExecuteMsg::UpdateKey {
+                    username: user.username.clone(),
+                    key_hash: new_user.key_hash,
+                    key: Op::Insert(new_user.key),
+                },
+                Coins::new(),
+            )?,
+        )?
+        .result
+        .should_fail_with_error("sender isn't a single signature account owned by user `user`");
+
+    // The new key can't be used before it's associated with the user.
+    new_user.sequence = user.sequence;
+
+    suite
+        .send_message(
+            &mut new_user,
+            Message::transfer(accounts.owner.address(), Coins::one("uusdc", 100)?)?,
+        )?
+        .result
+        .should_fail_with_error(format!(
+            "key hash {} isn't associated with user `user`",
+            new_user.key_hash
+        ));
+
+    // The user adds the new key, signing with the old key.
+    suite.execute(
+        &mut user,
+        contracts.account_factory,
+        &account_factory::ExecuteMsg::UpdateKey {
+            username: user.username.clone(),
+            key_hash: new_user.key_hash,
+            key: Op::Insert(new_user.key),
+        },
+        Coins::new(),
+    )?;
+
+    suite
+        .query_wasm_smart(
+            contracts.account_factory,
+            account_factory::QueryKeysByUserRequest {
+                username: user.username.clone(),
+            },
+        )
+        .should_succeed_and_equal(btree_map! {
+            user.key_hash => user.key,
+            new_user.key_hash => new_user.key,
+        });
+
+    // The same key can't be added twice.
+    suite
+        .send_message(
+            &mut user,
+            Message::execute(
+                contracts.account_factory,
+                &account_factory::ExecuteMsg::UpdateKey {
+                    username: user.username.clone(),
+                    key_hash: new_user.key_hash,
+                    key: Op::Insert(new_user.key),
+                },
+                Coins::new(),
+            )?,
+        )?
+        .result
+        .should_fail_with_error("is already associated with user `user`");
+
+    // The new key can now be used to sign transactions.
+    new_user.sequence = user.sequence;
+
+    suite.transfer(
+        &mut new_user,
+        accounts.owner.address(),
+        Coins::one("uusdc", 100)?,
+    )?;
+
+    suite
+        .query_balance(&new_user, "uusdc")
+        .should_succeed_and_equal(Uint128::new(100_000_000 - 100));
+
+    // Using the new key, the user removes the old key.
+    suite.execute(
+        &mut new_user,
+        contracts.account_factory,
+        &account_factory::ExecuteMsg::UpdateKey {
+            username: user.username.clone(),
+            key_hash: user.key_hash,
+            key: Op::Delete,
+        },
+        Coins::new(),
+    )?;
+
+    suite
+        .query_wasm_smart(
+            contracts.account_factory,
+            account_factory::QueryKeysByUserRequest {
+                username: user.username.clone(),
+            },
+        )
+        .should_succeed_and_equal(btree_map! {
+            new_user.key_hash => new_user.key,
+        });
+
+    // The old key isn't used by any other user, so it's deleted altogether.
+    suite
+        .query_wasm_smart(
+            contracts.account_factory,
+            account_factory::QueryKeyRequest {
+                hash: user.key_hash,
+            },
+        )
+        .should_fail();
+
+    // The old key is rejected right away.
+    user.sequence = new_user.sequence;
+
+    suite
+        .send_message(
+            &mut user,
+            Message::transfer(accounts.owner.address(), Coins::one("uusdc", 100)?)?,
+        )?
+        .result
+        .should_fail_with_error(format!(
+            "key hash {} isn't associated with user `user`",
+            user.key_hash
+        ));
+
+    Ok(())
+}
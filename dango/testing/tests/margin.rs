@@ -0,0 +1,403 @@
+use {
+    dango_testing::{setup_test, Factory, Margin, TestAccount},
+    dango_types::{
+        account::{
+            margin::{self, CollateralParams, QueryHealthRequest},
+            single,
+        },
+        account_factory::{self, AccountParams, Salt},
+        amm::{self, FeeRate, PoolParams, QueryTwapRequest, XykParams},
+        config::MARGIN_KEY,
+        lending::{self, QueryDebtRequest, QueryDepositRequest},
+        mock_ibc_transfer,
+    },
+    grug::{
+        btree_map, Addr, Addressable, Coin, Coins, ConfigUpdates, Denom, Duration, HashExt, IsZero,
+        JsonDeExt, JsonSerExt, Message, MultiplyFraction, Op, ResultExt, Udec128, Uint128,
+        UniqueVec,
+    },
+    std::{str::FromStr, sync::LazyLock},
+};
+
+static ATOM: LazyLock<Denom> = LazyLock::new(|| Denom::from_str("uatom").unwrap());
+static OSMO: LazyLock<Denom> = LazyLock::new(|| Denom::from_str("uosmo").unwrap());
+static USDC: LazyLock<Denom> = LazyLock::new(|| Denom::from_str("uusdc").unwrap());
+
+#[test]
+fn margin() {
+    let (mut suite, mut accounts, codes, contracts) = setup_test().unwrap();
+
+    // --------------------------------- Setup ---------------------------------
+
+    // Create an ATOM-USDC pool at a price of 10 USDC per ATOM. It will be used
+    // as the price source of ATOM.
+    suite
+        .execute(
+            &mut accounts.relayer,
+            contracts.amm,
+            &amm::ExecuteMsg::CreatePool(PoolParams::Xyk(XykParams {
+                liquidity_fee_rate: FeeRate::new_unchecked(Udec128::new_bps(20)),
+            })),
+            Coins::new_unchecked(btree_map! {
+                ATOM.clone() => Uint128::new(100_000_000_000),
+                // liquidity + pool creation fee
+                USDC.clone() => Uint128::new(1_000_010_000_000),
+            }),
+        )
+        .unwrap();
+
+    // From now on, make each block a minute apart, such that the TWAP always
+    // reflects the pool's price as of the previous block.
+    suite.block_time = Duration::from_seconds(60);
+
+    // Accept ATOM as collateral, priced by the pool above.
+    let mut cfg: margin::Config = suite
+        .query_app_config(MARGIN_KEY)
+        .unwrap()
+        .deserialize_json()
+        .unwrap();
+
+    cfg.twap_window = Duration::from_seconds(60);
+    cfg.collaterals.insert(ATOM.clone(), CollateralParams {
+        price_source: Some(1),
+        collateral_factor: Udec128::new_percent(80),
+    });
+
+    suite
+        .configure(&mut accounts.owner, ConfigUpdates::default(), btree_map! {
+            MARGIN_KEY.to_string() => Op::Insert(cfg.to_json_value().unwrap()),
+        })
+        .unwrap();
+
+    // Provide some liquidity to the lending pool.
+    suite
+        .transfer(
+            &mut accounts.relayer,
+            contracts.lending,
+            Coins::one(USDC.clone(), 100_000_000_000).unwrap(),
+        )
+        .unwrap();
+
+    suite
+        .query_wasm_smart(contracts.lending, QueryDepositRequest {
+            account: accounts.relayer.address(),
+        })
+        .should_succeed_and_equal(Coins::one(USDC.clone(), 100_000_000_000).unwrap());
+
+    // Onboard a user.
+    let mut user = TestAccount::new_random("user")
+        .unwrap()
+        .predict_address(
+            contracts.account_factory,
+            codes.account_spot.to_bytes().hash256(),
+            true,
+        )
+        .unwrap();
+
+    suite
+        .execute(
+            &mut accounts.relayer,
+            contracts.ibc_transfer,
+            &mock_ibc_transfer::ExecuteMsg::ReceiveTransfer {
+                recipient: user.address(),
+            },
+            Coins::one(USDC.clone(), 100_000_000).unwrap(),
+        )
+        .unwrap();
+
+    suite
+        .execute(
+            &mut Factory::new(contracts.account_factory),
+            contracts.account_factory,
+            &account_factory::ExecuteMsg::RegisterUser {
+                username: user.username.clone(),
+                key: user.key,
+                key_hash: user.key_hash,
+            },
+            Coins::new(),
+        )
+        .unwrap();
+
+    // The user creates a margin account.
+    suite
+        .execute(
+            &mut user,
+            contracts.account_factory,
+            &account_factory::ExecuteMsg::RegisterAccount {
+                params: AccountParams::Margin(single::Params {
+                    owner: user.username.clone(),
+                }),
+            },
+            Coins::new(),
+        )
+        .unwrap();
+
+    // Spot accounts can't borrow.
+    suite
+        .send_message(
+            &mut user,
+            Message::execute(
+                contracts.lending,
+                &lending::ExecuteMsg::Borrow {
+                    coins: Coins::one(USDC.clone(), 1_000_000).unwrap(),
+                },
+                Coins::new(),
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .result
+        .should_fail_with_error("only margin accounts can borrow");
+
+    // Derive the margin account's address.
+    // We have 3 genesis users + the user's spot account, so the margin
+    // account's index should be 4.
+    let margin_address = Addr::compute(
+        contracts.account_factory,
+        codes.account_margin.to_bytes().hash256(),
+        Salt { index: 4 }.into_bytes().as_slice(),
+    );
+    let mut margin = Margin::new(margin_address);
+
+    // Deposit 1,000,000 ATOM into the margin account as collateral. At a price
+    // of 10 USDC and a collateral factor of 80%, it's worth 8,000,000 USDC.
+    suite
+        .transfer(
+            &mut accounts.relayer,
+            margin_address,
+            Coins::one(ATOM.clone(), 1_000_000).unwrap(),
+        )
+        .unwrap();
+
+    suite
+        .query_wasm_smart(margin_address, QueryHealthRequest {})
+        .should_succeed_and(|health| {
+            health.collateral_value == Uint128::new(8_000_000)
+                && health.debt_value.is_zero()
+                && health.health_factor.is_none()
+                && health.is_healthy()
+        });
+
+    // ------------------------------- Borrowing -------------------------------
+
+    // Borrow 5,000,000 USDC and send it away.
+    suite
+        .send_messages(margin.with_signer(&user), vec![
+            Message::execute(
+                contracts.lending,
+                &lending::ExecuteMsg::Borrow {
+                    coins: Coins::one(USDC.clone(), 5_000_000).unwrap(),
+                },
+                Coins::new(),
+            )
+            .unwrap(),
+            Message::transfer(user.address(), Coins::one(USDC.clone(), 5_000_000).unwrap())
+                .unwrap(),
+        ])
+        .unwrap()
+        .result
+        .should_succeed();
+
+    suite
+        .query_wasm_smart(margin_address, QueryHealthRequest {})
+        .should_succeed_and(|health| {
+            health.debts == Coins::one(USDC.clone(), 5_000_000).unwrap()
+                && health.collateral_value == Uint128::new(8_000_000)
+                && health.debt_value == Uint128::new(5_000_000)
+                && health.health_factor == Some(Udec128::new_percent(160))
+        });
+
+    // Borrowing another 4,000,000 USDC would make the account
+    // undercollateralized, so the transaction is reverted in backrun.
+    suite
+        .send_messages(margin.with_signer(&user), vec![
+            Message::execute(
+                contracts.lending,
+                &lending::ExecuteMsg::Borrow {
+                    coins: Coins::one(USDC.clone(), 4_000_000).unwrap(),
+                },
+                Coins::new(),
+            )
+            .unwrap(),
+            Message::transfer(user.address(), Coins::one(USDC.clone(), 4_000_000).unwrap())
+                .unwrap(),
+        ])
+        .unwrap()
+        .result
+        .should_fail_with_error("account is undercollateralized!");
+
+    suite
+        .query_wasm_smart(contracts.lending, QueryDebtRequest {
+            account: margin_address,
+        })
+        .should_succeed_and_equal(Coins::one(USDC.clone(), 5_000_000).unwrap());
+
+    // ------------------------------ Liquidation ------------------------------
+
+    // A healthy account can't be liquidated.
+    suite
+        .send_message(
+            &mut accounts.relayer,
+            Message::execute(
+                margin_address,
+                &margin::ExecuteMsg::Liquidate {
+                    collateral_denom: ATOM.clone(),
+                },
+                Coins::one(USDC.clone(), 1_000_000).unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .result
+        .should_fail_with_error("account is healthy and can't be liquidated!");
+
+    // Crash the price of ATOM by dumping a lot of it into the pool.
+    suite
+        .execute(
+            &mut accounts.relayer,
+            contracts.amm,
+            &amm::ExecuteMsg::Swap {
+                route: UniqueVec::new_unchecked(vec![1]),
+                minimum_output: None,
+            },
+            Coin::new(ATOM.clone(), 100_000_000_000).unwrap(),
+        )
+        .unwrap();
+
+    // Make another block, such that the TWAP reflects the new price.
+    suite.make_empty_block().unwrap();
+
+    let atom_price = suite
+        .query_wasm_smart(contracts.amm, QueryTwapRequest {
+            pool_id: 1,
+            window: Duration::from_seconds(60),
+        })
+        .unwrap()
+        .inverse_price;
+
+    assert!(atom_price < Udec128::new(5));
+
+    // The account is now undercollateralized.
+    suite
+        .query_wasm_smart(margin_address, QueryHealthRequest {})
+        .should_succeed_and(|health| !health.is_healthy());
+
+    // Only denoms accepted as collateral can be claimed.
+    suite
+        .send_message(
+            &mut accounts.relayer,
+            Message::execute(
+                margin_address,
+                &margin::ExecuteMsg::Liquidate {
+                    collateral_denom: OSMO.clone(),
+                },
+                Coins::one(USDC.clone(), 1_000_000).unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .result
+        .should_fail_with_error("denom `uosmo` isn't supported by margin accounts");
+
+    let atom_balance_before = suite
+        .query_balance(&accounts.relayer, ATOM.clone())
+        .unwrap();
+    let usdc_balance_before = suite
+        .query_balance(&accounts.relayer, USDC.clone())
+        .unwrap();
+
+    // A third party repays 1,000,000 USDC of the account's debt.
+    suite
+        .execute(
+            &mut accounts.relayer,
+            margin_address,
+            &margin::ExecuteMsg::Liquidate {
+                collateral_denom: ATOM.clone(),
+            },
+            Coins::one(USDC.clone(), 1_000_000).unwrap(),
+        )
+        .unwrap();
+
+    // In return, the liquidator receives ATOM worth the repaid USDC plus the
+    // 5% liquidation bonus.
+    let reward = Uint128::new(1_050_000)
+        .checked_div_dec_floor(atom_price)
+        .unwrap();
+
+    suite
+        .query_balance(&accounts.relayer, ATOM.clone())
+        .should_succeed_and_equal(atom_balance_before + reward);
+    suite
+        .query_balance(&accounts.relayer, USDC.clone())
+        .should_succeed_and_equal(usdc_balance_before - Uint128::new(1_000_000));
+    suite
+        .query_balance(&margin, ATOM.clone())
+        .should_succeed_and_equal(Uint128::new(1_000_000) - reward);
+    suite
+        .query_wasm_smart(contracts.lending, QueryDebtRequest {
+            account: margin_address,
+        })
+        .should_succeed_and_equal(Coins::one(USDC.clone(), 4_000_000).unwrap());
+
+    // ------------------------------- Withdrawal ------------------------------
+
+    // Only deposits can be withdrawn.
+    suite
+        .send_message(
+            &mut user,
+            Message::execute(
+                contracts.lending,
+                &lending::ExecuteMsg::Withdraw {
+                    coins: Coins::one(USDC.clone(), 1).unwrap(),
+                },
+                Coins::new(),
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .result
+        .should_fail_with_error("insufficient deposit for denom `uusdc`: 0 < 1");
+
+    // Coins that have been lent out can't be withdrawn until they're repaid.
+    // The pool now holds the deposit minus the 4,000,000 USDC still owed.
+    suite
+        .send_message(
+            &mut accounts.relayer,
+            Message::execute(
+                contracts.lending,
+                &lending::ExecuteMsg::Withdraw {
+                    coins: Coins::one(USDC.clone(), 100_000_000_000).unwrap(),
+                },
+                Coins::new(),
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .result
+        .should_fail();
+
+    // The rest can be withdrawn.
+    let usdc_balance_before = suite
+        .query_balance(&accounts.relayer, USDC.clone())
+        .unwrap();
+
+    suite
+        .execute(
+            &mut accounts.relayer,
+            contracts.lending,
+            &lending::ExecuteMsg::Withdraw {
+                coins: Coins::one(USDC.clone(), 99_996_000_000).unwrap(),
+            },
+            Coins::new(),
+        )
+        .unwrap();
+
+    suite
+        .query_balance(&accounts.relayer, USDC.clone())
+        .should_succeed_and_equal(usdc_balance_before + Uint128::new(99_996_000_000));
+    suite
+        .query_wasm_smart(contracts.lending, QueryDepositRequest {
+            account: accounts.relayer.address(),
+        })
+        .should_succeed_and_equal(Coins::one(USDC.clone(), 4_000_000).unwrap());
+}
@@ -2,7 +2,10 @@ use {
     dango_testing::{setup_test, Factory, Safe, TestAccount},
     dango_types::{
         account::{
-            multi::{self, ParamUpdates, QueryProposalRequest, Status, Vote},
+            multi::{
+                self, Allowance, ParamUpdates, QueryAllowanceRequest, QueryProposalRequest,
+                QueryVoteRequest, Status, Vote,
+            },
             single,
         },
         account_factory::{
@@ -11,8 +14,8 @@ use {
         mock_ibc_transfer, ChangeSet,
     },
     grug::{
-        btree_map, btree_set, Addr, Addressable, Coins, HashExt, Message, NonZero, ResultExt,
-        Timestamp, Uint128,
+        btree_map, btree_set, Addr, Addressable, Coins, Duration, HashExt, Message, NonZero, Op,
+        ResultExt, Timestamp, Uint128,
     },
 };
 
@@ -78,6 +81,7 @@ fn safe() {
         threshold: NonZero::new(2).unwrap(),
         // For the purpose of this test, the Safe doesn't have a timelock.
         timelock: None,
+        allowances: btree_map! {},
     };
 
     // Member 1 sends a transaction to create the Safe.
@@ -148,6 +152,7 @@ fn safe() {
             safe.with_signer(&member1),
             safe_address,
             &multi::ExecuteMsg::Propose {
+                proposer: member1.username.clone(),
                 title: "send 123 uusdc to owner".to_string(),
                 description: None,
                 messages: vec![Message::transfer(
@@ -218,6 +223,7 @@ fn safe() {
         .unwrap(),
         voting_period: None,
         threshold: None,
+        allowances: btree_map! {},
     };
 
     suite
@@ -225,6 +231,7 @@ fn safe() {
             safe.with_signer(&member1),
             safe_address,
             &multi::ExecuteMsg::Propose {
+                proposer: member1.username.clone(),
                 title: "add owner as member".to_string(),
                 description: None,
                 messages: vec![Message::execute(
@@ -311,6 +318,7 @@ fn safe() {
             safe.with_signer(&member1),
             safe_address,
             &multi::ExecuteMsg::Propose {
+                proposer: member1.username.clone(),
                 title: "nothing".to_string(),
                 description: None,
                 messages: vec![],
@@ -355,4 +363,231 @@ fn safe() {
         .unwrap()
         .result
         .should_fail_with_error("proposal isn't passed or timelock hasn't elapsed");
+
+    // The failed proposal can be pruned.
+    suite
+        .execute(
+            safe.with_signer(&member1),
+            safe_address,
+            &multi::ExecuteMsg::Prune { proposal_id: 3 },
+            Coins::new(),
+        )
+        .unwrap();
+
+    suite
+        .query_wasm_smart(safe.address(), QueryProposalRequest { proposal_id: 3 })
+        .should_fail();
+
+    // ------------------------- Vote change and cancel -------------------------
+
+    // Member 1 makes a proposal.
+    suite
+        .execute(
+            safe.with_signer(&member1),
+            safe_address,
+            &multi::ExecuteMsg::Propose {
+                proposer: member1.username.clone(),
+                title: "nothing".to_string(),
+                description: None,
+                messages: vec![],
+            },
+            Coins::new(),
+        )
+        .unwrap();
+
+    // Member 2 votes YES, then changes their mind and votes NO.
+    for vote in [Vote::Yes, Vote::No] {
+        suite
+            .execute(
+                safe.with_signer(&member2),
+                safe_address,
+                &multi::ExecuteMsg::Vote {
+                    proposal_id: 4,
+                    voter: member2.username.clone(),
+                    vote,
+                    execute: false,
+                },
+                Coins::new(),
+            )
+            .unwrap();
+    }
+
+    suite
+        .query_wasm_smart(safe.address(), QueryVoteRequest {
+            proposal_id: 4,
+            member: member2.username.clone(),
+        })
+        .should_succeed_and_equal(Some(Vote::No));
+
+    suite
+        .query_wasm_smart(safe.address(), QueryProposalRequest { proposal_id: 4 })
+        .should_succeed_and(|prop| matches!(prop.status, Status::Voting { yes: 0, no: 1, .. }));
+
+    // Member 2 can't cancel the proposal, as they aren't the proposer.
+    suite
+        .send_message(
+            safe.with_signer(&member2),
+            Message::execute(
+                safe_address,
+                &multi::ExecuteMsg::Cancel {
+                    proposal_id: 4,
+                    proposer: Some(member2.username.clone()),
+                },
+                Coins::new(),
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .result
+        .should_fail_with_error("only the proposer can cancel a proposal without a vote");
+
+    // Member 1, the proposer, cancels the proposal.
+    suite
+        .execute(
+            safe.with_signer(&member1),
+            safe_address,
+            &multi::ExecuteMsg::Cancel {
+                proposal_id: 4,
+                proposer: Some(member1.username.clone()),
+            },
+            Coins::new(),
+        )
+        .unwrap();
+
+    suite
+        .query_wasm_smart(safe.address(), QueryProposalRequest { proposal_id: 4 })
+        .should_fail();
+
+    // ------------------------------- Allowances -------------------------------
+
+    // Member 1 proposes to give member 2 an allowance of 1,000 uusdc per minute,
+    // which can only be sent to the owner.
+    let updates = ParamUpdates {
+        members: ChangeSet::new(btree_map! {}, btree_set! {}).unwrap(),
+        voting_period: None,
+        threshold: None,
+        allowances: btree_map! {
+            member2.username.clone() => Op::Insert(Allowance {
+                denom: "uusdc".parse().unwrap(),
+                amount: NonZero::new(Uint128::new(1_000)).unwrap(),
+                period: NonZero::new(Duration::from_seconds(60)).unwrap(),
+                targets: btree_set! { accounts.owner.address() },
+            }),
+        },
+    };
+
+    suite
+        .execute(
+            safe.with_signer(&member1),
+            safe_address,
+            &multi::ExecuteMsg::Propose {
+                proposer: member1.username.clone(),
+                title: "give member 2 an allowance".to_string(),
+                description: None,
+                messages: vec![Message::execute(
+                    contracts.account_factory,
+                    &account_factory::ExecuteMsg::ConfigureSafe { updates },
+                    Coins::new(),
+                )
+                .unwrap()],
+            },
+            Coins::new(),
+        )
+        .unwrap();
+
+    for (member, execute) in [(&member2, false), (&accounts.owner, true)] {
+        suite
+            .execute(
+                safe.with_signer(member),
+                safe_address,
+                &multi::ExecuteMsg::Vote {
+                    proposal_id: 5,
+                    voter: member.username.clone(),
+                    vote: Vote::Yes,
+                    execute,
+                },
+                Coins::new(),
+            )
+            .unwrap();
+    }
+
+    // Member 2 sends tokens to the owner directly, without a proposal.
+    suite
+        .transfer(
+            safe.with_signer(&member2),
+            accounts.owner.address(),
+            Coins::one("uusdc", 600).unwrap(),
+        )
+        .unwrap();
+
+    suite
+        .query_wasm_smart(safe.address(), QueryAllowanceRequest {
+            member: member2.username.clone(),
+        })
+        .should_succeed_and(|allowance| {
+            allowance.as_ref().is_some_and(|allowance| {
+                allowance.used == Uint128::new(600) && allowance.remaining == Uint128::new(400)
+            })
+        });
+
+    // Note: in the following, the transactions fail authentication, which
+    // doesn't increment the Safe's sequence, so we roll it back each time.
+
+    // Member 2 can't exceed the allowance.
+    suite
+        .send_message(
+            safe.with_signer(&member2),
+            Message::transfer(accounts.owner.address(), Coins::one("uusdc", 500).unwrap()).unwrap(),
+        )
+        .unwrap()
+        .result
+        .should_fail_with_error("allowance exceeded! used: 1100, limit: 1000");
+
+    safe.sequence -= 1;
+
+    // Member 2 can't send tokens to other addresses.
+    suite
+        .send_message(
+            safe.with_signer(&member2),
+            Message::transfer(member2.address(), Coins::one("uusdc", 100).unwrap()).unwrap(),
+        )
+        .unwrap()
+        .result
+        .should_fail_with_error("isn't an allowed target of user `member2`'s allowance");
+
+    safe.sequence -= 1;
+
+    // Member 1 doesn't have an allowance.
+    suite
+        .send_message(
+            safe.with_signer(&member1),
+            Message::transfer(accounts.owner.address(), Coins::one("uusdc", 100).unwrap()).unwrap(),
+        )
+        .unwrap()
+        .result
+        .should_fail_with_error("user `member1` doesn't have an allowance in this Safe");
+
+    safe.sequence -= 1;
+
+    // Once the period has elapsed, the allowance is reset.
+    suite.block_time = Duration::from_seconds(60);
+    suite.make_empty_block().unwrap();
+
+    suite
+        .transfer(
+            safe.with_signer(&member2),
+            accounts.owner.address(),
+            Coins::one("uusdc", 1_000).unwrap(),
+        )
+        .unwrap();
+
+    suite
+        .query_wasm_smart(safe.address(), QueryAllowanceRequest {
+            member: member2.username.clone(),
+        })
+        .should_succeed_and(|allowance| {
+            allowance
+                .as_ref()
+                .is_some_and(|allowance| allowance.remaining == Uint128::ZERO)
+        });
 }
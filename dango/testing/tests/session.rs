@@ -0,0 +1,270 @@
+use {
+    dango_auth::{SESSION_SPENDINGS, SESSION_SPENDINGS_BY_EXPIRY},
+    dango_testing::{setup_test, Factory, Session, TestAccount},
+    dango_types::{
+        account_factory,
+        auth::{MessageType, SessionInfo, SessionSignDoc},
+        mock_ibc_transfer,
+    },
+    grug::{
+        btree_set, Addressable, Coins, Duration, Empty, HashExt, JsonSerExt, Message, Op,
+        ResultExt, Uint128,
+    },
+};
+
+#[test]
+fn session_key() -> anyhow::Result<()> {
+    let (mut suite, mut accounts, codes, contracts) = setup_test()?;
+
+    // Onboard a user.
+    let user = TestAccount::new_random("user")?.predict_address(
+        contracts.account_factory,
+        codes.account_spot.to_bytes().hash256(),
+        true,
+    )?;
+
+    suite.execute(
+        &mut accounts.relayer,
+        contracts.ibc_transfer,
+        &mock_ibc_transfer::ExecuteMsg::ReceiveTransfer {
+            recipient: user.address(),
+        },
+        Coins::one("uusdc", 100_000_000)?,
+    )?;
+
+    suite.execute(
+        &mut Factory::new(contracts.account_factory),
+        contracts.account_factory,
+        &account_factory::ExecuteMsg::RegisterUser {
+            username: user.username.clone(),
+            key: user.key,
+            key_hash: user.key_hash,
+        },
+        Coins::new(),
+    )?;
+
+    // The user authorizes a session key, which can only transfer up to 1,000
+    // uusdc to the owner, for the next 60 seconds.
+    let session_key = user.new_key()?;
+    let session_info = SessionInfo {
+        session_key: session_key.key,
+        expire_at: suite.block.timestamp + Duration::from_seconds(60),
+        allowed_targets: Some(btree_set! { accounts.owner.address() }),
+        allowed_message_types: Some(btree_set! { MessageType::Transfer }),
+        spend_limit: Some(Coins::one("uusdc", 1_000)?),
+    };
+    let session_hash = SessionSignDoc {
+        username: user.username.clone(),
+        sender: user.address(),
+        chain_id: suite.chain_id.clone(),
+        session_info: session_info.clone(),
+    }
+    .to_json_vec()?
+    .hash256();
+    let expire_at = session_info.expire_at;
+    let mut session = Session::new(&user, session_key, session_info);
+
+    // The session key can sign transactions within the scope.
+    suite.transfer(
+        &mut session,
+        accounts.owner.address(),
+        Coins::one("uusdc", 600)?,
+    )?;
+
+    suite
+        .query_balance(&user, "uusdc")
+        .should_succeed_and_equal(Uint128::new(100_000_000 - 600));
+
+    // Note: in the following, the transactions fail authentication, which
+    // doesn't increment the account's sequence, so we roll it back each time.
+
+    // The session key can't exceed the spend limit.
+    suite
+        .send_message(
+            &mut session,
+            Message::transfer(accounts.owner.address(), Coins::one("uusdc", 500)?)?,
+        )?
+        .result
+        .should_fail_with_error("session spend limit exceeded for denom `uusdc`: 1100 > 1000");
+
+    session.sequence -= 1;
+
+    // The session key can't send denoms not included in the spend limit.
+    suite
+        .send_message(
+            &mut session,
+            Message::transfer(accounts.owner.address(), Coins::one("uatom", 1)?)?,
+        )?
+        .result
+        .should_fail_with_error("session spend limit exceeded for denom `uatom`: 1 > 0");
+
+    session.sequence -= 1;
+
+    // The session key can't send coins to other addresses.
+    suite
+        .send_message(
+            &mut session,
+            Message::transfer(accounts.relayer.address(), Coins::one("uusdc", 100)?)?,
+        )?
+        .result
+        .should_fail_with_error("message target isn't allowed by the session");
+
+    session.sequence -= 1;
+
+    // The session key can't send other types of messages.
+    suite
+        .send_message(
+            &mut session,
+            Message::execute(accounts.owner.address(), &Empty {}, Coins::new())?,
+        )?
+        .result
+        .should_fail_with_error("message type Execute isn't allowed by the session");
+
+    session.sequence -= 1;
+
+    // The remaining allowance can still be spent.
+    suite.transfer(
+        &mut session,
+        accounts.owner.address(),
+        Coins::one("uusdc", 400)?,
+    )?;
+
+    suite
+        .query_balance(&user, "uusdc")
+        .should_succeed_and_equal(Uint128::new(100_000_000 - 1_000));
+
+    // A session key that isn't the one authorized can't sign transactions.
+    let mut forged = Session::new(&user, user.new_key()?, SessionInfo {
+        session_key: user.new_key()?.key,
+        expire_at: suite.block.timestamp + Duration::from_seconds(60),
+        allowed_targets: None,
+        allowed_message_types: None,
+        spend_limit: None,
+    });
+    forged.sequence = session.sequence;
+
+    suite
+        .send_message(
+            &mut forged,
+            Message::transfer(accounts.owner.address(), Coins::one("uusdc", 100)?)?,
+        )?
+        .result
+        .should_fail();
+
+    // After the session expires, the session key can no longer be used.
+    suite.block_time = Duration::from_seconds(60);
+    suite.make_empty_block()?;
+
+    suite
+        .send_message(
+            &mut session,
+            Message::transfer(accounts.owner.address(), Coins::one("uusdc", 1)?)?,
+        )?
+        .result
+        .should_fail_with_error("session expired");
+
+    session.sequence -= 1;
+
+    // The expired session's spending is still recorded, until the next time a
+    // session key is used.
+    suite
+        .query_wasm_raw(user.address(), SESSION_SPENDINGS.path(session_hash))
+        .should_succeed_and(|raw| raw.is_some());
+
+    // The user authorizes a new session key. Using it prunes the spending of
+    // the expired session.
+    let new_session_key = user.new_key()?;
+    let new_session_info = SessionInfo {
+        session_key: new_session_key.key,
+        expire_at: suite.block.timestamp + Duration::from_seconds(60),
+        allowed_targets: None,
+        allowed_message_types: None,
+        spend_limit: Some(Coins::one("uusdc", 1_000)?),
+    };
+    let mut new_session = Session::new(&user, new_session_key, new_session_info);
+    new_session.sequence = session.sequence;
+
+    suite.transfer(
+        &mut new_session,
+        accounts.owner.address(),
+        Coins::one("uusdc", 1)?,
+    )?;
+
+    suite
+        .query_wasm_raw(user.address(), SESSION_SPENDINGS.path(session_hash))
+        .should_succeed_and(|raw| raw.is_none());
+
+    suite
+        .query_wasm_raw(
+            user.address(),
+            SESSION_SPENDINGS_BY_EXPIRY.path((expire_at, session_hash)),
+        )
+        .should_succeed_and(|raw| raw.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn session_key_cannot_update_keys() -> anyhow::Result<()> {
+    let (mut suite, mut accounts, codes, contracts) = setup_test()?;
+
+    // Onboard a user.
+    let user = TestAccount::new_random("user")?.predict_address(
+        contracts.account_factory,
+        codes.account_spot.to_bytes().hash256(),
+        true,
+    )?;
+
+    suite.execute(
+        &mut accounts.relayer,
+        contracts.ibc_transfer,
+        &mock_ibc_transfer::ExecuteMsg::ReceiveTransfer {
+            recipient: user.address(),
+        },
+        Coins::one("uusdc", 100_000_000)?,
+    )?;
+
+    suite.execute(
+        &mut Factory::new(contracts.account_factory),
+        contracts.account_factory,
+        &account_factory::ExecuteMsg::RegisterUser {
+            username: user.username.clone(),
+            key: user.key,
+            key_hash: user.key_hash,
+        },
+        Coins::new(),
+    )?;
+
+    // The user authorizes a session key without any restriction on targets
+    // or message types.
+    let session_key = user.new_key()?;
+    let session_info = SessionInfo {
+        session_key: session_key.key,
+        expire_at: suite.block.timestamp + Duration::from_seconds(60),
+        allowed_targets: None,
+        allowed_message_types: None,
+        spend_limit: None,
+    };
+    let key = session_key.key;
+    let key_hash = session_key.key_hash;
+    let mut session = Session::new(&user, session_key, session_info);
+
+    // The session key still can't register itself as one of the user's keys.
+    suite
+        .send_message(
+            &mut session,
+            Message::execute(
+                contracts.account_factory,
+                &account_factory::ExecuteMsg::UpdateKey {
+                    username: user.username.clone(),
+                    key_hash,
+                    key: Op::Insert(key),
+                },
+                Coins::new(),
+            )?,
+        )?
+        .result
+        .should_fail_with_error("session keys can't send messages to the account factory");
+
+    Ok(())
+}
@@ -0,0 +1,131 @@
+use {
+    dango_testing::{setup_test, Factory, TestAccount},
+    dango_types::{
+        account::single::QuerySequenceRequest, account_factory, auth::Unordered, mock_ibc_transfer,
+    },
+    grug::{Addressable, Coins, Duration, HashExt, Message, ResultExt, Uint128},
+};
+
+const GAS_LIMIT: u64 = 50_000_000;
+
+#[test]
+fn unordered_transactions() -> anyhow::Result<()> {
+    let (mut suite, mut accounts, codes, contracts) = setup_test()?;
+
+    // Onboard a user.
+    let mut user = TestAccount::new_random("user")?.predict_address(
+        contracts.account_factory,
+        codes.account_spot.to_bytes().hash256(),
+        true,
+    )?;
+
+    suite.execute(
+        &mut accounts.relayer,
+        contracts.ibc_transfer,
+        &mock_ibc_transfer::ExecuteMsg::ReceiveTransfer {
+            recipient: user.address(),
+        },
+        Coins::one("uusdc", 100_000_000)?,
+    )?;
+
+    suite.execute(
+        &mut Factory::new(contracts.account_factory),
+        contracts.account_factory,
+        &account_factory::ExecuteMsg::RegisterUser {
+            username: user.username.clone(),
+            key: user.key,
+            key_hash: user.key_hash,
+        },
+        Coins::new(),
+    )?;
+
+    let transfer = Message::transfer(accounts.owner.address(), Coins::one("uusdc", 100)?)?;
+    let timeout = suite.block.timestamp + Duration::from_seconds(60);
+
+    // Sign two unordered transactions with different nonces. They can be
+    // included in either order.
+    let tx1 = user.sign_unordered_transaction(
+        vec![transfer.clone()],
+        &suite.chain_id,
+        GAS_LIMIT,
+        Unordered { nonce: 1, timeout },
+    )?;
+    let tx2 = user.sign_unordered_transaction(
+        vec![transfer.clone()],
+        &suite.chain_id,
+        GAS_LIMIT,
+        Unordered { nonce: 2, timeout },
+    )?;
+
+    suite.send_transaction(tx2)?.result.should_succeed();
+    suite.send_transaction(tx1.clone())?.result.should_succeed();
+
+    suite
+        .query_balance(&user, "uusdc")
+        .should_succeed_and_equal(Uint128::new(100_000_000 - 200));
+
+    // Unordered transactions don't use the sequence.
+    suite
+        .query_wasm_smart(user.address(), QuerySequenceRequest {})
+        .should_succeed_and_equal(0);
+
+    // A nonce can't be used again before the transaction times out.
+    suite
+        .send_transaction(tx1.clone())?
+        .result
+        .should_fail_with_error("nonce 1 has already been used");
+
+    // The timeout can't be too far in the future.
+    suite
+        .send_transaction(user.sign_unordered_transaction(
+            vec![transfer.clone()],
+            &suite.chain_id,
+            GAS_LIMIT,
+            Unordered {
+                nonce: 3,
+                timeout: suite.block.timestamp + Duration::from_seconds(2 * 60 * 60),
+            },
+        )?)?
+        .result
+        .should_fail_with_error("timeout too far in the future!");
+
+    // Ordered transactions still work alongside unordered ones.
+    suite.transfer(
+        &mut user,
+        accounts.owner.address(),
+        Coins::one("uusdc", 100)?,
+    )?;
+
+    suite
+        .query_wasm_smart(user.address(), QuerySequenceRequest {})
+        .should_succeed_and_equal(1);
+
+    // After the timeout, the transaction can no longer be included.
+    suite.block_time = Duration::from_seconds(60);
+    suite.make_empty_block()?;
+
+    suite
+        .send_transaction(tx1)?
+        .result
+        .should_fail_with_error("transaction timed out");
+
+    // The nonce has been pruned, so it can be used again with a new timeout.
+    suite
+        .send_transaction(user.sign_unordered_transaction(
+            vec![transfer],
+            &suite.chain_id,
+            GAS_LIMIT,
+            Unordered {
+                nonce: 1,
+                timeout: suite.block.timestamp + Duration::from_seconds(120),
+            },
+        )?)?
+        .result
+        .should_succeed();
+
+    suite
+        .query_balance(&user, "uusdc")
+        .should_succeed_and_equal(Uint128::new(100_000_000 - 400));
+
+    Ok(())
+}
@@ -1,5 +1,7 @@
 use grug::Empty;
 
+/// Types relevant for margin accounts.
+pub mod margin;
 /// Types relevant for multi-signature accounts.
 pub mod multi;
 /// Types relevant for single-signature accounts.
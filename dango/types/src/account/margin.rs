@@ -0,0 +1,87 @@
+use {
+    crate::amm::PoolId,
+    grug::{Addr, Coins, Denom, Duration, Udec128, Uint128},
+    std::collections::BTreeMap,
+};
+
+/// Global configurations of margin accounts.
+///
+/// Stored in the chain's app config under the `MARGIN_KEY` key, so that it is
+/// shared by all margin accounts.
+#[grug::derive(Serde)]
+pub struct Config {
+    /// Address of the lending pool contract, from which margin accounts borrow.
+    pub lending: Addr,
+    /// Address of the AMM contract, whose pools are used as price sources.
+    pub amm: Addr,
+    /// The denom in which collaterals and debts are valued.
+    pub quote_denom: Denom,
+    /// The period of time over which the time-weighted average prices are
+    /// taken from the AMM pools.
+    pub twap_window: Duration,
+    /// Denoms that are accepted as collaterals, or can be borrowed.
+    pub collaterals: BTreeMap<Denom, CollateralParams>,
+    /// The extra portion of the repaid debt's value a liquidator receives in
+    /// collateral, as a reward for carrying out the liquidation.
+    pub liquidation_bonus: Udec128,
+}
+
+/// Parameters of a denom accepted as collateral.
+#[grug::derive(Serde)]
+pub struct CollateralParams {
+    /// ID of the AMM pool between this denom and the quote denom, from which
+    /// the price of this denom is taken.
+    ///
+    /// `None` if this denom is the quote denom itself.
+    pub price_source: Option<PoolId>,
+    /// The portion of the collateral's value that counts towards the account's
+    /// borrowing power. Must be between zero and one.
+    pub collateral_factor: Udec128,
+}
+
+#[grug::derive(Serde)]
+pub enum ExecuteMsg {
+    /// Liquidate the account, if it's undercollateralized.
+    ///
+    /// The coins sent along with the message are used to repay the account's
+    /// debts; the part that exceeds the debts is refunded. In return, the
+    /// liquidator receives the given collateral, of a value equal to that of
+    /// the repaid debts plus the liquidation bonus.
+    Liquidate { collateral_denom: Denom },
+}
+
+#[grug::derive(Serde, QueryRequest)]
+pub enum QueryMsg {
+    /// Query the account's current sequence number.
+    #[returns(u32)]
+    Sequence {},
+    /// Query the account's collaterals, debts, and their values.
+    #[returns(Health)]
+    Health {},
+}
+
+/// The collaterals and debts of a margin account, and their values in the quote
+/// denom.
+#[grug::derive(Serde)]
+pub struct Health {
+    /// The account's balances of denoms accepted as collateral.
+    pub collaterals: Coins,
+    /// The account's outstanding debts.
+    pub debts: Coins,
+    /// Value of the collaterals, adjusted by their collateral factors.
+    pub collateral_value: Uint128,
+    /// Value of the debts.
+    pub debt_value: Uint128,
+    /// Adjusted collateral value divided by debt value.
+    ///
+    /// `None` if the account doesn't have any debt.
+    pub health_factor: Option<Udec128>,
+}
+
+impl Health {
+    /// An account is healthy if the adjusted value of its collaterals covers
+    /// the value of its debts.
+    pub fn is_healthy(&self) -> bool {
+        self.collateral_value >= self.debt_value
+    }
+}
@@ -1,8 +1,12 @@
 use {
     crate::{account_factory::Username, ChangeSet},
     anyhow::anyhow,
-    grug::{Duration, Inner, Message, NonZero, Timestamp},
-    std::collections::BTreeMap,
+    borsh::BorshDeserialize,
+    grug::{Addr, Denom, Duration, Inner, Message, NonZero, Op, Timestamp, Uint128},
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        io::{self, Read},
+    },
 };
 
 /// Identifier of a proposal.
@@ -26,6 +30,15 @@ pub struct Params {
     pub threshold: NonZero<Power>,
     /// The minimum delay after a proposal is passed before it can be executed.
     pub timelock: Option<NonZero<Duration>>,
+    /// Members who can send tokens from the Safe without a proposal, within
+    /// the limits of their respective allowances.
+    ///
+    /// Safes created before allowances were introduced don't have this field
+    /// in their stored params, in which case it's decoded as empty. They're
+    /// saved in the current layout the next time the params are updated.
+    #[serde(default)]
+    #[borsh(deserialize_with = "deserialize_allowances")]
+    pub allowances: BTreeMap<Username, Allowance>,
 }
 
 impl Params {
@@ -48,6 +61,7 @@ impl Params {
     pub fn apply_updates(&mut self, updates: ParamUpdates) {
         for member in updates.members.remove() {
             self.members.remove(member);
+            self.allowances.remove(member);
         }
 
         for (member, power) in updates.members.into_add() {
@@ -61,15 +75,86 @@ impl Params {
         if let Some(new) = updates.threshold {
             self.threshold = new;
         }
+
+        for (member, op) in updates.allowances {
+            match op {
+                Op::Insert(allowance) => {
+                    self.allowances.insert(member, allowance);
+                },
+                Op::Delete => {
+                    self.allowances.remove(&member);
+                },
+            }
+        }
     }
 }
 
+/// Deserialize the allowances of a Safe's params, which may be missing if the
+/// params were stored before allowances were introduced.
+///
+/// Note that this relies on the allowances being the last field in the Borsh
+/// encoding of the params, and the params being the last field in the encoding
+/// of an account, which is how they're stored in the account factory.
+fn deserialize_allowances<R>(reader: &mut R) -> io::Result<BTreeMap<Username, Allowance>>
+where
+    R: io::Read,
+{
+    let mut first_byte = Vec::with_capacity(1);
+
+    if reader.take(1).read_to_end(&mut first_byte)? == 0 {
+        return Ok(BTreeMap::new());
+    }
+
+    BorshDeserialize::deserialize_reader(&mut first_byte.as_slice().chain(reader))
+}
+
+/// Tokens a Safe member can send without a proposal, either by transferring
+/// or by executing contracts.
+#[grug::derive(Serde, Borsh)]
+pub struct Allowance {
+    /// The only denom that can be sent.
+    pub denom: Denom,
+    /// The maximum amount that can be sent within each period.
+    pub amount: NonZero<Uint128>,
+    /// The length of each period. The amount sent is reset once a period has
+    /// elapsed since the first transaction in it.
+    pub period: NonZero<Duration>,
+    /// Recipients of transfers, or contracts to be executed, that are allowed.
+    pub targets: BTreeSet<Addr>,
+}
+
+/// Tokens a Safe member has sent within the current period of their
+/// allowance.
+#[grug::derive(Serde, Borsh)]
+pub struct AllowanceUsage {
+    /// The time of the first transaction in the current period.
+    pub period_start: Timestamp,
+    /// The amount sent in the current period.
+    pub used: Uint128,
+}
+
+/// A Safe member's allowance, and how much of it is left in the current period.
+#[grug::derive(Serde)]
+pub struct CurrentAllowance {
+    pub allowance: Allowance,
+    /// The amount sent in the current period.
+    pub used: Uint128,
+    /// The amount that can still be sent in the current period.
+    pub remaining: Uint128,
+    /// The time when the current period ends and the usage is reset.
+    /// `None` if no token has been sent in the current period.
+    pub resets_at: Option<Timestamp>,
+}
+
 /// A set of updates to be applied to a Safe.
 #[grug::derive(Serde)]
 pub struct ParamUpdates {
     pub members: ChangeSet<Username, NonZero<Power>>,
     pub voting_period: Option<NonZero<Duration>>,
     pub threshold: Option<NonZero<Power>>,
+    /// Allowances to be granted, updated, or revoked.
+    #[serde(default)]
+    pub allowances: BTreeMap<Username, Op<Allowance>>,
     // Note that we don't allow changing the timelock, which is an important
     // parameter in limiting admin power and minimizing trust in DeFi protocols.
 }
@@ -78,6 +163,12 @@ pub struct ParamUpdates {
 // which includes `serde_json::Value`, doesn't implement those traits.
 #[grug::derive(Serde)]
 pub struct Proposal {
+    /// The member who created this proposal.
+    ///
+    /// `None` for proposals created before proposers were recorded. Such
+    /// proposals can only be cancelled through another proposal.
+    #[serde(default)]
+    pub proposer: Option<Username>,
     pub title: String,
     pub description: Option<String>,
     pub messages: Vec<Message>,
@@ -92,7 +183,9 @@ pub enum Status {
         /// Parameters for tallying the vote.
         ///
         /// These parameters can change at any time, so we save the parameters
-        /// _at the time the proposal was created_ inside the proposal.
+        /// _at the time the proposal was created_ inside the proposal. A change
+        /// of the parameters invalidates all proposals that haven't been
+        /// executed.
         params: Params,
         /// The time when voting period ends.
         until: Timestamp,
@@ -131,11 +224,15 @@ pub enum Vote {
 pub enum ExecuteMsg {
     /// Create a new proposal with the given title, descriptions, and messages.
     Propose {
+        proposer: Username,
         title: String,
         description: Option<String>,
         messages: Vec<Message>,
     },
     /// Vote on a proposal during its voting period.
+    ///
+    /// A member who has already voted can change their vote, as long as the
+    /// proposal is still in its voting period.
     Vote {
         proposal_id: ProposalId,
         voter: Username,
@@ -149,6 +246,19 @@ pub enum ExecuteMsg {
     /// Execute a proposal once it's passed and the timelock (if there is one)
     /// has elapsed.
     Execute { proposal_id: ProposalId },
+    /// Cancel a proposal that hasn't been executed, deleting it along with its
+    /// votes.
+    Cancel {
+        proposal_id: ProposalId,
+        /// If provided, the proposal is cancelled by its proposer.
+        ///
+        /// Otherwise, the cancellation must be done through another proposal,
+        /// that is, it must be approved by the Safe's threshold of members.
+        proposer: Option<Username>,
+    },
+    /// Delete a proposal that has failed, expired, or been invalidated by a
+    /// change of the Safe's params, along with its votes.
+    Prune { proposal_id: ProposalId },
 }
 
 // Note: we don't provide a method for querying the Safe's config. Query the
@@ -176,4 +286,117 @@ pub enum QueryMsg {
     /// Enumerate all votes in a proposal.
     #[returns(BTreeMap<Username, Vote>)]
     Votes { proposal_id: ProposalId },
+    /// Query a member's allowance, and how much of it is left in the current
+    /// period.
+    #[returns(Option<CurrentAllowance>)]
+    Allowance { member: Username },
+    /// Enumerate all members' allowances.
+    #[returns(BTreeMap<Username, CurrentAllowance>)]
+    Allowances {},
+}
+
+// ----------------------------------- tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::account_factory::{Account, AccountParams},
+        borsh::BorshSerialize,
+        grug::{btree_map, btree_set, json, BorshDeExt, BorshSerExt, JsonDeExt},
+        std::str::FromStr,
+    };
+
+    /// `Params` as it was encoded before allowances were added.
+    #[derive(BorshSerialize)]
+    struct LegacyParams {
+        members: BTreeMap<Username, NonZero<Power>>,
+        voting_period: NonZero<Duration>,
+        threshold: NonZero<Power>,
+        timelock: Option<NonZero<Duration>>,
+    }
+
+    #[derive(BorshSerialize)]
+    enum LegacyAccountParams {
+        _Spot,
+        _Margin,
+        Safe(LegacyParams),
+    }
+
+    #[derive(BorshSerialize)]
+    struct LegacyAccount {
+        index: u32,
+        params: LegacyAccountParams,
+    }
+
+    #[test]
+    fn decoding_accounts_without_allowances() {
+        let member = Username::from_str("member").unwrap();
+        let voting_period = NonZero::new(Duration::from_seconds(100)).unwrap();
+        let threshold = NonZero::new(1).unwrap();
+
+        let legacy = LegacyAccount {
+            index: 1,
+            params: LegacyAccountParams::Safe(LegacyParams {
+                members: btree_map! { member.clone() => threshold },
+                voting_period,
+                threshold,
+                timelock: None,
+            }),
+        }
+        .to_borsh_vec()
+        .unwrap();
+
+        let mut params = Params {
+            members: btree_map! { member.clone() => threshold },
+            voting_period,
+            threshold,
+            timelock: None,
+            allowances: BTreeMap::new(),
+        };
+
+        assert_eq!(legacy.deserialize_borsh::<Account>().unwrap(), Account {
+            index: 1,
+            params: AccountParams::Safe(params.clone()),
+        });
+
+        // Accounts with allowances are encoded and decoded as normal.
+        params.allowances.insert(member, Allowance {
+            denom: Denom::from_str("uusdc").unwrap(),
+            amount: NonZero::new(Uint128::new(100)).unwrap(),
+            period: voting_period,
+            targets: btree_set! { Addr::mock(1) },
+        });
+
+        let account = Account {
+            index: 1,
+            params: AccountParams::Safe(params),
+        };
+
+        assert_eq!(
+            account
+                .to_borsh_vec()
+                .unwrap()
+                .deserialize_borsh::<Account>()
+                .unwrap(),
+            account
+        );
+    }
+
+    #[test]
+    fn decoding_proposals_without_proposer() {
+        let legacy = json!({
+            "title": "title",
+            "messages": [],
+            "status": "executed",
+        });
+
+        assert_eq!(legacy.deserialize_json::<Proposal>().unwrap(), Proposal {
+            proposer: None,
+            title: "title".to_string(),
+            description: None,
+            messages: vec![],
+            status: Status::Executed,
+        });
+    }
 }
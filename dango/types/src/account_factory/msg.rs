@@ -4,7 +4,7 @@ use {
         account_factory::{Account, AccountIndex, AccountParams, AccountType, Username},
         auth::Key,
     },
-    grug::{Addr, Coins, Hash160, Hash256},
+    grug::{Addr, Coins, Hash160, Hash256, Op},
     std::collections::{BTreeMap, BTreeSet},
 };
 
@@ -45,6 +45,17 @@ pub enum ExecuteMsg {
         key: Key,
         key_hash: Hash160,
     },
+    /// Associate a new key with, or disassociate an existing key from, a user.
+    ///
+    /// Must be sent by a spot or margin account owned by the user, meaning the
+    /// transaction has been authenticated by one of the user's existing keys.
+    /// A user must always have at least one key, so the last key can't be
+    /// removed.
+    UpdateKey {
+        username: Username,
+        key_hash: Hash160,
+        key: Op<Key>,
+    },
     /// Register a new account for an existing user.
     RegisterAccount { params: AccountParams },
     /// Update a Safe account's parameters.
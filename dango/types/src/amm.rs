@@ -1,9 +1,10 @@
 mod config;
 mod msg;
 mod namespace;
+mod oracle;
 mod pool;
 
-pub use {config::*, msg::*, namespace::*, pool::*};
+pub use {config::*, msg::*, namespace::*, oracle::*, pool::*};
 
 use grug::Uint128;
 
@@ -14,3 +15,16 @@ use grug::Uint128;
 /// liquidity token's value. See:
 /// <https://ethereum.stackexchange.com/questions/132491/why-minimum-liquidity-is-used-in-dex-like-uniswap>
 pub const MINIMUM_LIQUIDITY: Uint128 = Uint128::new(1000);
+
+/// The maximum number of pools in a route found by the best route query.
+///
+/// The number of possible routes grows exponentially with the length, so we
+/// have to stop somewhere.
+pub const MAX_ROUTE_LENGTH: usize = 3;
+
+/// The maximum number of price observations kept for each pool.
+///
+/// Observations are kept in a ring buffer, so once it's full, each new
+/// observation overwrites the oldest one. This bounds how far back in time the
+/// time-weighted average price can be queried.
+pub const MAX_OBSERVATIONS: u32 = 1000;
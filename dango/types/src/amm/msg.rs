@@ -1,6 +1,6 @@
 use {
-    crate::amm::{Config, Pool, PoolId, PoolParams},
-    grug::{Coin, Coins, Uint128, UniqueVec},
+    crate::amm::{Config, Pool, PoolId, PoolParams, Twap},
+    grug::{Coin, Coins, Denom, Duration, Timestamp, Uint128, UniqueVec},
     std::collections::BTreeMap,
 };
 
@@ -20,6 +20,15 @@ pub enum ExecuteMsg {
         route: UniqueVec<PoolId>,
         minimum_output: Option<Uint128>,
     },
+    /// Perform a swap that outputs the given coin.
+    ///
+    /// The coin sent along with the message is the maximum input. The part of
+    /// it not needed for the swap is refunded. Due to rounding, the output may
+    /// slightly exceed the given amount.
+    SwapExactOutput {
+        route: UniqueVec<PoolId>,
+        output: Coin,
+    },
     /// Provide liquidity to a trading pool.
     ProvideLiquidity {
         pool_id: PoolId,
@@ -27,6 +36,20 @@ pub enum ExecuteMsg {
     },
     /// Withdraw liquidity from a trading pool.
     WithdrawLiquidity { pool_id: PoolId },
+    /// Linearly change the amplification coefficient of a stable pool to the
+    /// given value over time, until the given end time.
+    ///
+    /// Can only be called by the chain owner.
+    RampAmplification {
+        pool_id: PoolId,
+        amplification: Uint128,
+        end_time: Timestamp,
+    },
+    /// Stop an ongoing amplification ramp of a stable pool, keeping the
+    /// amplification coefficient at its current value.
+    ///
+    /// Can only be called by the chain owner.
+    StopRampAmplification { pool_id: PoolId },
 }
 
 #[grug::derive(Serde, QueryRequest)]
@@ -49,6 +72,20 @@ pub enum QueryMsg {
         input: Coin,
         route: UniqueVec<PoolId>,
     },
+    /// Simulate the input needed for a swap to output the given coin.
+    #[returns(Coin)]
+    SimulateExactOutput {
+        output: Coin,
+        route: UniqueVec<PoolId>,
+    },
+    /// Find the route, among all pools, that gives the most output when
+    /// swapping the given coin for the given denom.
+    #[returns(RouteOutcome)]
+    BestRoute { input: Coin, ask_denom: Denom },
+    /// Query the time-weighted average prices of a pool over the given period
+    /// of time until now.
+    #[returns(Twap)]
+    Twap { pool_id: PoolId, window: Duration },
 }
 
 /// The outcome of performing a swap.
@@ -61,3 +98,10 @@ pub struct SwapOutcome {
     /// The amount of fee paid to liquidity providers.
     pub liquidity_fees: Coins,
 }
+
+/// A swap route, and the outcome of swapping through it.
+#[grug::derive(Serde)]
+pub struct RouteOutcome {
+    pub route: UniqueVec<PoolId>,
+    pub outcome: SwapOutcome,
+}
@@ -0,0 +1,27 @@
+use grug::{Timestamp, Udec128, Udec256};
+
+/// Cumulative prices of a pool at a point in time.
+///
+/// The cumulative prices are the integrals of the pool's prices over time,
+/// measured in nanoseconds, since the pool's creation. The time-weighted
+/// average price between two observations is the difference between their
+/// cumulative prices, divided by the time elapsed between them.
+#[grug::derive(Serde, Borsh)]
+pub struct Observation {
+    pub timestamp: Timestamp,
+    /// Cumulative price of the second coin, in units of the first coin.
+    pub cumulative_price: Udec256,
+    /// Cumulative price of the first coin, in units of the second coin.
+    pub cumulative_inverse_price: Udec256,
+}
+
+/// Time-weighted average prices of a pool over a period of time.
+#[grug::derive(Serde)]
+pub struct Twap {
+    /// Average price of the second coin, in units of the first coin.
+    pub price: Udec128,
+    /// Average price of the first coin, in units of the second coin.
+    ///
+    /// Note: this is generally _not_ the inverse of `price`.
+    pub inverse_price: Udec128,
+}
@@ -1,6 +1,6 @@
 use {
     crate::amm::FeeRate,
-    grug::{CoinPair, Denom, Uint128},
+    grug::{CoinPair, Denom, Duration, Timestamp, Udec128, Uint128, Uint256},
 };
 
 /// Identifier of an AMM pool.
@@ -13,6 +13,7 @@ pub type PoolId = u32;
 pub enum PoolParams {
     Xyk(XykParams),
     Concentracted(ConcentratedParams),
+    Stable(StableParams),
 }
 
 /// Parameter of a constant product AMM pool (a.k.a. xyk pool).
@@ -24,9 +25,55 @@ pub struct XykParams {
 }
 
 /// Parameter of a concentracted liquidity AMM pool (a.k.a. Curve V2 pool).
+///
+/// See the [Curve V2 whitepaper](https://classic.curve.fi/files/crypto-pools-paper.pdf)
+/// for the meaning of each parameter.
 #[grug::derive(Serde, Borsh)]
 pub struct ConcentratedParams {
-    // TODO
+    /// The amplification coefficient, following Curve's convention of being
+    /// multiplied by `N ^ N * 10,000`, where `N = 2` is the number of coins.
+    /// E.g. `400,000` means the actual coefficient is `10`.
+    ///
+    /// The higher it is, the more the liquidity is concentrated around the
+    /// current price.
+    pub amplification: Uint128,
+    /// Determines how fast the liquidity concentration falls off as the price
+    /// moves away from the current price. The smaller it is, the sharper.
+    pub gamma: Udec128,
+    /// Liquidity fee rate when the pool is balanced.
+    pub mid_fee: FeeRate,
+    /// Liquidity fee rate when the pool is maximally imbalanced.
+    pub out_fee: FeeRate,
+    /// Determines how fast the liquidity fee rate changes from `mid_fee` to
+    /// `out_fee` as the pool becomes imbalanced.
+    pub fee_gamma: Udec128,
+    /// Profit, in terms of virtual price growth, that the pool may keep for
+    /// liquidity providers instead of spending it on repegging.
+    pub allowed_extra_profit: Udec128,
+    /// Minimum relative step by which the price scale moves towards the price
+    /// oracle when repegging.
+    pub adjustment_step: Udec128,
+    /// Half time of the exponential moving average of the price oracle.
+    pub ma_half_time: Duration,
+}
+
+/// Parameters of a stableswap AMM pool (a.k.a. Curve V1 pool).
+///
+/// See the [StableSwap whitepaper](https://classic.curve.fi/files/stableswap-paper.pdf)
+/// for the meaning of the amplification coefficient.
+#[grug::derive(Serde, Borsh)]
+pub struct StableParams {
+    /// The amplification coefficient `A`.
+    ///
+    /// The higher it is, the more the pool behaves like a constant sum pool,
+    /// i.e. the flatter the price is around the point where the pool is
+    /// balanced.
+    pub amplification: Uint128,
+    /// Percentage of swap output that is charged as liquidity fee, paid to
+    /// liquidity providers of the pool.
+    ///
+    /// Also charged on the imbalanced part of liquidity provisions.
+    pub liquidity_fee_rate: FeeRate,
 }
 
 // -------------------------------- pool state ---------------------------------
@@ -36,6 +83,7 @@ pub struct ConcentratedParams {
 pub enum Pool {
     Xyk(XykPool),
     Concentrated(ConcentratedPool),
+    Stable(StablePool),
 }
 
 impl Pool {
@@ -43,6 +91,7 @@ impl Pool {
         let (coin1, coin2) = match self {
             Pool::Xyk(xyk) => xyk.liquidity.as_ref(),
             Pool::Concentrated(concentrated) => concentrated.liquidity.as_ref(),
+            Pool::Stable(stable) => stable.liquidity.as_ref(),
         };
 
         (coin1.denom.clone(), coin2.denom.clone())
@@ -69,5 +118,54 @@ pub struct ConcentratedPool {
     pub liquidity: CoinPair,
     /// The total amount of liquidity shares outstanding.
     pub shares: Uint128,
-    // TODO: other params...
+    /// The invariant `D`, computed from the liquidity scaled by the price
+    /// scale. Denominated in the first coin.
+    pub invariant: Uint256,
+    /// The price of the second coin, in units of the first coin, around which
+    /// the liquidity is concentrated.
+    pub price_scale: Udec128,
+    /// Exponential moving average of the last prices, which the price scale is
+    /// moved towards when repegging.
+    pub price_oracle: Udec128,
+    /// The price of the second coin, in units of the first coin, at which the
+    /// last swap was executed.
+    pub last_price: Udec128,
+    /// The time when the price oracle was last updated.
+    pub last_price_timestamp: Timestamp,
+    /// Total growth of the virtual price since the pool's creation, without
+    /// taking repegging into account.
+    pub xcp_profit: Udec128,
+    /// Value of the liquidity, as the geometric mean of the balances at the
+    /// price scale, per liquidity share.
+    pub virtual_price: Udec128,
+    /// Whether the pool has made enough profit to repeg, but hasn't yet.
+    pub not_adjusted: bool,
+}
+
+/// State of a stableswap AMM pool (a.k.a. Curve V1 pool).
+#[grug::derive(Serde, Borsh)]
+pub struct StablePool {
+    /// The pool's parameters.
+    ///
+    /// If the amplification coefficient is being ramped, the amplification
+    /// here is the one to be reached when the ramp ends.
+    pub params: StableParams,
+    /// The amount of liquidity provided to this pool.
+    pub liquidity: CoinPair,
+    /// The total amount of liquidity shares outstanding.
+    pub shares: Uint128,
+    /// The latest ramp of the amplification coefficient, if any.
+    pub ramp: Option<AmplificationRamp>,
+}
+
+/// A linear change of a stable pool's amplification coefficient over time.
+#[grug::derive(Serde, Borsh)]
+pub struct AmplificationRamp {
+    /// The amplification coefficient when the ramp started.
+    pub initial_amplification: Uint128,
+    /// The time when the ramp started.
+    pub start_time: Timestamp,
+    /// The time when the ramp ends, at which point the amplification
+    /// coefficient reaches the one in the pool's parameters.
+    pub end_time: Timestamp,
 }
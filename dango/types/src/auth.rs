@@ -1,6 +1,7 @@
 use {
     crate::account_factory::Username,
-    grug::{Binary, ByteArray, Hash160, Message},
+    grug::{Addr, Binary, ByteArray, Coins, Hash160, Message, Timestamp},
+    std::collections::BTreeSet,
 };
 
 /// A public key that can be associated with a [`Username`](crate::auth::Username).
@@ -25,17 +26,22 @@ pub enum Credential {
     Secp256k1(ByteArray<64>),
     /// An Ed25519 signature.
     Ed25519(ByteArray<64>),
+    /// A signature by a session key, along with the session key's authorization
+    /// by one of the user's keys.
+    Session(SessionCredential),
 }
 
 /// Data that a transaction's sender must sign with their private key.
 ///
 /// This includes the messages to be included in the transaction, as well as
-/// chain ID and account sequence number for replay protection.
+/// chain ID and account sequence number (or, for unordered transactions, nonce
+/// and timeout) for replay protection.
 #[grug::derive(Serde)]
 pub struct SignDoc {
     pub messages: Vec<Message>,
     pub chain_id: String,
     pub sequence: u32,
+    pub unordered: Option<Unordered>,
 }
 
 /// Data that the account expects for the transaction's [`data`](grug::Tx::data)
@@ -47,7 +53,103 @@ pub struct Metadata {
     /// Identifies the key which the user used to sign this transaction.
     pub key_hash: Hash160,
     /// The sequence number this transaction was signed with.
+    ///
+    /// Ignored if the transaction is unordered.
     pub sequence: u32,
+    /// If provided, the transaction is unordered: instead of the sequence
+    /// number, it's protected from replay by a nonce that can't be reused
+    /// until the transaction times out.
+    ///
+    /// This allows an account to submit multiple transactions concurrently,
+    /// without one being dropped blocking the others.
+    pub unordered: Option<Unordered>,
+}
+
+/// Replay protection of an unordered transaction.
+#[grug::derive(Serde)]
+pub struct Unordered {
+    /// A number that must be unique among the account's unordered transactions
+    /// that haven't timed out.
+    pub nonce: u64,
+    /// The time at and after which the transaction can no longer be included
+    /// in a block.
+    pub timeout: Timestamp,
+}
+
+/// A session key, and the scope within which it can sign transactions on
+/// behalf of a user.
+#[grug::derive(Serde)]
+pub struct SessionInfo {
+    /// The ephemeral key that is authorized to sign transactions.
+    pub session_key: Key,
+    /// The time at and after which the session key is no longer valid.
+    pub expire_at: Timestamp,
+    /// Addresses the session key can execute, migrate, or send coins to.
+    ///
+    /// If `Some`, messages without a target address (configure, upload, and
+    /// instantiate) aren't allowed. If `None`, any address is allowed, except
+    /// for the account factory, which session keys can never send messages to,
+    /// so that they can't manage the user's keys or accounts.
+    pub allowed_targets: Option<BTreeSet<Addr>>,
+    /// Types of messages the session key can send. If `None`, any type is
+    /// allowed.
+    pub allowed_message_types: Option<BTreeSet<MessageType>>,
+    /// The maximum amount of coins, summed over the session's lifetime, that
+    /// can be sent in transfers and as funds attached to messages. Denoms not
+    /// included can't be sent at all. If `None`, there is no limit.
+    pub spend_limit: Option<Coins>,
+}
+
+/// Data that a user's key must sign to authorize a session key.
+#[grug::derive(Serde)]
+pub struct SessionSignDoc {
+    pub username: Username,
+    /// The account the session key can send transactions from.
+    ///
+    /// The session's spending is tracked in this account's storage, so the
+    /// authorization must be bound to it. Otherwise, the same authorization
+    /// could be used with each of the user's accounts, each with its own
+    /// spend limit.
+    pub sender: Addr,
+    pub chain_id: String,
+    pub session_info: SessionInfo,
+}
+
+/// Data that the account expects for the transaction's [`credential`](grug::Tx::credential)
+/// field, if the transaction is signed by a session key.
+#[grug::derive(Serde)]
+pub struct SessionCredential {
+    pub session_info: SessionInfo,
+    /// Signature of the [`SessionSignDoc`] by the user's key identified in the
+    /// transaction's metadata.
+    pub authorization: Box<Credential>,
+    /// Signature of the [`SignDoc`] by the session key.
+    pub session_signature: Box<Credential>,
+}
+
+/// Types of messages, used in the scope of a session.
+#[grug::derive(Serde)]
+#[derive(Copy, PartialOrd, Ord)]
+pub enum MessageType {
+    Configure,
+    Transfer,
+    Upload,
+    Instantiate,
+    Execute,
+    Migrate,
+}
+
+impl From<&Message> for MessageType {
+    fn from(msg: &Message) -> Self {
+        match msg {
+            Message::Configure { .. } => MessageType::Configure,
+            Message::Transfer { .. } => MessageType::Transfer,
+            Message::Upload { .. } => MessageType::Upload,
+            Message::Instantiate { .. } => MessageType::Instantiate,
+            Message::Execute { .. } => MessageType::Execute,
+            Message::Migrate { .. } => MessageType::Migrate,
+        }
+    }
 }
 
 /// An Secp256r1 signature generated by a Passkey via Webauthn, along with
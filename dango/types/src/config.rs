@@ -1,3 +1,5 @@
 pub const ACCOUNT_FACTORY_KEY: &str = "account_factory";
 
 pub const IBC_TRANSFER_KEY: &str = "ibc_transfer";
+
+pub const MARGIN_KEY: &str = "margin";
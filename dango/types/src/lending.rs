@@ -0,0 +1,45 @@
+use {
+    grug::{Addr, Coins, Empty},
+    std::collections::BTreeMap,
+};
+
+pub type InstantiateMsg = Empty;
+
+#[grug::derive(Serde)]
+pub enum ExecuteMsg {
+    /// Borrow the given coins from the lending pool.
+    ///
+    /// Can only be called by margin accounts.
+    Borrow { coins: Coins },
+    /// Repay debts using the coins sent along with the message.
+    ///
+    /// The part of the coins that exceeds the sender's debts is refunded.
+    Repay {},
+    /// Withdraw the given coins from the sender's deposits.
+    ///
+    /// Fails if the sender hasn't deposited enough, or if the coins have been
+    /// lent out and not yet repaid.
+    Withdraw { coins: Coins },
+}
+
+#[grug::derive(Serde, QueryRequest)]
+pub enum QueryMsg {
+    /// Query the outstanding debts of a single account.
+    #[returns(Coins)]
+    Debt { account: Addr },
+    /// Enumerate the outstanding debts of all accounts.
+    #[returns(BTreeMap<Addr, Coins>)]
+    Debts {
+        start_after: Option<Addr>,
+        limit: Option<u32>,
+    },
+    /// Query the deposits of a single account.
+    #[returns(Coins)]
+    Deposit { account: Addr },
+    /// Enumerate the deposits of all accounts.
+    #[returns(BTreeMap<Addr, Coins>)]
+    Deposits {
+        start_after: Option<Addr>,
+        limit: Option<u32>,
+    },
+}
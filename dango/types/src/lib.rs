@@ -5,6 +5,7 @@ pub mod auth;
 pub mod bank;
 mod changeset;
 pub mod config;
+pub mod lending;
 pub mod mock_ibc_transfer;
 pub mod taxman;
 pub mod token_factory;
@@ -1,27 +1,33 @@
 use {
-    crate::{App, AppError, Db, Vm},
+    crate::{App, AppError, Indexer, SnapshotDb, Vm},
     grug_types::{
-        Attribute, BlockInfo, Duration, Event, GenericResult, Hash256, Outcome, Timestamp,
-        TxOutcome, GENESIS_BLOCK_HASH,
+        Attribute, BlockInfo, BorshDeExt, BorshSerExt, Duration, Event, FlatEvent, GenericResult,
+        Hash256, HashExt, Outcome, Snapshot, StdResult, Timestamp, TxOutcome, GENESIS_BLOCK_HASH,
     },
     prost::bytes::Bytes,
     std::{any::type_name, net::ToSocketAddrs},
     tendermint_abci::{Application, Error as ABCIError, ServerBuilder},
     tendermint_proto::{
         abci::{
-            Event as TmEvent, EventAttribute as TmAttribute, ExecTxResult, RequestCheckTx,
-            RequestFinalizeBlock, RequestInfo, RequestInitChain, RequestQuery, ResponseCheckTx,
-            ResponseCommit, ResponseFinalizeBlock, ResponseInfo, ResponseInitChain, ResponseQuery,
+            response_apply_snapshot_chunk::Result as ApplySnapshotChunkResult,
+            response_offer_snapshot::Result as OfferSnapshotResult, Event as TmEvent,
+            EventAttribute as TmAttribute, ExecTxResult, RequestApplySnapshotChunk, RequestCheckTx,
+            RequestFinalizeBlock, RequestInfo, RequestInitChain, RequestLoadSnapshotChunk,
+            RequestOfferSnapshot, RequestQuery, ResponseApplySnapshotChunk, ResponseCheckTx,
+            ResponseCommit, ResponseFinalizeBlock, ResponseInfo, ResponseInitChain,
+            ResponseListSnapshots, ResponseLoadSnapshotChunk, ResponseOfferSnapshot, ResponseQuery,
+            Snapshot as TmSnapshot,
         },
         crypto::{ProofOp, ProofOps},
         google::protobuf::Timestamp as TmTimestamp,
     },
 };
 
-impl<DB, VM> App<DB, VM>
+impl<DB, VM, ID> App<DB, VM, ID>
 where
-    DB: Db + Clone + Send + 'static,
+    DB: SnapshotDb + Clone + Send + 'static,
     VM: Vm + Clone + Send + 'static,
+    ID: Indexer + Clone + Send + 'static,
     AppError: From<DB::Error> + From<VM::Error>,
 {
     pub fn start_abci_server<A>(self, read_buf_size: usize, addr: A) -> Result<(), ABCIError>
@@ -32,10 +38,11 @@ where
     }
 }
 
-impl<DB, VM> Application for App<DB, VM>
+impl<DB, VM, ID> Application for App<DB, VM, ID>
 where
-    DB: Db + Clone + Send + 'static,
+    DB: SnapshotDb + Clone + Send + 'static,
     VM: Vm + Clone + Send + 'static,
+    ID: Indexer + Clone + Send + 'static,
     AppError: From<DB::Error> + From<VM::Error>,
 {
     fn info(&self, _req: RequestInfo) -> ResponseInfo {
@@ -219,6 +226,79 @@ where
             },
         }
     }
+
+    fn list_snapshots(&self) -> ResponseListSnapshots {
+        match self
+            .do_list_snapshots()
+            .and_then(|snapshots| Ok(into_tm_snapshots(snapshots)?))
+        {
+            Ok(snapshots) => ResponseListSnapshots { snapshots },
+            Err(err) => panic!("failed to list snapshots: {err}"),
+        }
+    }
+
+    fn offer_snapshot(&self, req: RequestOfferSnapshot) -> ResponseOfferSnapshot {
+        // Reject the snapshot if its metadata doesn't match its hash or number
+        // of chunks.
+        let Some(snapshot) = req.snapshot.and_then(from_tm_snapshot) else {
+            return ResponseOfferSnapshot {
+                result: OfferSnapshotResult::Reject.into(),
+            };
+        };
+
+        // The app hash is that of the block at the snapshot's height, which has
+        // been verified by CometBFT using the light client protocol. This is
+        // the root hash the restored state must match.
+        let result = match self.do_offer_snapshot(snapshot, from_tm_hash(req.app_hash)) {
+            Ok(()) => OfferSnapshotResult::Accept,
+            // No snapshot of this format can be restored. Ask CometBFT to not
+            // offer any other one of the same format.
+            Err(AppError::UnsupportedSnapshotFormat { .. }) => OfferSnapshotResult::RejectFormat,
+            Err(_) => OfferSnapshotResult::Reject,
+        };
+
+        ResponseOfferSnapshot {
+            result: result.into(),
+        }
+    }
+
+    fn load_snapshot_chunk(&self, req: RequestLoadSnapshotChunk) -> ResponseLoadSnapshotChunk {
+        match self.do_load_snapshot_chunk(req.height, req.format, req.chunk) {
+            Ok(chunk) => ResponseLoadSnapshotChunk {
+                chunk: chunk.unwrap_or_default().into(),
+            },
+            Err(err) => panic!("failed to load snapshot chunk: {err}"),
+        }
+    }
+
+    fn apply_snapshot_chunk(&self, req: RequestApplySnapshotChunk) -> ResponseApplySnapshotChunk {
+        match self.do_apply_snapshot_chunk(req.index, &req.chunk) {
+            Ok(_) => ResponseApplySnapshotChunk {
+                result: ApplySnapshotChunkResult::Accept.into(),
+                ..Default::default()
+            },
+            // The chunk doesn't match its hash in the snapshot's metadata, so
+            // the sender can't be trusted, but the snapshot may still be good.
+            // Ask CometBFT to fetch the chunk again from a different peer.
+            Err(AppError::InvalidSnapshotChunk { .. }) => ResponseApplySnapshotChunk {
+                result: ApplySnapshotChunkResult::Retry.into(),
+                refetch_chunks: vec![req.index],
+                reject_senders: vec![req.sender],
+            },
+            // The restored state doesn't match the app hash. The snapshot itself
+            // is invalid; ask CometBFT to discard it and try a different one.
+            Err(AppError::InvalidSnapshot { .. }) => ResponseApplySnapshotChunk {
+                result: ApplySnapshotChunkResult::RejectSnapshot.into(),
+                ..Default::default()
+            },
+            // Any other error is our own, e.g. failing to write to the disk, so
+            // there's no point in trying other snapshots.
+            Err(_) => ResponseApplySnapshotChunk {
+                result: ApplySnapshotChunkResult::Abort.into(),
+                ..Default::default()
+            },
+        }
+    }
 }
 
 fn from_tm_block(height: i64, time: Option<TmTimestamp>, hash: Option<Bytes>) -> BlockInfo {
@@ -240,6 +320,41 @@ fn from_tm_hash(bytes: Bytes) -> Hash256 {
         .expect("incorrect block hash length")
 }
 
+// The snapshot metadata consists of the chunk hashes, and the snapshot hash is
+// the hash of the metadata.
+fn into_tm_snapshots(snapshots: Vec<Snapshot>) -> StdResult<Vec<TmSnapshot>> {
+    snapshots
+        .into_iter()
+        .map(|snapshot| {
+            let metadata = snapshot.chunks.to_borsh_vec()?;
+            Ok(TmSnapshot {
+                height: snapshot.version,
+                format: snapshot.format,
+                chunks: snapshot.chunks.len() as u32,
+                hash: metadata.hash256().into_vec().into(),
+                metadata: metadata.into(),
+            })
+        })
+        .collect()
+}
+
+fn from_tm_snapshot(snapshot: TmSnapshot) -> Option<Snapshot> {
+    if snapshot.metadata.hash256().as_ref() != snapshot.hash.as_ref() {
+        return None;
+    }
+
+    let chunks: Vec<Hash256> = snapshot.metadata.deserialize_borsh().ok()?;
+    if chunks.len() != snapshot.chunks as usize {
+        return None;
+    }
+
+    Some(Snapshot {
+        version: snapshot.height,
+        format: snapshot.format,
+        chunks,
+    })
+}
+
 fn into_tm_tx_result(outcome: TxOutcome) -> ExecTxResult {
     match outcome.result {
         GenericResult::Ok(_) => ExecTxResult {
@@ -265,10 +380,13 @@ fn into_tm_events<I>(events: I) -> Vec<TmEvent>
 where
     I: IntoIterator<Item = Event>,
 {
-    events.into_iter().map(into_tm_event).collect()
+    Event::flatten(events)
+        .into_iter()
+        .map(into_tm_event)
+        .collect()
 }
 
-fn into_tm_event(event: Event) -> TmEvent {
+fn into_tm_event(event: FlatEvent) -> TmEvent {
     TmEvent {
         r#type: event.r#type,
         attributes: into_tm_attributes(event.attributes),
@@ -3,18 +3,21 @@ use grug_types::{JsonDeExt, JsonSerExt};
 use {
     crate::{
         do_authenticate, do_backrun, do_configure, do_cron_execute, do_execute, do_finalize_fee,
-        do_instantiate, do_migrate, do_transfer, do_upload, do_withhold_fee, query_app_config,
-        query_app_configs, query_balance, query_balances, query_code, query_codes, query_config,
-        query_contract, query_contracts, query_supplies, query_supply, query_wasm_raw,
-        query_wasm_smart, AppError, AppResult, Buffer, Db, GasTracker, Shared, Vm, APP_CONFIGS,
-        CHAIN_ID, CONFIG, LAST_FINALIZED_BLOCK, NEXT_CRONJOBS,
+        do_instantiate, do_migrate, do_transfer, do_upload, do_withhold_fee,
+        process_txs_in_parallel, query_app_config, query_app_configs, query_balance,
+        query_balances, query_code, query_codes, query_config, query_contract, query_contracts,
+        query_gas_costs, query_supplies, query_supply, query_wasm_raw, query_wasm_smart, AppError,
+        AppResult, Buffer, Db, ExecutionMode, GasTracker, Indexer, NullIndexer, Shared, SnapshotDb,
+        Vm, APP_CONFIGS, CHAIN_ID, CONFIG, GAS_COSTS, LAST_FINALIZED_BLOCK, NEXT_CRONJOBS,
     },
     grug_storage::PrefixBound,
     grug_types::{
-        Addr, AuthMode, BlockInfo, BlockOutcome, BorshSerExt, Duration, Event, GenesisState,
-        Hash256, Json, Message, Order, Outcome, Permission, Query, QueryResponse, StdResult,
-        Storage, Timestamp, Tx, TxOutcome, UnsignedTx, GENESIS_SENDER,
+        Addr, AuthMode, BlockInfo, BlockOutcome, BorshSerExt, CallTrace, Duration, Event,
+        GasProfile, GenesisState, Hash256, HashExt, Json, Message, Order, Outcome, Permission,
+        Query, QueryResponse, Snapshot, StdResult, Storage, Timestamp, Tx, TxOutcome, UnsignedTx,
+        GENESIS_SENDER,
     },
+    std::sync::Arc,
 };
 
 /// The ABCI application.
@@ -22,9 +25,12 @@ use {
 /// Must be clonable which is required by `tendermint-abci` library:
 /// <https://github.com/informalsystems/tendermint-rs/blob/v0.34.0/abci/src/application.rs#L22-L25>
 #[derive(Clone)]
-pub struct App<DB, VM> {
+pub struct App<DB, VM, ID = NullIndexer> {
     db: DB,
     vm: VM,
+    /// Records blocks and their outcomes for off-chain services to query.
+    /// Doesn't record anything by default.
+    indexer: ID,
     /// The gas limit when serving ABCI `Query` calls.
     ///
     /// Prevents the situation where an attacker deploys a contract that
@@ -38,6 +44,9 @@ pub struct App<DB, VM> {
     /// Related config in CosmWasm:
     /// <https://github.com/CosmWasm/wasmd/blob/v0.51.0/x/wasm/types/types.go#L322-L323>
     query_gas_limit: u64,
+    /// Whether to execute the transactions in a block sequentially or in
+    /// parallel. Sequential by default.
+    execution_mode: ExecutionMode,
 }
 
 impl<DB, VM> App<DB, VM> {
@@ -45,15 +54,39 @@ impl<DB, VM> App<DB, VM> {
         Self {
             db,
             vm,
+            indexer: NullIndexer,
             query_gas_limit,
+            execution_mode: ExecutionMode::default(),
         }
     }
 }
 
-impl<DB, VM> App<DB, VM>
+impl<DB, VM, ID> App<DB, VM, ID> {
+    /// Set the indexer that records blocks and their outcomes.
+    pub fn with_indexer<ID2>(self, indexer: ID2) -> App<DB, VM, ID2> {
+        App {
+            db: self.db,
+            vm: self.vm,
+            indexer,
+            query_gas_limit: self.query_gas_limit,
+            execution_mode: self.execution_mode,
+        }
+    }
+
+    /// Set how transactions in a block are to be executed.
+    ///
+    /// The choice doesn't affect the block's outcome; it's purely a matter of
+    /// performance, and thus can be chosen per node.
+    pub fn set_execution_mode(&mut self, execution_mode: ExecutionMode) {
+        self.execution_mode = execution_mode;
+    }
+}
+
+impl<DB, VM, ID> App<DB, VM, ID>
 where
     DB: Db,
     VM: Vm + Clone,
+    ID: Indexer,
     AppError: From<DB::Error> + From<VM::Error>,
 {
     pub fn do_init_chain(
@@ -73,14 +106,19 @@ where
             });
         }
 
+        genesis_state.gas_costs.validate()?;
+        genesis_state.config.wasm_limits.validate()?;
+
         // Create gas tracker for genesis.
-        // During genesis, there is no gas limit.
-        let gas_tracker = GasTracker::new_limitless();
+        // During genesis, there is no gas limit, but the genesis gas costs apply.
+        let gas_tracker =
+            GasTracker::new_limitless().with_costs(Arc::new(genesis_state.gas_costs.clone()));
 
         // Save the config and genesis block, so that they can be queried when
         // executing genesis messages.
         CHAIN_ID.save(&mut buffer, &chain_id)?;
         CONFIG.save(&mut buffer, &genesis_state.config)?;
+        GAS_COSTS.save(&mut buffer, &genesis_state.gas_costs)?;
         LAST_FINALIZED_BLOCK.save(&mut buffer, &block)?;
 
         // Save app configs.
@@ -93,6 +131,8 @@ where
             schedule_cronjob(&mut buffer, contract, block.timestamp, interval)?;
         }
 
+        let mut events = vec![];
+
         // Loop through genesis messages and execute each one.
         //
         // It's expected that genesis messages should all successfully execute.
@@ -102,7 +142,7 @@ where
             #[cfg(feature = "tracing")]
             tracing::info!(idx = _idx, "Processing genesis message");
 
-            process_msg(
+            let new_events = process_msg(
                 self.vm.clone(),
                 Box::new(buffer.clone()),
                 gas_tracker.clone(),
@@ -111,6 +151,8 @@ where
                 GENESIS_SENDER,
                 msg,
             )?;
+
+            events.extend(new_events);
         }
 
         // Persist the state changes to disk
@@ -135,10 +177,24 @@ where
             "Completed genesis"
         );
 
+        if let Err(_err) = self
+            .indexer
+            .index_genesis_block(&block, root_hash.unwrap(), &events)
+        {
+            #[cfg(feature = "tracing")]
+            tracing::error!(err = _err.to_string(), "Failed to index genesis block");
+        }
+
         Ok(root_hash.unwrap())
     }
 
-    pub fn do_finalize_block(&self, block: BlockInfo, txs: Vec<Tx>) -> AppResult<BlockOutcome> {
+    /// Each transaction comes with its hash, i.e. the SHA-256 hash of its raw
+    /// bytes, which is only used for indexing.
+    pub fn do_finalize_block(
+        &self,
+        block: BlockInfo,
+        txs: Vec<(Tx, Hash256)>,
+    ) -> AppResult<BlockOutcome> {
         let mut buffer = Shared::new(Buffer::new(self.db.state_storage(None)?, None));
 
         let mut cron_outcomes = vec![];
@@ -147,6 +203,15 @@ where
         let cfg = CONFIG.load(&buffer)?;
         let last_finalized_block = LAST_FINALIZED_BLOCK.load(&buffer)?;
 
+        // Load the gas costs once at the beginning of the block. If they are
+        // updated by a transaction in this block, the change only takes effect
+        // starting from the next block.
+        //
+        // Chains initialized before gas costs became configurable don't have
+        // them in storage. These use the defaults, which are the costs they
+        // have been running with.
+        let gas_costs = Arc::new(GAS_COSTS.may_load(&buffer)?.unwrap_or_default());
+
         // Make sure the new block height is exactly the last finalized height
         // plus one. This ensures that block height always matches the DB version.
         if block.height != last_finalized_block.height + 1 {
@@ -185,7 +250,7 @@ where
             );
 
             // Cronjobs can use unlimited gas
-            let gas_tracker = GasTracker::new_limitless();
+            let gas_tracker = GasTracker::new_limitless().with_costs(gas_costs.clone());
 
             let result = do_cron_execute(
                 self.vm.clone(),
@@ -206,18 +271,38 @@ where
             )?;
         }
 
-        // Process transactions one-by-one.
-        for (_idx, tx) in txs.into_iter().enumerate() {
-            #[cfg(feature = "tracing")]
-            tracing::debug!(idx = _idx, "Processing transaction");
+        match self.execution_mode {
+            // Process transactions one-by-one.
+            ExecutionMode::Sequential => {
+                for (_idx, (tx, _)) in txs.iter().enumerate() {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(idx = _idx, "Processing transaction");
 
-            tx_outcomes.push(process_tx(
-                self.vm.clone(),
-                buffer.clone(),
-                block,
-                tx,
-                AuthMode::Finalize,
-            ));
+                    let gas_tracker =
+                        GasTracker::new_limited(tx.gas_limit).with_costs(gas_costs.clone());
+
+                    tx_outcomes.push(process_tx(
+                        self.vm.clone(),
+                        buffer.clone(),
+                        gas_tracker,
+                        block,
+                        tx.clone(),
+                        AuthMode::Finalize,
+                    ));
+                }
+            },
+            // Process transactions optimistically in parallel. The outcomes
+            // and state changes are identical to those of sequential execution.
+            ExecutionMode::Parallel { workers } => {
+                tx_outcomes = process_txs_in_parallel(
+                    self.vm.clone(),
+                    buffer.clone(),
+                    gas_costs,
+                    block,
+                    txs.iter().map(|(tx, _)| tx.clone()).collect(),
+                    workers,
+                );
+            },
         }
 
         // Save the last committed block.
@@ -246,16 +331,34 @@ where
             "Finalized block"
         );
 
-        Ok(BlockOutcome {
+        let outcome = BlockOutcome {
             app_hash: app_hash.unwrap(),
             cron_outcomes,
             tx_outcomes,
-        })
+        };
+
+        // The indexer isn't part of consensus, so failing to index the block
+        // shouldn't halt the chain.
+        if let Err(_err) = self.indexer.index_block(&block, &txs, &outcome) {
+            #[cfg(feature = "tracing")]
+            tracing::error!(
+                height = block.height,
+                err = _err.to_string(),
+                "Failed to index block"
+            );
+        }
+
+        Ok(outcome)
     }
 
     pub fn do_commit(&self) -> AppResult<()> {
         self.db.commit()?;
 
+        if let Err(_err) = self.indexer.commit() {
+            #[cfg(feature = "tracing")]
+            tracing::error!(err = _err.to_string(), "Failed to commit indexed block");
+        }
+
         #[cfg(feature = "tracing")]
         tracing::info!(height = self.db.latest_version(), "Committed state");
 
@@ -270,13 +373,14 @@ where
     pub fn do_check_tx(&self, tx: Tx) -> AppResult<Outcome> {
         let buffer = Shared::new(Buffer::new(self.db.state_storage(None)?, None));
         let block = LAST_FINALIZED_BLOCK.load(&buffer)?;
-        let gas_tracker = GasTracker::new_limited(tx.gas_limit);
+        let gas_costs = Arc::new(GAS_COSTS.may_load(&buffer)?.unwrap_or_default());
+        let gas_tracker = GasTracker::new_limited(tx.gas_limit).with_costs(gas_costs.clone());
         let mut events = vec![];
 
         match do_withhold_fee(
             self.vm.clone(),
             Box::new(buffer.clone()),
-            GasTracker::new_limitless(),
+            GasTracker::new_limitless().with_costs(gas_costs),
             block,
             &tx,
             AuthMode::Check,
@@ -347,10 +451,12 @@ where
         // Use the state storage at the given version to perform the query.
         let storage = self.db.state_storage(version)?;
         let block = LAST_FINALIZED_BLOCK.load(&storage)?;
+        let gas_costs = GAS_COSTS.may_load(&storage)?.unwrap_or_default();
 
         // The gas limit for serving this query.
         // This is set as an off-chain, per-node parameter.
-        let gas_tracker = GasTracker::new_limited(self.query_gas_limit);
+        let gas_tracker =
+            GasTracker::new_limited(self.query_gas_limit).with_costs(Arc::new(gas_costs));
 
         process_query(
             self.vm.clone(),
@@ -398,9 +504,52 @@ where
         height: u64,
         prove: bool,
     ) -> AppResult<TxOutcome> {
+        let (outcome, _) = self.simulate(unsigned_tx, height, prove, |gas_tracker| gas_tracker)?;
+
+        Ok(outcome)
+    }
+
+    /// Same as `do_simulate`, but additionally return a breakdown of the gas
+    /// consumed, by contract call frame and by operation.
+    pub fn do_simulate_with_gas_profile(
+        &self,
+        unsigned_tx: UnsignedTx,
+        height: u64,
+        prove: bool,
+    ) -> AppResult<(TxOutcome, GasProfile)> {
+        let (outcome, gas_tracker) =
+            self.simulate(unsigned_tx, height, prove, GasTracker::with_profiling)?;
+
+        // Profiling is enabled, so the profile must exist.
+        Ok((outcome, gas_tracker.profile().unwrap()))
+    }
+
+    /// Same as `do_simulate`, but additionally return a trace of the contract
+    /// calls made, including the nested ones.
+    pub fn do_simulate_with_call_traces(
+        &self,
+        unsigned_tx: UnsignedTx,
+        height: u64,
+        prove: bool,
+    ) -> AppResult<(TxOutcome, Vec<CallTrace>)> {
+        let (outcome, gas_tracker) =
+            self.simulate(unsigned_tx, height, prove, GasTracker::with_call_tracing)?;
+
+        // Call tracing is enabled, so the traces must exist.
+        Ok((outcome, gas_tracker.call_traces().unwrap()))
+    }
+
+    fn simulate(
+        &self,
+        unsigned_tx: UnsignedTx,
+        height: u64,
+        prove: bool,
+        instrument: fn(GasTracker) -> GasTracker,
+    ) -> AppResult<(TxOutcome, GasTracker)> {
         let buffer = Buffer::new(self.db.state_storage(None)?, None);
 
         let block = LAST_FINALIZED_BLOCK.load(&buffer)?;
+        let gas_costs = Arc::new(GAS_COSTS.may_load(&buffer)?.unwrap_or_default());
 
         // We can't "prove" a gas simulation
         if prove {
@@ -423,15 +572,48 @@ where
             credential: Json::Null,
         };
 
+        // Enable profiling or call tracing, if requested.
+        let gas_tracker = instrument(GasTracker::new_limited(tx.gas_limit).with_costs(gas_costs));
+
         // Run the transaction with `simulate` as `true`. Track how much gas was
         // consumed, and, if it was successful, what events were emitted.
-        Ok(process_tx(
+        let outcome = process_tx(
             self.vm.clone(),
             buffer,
+            gas_tracker.clone(),
             block,
             tx,
             AuthMode::Simulate,
-        ))
+        );
+
+        Ok((outcome, gas_tracker))
+    }
+}
+
+impl<DB, VM, ID> App<DB, VM, ID>
+where
+    DB: SnapshotDb,
+    AppError: From<DB::Error>,
+{
+    pub fn do_list_snapshots(&self) -> AppResult<Vec<Snapshot>> {
+        Ok(self.db.list_snapshots()?)
+    }
+
+    pub fn do_load_snapshot_chunk(
+        &self,
+        version: u64,
+        format: u32,
+        index: u32,
+    ) -> AppResult<Option<Vec<u8>>> {
+        Ok(self.db.load_snapshot_chunk(version, format, index)?)
+    }
+
+    pub fn do_offer_snapshot(&self, snapshot: Snapshot, app_hash: Hash256) -> AppResult<()> {
+        Ok(self.db.offer_snapshot(snapshot, app_hash)?)
+    }
+
+    pub fn do_apply_snapshot_chunk(&self, index: u32, chunk: &[u8]) -> AppResult<bool> {
+        Ok(self.db.apply_snapshot_chunk(index, chunk)?)
     }
 }
 
@@ -439,10 +621,11 @@ where
 // Borsh encoding. This is because these are the methods that clients interact
 // with, and it's difficult to do Borsh encoding in JS client (JS sucks).
 #[cfg(feature = "abci")]
-impl<DB, VM> App<DB, VM>
+impl<DB, VM, ID> App<DB, VM, ID>
 where
     DB: Db,
     VM: Vm + Clone,
+    ID: Indexer,
     AppError: From<DB::Error> + From<VM::Error>,
 {
     pub fn do_init_chain_raw(
@@ -466,7 +649,7 @@ where
     {
         let txs = raw_txs
             .iter()
-            .map(|raw_tx| raw_tx.deserialize_json())
+            .map(|raw_tx| Ok((raw_tx.deserialize_json()?, raw_tx.as_ref().hash256())))
             .collect::<StdResult<Vec<_>>>()?;
 
         self.do_finalize_block(block, txs)
@@ -498,7 +681,19 @@ where
     }
 }
 
-fn process_tx<S, VM>(vm: VM, storage: S, block: BlockInfo, tx: Tx, mode: AuthMode) -> TxOutcome
+/// Process a transaction.
+///
+/// The gas tracker should have the transaction's gas limit as its limit. Gas
+/// consumed by the taxman's `withhold_fee` and `finalize_fee` calls isn't
+/// tracked by it.
+pub(crate) fn process_tx<S, VM>(
+    vm: VM,
+    storage: S,
+    gas_tracker: GasTracker,
+    block: BlockInfo,
+    tx: Tx,
+    mode: AuthMode,
+) -> TxOutcome
 where
     S: Storage + Clone + 'static,
     VM: Vm + Clone,
@@ -511,10 +706,6 @@ where
     let buffer1 = Shared::new(Buffer::new(storage, None));
     let buffer2 = Shared::new(Buffer::new(buffer1.clone(), None));
 
-    // Create the gas tracker, with the limit being the gas limit requested by
-    // the transaction.
-    let gas_tracker = GasTracker::new_limited(tx.gas_limit);
-
     // Record the events emitted during the processing of this transaction.
     let mut events = Vec::new();
 
@@ -529,7 +720,7 @@ where
     match do_withhold_fee(
         vm.clone(),
         Box::new(buffer1.clone()),
-        GasTracker::new_limitless(),
+        GasTracker::new_limitless().with_costs(gas_tracker.costs().clone()),
         block,
         &tx,
         mode,
@@ -699,7 +890,7 @@ where
     match do_finalize_fee(
         vm,
         Box::new(buffer.clone()),
-        GasTracker::new_limitless(),
+        GasTracker::new_limitless().with_costs(gas_tracker.costs().clone()),
         block,
         &tx,
         &outcome_so_far,
@@ -747,7 +938,7 @@ where
             coins,
             true,
         ),
-        Message::Upload { code } => do_upload(&mut storage, gas_tracker, sender, &code),
+        Message::Upload { code } => do_upload(vm, &mut storage, gas_tracker, sender, &code),
         Message::Instantiate {
             code_hash,
             msg,
@@ -825,6 +1016,10 @@ where
             let res = query_app_configs(&storage, gas_tracker, start_after, limit)?;
             Ok(QueryResponse::AppConfigs(res))
         },
+        Query::GasCosts {} => {
+            let res = query_gas_costs(&storage, gas_tracker)?;
+            Ok(QueryResponse::GasCosts(res))
+        },
         Query::Balance { address, denom } => {
             let res = query_balance(vm, storage, gas_tracker, query_depth, block, address, denom)?;
             Ok(QueryResponse::Balance(res))
@@ -142,7 +142,9 @@ where
     }
 }
 
-struct Merged<'a, B, P>
+/// An iterator that merges records from a base storage with pending ops on
+/// top of it, such that the pending ops take precedence.
+pub struct Merged<'a, B, P>
 where
     B: Iterator<Item = Record>,
     P: Iterator<Item = (&'a Vec<u8>, &'a Op)>,
@@ -1,5 +1,6 @@
 use {
     grug_types::{Addr, Hash256, StdError},
+    std::convert::Infallible,
     thiserror::Error,
 };
 
@@ -38,11 +39,29 @@ pub enum AppError {
     #[error("code with hash `{code_hash}` already exists")]
     CodeExists { code_hash: Hash256 },
 
+    #[error("code with hash `{code_hash}` is invalid: {reason}")]
+    InvalidCode { code_hash: Hash256, reason: String },
+
     #[error("account with address `{address}` already exists")]
     AccountExists { address: Addr },
 
     #[error("max message depth exceeded")]
     ExceedMaxMessageDepth,
+
+    #[error("unsupported snapshot format: {format}")]
+    UnsupportedSnapshotFormat { format: u32 },
+
+    #[error("snapshot chunk {index} is invalid: {reason}")]
+    InvalidSnapshotChunk { index: u32, reason: String },
+
+    #[error("snapshot is invalid: {reason}")]
+    InvalidSnapshot { reason: String },
+}
+
+impl From<Infallible> for AppError {
+    fn from(err: Infallible) -> Self {
+        match err {}
+    }
 }
 
 pub type AppResult<T> = core::result::Result<T, AppError>;
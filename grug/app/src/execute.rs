@@ -1,14 +1,14 @@
 use {
     crate::{
         call_in_0_out_1_handle_response, call_in_1_out_1, call_in_1_out_1_handle_response,
-        call_in_2_out_1_handle_response, handle_response, has_permission, schedule_cronjob,
-        AppError, AppResult, GasTracker, MeteredItem, MeteredMap, Vm, APP_CONFIGS, CHAIN_ID, CODES,
-        CONFIG, CONTRACTS, NEXT_CRONJOBS,
+        call_in_2_out_1, call_in_2_out_1_handle_response, handle_response, has_permission,
+        schedule_cronjob, AppError, AppResult, GasTracker, MeteredItem, MeteredMap, Vm,
+        APP_CONFIGS, CHAIN_ID, CODES, CONFIG, CONTRACTS, GAS_COSTS, NEXT_CRONJOBS,
     },
     grug_types::{
-        Addr, AuthMode, AuthResponse, BankMsg, Binary, BlockInfo, Coins, ConfigUpdates, Context,
-        ContractInfo, Event, GenericResult, Hash256, HashExt, Json, Op, Storage, SubMsgResult, Tx,
-        TxOutcome,
+        Addr, Attribute, AuthMode, AuthResponse, BankMsg, Binary, BlockInfo, Coins, ConfigUpdates,
+        Context, ContractInfo, Event, Hash256, HashExt, Json, Op, Response, Storage, SubMsgResult,
+        Tx, TxOutcome,
     },
     std::collections::BTreeMap,
 };
@@ -85,9 +85,21 @@ fn _do_configure(
         cfg.permissions = new_permissions;
     }
 
+    if let Some(new_wasm_limits) = updates.wasm_limits {
+        new_wasm_limits.validate()?;
+        cfg.wasm_limits = new_wasm_limits;
+    }
+
     // Save the updated config.
     CONFIG.save(storage, &cfg)?;
 
+    // Save the updated gas costs. These are loaded at the beginning of each
+    // block, so the change takes effect starting from the next block.
+    if let Some(new_gas_costs) = updates.gas_costs {
+        new_gas_costs.validate()?;
+        GAS_COSTS.save(storage, &new_gas_costs)?;
+    }
+
     // Update app configs
     for (key, op) in app_updates {
         if let Op::Insert(value) = op {
@@ -102,13 +114,17 @@ fn _do_configure(
 
 // ---------------------------------- upload -----------------------------------
 
-pub fn do_upload(
+pub fn do_upload<VM>(
+    vm: VM,
     storage: &mut dyn Storage,
     gas_tracker: GasTracker,
     uploader: Addr,
     code: &Binary,
-) -> AppResult<Vec<Event>> {
-    match _do_upload(storage, gas_tracker, uploader, code) {
+) -> AppResult<Vec<Event>>
+where
+    VM: Vm,
+{
+    match _do_upload(vm, storage, gas_tracker, uploader, code) {
         Ok((event, _code_hash)) => {
             #[cfg(feature = "tracing")]
             tracing::info!(code_hash = _code_hash.to_string(), "Uploaded code");
@@ -125,12 +141,16 @@ pub fn do_upload(
 }
 
 // Return the hash of the code that is stored, for logging purpose.
-fn _do_upload(
+fn _do_upload<VM>(
+    mut vm: VM,
     storage: &mut dyn Storage,
     gas_tracker: GasTracker,
     uploader: Addr,
     code: &Binary,
-) -> AppResult<(Event, Hash256)> {
+) -> AppResult<(Event, Hash256)>
+where
+    VM: Vm,
+{
     // Make sure the user has the permission to upload contracts
     let cfg = CONFIG.load_with_gas(storage, gas_tracker.clone())?;
     if !has_permission(&cfg.permissions.upload, cfg.owner, uploader) {
@@ -143,6 +163,14 @@ fn _do_upload(
         return Err(AppError::CodeExists { code_hash });
     }
 
+    // Make sure the code is valid, so that the uploader doesn't pay for storing
+    // code that can't be instantiated.
+    vm.validate_code(code, &cfg.wasm_limits)
+        .map_err(|err| AppError::InvalidCode {
+            code_hash,
+            reason: err.to_string(),
+        })?;
+
     CODES.save_with_gas(storage, gas_tracker, code_hash, code)?;
 
     Ok((
@@ -378,8 +406,15 @@ where
     let contract = ContractInfo { code_hash, admin };
     CONTRACTS.save(&mut storage, address, &contract)?;
 
+    // Record the contract's creation, such that indexers know which code the
+    // contract runs on, who created it, and who the admin is.
+    let mut events = vec![Event::new("create_contract")
+        .add_attribute("address", address)
+        .add_attribute("code_hash", code_hash)
+        .add_attribute("creator", sender)
+        .add_attributes(admin.map(|admin| Attribute::new("admin", admin)))];
+
     // Make the fund transfer
-    let mut events = vec![];
     if !funds.is_empty() {
         events.extend(_do_transfer(
             vm.clone(),
@@ -729,7 +764,7 @@ where
     };
 
     let result = || -> AppResult<_> {
-        let auth_response = call_in_1_out_1::<_, _, GenericResult<AuthResponse>>(
+        let auth_response = call_in_1_out_1::<_, _, AuthResponse>(
             vm.clone(),
             storage.clone(),
             gas_tracker.clone(),
@@ -742,6 +777,10 @@ where
         )?
         .into_std_result()?;
 
+        // Submessages are handled after the call has returned, but for
+        // profiling and tracing, they are considered part of the call.
+        let _frame = gas_tracker.reenter_frame();
+
         let events = handle_response(
             vm,
             storage,
@@ -749,6 +788,7 @@ where
             0,
             "authenticate",
             &ctx,
+            None,
             auth_response.response,
         )?;
 
@@ -796,18 +836,28 @@ where
         mode: Some(mode),
     };
 
-    match call_in_1_out_1_handle_response(
-        vm,
-        storage,
-        gas_tracker,
-        0,
-        0,
-        true,
-        "backrun",
-        code_hash,
-        &ctx,
-        tx,
-    ) {
+    let result = (|| {
+        let response = call_in_1_out_1::<_, _, Response>(
+            vm.clone(),
+            storage.clone(),
+            gas_tracker.clone(),
+            0,
+            true,
+            "backrun",
+            code_hash,
+            &ctx,
+            tx,
+        )?
+        .into_std_result()?;
+
+        // Submessages are handled after the call has returned, but for
+        // profiling and tracing, they are considered part of the call.
+        let _frame = gas_tracker.reenter_frame();
+
+        handle_response(vm, storage, gas_tracker, 0, "backrun", &ctx, None, response)
+    })();
+
+    match result {
         Ok(events) => {
             #[cfg(feature = "tracing")]
             tracing::debug!(sender = tx.sender.to_string(), "Backran transaction");
@@ -851,17 +901,32 @@ where
             mode: Some(mode),
         };
 
-        call_in_1_out_1_handle_response(
-            vm,
-            storage,
-            gas_tracker,
-            0,
+        let response = call_in_1_out_1::<_, _, Response>(
+            vm.clone(),
+            storage.clone(),
+            gas_tracker.clone(),
             0,
             true,
             "withhold_fee",
             taxman.code_hash,
             &ctx,
             tx,
+        )?
+        .into_std_result()?;
+
+        // Submessages are handled after the call has returned, but for
+        // profiling and tracing, they are considered part of the call.
+        let _frame = gas_tracker.reenter_frame();
+
+        handle_response(
+            vm,
+            storage,
+            gas_tracker,
+            0,
+            "withhold_fee",
+            &ctx,
+            None,
+            response,
         )
     })();
 
@@ -908,11 +973,10 @@ where
             mode: Some(mode),
         };
 
-        call_in_2_out_1_handle_response(
-            vm,
-            storage,
-            gas_tracker,
-            0,
+        let response = call_in_2_out_1::<_, _, _, Response>(
+            vm.clone(),
+            storage.clone(),
+            gas_tracker.clone(),
             0,
             true,
             "finalize_fee",
@@ -920,6 +984,22 @@ where
             &ctx,
             tx,
             outcome,
+        )?
+        .into_std_result()?;
+
+        // Submessages are handled after the call has returned, but for
+        // profiling and tracing, they are considered part of the call.
+        let _frame = gas_tracker.reenter_frame();
+
+        handle_response(
+            vm,
+            storage,
+            gas_tracker,
+            0,
+            "finalize_fee",
+            &ctx,
+            None,
+            response,
         )
     })();
 
@@ -0,0 +1,144 @@
+use grug_types::{Addr, GasProfile};
+
+/// Builds a [`GasProfile`] as gas is consumed.
+pub(crate) struct GasProfiler {
+    // Frames that have been entered but not yet exited, outermost first. The
+    // first one is the root frame, which is never exited.
+    stack: Vec<GasProfile>,
+}
+
+impl GasProfiler {
+    pub(crate) fn new() -> Self {
+        Self {
+            stack: vec![GasProfile::default()],
+        }
+    }
+
+    /// Record gas consumed by an operation in the current frame.
+    pub(crate) fn record(&mut self, consumed: u64, comment: &'static str) {
+        if consumed == 0 {
+            return;
+        }
+
+        // The current frame and all its ancestors are still open, so the total
+        // gas used of each of them increases.
+        for frame in &mut self.stack {
+            frame.gas_used += consumed;
+        }
+
+        *self
+            .current()
+            .operations
+            .entry(comment.to_string())
+            .or_default() += consumed;
+    }
+
+    /// Enter a new frame, as a child of the current frame.
+    pub(crate) fn enter(&mut self, contract: Addr, entry_point: &'static str) {
+        self.stack.push(GasProfile {
+            contract: Some(contract),
+            entry_point: Some(entry_point.to_string()),
+            ..Default::default()
+        });
+    }
+
+    /// Exit the current frame, returning to its parent.
+    pub(crate) fn exit(&mut self) {
+        if self.stack.len() > 1 {
+            let frame = self.stack.pop().unwrap();
+            self.current().children.push(frame);
+        }
+    }
+
+    /// Enter the most recently exited child of the current frame again.
+    pub(crate) fn reenter(&mut self) {
+        if let Some(frame) = self.current().children.pop() {
+            self.stack.push(frame);
+        }
+    }
+
+    /// Return the profile, including frames that haven't been exited yet.
+    pub(crate) fn profile(&self) -> GasProfile {
+        let mut stack = self.stack.clone();
+
+        while stack.len() > 1 {
+            let frame = stack.pop().unwrap();
+            stack.last_mut().unwrap().children.push(frame);
+        }
+
+        stack.pop().unwrap()
+    }
+
+    fn current(&mut self) -> &mut GasProfile {
+        // The root frame is never popped, so the stack is never empty.
+        self.stack.last_mut().unwrap()
+    }
+}
+
+// ----------------------------------- tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use {super::*, grug_types::btree_map};
+
+    const CONTRACT1: Addr = Addr::mock(1);
+    const CONTRACT2: Addr = Addr::mock(2);
+
+    #[test]
+    fn profiling_works() {
+        let mut profiler = GasProfiler::new();
+
+        profiler.record(10, "db_read");
+        profiler.enter(CONTRACT1, "execute");
+        profiler.record(20, "wasm_metering");
+        profiler.record(0, "db_next");
+        profiler.enter(CONTRACT2, "query");
+        profiler.record(30, "db_read");
+        profiler.exit();
+        profiler.record(40, "wasm_metering");
+        profiler.exit();
+
+        // Handle the submessages of the call to contract 1.
+        profiler.reenter();
+        profiler.enter(CONTRACT2, "execute");
+        profiler.record(50, "db_write");
+
+        // The frames that haven't been exited should be included as well.
+        assert_eq!(profiler.profile(), GasProfile {
+            contract: None,
+            entry_point: None,
+            gas_used: 150,
+            operations: btree_map! {
+                "db_read".to_string() => 10,
+            },
+            children: vec![GasProfile {
+                contract: Some(CONTRACT1),
+                entry_point: Some("execute".to_string()),
+                gas_used: 140,
+                operations: btree_map! {
+                    "wasm_metering".to_string() => 60,
+                },
+                children: vec![
+                    GasProfile {
+                        contract: Some(CONTRACT2),
+                        entry_point: Some("query".to_string()),
+                        gas_used: 30,
+                        operations: btree_map! {
+                            "db_read".to_string() => 30,
+                        },
+                        children: vec![],
+                    },
+                    GasProfile {
+                        contract: Some(CONTRACT2),
+                        entry_point: Some("execute".to_string()),
+                        gas_used: 50,
+                        operations: btree_map! {
+                            "db_write".to_string() => 50,
+                        },
+                        children: vec![],
+                    },
+                ],
+            }],
+        });
+    }
+}
@@ -1,7 +1,14 @@
 use {
-    crate::Shared,
-    grug_types::{StdError, StdResult},
-    std::fmt::{self, Display},
+    crate::{AppResult, CallTracer, GasProfiler, Shared},
+    grug_types::{
+        CallTrace, Context, GasCosts, GasProfile, GenericResult, Json, JsonSerExt, StdError,
+        StdResult,
+    },
+    serde::Serialize,
+    std::{
+        fmt::{self, Display},
+        sync::Arc,
+    },
 };
 
 struct GasTrackerInner {
@@ -15,6 +22,14 @@ struct GasTrackerInner {
 #[derive(Clone)]
 pub struct GasTracker {
     inner: Shared<GasTrackerInner>,
+    // The gas costs of host operations. This is loaded from the chain state at
+    // the beginning of each block, and doesn't change during the block.
+    costs: Arc<GasCosts>,
+    // If profiling is enabled, records which operation consumed how much gas,
+    // in which contract call frame.
+    profiler: Option<Shared<GasProfiler>>,
+    // If call tracing is enabled, records the contract calls made.
+    tracer: Option<Shared<CallTracer>>,
 }
 
 impl GasTracker {
@@ -25,6 +40,9 @@ impl GasTracker {
                 limit: maybe_limit,
                 used: 0,
             }),
+            costs: Arc::new(GasCosts::default()),
+            profiler: None,
+            tracer: None,
         }
     }
 
@@ -35,6 +53,9 @@ impl GasTracker {
                 limit: None,
                 used: 0,
             }),
+            costs: Arc::new(GasCosts::default()),
+            profiler: None,
+            tracer: None,
         }
     }
 
@@ -45,6 +66,99 @@ impl GasTracker {
                 limit: Some(limit),
                 used: 0,
             }),
+            costs: Arc::new(GasCosts::default()),
+            profiler: None,
+            tracer: None,
+        }
+    }
+
+    /// Use the given gas costs, instead of the default ones.
+    pub fn with_costs(mut self, costs: Arc<GasCosts>) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    /// Enable gas profiling.
+    pub fn with_profiling(mut self) -> Self {
+        self.profiler = Some(Shared::new(GasProfiler::new()));
+        self
+    }
+
+    /// Enable call tracing.
+    pub fn with_call_tracing(mut self) -> Self {
+        self.tracer = Some(Shared::new(CallTracer::new()));
+        self
+    }
+
+    /// Return the gas costs of host operations.
+    pub fn costs(&self) -> &Arc<GasCosts> {
+        &self.costs
+    }
+
+    /// Return the gas profile. `None` if profiling isn't enabled.
+    ///
+    /// Panics if lock is poisoned.
+    pub fn profile(&self) -> Option<GasProfile> {
+        self.profiler
+            .as_ref()
+            .map(|profiler| profiler.read_access().profile())
+    }
+
+    /// Return the traces of the contract calls made. `None` if call tracing
+    /// isn't enabled.
+    ///
+    /// Panics if lock is poisoned.
+    pub fn call_traces(&self) -> Option<Vec<CallTrace>> {
+        self.tracer
+            .as_ref()
+            .map(|tracer| tracer.read_access().traces())
+    }
+
+    /// Enter a new contract call frame, for gas profiling and call tracing. The
+    /// frame is exited when the returned guard is dropped. The input is only
+    /// evaluated if call tracing is enabled.
+    ///
+    /// Panics if lock is poisoned.
+    pub fn enter_frame<F>(
+        &self,
+        ctx: &Context,
+        entry_point: &'static str,
+        input: F,
+    ) -> StdResult<CallFrameGuard>
+    where
+        F: FnOnce() -> StdResult<Json>,
+    {
+        if let Some(profiler) = &self.profiler {
+            profiler.write_access().enter(ctx.contract, entry_point);
+        }
+
+        if let Some(tracer) = &self.tracer {
+            let input = input()?;
+            tracer
+                .write_access()
+                .enter(ctx, entry_point, input, self.used());
+        }
+
+        Ok(CallFrameGuard {
+            gas_tracker: self.clone(),
+        })
+    }
+
+    /// Enter the most recently exited contract call frame again, such that the
+    /// handling of the submessages the call has emitted is attributed to it.
+    ///
+    /// Panics if lock is poisoned.
+    pub fn reenter_frame(&self) -> CallFrameGuard {
+        if let Some(profiler) = &self.profiler {
+            profiler.write_access().reenter();
+        }
+
+        if let Some(tracer) = &self.tracer {
+            tracer.write_access().reenter(self.used());
+        }
+
+        CallFrameGuard {
+            gas_tracker: self.clone(),
         }
     }
 
@@ -76,8 +190,51 @@ impl GasTracker {
     ///
     /// Panics if lock is poisoned.
     pub fn consume(&self, consumed: u64, comment: &'static str) -> StdResult<()> {
+        self.deduct(consumed, comment)?;
+
+        if let Some(profiler) = &self.profiler {
+            profiler.write_access().record(consumed, comment);
+        }
+
+        Ok(())
+    }
+
+    /// Consume the sum of the given parts of gas under the given comment, as a
+    /// single amount. Error if the limit is exceeded.
+    ///
+    /// This is equivalent to `consume` with the sum, except the profiler
+    /// records each part under its own comment. In particular, the gas used
+    /// when running out of gas doesn't depend on whether profiling is enabled.
+    ///
+    /// Panics if lock is poisoned.
+    pub fn consume_parts(
+        &self,
+        parts: &[(u64, &'static str)],
+        comment: &'static str,
+    ) -> StdResult<()> {
+        let consumed = parts
+            .iter()
+            .try_fold(0u64, |total, (part, _)| total.checked_add(*part))
+            .unwrap_or(u64::MAX);
+
+        self.deduct(consumed, comment)?;
+
+        if let Some(profiler) = &self.profiler {
+            let mut profiler = profiler.write_access();
+
+            for (part, comment) in parts {
+                profiler.record(*part, comment);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn deduct(&self, consumed: u64, comment: &'static str) -> StdResult<()> {
         self.inner.write_with(|mut inner| {
-            let used = inner.used + consumed;
+            // Saturate instead of overflowing, as the cost of an operation may
+            // be `u64::MAX` (see `LinearGasCost::cost`).
+            let used = inner.used.saturating_add(consumed);
 
             // If there is a limit, and the limit is exceeded, then throw error.
             if let Some(limit) = inner.limit {
@@ -103,6 +260,48 @@ impl GasTracker {
     }
 }
 
+/// Exits the gas tracker's current contract call frame when dropped.
+///
+/// Created by [`GasTracker::enter_frame`] and [`GasTracker::reenter_frame`].
+#[must_use = "the frame is exited as soon as the guard is dropped"]
+pub struct CallFrameGuard {
+    gas_tracker: GasTracker,
+}
+
+impl CallFrameGuard {
+    /// Record the result of the call, if call tracing is enabled.
+    ///
+    /// Panics if lock is poisoned.
+    pub fn finish<T>(&self, result: &AppResult<GenericResult<T>>)
+    where
+        T: Serialize,
+    {
+        let Some(tracer) = &self.gas_tracker.tracer else {
+            return;
+        };
+
+        let result = match result {
+            Ok(GenericResult::Ok(output)) => output.to_json_value().into(),
+            Ok(GenericResult::Err(err)) => GenericResult::Err(err.clone()),
+            Err(err) => GenericResult::Err(err.to_string()),
+        };
+
+        tracer.write_access().finish(result);
+    }
+}
+
+impl Drop for CallFrameGuard {
+    fn drop(&mut self) {
+        if let Some(profiler) = &self.gas_tracker.profiler {
+            profiler.write_access().exit();
+        }
+
+        if let Some(tracer) = &self.gas_tracker.tracer {
+            tracer.write_access().exit(self.gas_tracker.used());
+        }
+    }
+}
+
 impl Display for GasTracker {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.inner.read_with(|inner| {
@@ -114,3 +313,45 @@ impl Display for GasTracker {
         })
     }
 }
+
+// ----------------------------------- tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use {super::*, grug_types::btree_map};
+
+    #[test]
+    fn consuming_parts_works() {
+        for profiling in [false, true] {
+            let mut gas_tracker = GasTracker::new_limited(100);
+
+            if profiling {
+                gas_tracker = gas_tracker.with_profiling();
+            }
+
+            gas_tracker
+                .consume_parts(&[(20, "wasm_metering"), (30, "db_read")], "db_read")
+                .unwrap();
+            assert_eq!(gas_tracker.used(), 50);
+
+            // Running out of gas consumes none of the parts, regardless of
+            // whether profiling is enabled.
+            assert!(matches!(
+                gas_tracker.consume_parts(&[(40, "wasm_metering"), (20, "db_write")], "db_write"),
+                Err(StdError::OutOfGas {
+                    limit: 100,
+                    used: 110,
+                    comment: "db_write",
+                })
+            ));
+            assert_eq!(gas_tracker.used(), 50);
+
+            if profiling {
+                assert_eq!(gas_tracker.profile().unwrap().operations, btree_map! {
+                    "wasm_metering".to_string() => 20,
+                    "db_read".to_string() => 30,
+                });
+            }
+        }
+    }
+}
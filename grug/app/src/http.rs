@@ -0,0 +1,264 @@
+use {
+    crate::{App, AppError, AppResult, Db, Indexer, Shared, Vm},
+    axum::{
+        extract::{
+            ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
+            State,
+        },
+        http::StatusCode,
+        routing::{get, post},
+        Json as JsonBody, Router,
+    },
+    grug_types::{
+        Binary, BlockInfo, BlockOutcome, Event, Hash256, JsonSerExt, Query, Tx, UnsignedTx,
+    },
+    serde::{Deserialize, Serialize},
+    std::{convert::Infallible, io, num::NonZeroUsize, sync::Arc},
+    tokio::{
+        net::TcpListener,
+        sync::broadcast::{self, error::RecvError, Receiver, Sender},
+        task,
+    },
+};
+
+/// Request body of the `/query/app` endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct QueryAppRequest {
+    pub query: Query,
+    /// The block height at which to query. Zero means the latest.
+    #[serde(default)]
+    pub height: u64,
+    #[serde(default)]
+    pub prove: bool,
+}
+
+/// Request body of the `/query/store` endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct QueryStoreRequest {
+    pub key: Binary,
+    /// The block height at which to query. Zero means the latest.
+    #[serde(default)]
+    pub height: u64,
+    #[serde(default)]
+    pub prove: bool,
+}
+
+/// Response body of the `/query/store` endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct QueryStoreResponse {
+    /// The value corresponding to the key; `None` if the key doesn't exist.
+    pub value: Option<Binary>,
+    /// The Borsh-encoded Merkle proof; `None` if a proof isn't requested.
+    pub proof: Option<Binary>,
+}
+
+/// Request body of the `/simulate` endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SimulateRequest {
+    pub tx: UnsignedTx,
+    /// The block height at which to simulate. Zero means the latest.
+    #[serde(default)]
+    pub height: u64,
+    #[serde(default)]
+    pub prove: bool,
+}
+
+/// A block that has been committed, along with its outcome. This is what the
+/// `/subscribe/blocks` websocket endpoint streams.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CommittedBlock {
+    pub info: BlockInfo,
+    pub outcome: BlockOutcome,
+}
+
+// -------------------------------- block stream -------------------------------
+
+/// An indexer that broadcasts blocks to subscribers once they are committed.
+///
+/// Use it as the app's indexer, and pass it to the HTTP server, which streams
+/// the blocks to websocket clients.
+#[derive(Clone)]
+pub struct BlockStream {
+    sender: Sender<Arc<CommittedBlock>>,
+    pending: Shared<Option<CommittedBlock>>,
+}
+
+impl BlockStream {
+    /// Create a new stream, which holds up to `capacity` blocks for each
+    /// subscriber. A subscriber that falls further behind misses the oldest
+    /// blocks.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            sender: broadcast::channel(capacity.get()).0,
+            pending: Shared::new(None),
+        }
+    }
+
+    /// Receive blocks committed from now on.
+    pub fn subscribe(&self) -> Receiver<Arc<CommittedBlock>> {
+        self.sender.subscribe()
+    }
+}
+
+impl Indexer for BlockStream {
+    type Error = Infallible;
+
+    // Genesis isn't streamed, as it doesn't have a `BlockOutcome`.
+    fn index_genesis_block(
+        &self,
+        _block: &BlockInfo,
+        _app_hash: Hash256,
+        _events: &[Event],
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn index_block(
+        &self,
+        block: &BlockInfo,
+        _txs: &[(Tx, Hash256)],
+        outcome: &BlockOutcome,
+    ) -> Result<(), Self::Error> {
+        *self.pending.write_access() = Some(CommittedBlock {
+            info: *block,
+            outcome: outcome.clone(),
+        });
+
+        Ok(())
+    }
+
+    fn commit(&self) -> Result<(), Self::Error> {
+        if let Some(block) = self.pending.write_access().take() {
+            // This only fails if there's no subscriber, which is fine.
+            self.sender.send(Arc::new(block)).ok();
+        }
+
+        Ok(())
+    }
+}
+
+// ---------------------------------- server -----------------------------------
+
+#[derive(Clone)]
+struct ServerState<DB, VM, ID> {
+    app: App<DB, VM, ID>,
+    blocks: BlockStream,
+}
+
+impl<DB, VM, ID> App<DB, VM, ID>
+where
+    DB: Db + Clone + Send + Sync + 'static,
+    VM: Vm + Clone + Send + Sync + 'static,
+    ID: Indexer + Clone + Send + Sync + 'static,
+    AppError: From<DB::Error> + From<VM::Error>,
+{
+    /// Serve queries and simulations over HTTP with JSON bodies, without going
+    /// through CometBFT, and stream the blocks from the given stream to
+    /// websocket subscribers. Runs until the listener fails.
+    ///
+    /// Endpoints:
+    ///
+    /// - `POST /query/app`: [`QueryAppRequest`] -> `QueryResponse`
+    /// - `POST /query/store`: [`QueryStoreRequest`] -> [`QueryStoreResponse`]
+    /// - `POST /simulate`: [`SimulateRequest`] -> `TxOutcome`
+    /// - `GET /subscribe/blocks`: websocket of [`CommittedBlock`]s
+    ///
+    /// Requests that the app fails to handle are responded with status 400
+    /// and the error message as the body.
+    pub async fn start_http_server(
+        self,
+        listener: TcpListener,
+        blocks: BlockStream,
+    ) -> io::Result<()> {
+        let router = Router::new()
+            .route(
+                "/query/app",
+                post(
+                    |State(state): State<ServerState<DB, VM, ID>>,
+                     JsonBody(req): JsonBody<QueryAppRequest>| async move {
+                        run(move || state.app.do_query_app(req.query, req.height, req.prove))
+                            .await
+                    },
+                ),
+            )
+            .route(
+                "/query/store",
+                post(
+                    |State(state): State<ServerState<DB, VM, ID>>,
+                     JsonBody(req): JsonBody<QueryStoreRequest>| async move {
+                        run(move || {
+                            let (value, proof) =
+                                state.app.do_query_store(&req.key, req.height, req.prove)?;
+
+                            Ok(QueryStoreResponse {
+                                value: value.map(Binary::from),
+                                proof: proof.map(Binary::from),
+                            })
+                        })
+                        .await
+                    },
+                ),
+            )
+            .route(
+                "/simulate",
+                post(
+                    |State(state): State<ServerState<DB, VM, ID>>,
+                     JsonBody(req): JsonBody<SimulateRequest>| async move {
+                        run(move || state.app.do_simulate(req.tx, req.height, req.prove)).await
+                    },
+                ),
+            )
+            .route(
+                "/subscribe/blocks",
+                get(
+                    |State(state): State<ServerState<DB, VM, ID>>,
+                     ws: WebSocketUpgrade| async move {
+                        // Subscribe before the connection is upgraded, such
+                        // that the client doesn't miss any block committed
+                        // after it's connected.
+                        let blocks = state.blocks.subscribe();
+
+                        ws.on_upgrade(move |socket| stream_blocks(socket, blocks))
+                    },
+                ),
+            )
+            .with_state(ServerState { app: self, blocks });
+
+        axum::serve(listener, router).await
+    }
+}
+
+type HttpResult<T> = Result<JsonBody<T>, (StatusCode, String)>;
+
+// The app's methods are blocking, so run them on a thread where blocking is
+// acceptable, instead of on the async runtime.
+async fn run<F, T>(action: F) -> HttpResult<T>
+where
+    F: FnOnce() -> AppResult<T> + Send + 'static,
+    T: Send + 'static,
+{
+    match task::spawn_blocking(action).await {
+        Ok(Ok(res)) => Ok(JsonBody(res)),
+        Ok(Err(err)) => Err((StatusCode::BAD_REQUEST, err.to_string())),
+        Err(err) => Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
+    }
+}
+
+async fn stream_blocks(mut socket: WebSocket, mut blocks: Receiver<Arc<CommittedBlock>>) {
+    loop {
+        let block = match blocks.recv().await {
+            Ok(block) => block,
+            // The subscriber isn't keeping up. Skip the blocks it has missed.
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        };
+
+        // Serializing the block can't fail.
+        let text = (*block).to_json_string().unwrap();
+
+        // Sending fails if the subscriber has disconnected.
+        if socket.send(WsMessage::Text(text)).await.is_err() {
+            break;
+        }
+    }
+}
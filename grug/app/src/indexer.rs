@@ -0,0 +1,36 @@
+use {
+    crate::Indexer,
+    grug_types::{BlockInfo, BlockOutcome, Event, Hash256, Tx},
+    std::convert::Infallible,
+};
+
+/// An indexer that doesn't record anything. Used by the app unless another
+/// indexer is set.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NullIndexer;
+
+impl Indexer for NullIndexer {
+    type Error = Infallible;
+
+    fn index_genesis_block(
+        &self,
+        _block: &BlockInfo,
+        _app_hash: Hash256,
+        _events: &[Event],
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn index_block(
+        &self,
+        _block: &BlockInfo,
+        _txs: &[(Tx, Hash256)],
+        _outcome: &BlockOutcome,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn commit(&self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
@@ -3,18 +3,24 @@ mod abci;
 mod app;
 mod buffer;
 mod error;
-mod events;
 mod execute;
 mod gas;
+#[cfg(feature = "http")]
+mod http;
+mod indexer;
+mod parallel;
 mod providers;
 mod query;
 mod shared;
 mod state;
 mod submessage;
+mod tracer;
 mod traits;
 mod vm;
 
+#[cfg(feature = "http")]
+pub use crate::http::*;
 pub use crate::{
-    app::*, buffer::*, error::*, events::*, execute::*, gas::*, providers::*, query::*, shared::*,
-    state::*, submessage::*, traits::*, vm::*,
+    app::*, buffer::*, error::*, execute::*, gas::*, indexer::*, parallel::*, providers::*,
+    query::*, shared::*, state::*, submessage::*, tracer::*, traits::*, vm::*,
 };
@@ -0,0 +1,451 @@
+use {
+    crate::{process_tx, AppError, Buffer, GasTracker, Shared, Vm},
+    grug_types::{AuthMode, Batch, BlockInfo, GasCosts, Order, Record, Storage, Tx, TxOutcome},
+    std::{
+        num::NonZeroUsize,
+        sync::{
+            atomic::{self, AtomicUsize},
+            Arc,
+        },
+        thread,
+    },
+};
+
+/// Describes how `App::do_finalize_block` executes the transactions in a block.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionMode {
+    /// Execute the transactions one by one, in the order they appear in the
+    /// block.
+    #[default]
+    Sequential,
+    /// Execute the transactions optimistically in parallel, using the given
+    /// number of worker threads.
+    ///
+    /// The resulting state and `BlockOutcome` are guaranteed to be identical
+    /// to those of sequential execution.
+    Parallel { workers: NonZeroUsize },
+}
+
+// ---------------------------------- execute ----------------------------------
+
+/// Outcome of speculatively executing a transaction.
+struct Speculation {
+    outcome: TxOutcome,
+    reads: Vec<Read>,
+    writes: Batch,
+}
+
+/// Execute the transactions in a block optimistically in parallel.
+///
+/// This is a simplified variant of [Block-STM](https://arxiv.org/abs/2203.06871),
+/// which works in two phases:
+///
+/// 1. **Speculation.** The transactions are executed in parallel, each against
+///    its own `Buffer` on top of the state prior to the first transaction.
+///    Every read that reaches the underlying state is recorded as the tx's
+///    _read set_. The changes the tx makes are kept in the buffer as the tx's
+///    _write set_.
+/// 2. **Validation.** Going through the transactions in block order, we check
+///    whether each read in the read set still yields the same result against
+///    the state that includes the writes of all preceding transactions. If so,
+///    the tx would have behaved exactly the same had it been executed
+///    sequentially, so we accept its outcome and apply its write set.
+///    Otherwise, there is a conflict, and we re-execute the tx against the
+///    up-to-date state.
+///
+/// Since the write sets are always applied in block order, and a speculative
+/// outcome is only accepted if it's identical to what sequential execution
+/// would have produced, the resulting state and outcomes are byte-for-byte
+/// identical to those of sequential execution.
+pub(crate) fn process_txs_in_parallel<S, VM>(
+    vm: VM,
+    mut buffer: Shared<Buffer<S>>,
+    gas_costs: Arc<GasCosts>,
+    block: BlockInfo,
+    txs: Vec<Tx>,
+    workers: NonZeroUsize,
+) -> Vec<TxOutcome>
+where
+    S: Storage + Clone + 'static,
+    VM: Vm + Clone,
+    AppError: From<VM::Error>,
+{
+    // ------------------------------ speculation ------------------------------
+
+    // Index of the next transaction to be picked up by a worker.
+    let next_idx = AtomicUsize::new(0);
+
+    let mut speculations = thread::scope(|s| {
+        let handles = (0..workers.get().min(txs.len()))
+            .map(|_| {
+                let vm = vm.clone();
+                let buffer = buffer.clone();
+                let gas_costs = gas_costs.clone();
+                let txs = &txs;
+                let next_idx = &next_idx;
+
+                s.spawn(move || {
+                    let mut speculations = vec![];
+
+                    loop {
+                        let idx = next_idx.fetch_add(1, atomic::Ordering::Relaxed);
+                        let Some(tx) = txs.get(idx) else {
+                            break;
+                        };
+
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!(idx, "Speculatively processing transaction");
+
+                        let speculation = speculate(
+                            vm.clone(),
+                            buffer.clone(),
+                            gas_costs.clone(),
+                            block,
+                            tx.clone(),
+                        );
+
+                        speculations.push((idx, speculation));
+                    }
+
+                    speculations
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                // If a worker panicked, propagate the panic, same as what would
+                // have happened in sequential execution.
+                handle
+                    .join()
+                    .unwrap_or_else(|err| std::panic::resume_unwind(err))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    // Each worker returns the speculations in the order they were picked up.
+    // Sort them by block order.
+    speculations.sort_by_key(|(idx, _)| *idx);
+
+    // ------------------------------ validation -------------------------------
+
+    let mut tx_outcomes = Vec::with_capacity(txs.len());
+    let mut _num_conflicts = 0;
+
+    for (tx, (_idx, speculation)) in txs.into_iter().zip(speculations) {
+        if speculation.reads.iter().all(|read| read.validate(&buffer)) {
+            buffer.flush(speculation.writes);
+            tx_outcomes.push(speculation.outcome);
+        } else {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(idx = _idx, "Conflict detected; re-processing transaction");
+
+            _num_conflicts += 1;
+
+            let gas_tracker = GasTracker::new_limited(tx.gas_limit).with_costs(gas_costs.clone());
+
+            tx_outcomes.push(process_tx(
+                vm.clone(),
+                buffer.clone(),
+                gas_tracker,
+                block,
+                tx,
+                AuthMode::Finalize,
+            ));
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        num_txs = tx_outcomes.len(),
+        num_conflicts = _num_conflicts,
+        "Processed transactions in parallel"
+    );
+
+    tx_outcomes
+}
+
+fn speculate<S, VM>(
+    vm: VM,
+    base: Shared<Buffer<S>>,
+    gas_costs: Arc<GasCosts>,
+    block: BlockInfo,
+    tx: Tx,
+) -> Speculation
+where
+    S: Storage + Clone + 'static,
+    VM: Vm + Clone,
+    AppError: From<VM::Error>,
+{
+    let reads = Shared::new(Vec::new());
+    let buffer = Shared::new(Buffer::new(Recorder::new(base, reads.clone()), None));
+
+    let gas_tracker = GasTracker::new_limited(tx.gas_limit).with_costs(gas_costs);
+    let outcome = process_tx(
+        vm,
+        buffer.clone(),
+        gas_tracker,
+        block,
+        tx,
+        AuthMode::Finalize,
+    );
+
+    // At this point, the changes made by the tx have been flushed into our
+    // buffer. These constitute the tx's write set.
+    let (recorder, writes) = buffer.disassemble().disassemble();
+
+    // Drop the recorder, so that we're holding the only reference to the read
+    // set, and can disassemble it.
+    drop(recorder);
+
+    Speculation {
+        outcome,
+        reads: reads.disassemble(),
+        writes,
+    }
+}
+
+// ----------------------------------- reads -----------------------------------
+
+/// A read made by a transaction against the underlying state.
+enum Read {
+    /// Read of a single key, and the value that was found.
+    Key {
+        key: Vec<u8>,
+        value: Option<Vec<u8>>,
+    },
+    /// An iteration, and the records that were yielded.
+    Scan {
+        min: Option<Vec<u8>>,
+        max: Option<Vec<u8>>,
+        order: Order,
+        records: Vec<Record>,
+        /// Whether the iterator was advanced until it returned `None`.
+        exhausted: bool,
+    },
+}
+
+impl Read {
+    /// Return whether performing this read against the given storage yields
+    /// the same result as originally observed.
+    fn validate(&self, storage: &dyn Storage) -> bool {
+        match self {
+            Read::Key { key, value } => storage.read(key) == *value,
+            Read::Scan {
+                min,
+                max,
+                order,
+                records,
+                exhausted,
+            } => {
+                let mut iter = storage.scan(min.as_deref(), max.as_deref(), *order);
+
+                for record in records {
+                    if iter.next().as_ref() != Some(record) {
+                        return false;
+                    }
+                }
+
+                // If the tx reached the end of the iterator, the iterator must
+                // still end at the same place. Otherwise, we don't care about
+                // what comes after the records that have been yielded.
+                !exhausted || iter.next().is_none()
+            },
+        }
+    }
+}
+
+/// A read-only storage that records all reads made against it.
+#[derive(Clone)]
+struct Recorder<S> {
+    base: S,
+    reads: Shared<Vec<Read>>,
+}
+
+impl<S> Recorder<S> {
+    fn new(base: S, reads: Shared<Vec<Read>>) -> Self {
+        Self { base, reads }
+    }
+}
+
+impl<S> Storage for Recorder<S>
+where
+    S: Storage + Clone,
+{
+    fn read(&self, key: &[u8]) -> Option<Vec<u8>> {
+        let value = self.base.read(key);
+
+        self.reads.write_access().push(Read::Key {
+            key: key.to_vec(),
+            value: value.clone(),
+        });
+
+        value
+    }
+
+    fn scan<'a>(
+        &'a self,
+        min: Option<&[u8]>,
+        max: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'a> {
+        let idx = self.reads.write_with(|mut reads| {
+            reads.push(Read::Scan {
+                min: min.map(|bytes| bytes.to_vec()),
+                max: max.map(|bytes| bytes.to_vec()),
+                order,
+                records: vec![],
+                exhausted: false,
+            });
+            reads.len() - 1
+        });
+
+        Box::new(RecorderIter {
+            inner: self.base.scan(min, max, order),
+            reads: self.reads.clone(),
+            idx,
+        })
+    }
+
+    // The records are recorded by `scan`, so we simply discard the values here.
+    // This may read more data than necessary from the base storage, but we need
+    // the values for validation anyways.
+    fn scan_keys<'a>(
+        &'a self,
+        min: Option<&[u8]>,
+        max: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Vec<u8>> + 'a> {
+        Box::new(self.scan(min, max, order).map(|(k, _)| k))
+    }
+
+    fn scan_values<'a>(
+        &'a self,
+        min: Option<&[u8]>,
+        max: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Vec<u8>> + 'a> {
+        Box::new(self.scan(min, max, order).map(|(_, v)| v))
+    }
+
+    // The recorder is always wrapped in a `Buffer`, which keeps all writes in
+    // memory. The buffer is disassembled, never committed, so writes never
+    // reach the recorder.
+
+    fn write(&mut self, _key: &[u8], _value: &[u8]) {
+        unreachable!("attempting to write to the state during speculative execution");
+    }
+
+    fn remove(&mut self, _key: &[u8]) {
+        unreachable!("attempting to write to the state during speculative execution");
+    }
+
+    fn remove_range(&mut self, _min: Option<&[u8]>, _max: Option<&[u8]>) {
+        unreachable!("attempting to write to the state during speculative execution");
+    }
+}
+
+struct RecorderIter<'a> {
+    inner: Box<dyn Iterator<Item = Record> + 'a>,
+    reads: Shared<Vec<Read>>,
+    idx: usize,
+}
+
+impl<'a> Iterator for RecorderIter<'a> {
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.inner.next();
+
+        self.reads.write_with(|mut reads| {
+            let Read::Scan {
+                records, exhausted, ..
+            } = &mut reads[self.idx]
+            else {
+                unreachable!("read at index {} isn't a scan", self.idx);
+            };
+
+            match &record {
+                Some(record) => records.push(record.clone()),
+                None => *exhausted = true,
+            }
+        });
+
+        record
+    }
+}
+
+// ----------------------------------- tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use {super::*, grug_types::MockStorage};
+
+    fn mock_storage() -> MockStorage {
+        let mut storage = MockStorage::new();
+        for i in 1..=5_u8 {
+            storage.write(&[i], &[i]);
+        }
+        storage
+    }
+
+    #[test]
+    fn validating_reads() {
+        let base = mock_storage();
+        let reads = Shared::new(Vec::new());
+        let recorder = Recorder::new(base.clone(), reads.clone());
+
+        // Read a key that exists and one that doesn't.
+        assert_eq!(recorder.read(&[1]), Some(vec![1]));
+        assert_eq!(recorder.read(&[9]), None);
+
+        // Iterate a range to the end, and take only the first record in
+        // another range.
+        assert_eq!(
+            recorder
+                .scan(Some(&[2]), Some(&[4]), Order::Ascending)
+                .count(),
+            2
+        );
+        assert_eq!(
+            recorder.scan(None, None, Order::Descending).next(),
+            Some((vec![5], vec![5]))
+        );
+
+        drop(recorder);
+        let reads = reads.disassemble();
+        assert_eq!(reads.len(), 4);
+
+        // Nothing has changed, so all reads should be valid.
+        assert!(reads.iter().all(|read| read.validate(&base)));
+
+        // Changing a key that wasn't observed doesn't invalidate anything.
+        let mut storage = base.clone();
+        storage.write(&[0], &[0]);
+        assert!(reads.iter().all(|read| read.validate(&storage)));
+
+        // Changing a key that was read invalidates the read.
+        let mut storage = base.clone();
+        storage.write(&[1], &[255]);
+        assert!(!reads[0].validate(&storage));
+
+        // Creating a key that was found to not exist invalidates the read.
+        let mut storage = base.clone();
+        storage.write(&[9], &[9]);
+        assert!(!reads[1].validate(&storage));
+
+        // Inserting a record in an exhausted range invalidates the scan.
+        let mut storage = base.clone();
+        storage.write(&[3, 0], &[3]);
+        assert!(!reads[2].validate(&storage));
+
+        // Inserting a record beyond the observed part of a range doesn't
+        // invalidate the scan, while inserting one within does.
+        let mut storage = base.clone();
+        storage.write(&[4, 0], &[4]);
+        assert!(reads[3].validate(&storage));
+        storage.write(&[6], &[6]);
+        assert!(!reads[3].validate(&storage));
+    }
+}
@@ -2,10 +2,11 @@ use {
     crate::{
         call_in_1_out_1, AppError, AppResult, GasTracker, MeteredItem, MeteredMap, MeteredStorage,
         StorageProvider, Vm, APP_CONFIGS, CHAIN_ID, CODES, CONFIG, CONTRACTS, CONTRACT_NAMESPACE,
+        GAS_COSTS,
     },
     grug_types::{
         Addr, BankQuery, BankQueryResponse, Binary, BlockInfo, Bound, Coin, Coins, Config, Context,
-        ContractInfo, Denom, GenericResult, Hash256, Json, Order, StdResult, Storage,
+        ContractInfo, Denom, GasCosts, Hash256, Json, Order, StdResult, Storage,
     },
     std::collections::BTreeMap,
 };
@@ -16,6 +17,14 @@ pub fn query_config(storage: &dyn Storage, gas_tracker: GasTracker) -> StdResult
     CONFIG.load_with_gas(storage, gas_tracker)
 }
 
+pub fn query_gas_costs(storage: &dyn Storage, gas_tracker: GasTracker) -> StdResult<GasCosts> {
+    // Chains initialized before gas costs became configurable don't have them
+    // in storage, and use the defaults.
+    Ok(GAS_COSTS
+        .may_load_with_gas(storage, gas_tracker)?
+        .unwrap_or_default())
+}
+
 pub fn query_app_config(
     storage: &dyn Storage,
     gas_tracker: GasTracker,
@@ -164,7 +173,7 @@ where
         mode: None,
     };
 
-    call_in_1_out_1::<_, _, GenericResult<BankQueryResponse>>(
+    call_in_1_out_1::<_, _, BankQueryResponse>(
         vm,
         storage,
         gas_tracker,
@@ -261,7 +270,7 @@ where
         mode: None,
     };
 
-    call_in_1_out_1::<_, _, GenericResult<Json>>(
+    call_in_1_out_1::<_, _, Json>(
         vm,
         storage,
         gas_tracker,
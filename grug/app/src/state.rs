@@ -1,6 +1,8 @@
 use {
     grug_storage::{Item, Map, Set},
-    grug_types::{Addr, Binary, BlockInfo, Config, ContractInfo, Hash256, Json, Timestamp},
+    grug_types::{
+        Addr, Binary, BlockInfo, Config, ContractInfo, GasCosts, Hash256, Json, Timestamp,
+    },
 };
 
 /// A string that identifies the chain
@@ -9,6 +11,12 @@ pub const CHAIN_ID: Item<String> = Item::new("chain_id");
 /// Chain-level configuration
 pub const CONFIG: Item<Config> = Item::new("config");
 
+/// Gas costs of host operations.
+///
+/// This is loaded at the beginning of each block, so updates made during a
+/// block take effect starting from the next block.
+pub const GAS_COSTS: Item<GasCosts> = Item::new("gas_costs");
+
 /// Application-specific configurations.
 pub const APP_CONFIGS: Map<&str, Json> = Map::new("app_config");
 
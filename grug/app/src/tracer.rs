@@ -0,0 +1,211 @@
+use grug_types::{CallTrace, Context, GenericResult, Json};
+
+/// Builds the [`CallTrace`]s of a transaction as contract calls are made.
+pub(crate) struct CallTracer {
+    // Calls that have been entered but not yet exited, outermost first, along
+    // with the amount of gas the transaction had used when each was entered.
+    stack: Vec<(CallTrace, u64)>,
+    // Calls that have been exited, and weren't made during another call.
+    traces: Vec<CallTrace>,
+}
+
+impl CallTracer {
+    pub(crate) fn new() -> Self {
+        Self {
+            stack: Vec::new(),
+            traces: Vec::new(),
+        }
+    }
+
+    /// Enter a new call, as a child of the current call, if any.
+    pub(crate) fn enter(
+        &mut self,
+        ctx: &Context,
+        entry_point: &'static str,
+        input: Json,
+        gas_used: u64,
+    ) {
+        let trace = CallTrace {
+            caller: ctx.sender,
+            callee: ctx.contract,
+            entry_point: entry_point.to_string(),
+            input,
+            funds: ctx.funds.clone(),
+            gas_used: 0,
+            result: GenericResult::Ok(Json::Null),
+            children: Vec::new(),
+        };
+
+        self.stack.push((trace, gas_used));
+    }
+
+    /// Record the result of the current call.
+    pub(crate) fn finish(&mut self, result: GenericResult<Json>) {
+        if let Some((trace, _)) = self.stack.last_mut() {
+            trace.result = result;
+        }
+    }
+
+    /// Exit the current call, returning to its parent, if any.
+    pub(crate) fn exit(&mut self, gas_used: u64) {
+        if let Some((mut trace, gas_used_before)) = self.stack.pop() {
+            trace.gas_used = gas_used - gas_used_before;
+            self.siblings().push(trace);
+        }
+    }
+
+    /// Enter the most recently exited child of the current call again.
+    pub(crate) fn reenter(&mut self, gas_used: u64) {
+        if let Some(trace) = self.siblings().pop() {
+            let gas_used_before = gas_used - trace.gas_used;
+            self.stack.push((trace, gas_used_before));
+        }
+    }
+
+    /// Return the traces, including calls that haven't been exited yet.
+    pub(crate) fn traces(&self) -> Vec<CallTrace> {
+        let mut stack = self.stack.clone();
+        let mut traces = self.traces.clone();
+
+        while let Some((trace, _)) = stack.pop() {
+            match stack.last_mut() {
+                Some((parent, _)) => parent.children.push(trace),
+                None => traces.push(trace),
+            }
+        }
+
+        traces
+    }
+
+    // Return the children of the current call, or the top-level traces if
+    // there isn't a current call.
+    fn siblings(&mut self) -> &mut Vec<CallTrace> {
+        match self.stack.last_mut() {
+            Some((parent, _)) => &mut parent.children,
+            None => &mut self.traces,
+        }
+    }
+}
+
+// ----------------------------------- tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        grug_types::{Addr, BlockInfo, Coins, Hash256, Timestamp},
+    };
+
+    const CONTRACT1: Addr = Addr::mock(1);
+    const CONTRACT2: Addr = Addr::mock(2);
+    const SENDER: Addr = Addr::mock(3);
+
+    fn mock_ctx(contract: Addr, sender: Option<Addr>) -> Context {
+        Context {
+            chain_id: "dev-1".to_string(),
+            block: BlockInfo {
+                height: 1,
+                timestamp: Timestamp::from_nanos(100),
+                hash: Hash256::ZERO,
+            },
+            contract,
+            sender,
+            funds: sender.map(|_| Coins::new()),
+            mode: None,
+        }
+    }
+
+    fn mock_trace(
+        contract: Addr,
+        sender: Option<Addr>,
+        entry_point: &str,
+        gas_used: u64,
+        result: GenericResult<Json>,
+        children: Vec<CallTrace>,
+    ) -> CallTrace {
+        CallTrace {
+            caller: sender,
+            callee: contract,
+            entry_point: entry_point.to_string(),
+            input: Json::Null,
+            funds: sender.map(|_| Coins::new()),
+            gas_used,
+            result,
+            children,
+        }
+    }
+
+    #[test]
+    fn tracing_works() {
+        let mut tracer = CallTracer::new();
+
+        // The sender executes contract 1, which queries contract 2.
+        tracer.enter(
+            &mock_ctx(CONTRACT1, Some(SENDER)),
+            "execute",
+            Json::Null,
+            10,
+        );
+        tracer.enter(&mock_ctx(CONTRACT2, None), "query", Json::Null, 15);
+        tracer.finish(GenericResult::Ok(Json::Null));
+        tracer.exit(20);
+        tracer.finish(GenericResult::Ok(Json::Null));
+        tracer.exit(30);
+
+        // Contract 1 emits a submessage that fails.
+        tracer.reenter(30);
+        tracer.enter(
+            &mock_ctx(CONTRACT2, Some(CONTRACT1)),
+            "execute",
+            Json::Null,
+            30,
+        );
+        tracer.finish(GenericResult::Err("oops".to_string()));
+        tracer.exit(35);
+        tracer.exit(35);
+
+        // The transaction makes another call, which hasn't been exited.
+        tracer.enter(
+            &mock_ctx(CONTRACT2, Some(SENDER)),
+            "execute",
+            Json::Null,
+            40,
+        );
+
+        assert_eq!(tracer.traces(), [
+            mock_trace(
+                CONTRACT1,
+                Some(SENDER),
+                "execute",
+                25,
+                GenericResult::Ok(Json::Null),
+                vec![
+                    mock_trace(
+                        CONTRACT2,
+                        None,
+                        "query",
+                        5,
+                        GenericResult::Ok(Json::Null),
+                        vec![],
+                    ),
+                    mock_trace(
+                        CONTRACT2,
+                        Some(CONTRACT1),
+                        "execute",
+                        5,
+                        GenericResult::Err("oops".to_string()),
+                        vec![],
+                    ),
+                ],
+            ),
+            mock_trace(
+                CONTRACT2,
+                Some(SENDER),
+                "execute",
+                0,
+                GenericResult::Ok(Json::Null),
+                vec![],
+            ),
+        ]);
+    }
+}
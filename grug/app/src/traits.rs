@@ -1,7 +1,9 @@
 use {
     crate::{GasTracker, QuerierProvider, StorageProvider},
     borsh::{BorshDeserialize, BorshSerialize},
-    grug_types::{Batch, Context, Hash256, Storage},
+    grug_types::{
+        Batch, BlockInfo, BlockOutcome, Context, Event, Hash256, Snapshot, Storage, Tx, WasmLimits,
+    },
     ics23::CommitmentProof,
 };
 
@@ -129,10 +131,50 @@ pub trait PrunableDb: Db {
     fn prune(&self, up_to_version: u64) -> Result<(), Self::Error>;
 }
 
+/// Represents a database that can export its state as snapshots, and restore
+/// its state from snapshots exported by other nodes.
+///
+/// This allows a new node to join the network without replaying all blocks
+/// from genesis, known as "state sync". These methods are only used by the ABCI
+/// state sync calls, so we split them off into a separate trait.
+pub trait SnapshotDb: Db {
+    /// Return the snapshots available in the database, which can be served to
+    /// other nodes.
+    fn list_snapshots(&self) -> Result<Vec<Snapshot>, Self::Error>;
+
+    /// Load a chunk of the snapshot of the given version and format.
+    ///
+    /// `None` if either the snapshot or the chunk doesn't exist.
+    fn load_snapshot_chunk(
+        &self,
+        version: u64,
+        format: u32,
+        index: u32,
+    ) -> Result<Option<Vec<u8>>, Self::Error>;
+
+    /// Start restoring the state from the given snapshot.
+    ///
+    /// The database must be empty. The restored state must match the given
+    /// root hash, which comes from a trusted source (typically, the app hash
+    /// of the block at the snapshot's height, verified by a light client).
+    fn offer_snapshot(&self, snapshot: Snapshot, root_hash: Hash256) -> Result<(), Self::Error>;
+
+    /// Apply a chunk of the snapshot being restored. Chunks must be applied in
+    /// order.
+    ///
+    /// Return `true` if this is the last chunk, in which case the restored
+    /// state has been verified against the root hash and the restoration is
+    /// complete.
+    fn apply_snapshot_chunk(&self, index: u32, chunk: &[u8]) -> Result<bool, Self::Error>;
+}
+
 // ------------------------------------ vm -------------------------------------
 
 /// Represents a virtual machine that can execute programs.
-pub trait Vm: Sized {
+///
+/// The VM must be `Send`, such that it can be moved into worker threads when
+/// executing transactions in parallel.
+pub trait Vm: Sized + Send {
     type Error: ToString;
     type Instance: Instance<Error = Self::Error>;
 
@@ -151,6 +193,14 @@ pub trait Vm: Sized {
         query_depth: usize,
         gas_tracker: GasTracker,
     ) -> Result<Self::Instance, Self::Error>;
+
+    /// Perform static checks on a guest program when it's uploaded, such that
+    /// invalid programs are rejected before they're stored on chain, rather
+    /// than failing later when being instantiated.
+    ///
+    /// The program must not exceed the given limits. These only apply at the
+    /// time of uploading, so must not be enforced when building instances.
+    fn validate_code(&mut self, code: &[u8], limits: &WasmLimits) -> Result<(), Self::Error>;
 }
 
 pub trait Instance {
@@ -184,3 +234,52 @@ pub trait Instance {
         P1: AsRef<[u8]>,
         P2: AsRef<[u8]>;
 }
+
+// ---------------------------------- indexer ----------------------------------
+
+/// Represents a service that records blocks and their outcomes outside of the
+/// chain's state, such that they can be queried by explorers and other
+/// off-chain services.
+///
+/// Like `Db`, the methods take an immutable reference of self (`&self`), so the
+/// implementation should use the interior mutability pattern.
+///
+/// The indexer isn't part of consensus: the app logs errors returned by it,
+/// instead of failing the ABCI call. Also note that the methods may be
+/// invoked more than once for the same block, e.g. if the node crashes before
+/// committing and replays the block after restarting. The implementation
+/// should handle this, e.g. by overwriting the existing records.
+pub trait Indexer {
+    type Error: ToString;
+
+    /// Record the genesis block, along with the events emitted by the genesis
+    /// messages.
+    ///
+    /// This is invoked at the end of the ABCI `InitChain` call. Unlike other
+    /// blocks, the genesis state is committed right away, so the record should
+    /// be persisted right away as well.
+    fn index_genesis_block(
+        &self,
+        block: &BlockInfo,
+        app_hash: Hash256,
+        events: &[Event],
+    ) -> Result<(), Self::Error>;
+
+    /// Record a block that has just been finalized, but whose state changes
+    /// haven't been committed yet. Each transaction comes with its hash, i.e.
+    /// the SHA-256 hash of its raw bytes, by which CometBFT identifies it.
+    ///
+    /// This is invoked at the end of the ABCI `FinalizeBlock` call. If it's
+    /// invoked again before `commit`, the new block replaces the previous one.
+    fn index_block(
+        &self,
+        block: &BlockInfo,
+        txs: &[(Tx, Hash256)],
+        outcome: &BlockOutcome,
+    ) -> Result<(), Self::Error>;
+
+    /// Persist the block recorded in the `index_block` method.
+    ///
+    /// This is invoked at the end of the ABCI `Commit` call.
+    fn commit(&self) -> Result<(), Self::Error>;
+}
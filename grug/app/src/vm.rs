@@ -1,13 +1,14 @@
 use {
     crate::{
         handle_submessages, AppError, AppResult, GasTracker, Instance, QuerierProvider,
-        StorageProvider, Vm, CODES, CONTRACT_ADDRESS_KEY, CONTRACT_NAMESPACE,
+        StorageProvider, Vm, CODES, CONTRACT_NAMESPACE,
     },
     borsh::{BorshDeserialize, BorshSerialize},
     grug_types::{
-        Addr, BlockInfo, BorshDeExt, BorshSerExt, Context, Event, GenericResult, Hash256, Response,
-        Storage,
+        json, Addr, BlockInfo, BorshDeExt, BorshSerExt, Context, Event, GenericResult, Hash256,
+        Json, JsonSerExt, Response, Storage,
     },
+    serde::Serialize,
 };
 
 /// Create a VM instance, and call a function that takes no input parameter and
@@ -21,7 +22,41 @@ pub fn call_in_0_out_1<VM, R>(
     name: &'static str,
     code_hash: Hash256,
     ctx: &Context,
-) -> AppResult<R>
+) -> AppResult<GenericResult<R>>
+where
+    R: BorshDeserialize + Serialize,
+    VM: Vm + Clone,
+    AppError: From<VM::Error>,
+{
+    // Record this call in a new frame, if profiling or tracing.
+    let frame = gas_tracker.enter_frame(ctx, name, || Ok(Json::Null))?;
+
+    let result = _call_in_0_out_1(
+        vm,
+        storage,
+        gas_tracker,
+        query_depth,
+        state_mutable,
+        name,
+        code_hash,
+        ctx,
+    );
+
+    frame.finish(&result);
+
+    result
+}
+
+fn _call_in_0_out_1<VM, R>(
+    vm: VM,
+    storage: Box<dyn Storage>,
+    gas_tracker: GasTracker,
+    query_depth: usize,
+    state_mutable: bool,
+    name: &'static str,
+    code_hash: Hash256,
+    ctx: &Context,
+) -> AppResult<GenericResult<R>>
 where
     R: BorshDeserialize,
     VM: Vm + Clone,
@@ -58,7 +93,44 @@ pub fn call_in_1_out_1<VM, P, R>(
     code_hash: Hash256,
     ctx: &Context,
     param: &P,
-) -> AppResult<R>
+) -> AppResult<GenericResult<R>>
+where
+    P: BorshSerialize + Serialize,
+    R: BorshDeserialize + Serialize,
+    VM: Vm + Clone,
+    AppError: From<VM::Error>,
+{
+    // Record this call in a new frame, if profiling or tracing.
+    let frame = gas_tracker.enter_frame(ctx, name, || param.to_json_value())?;
+
+    let result = _call_in_1_out_1(
+        vm,
+        storage,
+        gas_tracker,
+        query_depth,
+        state_mutable,
+        name,
+        code_hash,
+        ctx,
+        param,
+    );
+
+    frame.finish(&result);
+
+    result
+}
+
+fn _call_in_1_out_1<VM, P, R>(
+    vm: VM,
+    storage: Box<dyn Storage>,
+    gas_tracker: GasTracker,
+    query_depth: usize,
+    state_mutable: bool,
+    name: &'static str,
+    code_hash: Hash256,
+    ctx: &Context,
+    param: &P,
+) -> AppResult<GenericResult<R>>
 where
     P: BorshSerialize,
     R: BorshDeserialize,
@@ -100,7 +172,49 @@ pub fn call_in_2_out_1<VM, P1, P2, R>(
     ctx: &Context,
     param1: &P1,
     param2: &P2,
-) -> AppResult<R>
+) -> AppResult<GenericResult<R>>
+where
+    P1: BorshSerialize + Serialize,
+    P2: BorshSerialize + Serialize,
+    R: BorshDeserialize + Serialize,
+    VM: Vm + Clone,
+    AppError: From<VM::Error>,
+{
+    // Record this call in a new frame, if profiling or tracing.
+    let frame = gas_tracker.enter_frame(ctx, name, || {
+        Ok(json!([param1.to_json_value()?, param2.to_json_value()?]))
+    })?;
+
+    let result = _call_in_2_out_1(
+        vm,
+        storage,
+        gas_tracker,
+        query_depth,
+        state_mutable,
+        name,
+        code_hash,
+        ctx,
+        param1,
+        param2,
+    );
+
+    frame.finish(&result);
+
+    result
+}
+
+fn _call_in_2_out_1<VM, P1, P2, R>(
+    vm: VM,
+    storage: Box<dyn Storage>,
+    gas_tracker: GasTracker,
+    query_depth: usize,
+    state_mutable: bool,
+    name: &'static str,
+    code_hash: Hash256,
+    ctx: &Context,
+    param1: &P1,
+    param2: &P2,
+) -> AppResult<GenericResult<R>>
 where
     P1: BorshSerialize,
     P2: BorshSerialize,
@@ -149,7 +263,7 @@ where
     VM: Vm + Clone,
     AppError: From<VM::Error>,
 {
-    let response = call_in_0_out_1::<_, GenericResult<Response>>(
+    let response = call_in_0_out_1::<_, Response>(
         vm.clone(),
         storage.clone(),
         gas_tracker.clone(),
@@ -161,7 +275,20 @@ where
     )?
     .into_std_result()?;
 
-    handle_response(vm, storage, gas_tracker, msg_depth, name, ctx, response)
+    // Submessages are handled after the call has returned, but for profiling
+    // and tracing, they are considered part of the call.
+    let _frame = gas_tracker.reenter_frame();
+
+    handle_response(
+        vm,
+        storage,
+        gas_tracker,
+        msg_depth,
+        name,
+        ctx,
+        None,
+        response,
+    )
 }
 
 /// Create a VM instance, call a function that takes exactly one parameter and
@@ -180,11 +307,11 @@ pub fn call_in_1_out_1_handle_response<VM, P>(
     param: &P,
 ) -> AppResult<Vec<Event>>
 where
-    P: BorshSerialize,
+    P: BorshSerialize + Serialize,
     VM: Vm + Clone,
     AppError: From<VM::Error>,
 {
-    let response = call_in_1_out_1::<_, _, GenericResult<Response>>(
+    let response = call_in_1_out_1::<_, _, Response>(
         vm.clone(),
         storage.clone(),
         gas_tracker.clone(),
@@ -197,7 +324,22 @@ where
     )?
     .into_std_result()?;
 
-    handle_response(vm, storage, gas_tracker, msg_depth, name, ctx, response)
+    // Submessages are handled after the call has returned, but for profiling
+    // and tracing, they are considered part of the call.
+    let _frame = gas_tracker.reenter_frame();
+
+    let msg = param.to_json_value()?;
+
+    handle_response(
+        vm,
+        storage,
+        gas_tracker,
+        msg_depth,
+        name,
+        ctx,
+        Some(msg),
+        response,
+    )
 }
 
 /// Create a VM instance, call a function that takes exactly two parameter and
@@ -217,12 +359,12 @@ pub fn call_in_2_out_1_handle_response<VM, P1, P2>(
     param2: &P2,
 ) -> AppResult<Vec<Event>>
 where
-    P1: BorshSerialize,
-    P2: BorshSerialize,
+    P1: BorshSerialize + Serialize,
+    P2: BorshSerialize + Serialize,
     VM: Vm + Clone,
     AppError: From<VM::Error>,
 {
-    let response = call_in_2_out_1::<_, _, _, GenericResult<Response>>(
+    let response = call_in_2_out_1::<_, _, _, Response>(
         vm.clone(),
         storage.clone(),
         gas_tracker.clone(),
@@ -236,7 +378,22 @@ where
     )?
     .into_std_result()?;
 
-    handle_response(vm, storage, gas_tracker, msg_depth, name, ctx, response)
+    // Submessages are handled after the call has returned, but for profiling
+    // and tracing, they are considered part of the call.
+    let _frame = gas_tracker.reenter_frame();
+
+    let msg = json!([param1.to_json_value()?, param2.to_json_value()?]);
+
+    handle_response(
+        vm,
+        storage,
+        gas_tracker,
+        msg_depth,
+        name,
+        ctx,
+        Some(msg),
+        response,
+    )
 }
 
 fn create_vm_instance<VM>(
@@ -278,20 +435,16 @@ pub(crate) fn handle_response<VM>(
     msg_depth: usize,
     name: &'static str,
     ctx: &Context,
+    msg: Option<Json>,
     response: Response,
 ) -> AppResult<Vec<Event>>
 where
     VM: Vm + Clone,
     AppError: From<VM::Error>,
 {
-    // Create an event for this call
-    let event = Event::new(name)
-        .add_attribute(CONTRACT_ADDRESS_KEY, ctx.contract)
-        .add_attributes(response.attributes);
-
-    // Handle submessages; append events emitted during submessage handling
-    let mut events = vec![event];
-    events.extend(handle_submessages(
+    // Handle submessages; events emitted during submessage handling are nested
+    // under the event for this call.
+    let children = handle_submessages(
         vm,
         storage,
         ctx.block,
@@ -299,7 +452,17 @@ where
         msg_depth,
         ctx.contract,
         response.submsgs,
-    )?);
+    )?;
+
+    // Create an event for this call
+    let event = Event {
+        r#type: name.to_string(),
+        contract: Some(ctx.contract),
+        msg,
+        attributes: response.attributes,
+        contract_events: response.events,
+        children,
+    };
 
-    Ok(events)
+    Ok(vec![event])
 }
@@ -60,10 +60,11 @@ async fn main() -> anyhow::Result<()> {
     };
 
     let data_dir = app_dir.join("data");
+    let wasm_dir = app_dir.join("wasm");
 
     match cli.command {
         Command::Db(cmd) => cmd.run(data_dir),
         Command::Query(cmd) => cmd.run().await,
-        Command::Start(cmd) => cmd.run(data_dir).await,
+        Command::Start(cmd) => cmd.run(data_dir, wasm_dir).await,
     }
 }
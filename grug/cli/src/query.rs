@@ -45,6 +45,8 @@ enum SubCmd {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Query the gas costs of host operations
+    GasCosts,
     /// Query an account's balance in a single denom
     Balance {
         /// Account address
@@ -141,6 +143,7 @@ impl QueryCmd {
             SubCmd::Config => Query::Config {},
             SubCmd::AppConfig { key } => Query::AppConfig { key },
             SubCmd::AppConfigs { start_after, limit } => Query::AppConfigs { start_after, limit },
+            SubCmd::GasCosts => Query::GasCosts {},
             SubCmd::Balance { address, denom } => {
                 let denom = Denom::try_from(denom)?;
                 Query::Balance { address, denom }
@@ -1,4 +1,31 @@
-use {clap::Parser, grug_app::App, grug_db_disk::DiskDb, grug_vm_wasm::WasmVm, std::path::PathBuf};
+use {
+    clap::{Parser, ValueEnum},
+    grug_app::{App, BlockStream, Db, ExecutionMode},
+    grug_db_disk::{DiskDb, DiskDbOptions, SnapshotOptions},
+    grug_types::{Addr, Hash256},
+    grug_vm_wasm::{Compiler, WasmVm},
+    std::{
+        num::{NonZeroU64, NonZeroUsize},
+        path::PathBuf,
+        time::Duration,
+    },
+    tokio::{net::TcpListener, time},
+};
+
+#[derive(Clone, Copy, ValueEnum)]
+enum WasmCompiler {
+    Singlepass,
+    Cranelift,
+}
+
+impl From<WasmCompiler> for Compiler {
+    fn from(compiler: WasmCompiler) -> Self {
+        match compiler {
+            WasmCompiler::Singlepass => Compiler::Singlepass,
+            WasmCompiler::Cranelift => Compiler::Cranelift,
+        }
+    }
+}
 
 #[derive(Parser)]
 pub struct StartCmd {
@@ -6,6 +33,14 @@ pub struct StartCmd {
     #[arg(long, default_value = "127.0.0.1:26658")]
     abci_addr: String,
 
+    /// Listening address of the HTTP server for queries, simulations, and block subscriptions; the server is not started if not provided
+    #[arg(long)]
+    http_addr: Option<String>,
+
+    /// Number of blocks to buffer for each websocket subscriber that falls behind
+    #[arg(long, default_value = "100")]
+    http_block_buffer: NonZeroUsize,
+
     /// Size of the read buffer for each incoming connection to the ABCI server, in bytes
     #[arg(long, default_value = "1048576")]
     read_buf_size: usize,
@@ -14,17 +49,142 @@ pub struct StartCmd {
     #[arg(long, default_value = "1000")]
     wasm_cache_capacity: usize,
 
+    /// Compiler for wasm modules; singlepass compiles faster, while the others produce faster code
+    #[arg(long, value_enum, default_value = "singlepass")]
+    wasm_compiler: WasmCompiler,
+
+    /// Persist compiled wasm modules to disk, so that they don't need to be recompiled after a restart
+    #[arg(long)]
+    wasm_disk_cache: bool,
+
+    /// Contracts whose wasm modules are to be kept in the cache permanently, in addition to the bank and taxman
+    #[arg(long, value_delimiter = ',')]
+    wasm_pinned_contracts: Vec<Addr>,
+
+    /// Hashes of codes whose wasm modules are to be kept in the cache permanently, such as those of user accounts
+    #[arg(long, value_delimiter = ',')]
+    wasm_pinned_codes: Vec<Hash256>,
+
+    /// Log statistics of the wasm module cache every this many seconds, which helps with choosing the cache capacity; zero means do not log
+    #[arg(long, default_value = "0")]
+    wasm_cache_metrics_interval: u64,
+
     /// Gas limit when serving query requests [default: u64::MAX]
     #[arg(long)]
     query_gas_limit: Option<u64>,
+
+    /// Number of threads for executing transactions in parallel; zero means to execute sequentially
+    #[arg(long, default_value = "0")]
+    execution_workers: usize,
+
+    /// Create a state sync snapshot every this many blocks; zero means do not create snapshots
+    #[arg(long, default_value = "0")]
+    snapshot_interval: u64,
+
+    /// Number of most recent state sync snapshots to keep
+    #[arg(long, default_value = "2")]
+    snapshot_keep_recent: NonZeroUsize,
+
+    /// Size of each state sync snapshot chunk, in bytes
+    #[arg(long, default_value = "10485760")]
+    snapshot_chunk_size: NonZeroUsize,
+
+    /// Write finalized but uncommitted state changes to a write-ahead log, so that they survive a crash
+    #[arg(long)]
+    wal: bool,
+
+    /// Maximum number of committed blocks waiting to be written to disk by a background thread; zero means to write synchronously
+    #[arg(long, default_value = "0")]
+    async_commit_queue: usize,
 }
 
 impl StartCmd {
-    pub async fn run(self, data_dir: PathBuf) -> anyhow::Result<()> {
-        let db = DiskDb::open(data_dir)?;
-        let vm = WasmVm::new(self.wasm_cache_capacity);
-        let app = App::new(db, vm, self.query_gas_limit.unwrap_or(u64::MAX));
+    pub async fn run(self, data_dir: PathBuf, wasm_dir: PathBuf) -> anyhow::Result<()> {
+        let snapshots = NonZeroU64::new(self.snapshot_interval).map(|interval| SnapshotOptions {
+            interval,
+            keep_recent: self.snapshot_keep_recent,
+            chunk_size: self.snapshot_chunk_size,
+        });
+        let db = DiskDb::open_with_options(data_dir, DiskDbOptions {
+            snapshots,
+            wal: self.wal,
+            async_commit: NonZeroUsize::new(self.async_commit_queue),
+        })?;
+
+        let mut vm = WasmVm::new_with_compiler(self.wasm_cache_capacity, self.wasm_compiler.into());
+
+        if self.wasm_disk_cache {
+            vm = vm.with_disk_cache(wasm_dir)?;
+        }
+
+        vm.warm_up(
+            &db.state_storage(None)?,
+            &self.wasm_pinned_contracts,
+            &self.wasm_pinned_codes,
+        )?;
+
+        if self.wasm_cache_metrics_interval > 0 {
+            tokio::spawn(log_cache_metrics(
+                vm.clone(),
+                Duration::from_secs(self.wasm_cache_metrics_interval),
+            ));
+        }
+
+        let mut app = App::new(db, vm, self.query_gas_limit.unwrap_or(u64::MAX));
+
+        if let Some(workers) = NonZeroUsize::new(self.execution_workers) {
+            app.set_execution_mode(ExecutionMode::Parallel { workers });
+        }
+
+        let Some(http_addr) = self.http_addr else {
+            return Ok(app.start_abci_server(self.read_buf_size, self.abci_addr)?);
+        };
+
+        let blocks = BlockStream::new(self.http_block_buffer);
+        let app = app.with_indexer(blocks.clone());
+        let listener = TcpListener::bind(http_addr).await?;
+
+        let http_app = app.clone();
+
+        tokio::spawn(async move {
+            if let Err(err) = http_app.start_http_server(listener, blocks).await {
+                tracing::error!(err = err.to_string(), "HTTP server stopped");
+            }
+        });
 
         Ok(app.start_abci_server(self.read_buf_size, self.abci_addr)?)
     }
 }
+
+async fn log_cache_metrics(vm: WasmVm, interval: Duration) {
+    let mut interval = time::interval(interval);
+
+    loop {
+        interval.tick().await;
+
+        let Some(metrics) = vm.cache_metrics() else {
+            return;
+        };
+
+        tracing::info!(
+            capacity = metrics.capacity,
+            len = metrics.len(),
+            pinned = metrics.pinned(),
+            size = metrics.size(),
+            hits = metrics.hits,
+            misses = metrics.misses,
+            "Wasm cache metrics"
+        );
+
+        for (code_hash, entry) in metrics.entries {
+            tracing::debug!(
+                code_hash = code_hash.to_string(),
+                pinned = entry.pinned,
+                hits = entry.hits,
+                build_time_ms = entry.build_time.as_millis(),
+                size = entry.size,
+                "Wasm cache entry"
+            );
+        }
+    }
+}
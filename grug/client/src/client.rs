@@ -4,7 +4,7 @@ use {
     grug_jmt::Proof,
     grug_types::{
         Addr, AsyncSigner, Binary, Coin, Coins, Config, ConfigUpdates, ContractInfo, Denom,
-        GenericResult, Hash256, HashExt, Json, JsonDeExt, JsonSerExt, Message, Op, Query,
+        GasCosts, GenericResult, Hash256, HashExt, Json, JsonDeExt, JsonSerExt, Message, Op, Query,
         QueryResponse, StdError, Tx, TxOutcome, UnsignedTx,
     },
     serde::{de::DeserializeOwned, ser::Serialize},
@@ -176,6 +176,13 @@ impl Client {
             .map(|res| res.as_config())
     }
 
+    /// Query the gas costs of host operations.
+    pub async fn query_gas_costs(&self, height: Option<u64>) -> anyhow::Result<GasCosts> {
+        self.query_app(&Query::GasCosts {}, height)
+            .await
+            .map(|res| res.as_gas_costs())
+    }
+
     /// Query an account's balance in a single denom.
     pub async fn query_balance(
         &self,
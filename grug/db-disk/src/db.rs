@@ -1,6 +1,10 @@
 use {
-    crate::{DbError, DbResult, U64Comparator, U64Timestamp},
-    grug_app::{Buffer, Db, PrunableDb},
+    crate::{
+        wal_key, AsyncCommit, DbError, DbResult, PeriodicSnapshots, Restoration, Snapshotter,
+        U64Comparator, U64Timestamp, Writer,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    grug_app::{Buffer, Db, Merged, PrunableDb},
     grug_jmt::{MerkleTree, Proof, ICS23_PROOF_SPEC},
     grug_types::{Batch, Hash256, HashExt, Op, Order, Record, Storage},
     ics23::{
@@ -13,24 +17,27 @@ use {
     },
     std::{
         collections::BTreeMap,
+        iter,
+        num::NonZeroUsize,
+        ops::Bound,
         path::Path,
-        sync::{Arc, RwLock},
+        sync::{Arc, Mutex, RwLock},
     },
 };
 
-/// We use three column families (CFs) for storing data.
-/// The default family is used for metadata. Currently the only metadata we have
-/// is the latest version.
+/// We use four column families (CFs) for storing data.
+/// The default family is used for metadata, such as the latest and oldest
+/// versions, as well as the write-ahead log (WAL), if enabled.
 const CF_NAME_DEFAULT: &str = "default";
 
 /// The preimage column family maps key hashes to raw keys. This is necessary
 /// for generating ICS-23 compatible Merkle proofs.
-const CF_NAME_PREIMAGES: &str = "preimages";
+pub(crate) const CF_NAME_PREIMAGES: &str = "preimages";
 
 /// The state commitment (SC) family stores Merkle tree nodes, which hold hashed
 /// key-value pair data. We use this CF for deriving the Merkle root hash for the
 /// state (used in consensus) and generating Merkle proofs (used in light clients).
-const CF_NAME_STATE_COMMITMENT: &str = "state_commitment";
+pub(crate) const CF_NAME_STATE_COMMITMENT: &str = "state_commitment";
 
 /// The state storage (SS) family stores raw, prehash key-value pair data.
 /// When performing normal read/write/remove/scan interactions, we use this CF.
@@ -42,16 +49,25 @@ const CF_NAME_STATE_COMMITMENT: &str = "state_commitment";
 /// Unfortunately the Rust API for RocksDB does not support timestamping,
 /// we have to add it in. Our fork is here, under the `0.21.0-cw` branch:
 /// https://github.com/left-curve/rust-rocksdb/tree/v0.21.0-cw
-const CF_NAME_STATE_STORAGE: &str = "state_storage";
+pub(crate) const CF_NAME_STATE_STORAGE: &str = "state_storage";
+
+/// The snapshots family stores state sync snapshots, both their metadata and
+/// their chunks. Chunks are keyed by their hashes, so that chunks shared by
+/// multiple snapshots are only stored once.
+const CF_NAME_SNAPSHOTS: &str = "snapshots";
 
 /// Storage key for the latest version.
-const LATEST_VERSION_KEY: &[u8] = b"latest_version";
+pub(crate) const LATEST_VERSION_KEY: &[u8] = b"latest_version";
 
 /// Storage key for the oldest version.
-const OLDEST_VERSION_KEY: &[u8] = b"oldest_version";
+pub(crate) const OLDEST_VERSION_KEY: &[u8] = b"oldest_version";
+
+/// Prefix of the storage keys for the write-ahead log (WAL). Each entry is
+/// keyed by this prefix followed by the version in big endian.
+pub(crate) const WAL_PREFIX: &[u8] = b"wal";
 
 /// Jellyfish Merkle tree (JMT) using default namespaces.
-const MERKLE_TREE: MerkleTree = MerkleTree::new_default();
+pub(crate) const MERKLE_TREE: MerkleTree = MerkleTree::new_default();
 
 /// The base storage primitive.
 ///
@@ -73,35 +89,87 @@ const MERKLE_TREE: MerkleTree = MerkleTree::new_default();
 /// Our design mostly resembles Sei's with the differences being that:
 /// - we use a binary Jellyfish Merkle tree (JMT) instead of IAVL;
 /// - we store JMT data in a RocksDB instance, instead of using memory map (mmap);
-/// - we don't have asynchronous commit;
-/// - we don't store snapshots or use a WAL to recover the latest state.
+/// - our WAL, which is optional, only holds versions that have been flushed
+///   but not yet written to the physical database, instead of a sequence of
+///   change sets.
 ///
 /// These differences are not because we don't agree with Sei's approach...
 /// it's just because we're having here is sort of a quick hack and we don't
 /// have time to look into those advanced features yet. We will keep experimenting
 /// and maybe our implementation will converge with Sei's some time later.
 pub struct DiskDb {
-    inner: Arc<DiskDbInner>,
+    pub(crate) inner: Arc<DiskDbInner>,
+    // Handle to the background writer thread, if asynchronous commit is
+    // enabled. The thread is stopped once all handles are dropped.
+    pub(crate) writer: Option<Arc<Writer>>,
+    // Handle to the background thread that creates state sync snapshots, if
+    // enabled. The thread is stopped once all handles are dropped.
+    pub(crate) snapshotter: Option<Arc<Snapshotter>>,
 }
 
-struct DiskDbInner {
-    db: DBWithThreadMode<MultiThreaded>,
+pub(crate) struct DiskDbInner {
+    pub(crate) db: DBWithThreadMode<MultiThreaded>,
     // Data that are ready to be persisted to the physical database.
     // Ideally we want to just use a `rocksdb::WriteBatch` here, but it's not
     // thread-safe.
-    pending_data: RwLock<Option<PendingData>>,
+    pub(crate) pending_data: RwLock<Option<PendingData>>,
+    // If set, state sync snapshots are periodically created by a background
+    // thread.
+    pub(crate) snapshots: Option<PeriodicSnapshots>,
+    // Whether to durably write pending data to the WAL before committing.
+    pub(crate) wal: bool,
+    // If set, committed data are written to the physical database by a
+    // background thread.
+    pub(crate) async_commit: Option<AsyncCommit>,
+    // The state sync snapshot currently being restored, if any.
+    pub(crate) restoration: Mutex<Option<Restoration>>,
 }
 
+#[derive(BorshSerialize, BorshDeserialize)]
 pub(crate) struct PendingData {
-    version: u64,
-    state_commitment: Batch,
-    state_storage: Batch,
-    preimages: BTreeMap<Hash256, Vec<u8>>,
+    pub(crate) version: u64,
+    pub(crate) state_commitment: Batch,
+    pub(crate) state_storage: Batch,
+    pub(crate) preimages: BTreeMap<Hash256, Vec<u8>>,
+}
+
+/// Configurations for opening a `DiskDb`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DiskDbOptions {
+    /// If set, state sync snapshots are periodically created as new versions
+    /// are committed.
+    pub snapshots: Option<SnapshotOptions>,
+    /// If true, data that have been flushed but not yet committed are durably
+    /// written to a write-ahead log (WAL), so that if the process crashes
+    /// before committing, they are recovered the next time the DB is opened.
+    pub wal: bool,
+    /// If set, `commit` hands the data over to a background thread to be
+    /// written to the physical database, and returns without waiting for the
+    /// write to finish, unless this many versions are already waiting to be
+    /// written, in which case it blocks until one of them is.
+    ///
+    /// Before being written, the data are served from memory. Once all handles
+    /// to the DB are dropped, the thread finishes writing all of them before
+    /// stopping.
+    pub async_commit: Option<NonZeroUsize>,
 }
 
 impl DiskDb {
     /// Create a DiskDb instance by opening a physical RocksDB instance.
     pub fn open<P>(data_dir: P) -> DbResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::open_with_options(data_dir, DiskDbOptions::default())
+    }
+
+    /// Create a DiskDb instance by opening a physical RocksDB instance, with
+    /// the given configurations.
+    ///
+    /// If the WAL contains versions that were flushed but not written before
+    /// the process exited, they are written here. This happens regardless of
+    /// whether WAL is enabled in the options.
+    pub fn open_with_options<P>(data_dir: P, opts: DiskDbOptions) -> DbResult<Self>
     where
         P: AsRef<Path>,
     {
@@ -112,14 +180,88 @@ impl DiskDb {
             (CF_NAME_PREIMAGES, new_cf_options_with_ts()),
             (CF_NAME_STATE_STORAGE, new_cf_options_with_ts()),
             (CF_NAME_STATE_COMMITMENT, Options::default()),
+            (CF_NAME_SNAPSHOTS, Options::default()),
         ])?;
 
-        Ok(Self {
+        let mut db = Self {
             inner: Arc::new(DiskDbInner {
                 db,
                 pending_data: RwLock::new(None),
+                snapshots: opts.snapshots.map(PeriodicSnapshots::new),
+                wal: opts.wal,
+                async_commit: opts.async_commit.map(AsyncCommit::new),
+                restoration: Mutex::new(None),
             }),
-        })
+            writer: None,
+            snapshotter: None,
+        };
+
+        db.recover_from_wal()?;
+
+        // Only start the writer after the recovery is done, so that recovered
+        // versions are written synchronously.
+        if db.inner.async_commit.is_some() {
+            db.writer = Some(Arc::new(Writer::spawn(Arc::clone(&db.inner))));
+        }
+
+        if db.inner.snapshots.is_some() {
+            db.snapshotter = Some(Arc::new(Snapshotter::spawn(Arc::clone(&db.inner))));
+        }
+
+        Ok(db)
+    }
+
+    /// Write a version of data to the physical database, and schedule a state
+    /// sync snapshot if it's due.
+    pub(crate) fn write_pending(&self, pending: &PendingData) -> DbResult<()> {
+        let mut batch = WriteBatch::default();
+        let version = pending.version;
+        let ts = U64Timestamp::from(version);
+
+        // Set the new version (note: use little endian)
+        let cf = cf_default(&self.inner.db);
+        batch.put_cf(&cf, LATEST_VERSION_KEY, version.to_le_bytes());
+
+        // Delete the WAL, atomically with the data being written, such that
+        // the version is never written twice.
+        batch.delete_cf(&cf, wal_key(version));
+
+        // Writes in preimages (note: don't forget timestamping)
+        let cf = cf_preimages(&self.inner.db);
+        for (key_hash, key) in &pending.preimages {
+            batch.put_cf_with_ts(&cf, key_hash, ts, key);
+        }
+
+        // Writes in state commitment
+        let cf = cf_state_commitment(&self.inner.db);
+        for (key, op) in &pending.state_commitment {
+            if let Op::Insert(value) = op {
+                batch.put_cf(&cf, key, value);
+            } else {
+                batch.delete_cf(&cf, key);
+            }
+        }
+
+        // Writes in state storage (note: don't forget timestamping)
+        let cf = cf_state_storage(&self.inner.db);
+        for (key, op) in &pending.state_storage {
+            if let Op::Insert(value) = op {
+                batch.put_cf_with_ts(&cf, key, ts, value);
+            } else {
+                batch.delete_cf_with_ts(&cf, key, ts);
+            }
+        }
+
+        self.inner.db.write(batch)?;
+
+        // Schedule a snapshot if it's due. It's created by a background thread
+        // once the version has been written, so that committing doesn't wait
+        // for the whole state to be exported.
+        if let Some(snapshots) = &self.inner.snapshots {
+            snapshots.schedule(version);
+        }
+
+        Ok(())
     }
 }
 
@@ -127,6 +269,8 @@ impl Clone for DiskDb {
     fn clone(&self) -> Self {
         Self {
             inner: Arc::clone(&self.inner),
+            writer: self.writer.clone(),
+            snapshotter: self.snapshotter.clone(),
         }
     }
 }
@@ -138,8 +282,20 @@ impl Db for DiskDb {
     type StateStorage = StateStorage;
 
     fn state_commitment(&self) -> StateCommitment {
+        // Include the Merkle tree nodes that haven't been written yet.
+        let overlay = self.inner.async_commit.as_ref().and_then(|async_commit| {
+            async_commit
+                .queue
+                .lock()
+                .unwrap_or_else(|err| {
+                    panic!("failed to read from write queue: {err}");
+                })
+                .state_commitment_overlay()
+        });
+
         StateCommitment {
             inner: Arc::clone(&self.inner),
+            overlay,
         }
     }
 
@@ -176,13 +332,32 @@ impl Db for DiskDb {
             }
         }
 
+        // If the version hasn't been fully written yet, include the data that
+        // are yet to be written.
+        let overlay = match &self.inner.async_commit {
+            Some(async_commit) => async_commit.queue.lock()?.state_storage_overlay(version),
+            None => None,
+        };
+
         Ok(StateStorage {
             inner: Arc::clone(&self.inner),
             version,
+            overlay,
         })
     }
 
     fn latest_version(&self) -> Option<u64> {
+        // If there are versions waiting to be written, the newest of them is
+        // the latest version.
+        if let Some(async_commit) = &self.inner.async_commit {
+            let queue = async_commit.queue.lock().unwrap_or_else(|err| {
+                panic!("failed to read from write queue: {err}");
+            });
+            if let Some(version) = queue.newest_version() {
+                return Some(version);
+            }
+        }
+
         let cf = cf_default(&self.inner.db);
         let bytes = self
             .inner
@@ -216,6 +391,13 @@ impl Db for DiskDb {
         version: Option<u64>,
     ) -> Result<CommitmentProof, Self::Error> {
         let version = version.unwrap_or_else(|| self.latest_version().unwrap_or(0));
+
+        // Preimages are only read from the physical database, so the version
+        // must have been written.
+        if let Some(async_commit) = &self.inner.async_commit {
+            async_commit.wait_until_written(version)?;
+        }
+
         let state_storage = self.state_storage(Some(version))?;
         let state_commitment = self.state_commitment();
 
@@ -311,12 +493,20 @@ impl Db for DiskDb {
             .map(|key| (key.hash256(), key.clone()))
             .collect();
 
-        *(self.inner.pending_data.write()?) = Some(PendingData {
+        let pending = PendingData {
             version: new_version,
             state_commitment: pending,
             state_storage: batch,
             preimages,
-        });
+        };
+
+        // If enabled, durably write the pending data to the WAL, before
+        // returning the root hash to the caller.
+        if self.inner.wal {
+            self.write_wal(&pending)?;
+        }
+
+        *(self.inner.pending_data.write()?) = Some(pending);
 
         Ok((new_version, root_hash))
     }
@@ -328,40 +518,11 @@ impl Db for DiskDb {
             .write()?
             .take()
             .ok_or(DbError::PendingDataNotSet)?;
-        let mut batch = WriteBatch::default();
-        let ts = U64Timestamp::from(pending.version);
 
-        // Set the new version (note: use little endian)
-        let cf = cf_default(&self.inner.db);
-        batch.put_cf(&cf, LATEST_VERSION_KEY, pending.version.to_le_bytes());
-
-        // Writes in preimages (note: don't forget timestamping)
-        let cf = cf_preimages(&self.inner.db);
-        for (key_hash, key) in pending.preimages {
-            batch.put_cf_with_ts(&cf, key_hash, ts, key);
+        match &self.inner.async_commit {
+            Some(async_commit) => async_commit.enqueue(pending),
+            None => self.write_pending(&pending),
         }
-
-        // Writes in state commitment
-        let cf = cf_state_commitment(&self.inner.db);
-        for (key, op) in pending.state_commitment {
-            if let Op::Insert(value) = op {
-                batch.put_cf(&cf, key, value);
-            } else {
-                batch.delete_cf(&cf, key);
-            }
-        }
-
-        // Writes in state storage (note: don't forget timestamping)
-        let cf = cf_state_storage(&self.inner.db);
-        for (key, op) in pending.state_storage {
-            if let Op::Insert(value) = op {
-                batch.put_cf_with_ts(&cf, key, ts, value);
-            } else {
-                batch.delete_cf_with_ts(&cf, key, ts);
-            }
-        }
-
-        Ok(self.inner.db.write(batch)?)
     }
 }
 
@@ -385,6 +546,18 @@ impl PrunableDb for DiskDb {
     }
 
     fn prune(&self, up_to_version: u64) -> DbResult<()> {
+        // Pruning reads from and writes to the physical database directly, so
+        // wait for all pending writes to finish first.
+        if let Some(async_commit) = &self.inner.async_commit {
+            async_commit.wait_until_written(u64::MAX)?;
+        }
+
+        // Creating a snapshot reads the state at the snapshot's version, which
+        // may be among those to be pruned, so wait for it to finish as well.
+        if let Some(snapshots) = &self.inner.snapshots {
+            snapshots.wait_until_created();
+        }
+
         let ts = U64Timestamp::from(up_to_version);
 
         // Prune state storage.
@@ -430,18 +603,26 @@ impl PrunableDb for DiskDb {
 
 pub struct StateCommitment {
     inner: Arc<DiskDbInner>,
+    // Merkle tree nodes in versions that have been committed but not yet
+    // written to the physical database.
+    overlay: Option<Arc<Batch>>,
 }
 
 impl Clone for StateCommitment {
     fn clone(&self) -> Self {
         Self {
             inner: Arc::clone(&self.inner),
+            overlay: self.overlay.clone(),
         }
     }
 }
 
 impl Storage for StateCommitment {
     fn read(&self, key: &[u8]) -> Option<Vec<u8>> {
+        if let Some(op) = self.overlay.as_ref().and_then(|overlay| overlay.get(key)) {
+            return read_op(op);
+        }
+
         self.inner
             .db
             .get_cf(&cf_state_commitment(&self.inner.db), key)
@@ -468,7 +649,7 @@ impl Storage for StateCommitment {
                 });
                 (k.to_vec(), v.to_vec())
             });
-        Box::new(iter)
+        merge_overlay(iter, self.overlay.as_deref(), min, max, order)
     }
 
     fn scan_keys<'a>(
@@ -477,6 +658,10 @@ impl Storage for StateCommitment {
         max: Option<&[u8]>,
         order: Order,
     ) -> Box<dyn Iterator<Item = Vec<u8>> + 'a> {
+        if self.overlay.is_some() {
+            return Box::new(self.scan(min, max, order).map(|(k, _)| k));
+        }
+
         let opts = new_read_options(None, min, max);
         let mode = into_iterator_mode(order);
         let iter = self
@@ -498,6 +683,10 @@ impl Storage for StateCommitment {
         max: Option<&[u8]>,
         order: Order,
     ) -> Box<dyn Iterator<Item = Vec<u8>> + 'a> {
+        if self.overlay.is_some() {
+            return Box::new(self.scan(min, max, order).map(|(_, v)| v));
+        }
+
         let opts = new_read_options(None, min, max);
         let mode = into_iterator_mode(order);
         let iter = self
@@ -530,12 +719,19 @@ impl Storage for StateCommitment {
 
 #[derive(Clone)]
 pub struct StateStorage {
-    inner: Arc<DiskDbInner>,
-    version: u64,
+    pub(crate) inner: Arc<DiskDbInner>,
+    pub(crate) version: u64,
+    // Changes in versions up to `version` that have been committed but not yet
+    // written to the physical database.
+    pub(crate) overlay: Option<Arc<Batch>>,
 }
 
 impl Storage for StateStorage {
     fn read(&self, key: &[u8]) -> Option<Vec<u8>> {
+        if let Some(op) = self.overlay.as_ref().and_then(|overlay| overlay.get(key)) {
+            return read_op(op);
+        }
+
         let opts = new_read_options(Some(self.version), None, None);
         self.inner
             .db
@@ -563,7 +759,7 @@ impl Storage for StateStorage {
                 });
                 (k.to_vec(), v.to_vec())
             });
-        Box::new(iter)
+        merge_overlay(iter, self.overlay.as_deref(), min, max, order)
     }
 
     fn scan_keys<'a>(
@@ -572,6 +768,10 @@ impl Storage for StateStorage {
         max: Option<&[u8]>,
         order: Order,
     ) -> Box<dyn Iterator<Item = Vec<u8>> + 'a> {
+        if self.overlay.is_some() {
+            return Box::new(self.scan(min, max, order).map(|(k, _)| k));
+        }
+
         let opts = new_read_options(Some(self.version), min, max);
         let mode = into_iterator_mode(order);
         let iter = self
@@ -593,6 +793,10 @@ impl Storage for StateStorage {
         max: Option<&[u8]>,
         order: Order,
     ) -> Box<dyn Iterator<Item = Vec<u8>> + 'a> {
+        if self.overlay.is_some() {
+            return Box::new(self.scan(min, max, order).map(|(_, v)| v));
+        }
+
         let opts = new_read_options(Some(self.version), min, max);
         let mode = into_iterator_mode(order);
         let iter = self
@@ -623,6 +827,47 @@ impl Storage for StateStorage {
 
 // ---------------------------------- helpers ----------------------------------
 
+#[inline]
+fn read_op(op: &Op) -> Option<Vec<u8>> {
+    match op {
+        Op::Insert(value) => Some(value.clone()),
+        Op::Delete => None,
+    }
+}
+
+/// Merge records read from the physical database with the in-memory overlay
+/// of changes that haven't been written yet, if any.
+fn merge_overlay<'a, I>(
+    base: I,
+    overlay: Option<&'a Batch>,
+    min: Option<&[u8]>,
+    max: Option<&[u8]>,
+    order: Order,
+) -> Box<dyn Iterator<Item = Record> + 'a>
+where
+    I: Iterator<Item = Record> + 'a,
+{
+    let Some(overlay) = overlay else {
+        return Box::new(base);
+    };
+
+    if let (Some(min), Some(max)) = (min, max) {
+        if min > max {
+            return Box::new(iter::empty());
+        }
+    }
+
+    let min = min.map_or(Bound::Unbounded, |bytes| Bound::Included(bytes.to_vec()));
+    let max = max.map_or(Bound::Unbounded, |bytes| Bound::Excluded(bytes.to_vec()));
+    let overlay_raw = overlay.range((min, max));
+    let overlay: Box<dyn Iterator<Item = _>> = match order {
+        Order::Ascending => Box::new(overlay_raw),
+        Order::Descending => Box::new(overlay_raw.rev()),
+    };
+
+    Box::new(Merged::new(base, overlay, order))
+}
+
 #[inline]
 fn into_iterator_mode(order: Order) -> IteratorMode<'static> {
     match order {
@@ -641,7 +886,7 @@ fn new_db_options() -> Options {
     opts
 }
 
-fn new_cf_options_with_ts() -> Options {
+pub(crate) fn new_cf_options_with_ts() -> Options {
     let mut opts = Options::default();
     // Must use a timestamp-enabled comparator
     opts.set_comparator_with_ts(
@@ -654,7 +899,7 @@ fn new_cf_options_with_ts() -> Options {
     opts
 }
 
-fn new_read_options(
+pub(crate) fn new_read_options(
     version: Option<u64>,
     iterate_lower_bound: Option<&[u8]>,
     iterate_upper_bound: Option<&[u8]>,
@@ -672,30 +917,36 @@ fn new_read_options(
     opts
 }
 
-fn cf_default(db: &DBWithThreadMode<MultiThreaded>) -> Arc<BoundColumnFamily> {
+pub(crate) fn cf_default(db: &DBWithThreadMode<MultiThreaded>) -> Arc<BoundColumnFamily> {
     db.cf_handle(CF_NAME_DEFAULT).unwrap_or_else(|| {
         panic!("failed to find default column family");
     })
 }
 
-fn cf_preimages(db: &DBWithThreadMode<MultiThreaded>) -> Arc<BoundColumnFamily> {
+pub(crate) fn cf_preimages(db: &DBWithThreadMode<MultiThreaded>) -> Arc<BoundColumnFamily> {
     db.cf_handle(CF_NAME_PREIMAGES).unwrap_or_else(|| {
         panic!("failed to find default column family");
     })
 }
 
-fn cf_state_storage(db: &DBWithThreadMode<MultiThreaded>) -> Arc<BoundColumnFamily> {
+pub(crate) fn cf_state_storage(db: &DBWithThreadMode<MultiThreaded>) -> Arc<BoundColumnFamily> {
     db.cf_handle(CF_NAME_STATE_STORAGE).unwrap_or_else(|| {
         panic!("failed to find state storage column family");
     })
 }
 
-fn cf_state_commitment(db: &DBWithThreadMode<MultiThreaded>) -> Arc<BoundColumnFamily> {
+pub(crate) fn cf_state_commitment(db: &DBWithThreadMode<MultiThreaded>) -> Arc<BoundColumnFamily> {
     db.cf_handle(CF_NAME_STATE_COMMITMENT).unwrap_or_else(|| {
         panic!("failed to find state commitment column family");
     })
 }
 
+pub(crate) fn cf_snapshots(db: &DBWithThreadMode<MultiThreaded>) -> Arc<BoundColumnFamily> {
+    db.cf_handle(CF_NAME_SNAPSHOTS).unwrap_or_else(|| {
+        panic!("failed to find snapshots column family");
+    })
+}
+
 // ----------------------------------- test ------------------------------------
 
 #[cfg(test)]
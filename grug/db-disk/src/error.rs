@@ -1,8 +1,8 @@
 use {
-    crate::PendingData,
+    crate::{PendingData, Restoration, WriteQueue},
     grug_app::AppError,
-    grug_types::StdError,
-    std::sync::{PoisonError, RwLockReadGuard, RwLockWriteGuard},
+    grug_types::{Hash256, StdError},
+    std::sync::{MutexGuard, PoisonError, RwLockReadGuard, RwLockWriteGuard},
     thiserror::Error,
 };
 
@@ -28,6 +28,59 @@ pub enum DbError {
 
     #[error("requested version ({version}) is older than the oldest available version ({oldest_version})")]
     VersionTooOld { version: u64, oldest_version: u64 },
+
+    #[error("mutex for the snapshot restoration is poisoned")]
+    RestorationPoisoned,
+
+    #[error("unsupported snapshot format: {format}")]
+    UnsupportedSnapshotFormat { format: u32 },
+
+    #[error("snapshot doesn't contain any chunk")]
+    EmptySnapshot,
+
+    #[error(
+        "cannot restore from snapshot when the DB is not empty; latest version: {latest_version}"
+    )]
+    RestoreIntoNonEmptyDb { latest_version: u64 },
+
+    #[error("cannot apply snapshot chunk when no snapshot is being restored")]
+    RestorationNotStarted,
+
+    #[error("unexpected snapshot chunk! expecting: {expect}, actual: {actual}")]
+    UnexpectedSnapshotChunk { expect: u32, actual: u32 },
+
+    #[error("snapshot chunk hash mismatch! index: {index}, expecting: {expect}, actual: {actual}")]
+    SnapshotChunkHashMismatch {
+        index: u32,
+        expect: Hash256,
+        actual: Hash256,
+    },
+
+    #[error("snapshot contains an item of unknown type: {tag}")]
+    UnknownSnapshotItem { tag: u8 },
+
+    #[error("snapshot ends with an incomplete item")]
+    IncompleteSnapshotItem,
+
+    #[error(
+        "root hash of the restored state doesn't match! expecting: {expect}, actual: {actual:?}"
+    )]
+    SnapshotRootHashMismatch {
+        expect: Hash256,
+        actual: Option<Hash256>,
+    },
+
+    #[error("node hash in the restored Merkle tree doesn't match! expecting: {expect}, actual: {actual}")]
+    SnapshotNodeHashMismatch { expect: Hash256, actual: Hash256 },
+
+    #[error("restored state storage doesn't match the restored Merkle tree")]
+    SnapshotStateMismatch,
+
+    #[error("mutex for the write queue is poisoned")]
+    WriteQueuePoisoned,
+
+    #[error("background writer failed to write to the physical database: {reason}")]
+    AsyncWriteFailed { reason: String },
 }
 
 impl<'a> From<PoisonError<RwLockReadGuard<'a, Option<PendingData>>>> for DbError {
@@ -42,9 +95,40 @@ impl<'a> From<PoisonError<RwLockWriteGuard<'a, Option<PendingData>>>> for DbErro
     }
 }
 
+impl<'a> From<PoisonError<MutexGuard<'a, Option<Restoration>>>> for DbError {
+    fn from(_: PoisonError<MutexGuard<'a, Option<Restoration>>>) -> Self {
+        Self::RestorationPoisoned
+    }
+}
+
+impl<'a> From<PoisonError<MutexGuard<'a, WriteQueue>>> for DbError {
+    fn from(_: PoisonError<MutexGuard<'a, WriteQueue>>) -> Self {
+        Self::WriteQueuePoisoned
+    }
+}
+
 impl From<DbError> for AppError {
     fn from(err: DbError) -> Self {
-        AppError::Db(err.to_string())
+        match err {
+            // Errors in restoring a snapshot are told apart, so that the ABCI
+            // app can tell CometBFT whether to refetch a chunk, or to discard
+            // the snapshot or all snapshots of the format.
+            DbError::UnsupportedSnapshotFormat { format } => {
+                AppError::UnsupportedSnapshotFormat { format }
+            },
+            DbError::SnapshotChunkHashMismatch { index, .. } => AppError::InvalidSnapshotChunk {
+                index,
+                reason: err.to_string(),
+            },
+            DbError::UnknownSnapshotItem { .. }
+            | DbError::IncompleteSnapshotItem
+            | DbError::SnapshotRootHashMismatch { .. }
+            | DbError::SnapshotNodeHashMismatch { .. }
+            | DbError::SnapshotStateMismatch => AppError::InvalidSnapshot {
+                reason: err.to_string(),
+            },
+            err => AppError::Db(err.to_string()),
+        }
     }
 }
 
@@ -1,6 +1,9 @@
 mod db;
 mod error;
+mod snapshot;
 mod testing;
 mod timestamp;
+mod wal;
+mod writer;
 
-pub use {db::*, error::*, testing::*, timestamp::*};
+pub use {db::*, error::*, snapshot::*, testing::*, timestamp::*, wal::*, writer::*};
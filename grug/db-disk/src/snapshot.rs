@@ -0,0 +1,890 @@
+use {
+    crate::{
+        cf_default, cf_preimages, cf_snapshots, cf_state_commitment, cf_state_storage,
+        new_cf_options_with_ts, new_read_options, DbError, DbResult, DiskDb, DiskDbInner,
+        StateStorage, U64Timestamp, CF_NAME_PREIMAGES, CF_NAME_STATE_COMMITMENT,
+        CF_NAME_STATE_STORAGE, LATEST_VERSION_KEY, MERKLE_TREE, OLDEST_VERSION_KEY,
+    },
+    grug_app::{Db, SnapshotDb},
+    grug_jmt::{InternalNode, LeafNode, Node},
+    grug_types::{BorshDeExt, BorshSerExt, Hash256, HashExt, Order, Snapshot, Storage},
+    rocksdb::{DBWithThreadMode, IteratorMode, MultiThreaded, Options, WriteBatch},
+    std::{
+        collections::{BTreeSet, VecDeque},
+        mem,
+        num::{NonZeroU64, NonZeroUsize},
+        sync::{Arc, Condvar, Mutex, PoisonError},
+        thread::{self, JoinHandle},
+    },
+};
+
+/// The encoding format of snapshots created by `DiskDb`.
+///
+/// The snapshot data is a sequence of items, each being a KV pair in either the
+/// state storage or the state commitment, encoded as:
+///
+/// ```plain
+/// tag (1 byte) | key length (4 bytes) | key | value length (4 bytes) | value
+/// ```
+///
+/// The data is then split into chunks of a fixed size, except for the last
+/// chunk which may be smaller. An item may span across multiple chunks.
+pub const SNAPSHOT_FORMAT: u32 = 1;
+
+/// Tag of an item that is a KV pair in the state storage.
+const ITEM_STATE_STORAGE: u8 = 0;
+
+/// Tag of an item that is a Merkle tree node in the state commitment.
+const ITEM_STATE_COMMITMENT: u8 = 1;
+
+/// In the snapshots column family, snapshot metadata are keyed by this byte
+/// followed by the version in big endian...
+const SNAPSHOT_NAMESPACE: u8 = b's';
+
+/// ...while chunks are keyed by this byte followed by the chunk's hash.
+const CHUNK_NAMESPACE: u8 = b'c';
+
+/// Configurations for periodically creating state sync snapshots.
+#[derive(Debug, Clone, Copy)]
+pub struct SnapshotOptions {
+    /// Create a snapshot every time a version that is a multiple of this
+    /// number is committed.
+    pub interval: NonZeroU64,
+    /// Number of most recent snapshots to keep. Older ones are deleted.
+    pub keep_recent: NonZeroUsize,
+    /// Size of each chunk, in bytes.
+    pub chunk_size: NonZeroUsize,
+}
+
+/// Shared state between `DiskDb` handles and the background thread that
+/// creates snapshots, when periodic snapshots are enabled.
+///
+/// Snapshots are only an optimization for nodes joining the network, so
+/// failing to create one doesn't affect committing; the error is logged.
+pub(crate) struct PeriodicSnapshots {
+    opts: SnapshotOptions,
+    queue: Mutex<SnapshotQueue>,
+    // Notified whenever the queue changes, or the thread is told to stop.
+    cond: Condvar,
+}
+
+#[derive(Default)]
+struct SnapshotQueue {
+    // Versions of which snapshots are to be created, oldest first. A version
+    // is only removed from here after its snapshot has been created (or has
+    // failed to be).
+    versions: VecDeque<u64>,
+    // Whether the thread should stop once the queue is empty.
+    shutdown: bool,
+}
+
+/// A snapshot that is being restored.
+pub(crate) struct Restoration {
+    snapshot: Snapshot,
+    root_hash: Hash256,
+    // Index of the chunk that is to be applied next.
+    next_index: u32,
+    // Bytes at the end of the chunks applied so far that don't make up a
+    // complete item yet.
+    leftover: Vec<u8>,
+}
+
+impl DiskDb {
+    /// Create a snapshot of the state at the given version, and save it in the
+    /// database, so that it can be served to other nodes.
+    ///
+    /// The snapshot consists of the KV pairs in the state storage, and the nodes
+    /// in the Merkle tree, at that version.
+    pub fn create_snapshot(&self, version: u64, chunk_size: NonZeroUsize) -> DbResult<Snapshot> {
+        let state_storage = self.state_storage(Some(version))?;
+        let state_commitment = self.state_commitment();
+        let mut writer = ChunkWriter::new(&self.inner.db, chunk_size.get());
+
+        for (key, value) in state_storage.scan(None, None, Order::Ascending) {
+            writer.write_item(ITEM_STATE_STORAGE, &key, &value)?;
+        }
+
+        for res in MERKLE_TREE.iter_nodes(&state_commitment, version) {
+            let ((key, value), _) = res?;
+            writer.write_item(ITEM_STATE_COMMITMENT, &key, &value)?;
+        }
+
+        let snapshot = Snapshot {
+            version,
+            format: SNAPSHOT_FORMAT,
+            chunks: writer.finish()?,
+        };
+
+        // Save the metadata after all the chunks have been saved, so that the
+        // snapshot isn't listed until it's complete.
+        let cf = cf_snapshots(&self.inner.db);
+        self.inner
+            .db
+            .put_cf(&cf, snapshot_key(version), snapshot.to_borsh_vec()?)?;
+
+        Ok(snapshot)
+    }
+
+    /// Delete all snapshots except for the most recent ones.
+    pub fn prune_snapshots(&self, keep_recent: NonZeroUsize) -> DbResult<()> {
+        // Snapshots are listed in ascending order of versions.
+        let mut snapshots = self.list_snapshots()?;
+        if snapshots.len() <= keep_recent.get() {
+            return Ok(());
+        }
+
+        let retained = snapshots.split_off(snapshots.len() - keep_recent.get());
+
+        // Chunks are addressed by their hashes, so a chunk may be shared by
+        // multiple snapshots. Don't delete the ones still used by the snapshots
+        // that are to be retained.
+        let retained_chunks = retained
+            .iter()
+            .flat_map(|snapshot| &snapshot.chunks)
+            .collect::<BTreeSet<_>>();
+
+        let cf = cf_snapshots(&self.inner.db);
+        let mut batch = WriteBatch::default();
+
+        for snapshot in &snapshots {
+            batch.delete_cf(&cf, snapshot_key(snapshot.version));
+
+            for chunk_hash in &snapshot.chunks {
+                if !retained_chunks.contains(chunk_hash) {
+                    batch.delete_cf(&cf, chunk_key(chunk_hash));
+                }
+            }
+        }
+
+        Ok(self.inner.db.write(batch)?)
+    }
+
+    /// Delete all data in the state storage, state commitment, and preimages,
+    /// by dropping and recreating the column families.
+    ///
+    /// This is used to discard the data written by a restoration that failed
+    /// or was abandoned.
+    fn wipe_state(&self) -> DbResult<()> {
+        for (name, opts) in [
+            (CF_NAME_PREIMAGES, new_cf_options_with_ts()),
+            (CF_NAME_STATE_STORAGE, new_cf_options_with_ts()),
+            (CF_NAME_STATE_COMMITMENT, Options::default()),
+        ] {
+            self.inner.db.drop_cf(name)?;
+            self.inner.db.create_cf(name, &opts)?;
+        }
+
+        Ok(())
+    }
+
+    /// Verify the restored state is consistent with the trusted root hash.
+    ///
+    /// The hash of each node in the Merkle tree must match that recorded in its
+    /// parent (or in the case of the root node, the trusted root hash), and the
+    /// leaf nodes must correspond exactly to the KV pairs in the state storage.
+    fn verify_restored_state(&self, version: u64, root_hash: Hash256) -> DbResult<()> {
+        let state_commitment = self.state_commitment();
+        let state_storage = StateStorage {
+            inner: Arc::clone(&self.inner),
+            version,
+            overlay: None,
+        };
+
+        let actual = MERKLE_TREE.root_hash(&state_commitment, version)?;
+        if actual != Some(root_hash) {
+            return Err(DbError::SnapshotRootHashMismatch {
+                expect: root_hash,
+                actual,
+            });
+        }
+
+        // The nodes are visited parent first, left child before right child.
+        // We push the expected hashes of the children into a stack in the same
+        // order as the tree walk does, so that the expected hash popped from
+        // the stack always corresponds to the node being visited.
+        let mut expected_hashes = vec![root_hash];
+
+        // The preimages are sorted by key hashes, which is the same order the
+        // leaf nodes are visited.
+        let cf = cf_preimages(&self.inner.db);
+        let opts = new_read_options(Some(version), None, None);
+        let mut preimages = self
+            .inner
+            .db
+            .iterator_cf_opt(&cf, opts, IteratorMode::Start)
+            .map(|res| res.map(|(_, key)| key));
+
+        for res in MERKLE_TREE.iter_nodes(&state_commitment, version) {
+            let (_, node) = res?;
+
+            let Some(expect) = expected_hashes.pop() else {
+                unreachable!("expected hashes don't match the tree walk");
+            };
+
+            let actual = node.hash();
+            if actual != expect {
+                return Err(DbError::SnapshotNodeHashMismatch { expect, actual });
+            }
+
+            match node {
+                Node::Internal(InternalNode {
+                    left_child,
+                    right_child,
+                }) => {
+                    if let Some(child) = right_child {
+                        expected_hashes.push(child.hash);
+                    }
+
+                    if let Some(child) = left_child {
+                        expected_hashes.push(child.hash);
+                    }
+                },
+                Node::Leaf(LeafNode {
+                    key_hash,
+                    value_hash,
+                }) => {
+                    let key = preimages
+                        .next()
+                        .transpose()?
+                        .ok_or(DbError::SnapshotStateMismatch)?;
+                    let value = state_storage
+                        .read(&key)
+                        .ok_or(DbError::SnapshotStateMismatch)?;
+
+                    if key.hash256() != key_hash || value.hash256() != value_hash {
+                        return Err(DbError::SnapshotStateMismatch);
+                    }
+                },
+            }
+        }
+
+        // The state storage must not contain any KV pair that isn't in the tree.
+        if preimages.next().is_some() {
+            return Err(DbError::SnapshotStateMismatch);
+        }
+
+        Ok(())
+    }
+}
+
+impl PeriodicSnapshots {
+    pub(crate) fn new(opts: SnapshotOptions) -> Self {
+        Self {
+            opts,
+            queue: Mutex::new(SnapshotQueue::default()),
+            cond: Condvar::new(),
+        }
+    }
+
+    /// Schedule a snapshot of the given version to be created, if it's due.
+    /// The version must have been written to the physical database.
+    pub(crate) fn schedule(&self, version: u64) {
+        if version % self.opts.interval.get() != 0 {
+            return;
+        }
+
+        // The queue only holds versions, so it can't be left in an invalid
+        // state by a thread panicking while holding the lock.
+        let mut queue = self.queue.lock().unwrap_or_else(PoisonError::into_inner);
+        queue.versions.push_back(version);
+        self.cond.notify_all();
+    }
+
+    /// Block until all scheduled snapshots have been created.
+    pub(crate) fn wait_until_created(&self) {
+        let queue = self.queue.lock().unwrap_or_else(PoisonError::into_inner);
+        let _queue = self
+            .cond
+            .wait_while(queue, |queue| !queue.versions.is_empty())
+            .unwrap_or_else(PoisonError::into_inner);
+    }
+}
+
+/// Handle to the background thread that creates snapshots.
+///
+/// When dropped, it waits for the thread to create all scheduled snapshots.
+pub(crate) struct Snapshotter {
+    inner: Arc<DiskDbInner>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Snapshotter {
+    pub(crate) fn spawn(inner: Arc<DiskDbInner>) -> Self {
+        let db = DiskDb {
+            inner: Arc::clone(&inner),
+            writer: None,
+            snapshotter: None,
+        };
+
+        Self {
+            inner,
+            handle: Some(thread::spawn(move || run_snapshotter(db))),
+        }
+    }
+}
+
+impl Drop for Snapshotter {
+    fn drop(&mut self) {
+        if let Some(snapshots) = &self.inner.snapshots {
+            snapshots
+                .queue
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .shutdown = true;
+            snapshots.cond.notify_all();
+        }
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl SnapshotDb for DiskDb {
+    fn list_snapshots(&self) -> DbResult<Vec<Snapshot>> {
+        let cf = cf_snapshots(&self.inner.db);
+        let opts = new_read_options(
+            None,
+            Some(&[SNAPSHOT_NAMESPACE]),
+            Some(&[SNAPSHOT_NAMESPACE + 1]),
+        );
+
+        self.inner
+            .db
+            .iterator_cf_opt(&cf, opts, IteratorMode::Start)
+            .map(|res| -> DbResult<_> {
+                let (_, value) = res?;
+                Ok(value.deserialize_borsh()?)
+            })
+            .collect()
+    }
+
+    fn load_snapshot_chunk(
+        &self,
+        version: u64,
+        format: u32,
+        index: u32,
+    ) -> DbResult<Option<Vec<u8>>> {
+        if format != SNAPSHOT_FORMAT {
+            return Ok(None);
+        }
+
+        let cf = cf_snapshots(&self.inner.db);
+
+        let Some(snapshot) = self.inner.db.get_cf(&cf, snapshot_key(version))? else {
+            return Ok(None);
+        };
+
+        let snapshot: Snapshot = snapshot.deserialize_borsh()?;
+
+        let Some(chunk_hash) = snapshot.chunks.get(index as usize) else {
+            return Ok(None);
+        };
+
+        Ok(self.inner.db.get_cf(&cf, chunk_key(chunk_hash))?)
+    }
+
+    fn offer_snapshot(&self, snapshot: Snapshot, root_hash: Hash256) -> DbResult<()> {
+        if snapshot.format != SNAPSHOT_FORMAT {
+            return Err(DbError::UnsupportedSnapshotFormat {
+                format: snapshot.format,
+            });
+        }
+
+        if snapshot.chunks.is_empty() {
+            return Err(DbError::EmptySnapshot);
+        }
+
+        if let Some(latest_version) = self.latest_version() {
+            return Err(DbError::RestoreIntoNonEmptyDb { latest_version });
+        }
+
+        let mut restoration = self.inner.restoration.lock()?;
+
+        // A previous restoration may have been abandoned halfway, e.g. because
+        // the chunks couldn't be fetched. Discard the data it has written.
+        self.wipe_state()?;
+
+        *restoration = Some(Restoration {
+            snapshot,
+            root_hash,
+            next_index: 0,
+            leftover: Vec::new(),
+        });
+
+        Ok(())
+    }
+
+    fn apply_snapshot_chunk(&self, index: u32, chunk: &[u8]) -> DbResult<bool> {
+        let mut guard = self.inner.restoration.lock()?;
+        let restoration = guard.as_mut().ok_or(DbError::RestorationNotStarted)?;
+
+        if index != restoration.next_index {
+            return Err(DbError::UnexpectedSnapshotChunk {
+                expect: restoration.next_index,
+                actual: index,
+            });
+        }
+
+        // This can't go out of bounds, because the restoration is removed once
+        // the last chunk is applied.
+        let expect = restoration.snapshot.chunks[index as usize];
+        let actual = chunk.hash256();
+        if actual != expect {
+            return Err(DbError::SnapshotChunkHashMismatch {
+                index,
+                expect,
+                actual,
+            });
+        }
+
+        let version = restoration.snapshot.version;
+        let ts = U64Timestamp::from(version);
+        let mut batch = WriteBatch::default();
+        let mut offset = 0;
+
+        restoration.leftover.extend_from_slice(chunk);
+
+        while let Some(((tag, key, value), len)) = decode_item(&restoration.leftover[offset..]) {
+            match tag {
+                // Note: don't forget timestamping. Preimages aren't included in
+                // the snapshot, as they can be derived from the state storage.
+                ITEM_STATE_STORAGE => {
+                    let cf = cf_state_storage(&self.inner.db);
+                    batch.put_cf_with_ts(&cf, key, ts, value);
+
+                    let cf = cf_preimages(&self.inner.db);
+                    batch.put_cf_with_ts(&cf, key.hash256(), ts, key);
+                },
+                ITEM_STATE_COMMITMENT => {
+                    let cf = cf_state_commitment(&self.inner.db);
+                    batch.put_cf(&cf, key, value);
+                },
+                tag => {
+                    return Err(DbError::UnknownSnapshotItem { tag });
+                },
+            }
+
+            offset += len;
+        }
+
+        restoration.leftover.drain(..offset);
+        restoration.next_index += 1;
+
+        self.inner.db.write(batch)?;
+
+        if (restoration.next_index as usize) < restoration.snapshot.chunks.len() {
+            return Ok(false);
+        }
+
+        // This is the last chunk. The restoration is complete one way or the
+        // other, so remove it.
+        let Restoration {
+            root_hash,
+            leftover,
+            ..
+        } = guard.take().unwrap();
+
+        let res = if leftover.is_empty() {
+            self.verify_restored_state(version, root_hash)
+        } else {
+            Err(DbError::IncompleteSnapshotItem)
+        };
+
+        if let Err(err) = res {
+            self.wipe_state()?;
+            return Err(err);
+        }
+
+        // Set the latest version. Since versions prior to the snapshot aren't
+        // available, also set the oldest version.
+        let mut batch = WriteBatch::default();
+        let cf = cf_default(&self.inner.db);
+        batch.put_cf(&cf, LATEST_VERSION_KEY, version.to_le_bytes());
+        batch.put_cf(&cf, OLDEST_VERSION_KEY, version.to_le_bytes());
+
+        self.inner.db.write(batch)?;
+
+        Ok(true)
+    }
+}
+
+// ---------------------------------- helpers ----------------------------------
+
+fn run_snapshotter(db: DiskDb) {
+    let Some(snapshots) = &db.inner.snapshots else {
+        unreachable!("snapshotter spawned without periodic snapshots enabled");
+    };
+
+    loop {
+        let version = {
+            let queue = snapshots
+                .queue
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let queue = snapshots
+                .cond
+                .wait_while(queue, |queue| queue.versions.is_empty() && !queue.shutdown)
+                .unwrap_or_else(PoisonError::into_inner);
+
+            // If the queue is empty, it means we're told to stop.
+            match queue.versions.front() {
+                Some(version) => *version,
+                None => return,
+            }
+        };
+
+        // The version has already been written, and its state doesn't change
+        // afterwards, so it can be read without holding any lock.
+        let res = db
+            .create_snapshot(version, snapshots.opts.chunk_size)
+            .and_then(|_| db.prune_snapshots(snapshots.opts.keep_recent));
+
+        if let Err(err) = res {
+            tracing::error!(
+                version,
+                err = err.to_string(),
+                "Failed to create state sync snapshot"
+            );
+        }
+
+        snapshots
+            .queue
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .versions
+            .pop_front();
+        snapshots.cond.notify_all();
+    }
+}
+
+/// Encodes items into bytes, splits the bytes into chunks, and saves the chunks
+/// in the snapshots column family.
+struct ChunkWriter<'a> {
+    db: &'a DBWithThreadMode<MultiThreaded>,
+    chunk_size: usize,
+    buffer: Vec<u8>,
+    chunks: Vec<Hash256>,
+}
+
+impl<'a> ChunkWriter<'a> {
+    fn new(db: &'a DBWithThreadMode<MultiThreaded>, chunk_size: usize) -> Self {
+        Self {
+            db,
+            chunk_size,
+            buffer: Vec::with_capacity(chunk_size),
+            chunks: Vec::new(),
+        }
+    }
+
+    fn write_item(&mut self, tag: u8, key: &[u8], value: &[u8]) -> DbResult<()> {
+        self.buffer.push(tag);
+        self.buffer
+            .extend_from_slice(&(key.len() as u32).to_be_bytes());
+        self.buffer.extend_from_slice(key);
+        self.buffer
+            .extend_from_slice(&(value.len() as u32).to_be_bytes());
+        self.buffer.extend_from_slice(value);
+
+        while self.buffer.len() >= self.chunk_size {
+            let rest = self.buffer.split_off(self.chunk_size);
+            let chunk = mem::replace(&mut self.buffer, rest);
+            self.save_chunk(chunk)?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(mut self) -> DbResult<Vec<Hash256>> {
+        if !self.buffer.is_empty() {
+            let chunk = mem::take(&mut self.buffer);
+            self.save_chunk(chunk)?;
+        }
+
+        Ok(self.chunks)
+    }
+
+    fn save_chunk(&mut self, chunk: Vec<u8>) -> DbResult<()> {
+        let chunk_hash = chunk.hash256();
+        let cf = cf_snapshots(self.db);
+        self.db.put_cf(&cf, chunk_key(&chunk_hash), chunk)?;
+        self.chunks.push(chunk_hash);
+        Ok(())
+    }
+}
+
+/// Attempt to decode an item at the start of the given bytes. Return the item,
+/// as well as the number of bytes it takes up.
+///
+/// `None` if the bytes don't contain a complete item.
+fn decode_item(bytes: &[u8]) -> Option<((u8, &[u8], &[u8]), usize)> {
+    let tag = *bytes.first()?;
+    let (key, offset) = decode_length_prefixed(bytes, 1)?;
+    let (value, offset) = decode_length_prefixed(bytes, offset)?;
+    Some(((tag, key, value), offset))
+}
+
+fn decode_length_prefixed(bytes: &[u8], offset: usize) -> Option<(&[u8], usize)> {
+    let len_bytes = bytes.get(offset..offset + 4)?;
+    let len = u32::from_be_bytes(len_bytes.try_into().unwrap()) as usize;
+    let start = offset + 4;
+    let data = bytes.get(start..start + len)?;
+    Some((data, start + len))
+}
+
+fn snapshot_key(version: u64) -> Vec<u8> {
+    let mut key = Vec::with_capacity(1 + 8);
+    key.push(SNAPSHOT_NAMESPACE);
+    key.extend_from_slice(&version.to_be_bytes());
+    key
+}
+
+fn chunk_key(chunk_hash: &Hash256) -> Vec<u8> {
+    let mut key = Vec::with_capacity(1 + Hash256::LENGTH);
+    key.push(CHUNK_NAMESPACE);
+    key.extend_from_slice(chunk_hash);
+    key
+}
+
+// ----------------------------------- tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{DiskDbOptions, TempDataDir},
+        grug_types::{Batch, Op},
+    };
+
+    const CHUNK_SIZE: NonZeroUsize = match NonZeroUsize::new(16) {
+        Some(size) => size,
+        None => unreachable!(),
+    };
+
+    /// Create a DB with a few versions, and a snapshot at the latest version.
+    fn setup_source_db(path: &TempDataDir) -> (DiskDb, Snapshot, Hash256) {
+        let db = DiskDb::open(path).unwrap();
+
+        for batch in [
+            // v0
+            Batch::from([
+                (b"donald".to_vec(), Op::Insert(b"trump".to_vec())),
+                (b"jake".to_vec(), Op::Insert(b"shepherd".to_vec())),
+                (b"joe".to_vec(), Op::Insert(b"biden".to_vec())),
+                (b"larry".to_vec(), Op::Insert(b"engineer".to_vec())),
+            ]),
+            // v1
+            Batch::from([
+                (b"donald".to_vec(), Op::Insert(b"duck".to_vec())),
+                (b"joe".to_vec(), Op::Delete),
+                (b"pumpkin".to_vec(), Op::Insert(b"cat".to_vec())),
+            ]),
+            // v2
+            Batch::from([(b"larry".to_vec(), Op::Insert(b"fitzgerald".to_vec()))]),
+        ] {
+            db.flush_and_commit(batch).unwrap();
+        }
+
+        let snapshot = db.create_snapshot(2, CHUNK_SIZE).unwrap();
+        let root_hash = db.root_hash(Some(2)).unwrap().unwrap();
+
+        (db, snapshot, root_hash)
+    }
+
+    fn load_chunks(db: &DiskDb, snapshot: &Snapshot) -> Vec<Vec<u8>> {
+        (0..snapshot.chunks.len() as u32)
+            .map(|index| {
+                db.load_snapshot_chunk(snapshot.version, snapshot.format, index)
+                    .unwrap()
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn snapshot_works() {
+        let src_path = TempDataDir::new("_grug_disk_db_snapshot_works_src");
+        let (src_db, snapshot, root_hash) = setup_source_db(&src_path);
+
+        // The snapshot should be listed, and should have more than one chunk.
+        assert_eq!(src_db.list_snapshots().unwrap(), [snapshot.clone()]);
+        assert!(snapshot.chunks.len() > 1);
+
+        // Chunks should be addressed by their hashes.
+        let chunks = load_chunks(&src_db, &snapshot);
+        for (chunk, chunk_hash) in chunks.iter().zip(&snapshot.chunks) {
+            assert_eq!(chunk.hash256(), *chunk_hash);
+        }
+
+        // Restore the snapshot into an empty DB.
+        let dst_path = TempDataDir::new("_grug_disk_db_snapshot_works_dst");
+        let dst_db = DiskDb::open(&dst_path).unwrap();
+        dst_db.offer_snapshot(snapshot.clone(), root_hash).unwrap();
+
+        for (index, chunk) in chunks.iter().enumerate() {
+            let done = dst_db.apply_snapshot_chunk(index as u32, chunk).unwrap();
+            assert_eq!(done, index == chunks.len() - 1);
+        }
+
+        // The restored DB should have the same version, root hash, and state.
+        assert_eq!(dst_db.latest_version(), Some(2));
+        assert_eq!(dst_db.root_hash(None).unwrap(), Some(root_hash));
+        assert_eq!(
+            dst_db
+                .state_storage(None)
+                .unwrap()
+                .scan(None, None, Order::Ascending)
+                .collect::<Vec<_>>(),
+            src_db
+                .state_storage(Some(2))
+                .unwrap()
+                .scan(None, None, Order::Ascending)
+                .collect::<Vec<_>>()
+        );
+
+        // Versions prior to the snapshot should not be available.
+        assert!(dst_db.state_storage(Some(1)).is_err_and(|err| {
+            err.to_string()
+                .contains("older than the oldest available version (2)")
+        }));
+
+        // Both DBs should be able to continue from here, and arrive at the same
+        // root hash.
+        let batch = Batch::from([
+            (b"jake".to_vec(), Op::Delete),
+            (b"joe".to_vec(), Op::Insert(b"biden".to_vec())),
+        ]);
+        let (src_version, src_root_hash) = src_db.flush_and_commit(batch.clone()).unwrap();
+        let (dst_version, dst_root_hash) = dst_db.flush_and_commit(batch).unwrap();
+        assert_eq!(src_version, 3);
+        assert_eq!(dst_version, 3);
+        assert_eq!(src_root_hash, dst_root_hash);
+
+        // The restored DB should be able to generate proofs.
+        assert_eq!(
+            dst_db.prove(b"joe", None).unwrap(),
+            src_db.prove(b"joe", None).unwrap()
+        );
+
+        // Cannot restore into a non-empty DB.
+        assert!(dst_db
+            .offer_snapshot(snapshot, root_hash)
+            .is_err_and(|err| matches!(err, DbError::RestoreIntoNonEmptyDb { .. })));
+    }
+
+    #[test]
+    fn restoring_invalid_snapshot_fails() {
+        let src_path = TempDataDir::new("_grug_disk_db_restoring_invalid_snapshot_fails_src");
+        let (src_db, snapshot, root_hash) = setup_source_db(&src_path);
+        let chunks = load_chunks(&src_db, &snapshot);
+
+        let dst_path = TempDataDir::new("_grug_disk_db_restoring_invalid_snapshot_fails_dst");
+        let dst_db = DiskDb::open(&dst_path).unwrap();
+
+        // Applying a chunk before a snapshot is offered.
+        assert!(dst_db
+            .apply_snapshot_chunk(0, &chunks[0])
+            .is_err_and(|err| matches!(err, DbError::RestorationNotStarted)));
+
+        // Offer the snapshot with an incorrect root hash. The chunks should be
+        // accepted, but verification should fail after the last one.
+        let wrong_root_hash = b"wrong".hash256();
+        dst_db
+            .offer_snapshot(snapshot.clone(), wrong_root_hash)
+            .unwrap();
+
+        // Applying chunks out of order.
+        assert!(dst_db
+            .apply_snapshot_chunk(1, &chunks[1])
+            .is_err_and(|err| {
+                matches!(err, DbError::UnexpectedSnapshotChunk {
+                    expect: 0,
+                    actual: 1
+                })
+            }));
+
+        // Applying a tampered chunk.
+        let mut tampered = chunks[0].clone();
+        tampered[0] ^= 1;
+        assert!(dst_db
+            .apply_snapshot_chunk(0, &tampered)
+            .is_err_and(|err| matches!(err, DbError::SnapshotChunkHashMismatch { index: 0, .. })));
+
+        let (last, rest) = chunks.split_last().unwrap();
+        for (index, chunk) in rest.iter().enumerate() {
+            assert!(!dst_db.apply_snapshot_chunk(index as u32, chunk).unwrap());
+        }
+
+        assert!(dst_db
+            .apply_snapshot_chunk(rest.len() as u32, last)
+            .is_err_and(|err| {
+                matches!(err, DbError::SnapshotRootHashMismatch { expect, actual }
+                    if expect == wrong_root_hash && actual == Some(root_hash))
+            }));
+
+        // The DB should have been wiped clean.
+        assert_eq!(dst_db.latest_version(), None);
+        assert!(dst_db
+            .state_storage(None)
+            .unwrap()
+            .scan(None, None, Order::Ascending)
+            .next()
+            .is_none());
+        assert!(dst_db
+            .state_commitment()
+            .scan(None, None, Order::Ascending)
+            .next()
+            .is_none());
+
+        // Now offer the snapshot with the correct root hash. Should work.
+        dst_db.offer_snapshot(snapshot, root_hash).unwrap();
+        for (index, chunk) in chunks.iter().enumerate() {
+            dst_db.apply_snapshot_chunk(index as u32, chunk).unwrap();
+        }
+        assert_eq!(dst_db.root_hash(None).unwrap(), Some(root_hash));
+    }
+
+    #[test]
+    fn creating_and_pruning_snapshots_works() {
+        let path = TempDataDir::new("_grug_disk_db_creating_and_pruning_snapshots_works");
+        let db = DiskDb::open_with_options(&path, DiskDbOptions {
+            snapshots: Some(SnapshotOptions {
+                interval: NonZeroU64::new(2).unwrap(),
+                keep_recent: NonZeroUsize::new(2).unwrap(),
+                chunk_size: CHUNK_SIZE,
+            }),
+            wal: false,
+            async_commit: None,
+        })
+        .unwrap();
+
+        for i in 0..=6_u8 {
+            let batch = Batch::from([(vec![i], Op::Insert(vec![i]))]);
+            db.flush_and_commit(batch).unwrap();
+        }
+
+        // Snapshots are created in the background.
+        db.inner.snapshots.as_ref().unwrap().wait_until_created();
+
+        // Snapshots are created at versions 0, 2, 4, 6; only the most recent
+        // two should be kept.
+        let snapshots = db.list_snapshots().unwrap();
+        assert_eq!(
+            snapshots
+                .iter()
+                .map(|snapshot| snapshot.version)
+                .collect::<Vec<_>>(),
+            [4, 6]
+        );
+
+        // Chunks of the retained snapshots should be available, while those of
+        // the pruned ones should not.
+        for snapshot in &snapshots {
+            assert_eq!(load_chunks(&db, snapshot).len(), snapshot.chunks.len());
+        }
+        assert!(db
+            .load_snapshot_chunk(2, SNAPSHOT_FORMAT, 0)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,272 @@
+use {
+    crate::{cf_default, DbResult, DiskDb, PendingData, WAL_PREFIX},
+    grug_app::Db,
+    grug_types::{BorshDeExt, BorshSerExt, Hash256, HashExt},
+    rocksdb::{Direction, IteratorMode, WriteOptions},
+};
+
+impl DiskDb {
+    /// Durably write the pending data to the write-ahead log (WAL).
+    ///
+    /// The WAL is encoded as the SHA-256 hash of the Borsh-encoded pending
+    /// data, followed by the data itself. The hash allows us to detect a WAL
+    /// that was only partially written.
+    pub(crate) fn write_wal(&self, pending: &PendingData) -> DbResult<()> {
+        let payload = pending.to_borsh_vec()?;
+
+        let mut bytes = Vec::with_capacity(Hash256::LENGTH + payload.len());
+        bytes.extend_from_slice(&payload.hash256());
+        bytes.extend_from_slice(&payload);
+
+        // Wait for the write to be synced to disk before returning.
+        let mut opts = WriteOptions::default();
+        opts.set_sync(true);
+
+        let cf = cf_default(&self.inner.db);
+        self.inner
+            .db
+            .put_cf_opt(&cf, wal_key(pending.version), bytes, &opts)?;
+
+        Ok(())
+    }
+
+    /// Look for versions that have been flushed but not written in the WAL.
+    /// If found, write them to the physical database.
+    ///
+    /// An entry is discarded without being written if it's corrupted, or if
+    /// its version doesn't immediately follow the latest written version.
+    pub(crate) fn recover_from_wal(&self) -> DbResult<()> {
+        let cf = cf_default(&self.inner.db);
+
+        let entries = self
+            .inner
+            .db
+            .iterator_cf(&cf, IteratorMode::From(WAL_PREFIX, Direction::Forward))
+            .take_while(|res| {
+                res.as_ref()
+                    .map_or(true, |(k, _)| k.starts_with(WAL_PREFIX))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The entries are sorted by versions in ascending order.
+        for (key, bytes) in entries {
+            let expect_version = self.latest_version().map_or(0, |v| v + 1);
+
+            match decode_wal(&bytes) {
+                // This also deletes the entry.
+                Some(pending) if pending.version == expect_version => {
+                    self.write_pending(&pending)?;
+                },
+                _ => {
+                    self.inner.db.delete_cf(&cf, key)?;
+                },
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// ---------------------------------- helpers ----------------------------------
+
+pub(crate) fn wal_key(version: u64) -> Vec<u8> {
+    let mut key = Vec::with_capacity(WAL_PREFIX.len() + 8);
+    key.extend_from_slice(WAL_PREFIX);
+    key.extend_from_slice(&version.to_be_bytes());
+    key
+}
+
+fn decode_wal(bytes: &[u8]) -> Option<PendingData> {
+    if bytes.len() < Hash256::LENGTH {
+        return None;
+    }
+
+    let (hash, payload) = bytes.split_at(Hash256::LENGTH);
+
+    if hash != &payload.hash256()[..] {
+        return None;
+    }
+
+    payload.deserialize_borsh().ok()
+}
+
+// ----------------------------------- tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{DiskDbOptions, TempDataDir},
+        grug_types::{Batch, Op, Storage},
+    };
+
+    /// Points in a block's lifecycle at which the process may crash.
+    #[derive(Debug, Clone, Copy)]
+    enum CrashPoint {
+        BeforeFlush,
+        AfterFlush,
+        AfterCommit,
+    }
+
+    const WAL_ENABLED: DiskDbOptions = DiskDbOptions {
+        snapshots: None,
+        wal: true,
+        async_commit: None,
+    };
+
+    fn open_and_commit_v0(path: &TempDataDir, opts: DiskDbOptions) -> DiskDb {
+        let db = DiskDb::open_with_options(path, opts).unwrap();
+        let batch = Batch::from([
+            (b"donald".to_vec(), Op::Insert(b"trump".to_vec())),
+            (b"joe".to_vec(), Op::Insert(b"biden".to_vec())),
+        ]);
+        db.flush_and_commit(batch).unwrap();
+        db
+    }
+
+    fn v1_batch() -> Batch {
+        Batch::from([
+            (b"donald".to_vec(), Op::Insert(b"duck".to_vec())),
+            (b"joe".to_vec(), Op::Delete),
+            (b"pumpkin".to_vec(), Op::Insert(b"cat".to_vec())),
+        ])
+    }
+
+    fn wal_exists(db: &DiskDb) -> bool {
+        let cf = cf_default(&db.inner.db);
+        db.inner.db.get_cf(&cf, wal_key(1)).unwrap().is_some()
+    }
+
+    fn assert_v1_committed(db: &DiskDb, root_hash: Option<Hash256>) {
+        assert_eq!(db.latest_version(), Some(1));
+        assert_eq!(db.root_hash(None).unwrap(), root_hash);
+
+        let state_storage = db.state_storage(None).unwrap();
+        assert_eq!(state_storage.read(b"donald"), Some(b"duck".to_vec()));
+        assert_eq!(state_storage.read(b"joe"), None);
+        assert_eq!(state_storage.read(b"pumpkin"), Some(b"cat".to_vec()));
+    }
+
+    #[test]
+    fn recovering_from_crash_works() {
+        for crash_point in [
+            CrashPoint::BeforeFlush,
+            CrashPoint::AfterFlush,
+            CrashPoint::AfterCommit,
+        ] {
+            let path = TempDataDir::new("_grug_disk_db_recovering_from_crash_works");
+            let db = open_and_commit_v0(&path, WAL_ENABLED);
+            let v0_root_hash = db.root_hash(None).unwrap();
+            let mut v1_root_hash = None;
+
+            if !matches!(crash_point, CrashPoint::BeforeFlush) {
+                let (version, root_hash) = db.flush_but_not_commit(v1_batch()).unwrap();
+                assert_eq!(version, 1);
+                assert!(wal_exists(&db));
+                v1_root_hash = root_hash;
+            }
+
+            if matches!(crash_point, CrashPoint::AfterCommit) {
+                db.commit().unwrap();
+                assert!(!wal_exists(&db));
+            }
+
+            // Simulate the crash by dropping the DB without committing the
+            // pending data held in memory, then reopen it.
+            drop(db);
+            let db = DiskDb::open_with_options(&path, WAL_ENABLED).unwrap();
+
+            // The WAL should have been consumed in any case.
+            assert!(!wal_exists(&db));
+
+            match crash_point {
+                CrashPoint::BeforeFlush => {
+                    assert_eq!(db.latest_version(), Some(0));
+                    assert_eq!(db.root_hash(None).unwrap(), v0_root_hash);
+                },
+                CrashPoint::AfterFlush | CrashPoint::AfterCommit => {
+                    assert_v1_committed(&db, v1_root_hash);
+                },
+            }
+
+            // The DB should be able to continue normally.
+            let (version, _) = db
+                .flush_and_commit(Batch::from([(
+                    b"larry".to_vec(),
+                    Op::Insert(b"engineer".to_vec()),
+                )]))
+                .unwrap();
+            assert_eq!(version, db.latest_version().unwrap());
+        }
+    }
+
+    #[test]
+    fn pending_data_is_lost_without_wal() {
+        let path = TempDataDir::new("_grug_disk_db_pending_data_is_lost_without_wal");
+        let db = open_and_commit_v0(&path, DiskDbOptions::default());
+
+        db.flush_but_not_commit(v1_batch()).unwrap();
+        assert!(!wal_exists(&db));
+
+        drop(db);
+        let db = DiskDb::open(&path).unwrap();
+
+        assert_eq!(db.latest_version(), Some(0));
+    }
+
+    #[test]
+    fn recovering_without_wal_enabled_works() {
+        let path = TempDataDir::new("_grug_disk_db_recovering_without_wal_enabled_works");
+        let db = open_and_commit_v0(&path, WAL_ENABLED);
+        let (_, root_hash) = db.flush_but_not_commit(v1_batch()).unwrap();
+
+        // Crash, then restart with WAL disabled. The WAL should still be
+        // recovered.
+        drop(db);
+        let db = DiskDb::open(&path).unwrap();
+
+        assert!(!wal_exists(&db));
+        assert_v1_committed(&db, root_hash);
+    }
+
+    #[test]
+    fn discarding_corrupted_wal_works() {
+        let path = TempDataDir::new("_grug_disk_db_discarding_corrupted_wal_works");
+        let db = open_and_commit_v0(&path, WAL_ENABLED);
+        db.flush_but_not_commit(v1_batch()).unwrap();
+
+        // Simulate a crash in the middle of writing the WAL, by truncating it.
+        let cf = cf_default(&db.inner.db);
+        let bytes = db.inner.db.get_cf(&cf, wal_key(1)).unwrap().unwrap();
+        let truncated = &bytes[..bytes.len() - 1];
+        db.inner.db.put_cf(&cf, wal_key(1), truncated).unwrap();
+        drop(cf);
+
+        drop(db);
+        let db = DiskDb::open_with_options(&path, WAL_ENABLED).unwrap();
+
+        assert!(!wal_exists(&db));
+        assert_eq!(db.latest_version(), Some(0));
+    }
+
+    #[test]
+    fn discarding_stale_wal_works() {
+        let path = TempDataDir::new("_grug_disk_db_discarding_stale_wal_works");
+        let db = open_and_commit_v0(&path, WAL_ENABLED);
+        let (_, root_hash) = db.flush_but_not_commit(v1_batch()).unwrap();
+
+        // Commit v1, then put its WAL back, as if it was never deleted.
+        let cf = cf_default(&db.inner.db);
+        let bytes = db.inner.db.get_cf(&cf, wal_key(1)).unwrap().unwrap();
+        db.commit().unwrap();
+        db.inner.db.put_cf(&cf, wal_key(1), bytes).unwrap();
+        drop(cf);
+
+        drop(db);
+        let db = DiskDb::open_with_options(&path, WAL_ENABLED).unwrap();
+
+        // v1 shouldn't be committed a second time as v2.
+        assert!(!wal_exists(&db));
+        assert_v1_committed(&db, root_hash);
+    }
+}
@@ -0,0 +1,392 @@
+use {
+    crate::{DbError, DbResult, DiskDb, DiskDbInner, PendingData},
+    grug_types::Batch,
+    std::{
+        collections::VecDeque,
+        num::NonZeroUsize,
+        sync::{Arc, Condvar, Mutex, PoisonError},
+        thread::{self, JoinHandle},
+    },
+};
+
+/// Shared state between `DiskDb` handles and the background writer thread,
+/// when asynchronous commit is enabled.
+pub(crate) struct AsyncCommit {
+    // Maximum number of versions that can be waiting to be written. Once this
+    // is reached, `commit` blocks until the writer catches up.
+    max_queued: NonZeroUsize,
+    pub(crate) queue: Mutex<WriteQueue>,
+    // Notified whenever the queue changes, or the writer is told to stop.
+    cond: Condvar,
+}
+
+#[derive(Default)]
+pub(crate) struct WriteQueue {
+    // Versions that have been committed but not yet written, oldest first.
+    // A version is only removed from here after it's been written, so that
+    // reads never miss it.
+    versions: VecDeque<Arc<PendingData>>,
+    // Combined changes of all versions in the queue, where changes in newer
+    // versions override those in older ones.
+    state_commitment: Arc<Batch>,
+    state_storage: Arc<Batch>,
+    // If the writer failed to write a version, the reason of the failure.
+    // The writer stops, and the version remains in the queue.
+    error: Option<String>,
+    // Whether the writer should stop once the queue is empty.
+    shutdown: bool,
+}
+
+impl AsyncCommit {
+    pub(crate) fn new(max_queued: NonZeroUsize) -> Self {
+        Self {
+            max_queued,
+            queue: Mutex::new(WriteQueue::default()),
+            cond: Condvar::new(),
+        }
+    }
+
+    /// Add a version to the end of the queue, to be written by the writer.
+    /// Block if the queue is full.
+    pub(crate) fn enqueue(&self, pending: PendingData) -> DbResult<()> {
+        let queue = self.queue.lock()?;
+        let mut queue = self.cond.wait_while(queue, |queue| {
+            queue.error.is_none() && queue.versions.len() >= self.max_queued.get()
+        })?;
+
+        if let Some(reason) = &queue.error {
+            return Err(DbError::AsyncWriteFailed {
+                reason: reason.clone(),
+            });
+        }
+
+        queue.push(Arc::new(pending));
+        self.cond.notify_all();
+
+        Ok(())
+    }
+
+    /// Block until all versions no newer than the given version have been
+    /// written.
+    pub(crate) fn wait_until_written(&self, version: u64) -> DbResult<()> {
+        let queue = self.queue.lock()?;
+        let queue = self.cond.wait_while(queue, |queue| {
+            queue.error.is_none() && queue.oldest_version().is_some_and(|v| v <= version)
+        })?;
+
+        match &queue.error {
+            Some(reason) if queue.oldest_version().is_some_and(|v| v <= version) => {
+                Err(DbError::AsyncWriteFailed {
+                    reason: reason.clone(),
+                })
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+impl WriteQueue {
+    pub(crate) fn oldest_version(&self) -> Option<u64> {
+        self.versions.front().map(|pending| pending.version)
+    }
+
+    pub(crate) fn newest_version(&self) -> Option<u64> {
+        self.versions.back().map(|pending| pending.version)
+    }
+
+    /// Return the combined Merkle tree nodes of all versions in the queue, or
+    /// `None` if the queue is empty.
+    pub(crate) fn state_commitment_overlay(&self) -> Option<Arc<Batch>> {
+        if self.versions.is_empty() {
+            return None;
+        }
+
+        Some(Arc::clone(&self.state_commitment))
+    }
+
+    /// Return the combined state storage changes of versions in the queue no
+    /// newer than the given version, or `None` if the given version has already
+    /// been written.
+    pub(crate) fn state_storage_overlay(&self, version: u64) -> Option<Arc<Batch>> {
+        if self.oldest_version()? > version {
+            return None;
+        }
+
+        // The most common case: reading the newest version. Use the combined
+        // changes we already have.
+        if self.newest_version() == Some(version) {
+            return Some(Arc::clone(&self.state_storage));
+        }
+
+        let mut overlay = Batch::new();
+        for pending in self.versions.iter().take_while(|p| p.version <= version) {
+            overlay.extend(pending.state_storage.clone());
+        }
+
+        Some(Arc::new(overlay))
+    }
+
+    fn push(&mut self, pending: Arc<PendingData>) {
+        // If there are `StateCommitment` or `StateStorage` instances holding
+        // the overlays, `make_mut` clones them, so the changes aren't visible
+        // to those instances.
+        Arc::make_mut(&mut self.state_commitment).extend(pending.state_commitment.clone());
+        Arc::make_mut(&mut self.state_storage).extend(pending.state_storage.clone());
+
+        self.versions.push_back(pending);
+    }
+
+    fn pop(&mut self) {
+        let Some(written) = self.versions.pop_front() else {
+            return;
+        };
+
+        // Remove the changes from the overlays, unless they are overridden by
+        // a newer version that's still in the queue.
+        remove_written(
+            &mut self.state_commitment,
+            &written.state_commitment,
+            &self.versions,
+            |pending| &pending.state_commitment,
+        );
+        remove_written(
+            &mut self.state_storage,
+            &written.state_storage,
+            &self.versions,
+            |pending| &pending.state_storage,
+        );
+    }
+}
+
+/// Handle to the background thread that writes committed versions to the
+/// physical database.
+///
+/// When dropped, it waits for the thread to write all versions in the queue.
+pub(crate) struct Writer {
+    inner: Arc<DiskDbInner>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Writer {
+    pub(crate) fn spawn(inner: Arc<DiskDbInner>) -> Self {
+        // The thread gets a handle without a writer, which writes synchronously.
+        let db = DiskDb {
+            inner: Arc::clone(&inner),
+            writer: None,
+            snapshotter: None,
+        };
+
+        Self {
+            inner,
+            handle: Some(thread::spawn(move || run_writer(db))),
+        }
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        if let Some(async_commit) = &self.inner.async_commit {
+            // Even if the mutex is poisoned, we still need to tell the thread
+            // to stop, otherwise joining it below will block forever.
+            async_commit
+                .queue
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .shutdown = true;
+            async_commit.cond.notify_all();
+        }
+
+        if let Some(handle) = self.handle.take() {
+            // The thread doesn't panic unless the mutex is poisoned, in which
+            // case there isn't anything we can do.
+            let _ = handle.join();
+        }
+    }
+}
+
+// ---------------------------------- helpers ----------------------------------
+
+fn run_writer(db: DiskDb) {
+    let Some(async_commit) = &db.inner.async_commit else {
+        unreachable!("writer spawned without asynchronous commit enabled");
+    };
+
+    loop {
+        let pending = {
+            let queue = async_commit.queue.lock().unwrap_or_else(|err| {
+                panic!("failed to read from write queue: {err}");
+            });
+            let queue = async_commit
+                .cond
+                .wait_while(queue, |queue| queue.versions.is_empty() && !queue.shutdown)
+                .unwrap_or_else(|err| {
+                    panic!("failed to read from write queue: {err}");
+                });
+
+            // If the queue is empty, it means we're told to stop.
+            match queue.versions.front() {
+                Some(pending) => Arc::clone(pending),
+                None => return,
+            }
+        };
+
+        // Write without holding the lock, so that reads aren't blocked.
+        let res = db.write_pending(&pending);
+
+        let mut queue = async_commit.queue.lock().unwrap_or_else(|err| {
+            panic!("failed to write to write queue: {err}");
+        });
+
+        match res {
+            Ok(()) => {
+                queue.pop();
+                async_commit.cond.notify_all();
+            },
+            Err(err) => {
+                queue.error = Some(err.to_string());
+                async_commit.cond.notify_all();
+                return;
+            },
+        }
+    }
+}
+
+fn remove_written<F>(
+    overlay: &mut Arc<Batch>,
+    written: &Batch,
+    remaining: &VecDeque<Arc<PendingData>>,
+    get_batch: F,
+) where
+    F: Fn(&PendingData) -> &Batch,
+{
+    // If the queue is now empty, simply clear the overlay.
+    if remaining.is_empty() {
+        *overlay = Arc::new(Batch::new());
+        return;
+    }
+
+    for key in written.keys() {
+        if !remaining
+            .iter()
+            .any(|pending| get_batch(pending).contains_key(key))
+        {
+            Arc::make_mut(overlay).remove(key);
+        }
+    }
+}
+
+// ----------------------------------- tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{DiskDbOptions, TempDataDir},
+        grug_app::Db,
+        grug_types::{Op, Order, Storage},
+    };
+
+    const MAX_QUEUED: NonZeroUsize = match NonZeroUsize::new(2) {
+        Some(max) => max,
+        None => unreachable!(),
+    };
+
+    fn batches() -> Vec<Batch> {
+        (0..10_u8)
+            .map(|i| {
+                let mut batch = Batch::from([
+                    (vec![i], Op::Insert(vec![i])),
+                    (b"counter".to_vec(), Op::Insert(vec![i])),
+                ]);
+                // Delete a key written by an earlier version.
+                if i >= 3 {
+                    batch.insert(vec![i - 3], Op::Delete);
+                }
+                batch
+            })
+            .collect()
+    }
+
+    fn assert_same_state(sync_db: &DiskDb, async_db: &DiskDb) {
+        let latest_version = sync_db.latest_version();
+        assert_eq!(async_db.latest_version(), latest_version);
+
+        for version in 0..=latest_version.unwrap() {
+            assert_eq!(
+                async_db.root_hash(Some(version)).unwrap(),
+                sync_db.root_hash(Some(version)).unwrap()
+            );
+
+            let sync_state = sync_db.state_storage(Some(version)).unwrap();
+            let async_state = async_db.state_storage(Some(version)).unwrap();
+            for order in [Order::Ascending, Order::Descending] {
+                assert_eq!(
+                    async_state.scan(None, None, order).collect::<Vec<_>>(),
+                    sync_state.scan(None, None, order).collect::<Vec<_>>()
+                );
+            }
+            assert_eq!(async_state.read(b"counter"), sync_state.read(b"counter"));
+        }
+    }
+
+    #[test]
+    fn async_commit_works() {
+        let sync_path = TempDataDir::new("_grug_disk_db_async_commit_works_sync");
+        let async_path = TempDataDir::new("_grug_disk_db_async_commit_works_async");
+        let sync_db = DiskDb::open(&sync_path).unwrap();
+        let async_db = DiskDb::open_with_options(&async_path, DiskDbOptions {
+            async_commit: Some(MAX_QUEUED),
+            ..Default::default()
+        })
+        .unwrap();
+
+        for batch in batches() {
+            let (sync_version, sync_root_hash) = sync_db.flush_and_commit(batch.clone()).unwrap();
+            let (async_version, async_root_hash) = async_db.flush_and_commit(batch).unwrap();
+            assert_eq!(async_version, sync_version);
+            assert_eq!(async_root_hash, sync_root_hash);
+
+            // The number of versions waiting to be written must never exceed
+            // the limit.
+            let Some(async_commit) = &async_db.inner.async_commit else {
+                unreachable!();
+            };
+            assert!(async_commit.queue.lock().unwrap().versions.len() <= MAX_QUEUED.get());
+
+            // Whether or not the versions have been written, reads should give
+            // the same results.
+            assert_same_state(&sync_db, &async_db);
+        }
+
+        // Dropping the DB should write all versions before returning. When
+        // reopened without asynchronous commit, everything should be there.
+        drop(async_db);
+        let async_db = DiskDb::open(&async_path).unwrap();
+
+        assert_same_state(&sync_db, &async_db);
+    }
+
+    #[test]
+    fn waiting_until_written_works() {
+        let path = TempDataDir::new("_grug_disk_db_waiting_until_written_works");
+        let db = DiskDb::open_with_options(&path, DiskDbOptions {
+            async_commit: Some(MAX_QUEUED),
+            ..Default::default()
+        })
+        .unwrap();
+
+        for batch in batches() {
+            db.flush_and_commit(batch).unwrap();
+        }
+
+        let Some(async_commit) = &db.inner.async_commit else {
+            unreachable!();
+        };
+        async_commit.wait_until_written(u64::MAX).unwrap();
+
+        let queue = async_commit.queue.lock().unwrap();
+        assert!(queue.versions.is_empty());
+        assert!(queue.state_commitment.is_empty());
+        assert!(queue.state_storage.is_empty());
+    }
+}
@@ -28,6 +28,12 @@ extern "C" fn deallocate(region_addr: usize) {
     // data is dropped here, which calls Vec<u8> destructor, freeing the memory
 }
 
+/// Signal the version of the interface between the host and the contract, i.e.
+/// the set of imports and exports, such that the host can reject contracts
+/// built against an incompatible version when they're uploaded.
+#[no_mangle]
+extern "C" fn interface_version_1() {}
+
 pub fn do_instantiate<M, E>(
     instantiate_fn: &dyn Fn(MutableCtx, M) -> Result<Response, E>,
     ctx_ptr: usize,
@@ -0,0 +1,42 @@
+use {
+    crate::PendingBlock,
+    grug_types::StdError,
+    rusqlite::Connection,
+    std::sync::{MutexGuard, PoisonError},
+    thiserror::Error,
+};
+
+#[derive(Debug, Error)]
+pub enum IndexerError {
+    #[error(transparent)]
+    Std(#[from] StdError),
+
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("cannot commit when no block has been indexed")]
+    PendingBlockNotSet,
+
+    #[error("mutex for the pending block is poisoned")]
+    PendingBlockPoisoned,
+
+    #[error("mutex for the SQLite connection is poisoned")]
+    ConnectionPoisoned,
+
+    #[error("`{ty}` event is missing attribute `{key}`")]
+    MissingAttribute { ty: String, key: &'static str },
+}
+
+impl<'a> From<PoisonError<MutexGuard<'a, Option<PendingBlock>>>> for IndexerError {
+    fn from(_: PoisonError<MutexGuard<'a, Option<PendingBlock>>>) -> Self {
+        Self::PendingBlockPoisoned
+    }
+}
+
+impl<'a> From<PoisonError<MutexGuard<'a, Connection>>> for IndexerError {
+    fn from(_: PoisonError<MutexGuard<'a, Connection>>) -> Self {
+        Self::ConnectionPoisoned
+    }
+}
+
+pub type IndexerResult<T> = core::result::Result<T, IndexerError>;
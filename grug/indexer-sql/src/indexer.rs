@@ -0,0 +1,600 @@
+use {
+    crate::{IndexerError, IndexerResult},
+    grug_app::Indexer,
+    grug_types::{
+        Addr, BankMsg, BlockInfo, BlockOutcome, Event, Hash256, JsonDeExt, JsonSerExt, Tx,
+        TxOutcome,
+    },
+    rusqlite::{params, Connection, Transaction},
+    std::{
+        mem,
+        path::Path,
+        sync::{Arc, Mutex},
+    },
+};
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS blocks (
+    height    INTEGER PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    hash      TEXT    NOT NULL,
+    app_hash  TEXT    NOT NULL,
+    num_txs   INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS txs (
+    id      INTEGER PRIMARY KEY AUTOINCREMENT,
+    hash    TEXT    NOT NULL UNIQUE,
+    height  INTEGER NOT NULL,
+    idx     INTEGER NOT NULL,
+    sender  TEXT    NOT NULL,
+    tx      TEXT    NOT NULL,
+    outcome TEXT    NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS txs_by_sender ON txs (sender, id);
+
+CREATE TABLE IF NOT EXISTS events (
+    id        INTEGER PRIMARY KEY AUTOINCREMENT,
+    parent_id INTEGER,
+    height    INTEGER NOT NULL,
+    tx_hash   TEXT,
+    type      TEXT    NOT NULL,
+    contract  TEXT,
+    event     TEXT    NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS events_by_tx ON events (tx_hash, id);
+CREATE INDEX IF NOT EXISTS events_by_contract ON events (contract, id);
+
+CREATE TABLE IF NOT EXISTS transfers (
+    id        INTEGER PRIMARY KEY AUTOINCREMENT,
+    height    INTEGER NOT NULL,
+    tx_hash   TEXT,
+    sender    TEXT    NOT NULL,
+    recipient TEXT    NOT NULL,
+    coins     TEXT    NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS transfers_by_sender ON transfers (sender, id);
+CREATE INDEX IF NOT EXISTS transfers_by_recipient ON transfers (recipient, id);
+
+CREATE TABLE IF NOT EXISTS contracts (
+    address   TEXT    PRIMARY KEY,
+    code_hash TEXT    NOT NULL,
+    creator   TEXT    NOT NULL,
+    admin     TEXT,
+    height    INTEGER NOT NULL,
+    tx_hash   TEXT
+);
+
+CREATE INDEX IF NOT EXISTS contracts_by_code_hash ON contracts (code_hash, address);
+"#;
+
+/// Data of a block that has been finalized, but not yet committed.
+pub struct PendingBlock {
+    block: BlockInfo,
+    app_hash: Hash256,
+    /// Events that don't belong to any transaction, i.e. those emitted by
+    /// cronjobs, or by genesis messages.
+    events: Vec<Event>,
+    txs: Vec<(Tx, Hash256, TxOutcome)>,
+}
+
+/// An indexer that records blocks, transactions and their outcomes, events,
+/// transfers, and contract creations in an embedded SQLite database.
+///
+/// A block is only written to the database once it's committed, such that the
+/// database never contains blocks that the chain might not have. Writing a
+/// block replaces any existing records of the same height, so blocks that are
+/// replayed after a crash aren't recorded twice.
+#[derive(Clone)]
+pub struct SqlIndexer {
+    pub(crate) conn: Arc<Mutex<Connection>>,
+    pending: Arc<Mutex<Option<PendingBlock>>>,
+}
+
+impl SqlIndexer {
+    /// Open the database at the given path, creating it if it doesn't exist.
+    pub fn open<P>(path: P) -> IndexerResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::new(Connection::open(path)?)
+    }
+
+    /// Open a temporary database that only lives in memory.
+    pub fn open_in_memory() -> IndexerResult<Self> {
+        Self::new(Connection::open_in_memory()?)
+    }
+
+    fn new(conn: Connection) -> IndexerResult<Self> {
+        conn.execute_batch(SCHEMA)?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            pending: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    fn write(&self, pending: PendingBlock) -> IndexerResult<()> {
+        let mut conn = self.conn.lock()?;
+        let sql_tx = conn.transaction()?;
+
+        write_block(&sql_tx, pending)?;
+
+        Ok(sql_tx.commit()?)
+    }
+}
+
+impl Indexer for SqlIndexer {
+    type Error = IndexerError;
+
+    fn index_genesis_block(
+        &self,
+        block: &BlockInfo,
+        app_hash: Hash256,
+        events: &[Event],
+    ) -> IndexerResult<()> {
+        self.write(PendingBlock {
+            block: *block,
+            app_hash,
+            events: events.to_vec(),
+            txs: vec![],
+        })
+    }
+
+    fn index_block(
+        &self,
+        block: &BlockInfo,
+        txs: &[(Tx, Hash256)],
+        outcome: &BlockOutcome,
+    ) -> IndexerResult<()> {
+        // Failed cronjobs don't emit events.
+        let events = outcome
+            .cron_outcomes
+            .iter()
+            .filter_map(|outcome| outcome.result.clone().ok())
+            .flatten()
+            .collect();
+
+        let txs = txs
+            .iter()
+            .zip(&outcome.tx_outcomes)
+            .map(|((tx, tx_hash), outcome)| (tx.clone(), *tx_hash, outcome.clone()))
+            .collect();
+
+        // If a block is already pending, it's one that the chain didn't commit
+        // (e.g. the node crashed), so simply replace it.
+        *self.pending.lock()? = Some(PendingBlock {
+            block: *block,
+            app_hash: outcome.app_hash,
+            events,
+            txs,
+        });
+
+        Ok(())
+    }
+
+    fn commit(&self) -> IndexerResult<()> {
+        let Some(pending) = self.pending.lock()?.take() else {
+            return Err(IndexerError::PendingBlockNotSet);
+        };
+
+        self.write(pending)
+    }
+}
+
+fn write_block(sql_tx: &Transaction, pending: PendingBlock) -> IndexerResult<()> {
+    let PendingBlock {
+        block,
+        app_hash,
+        events,
+        txs,
+    } = pending;
+
+    // Remove any records of the same height that were written before, in case
+    // the block is being replayed.
+    for table in ["txs", "events", "transfers", "contracts"] {
+        sql_tx.execute(&format!("DELETE FROM {table} WHERE height = ?1"), params![
+            block.height
+        ])?;
+    }
+
+    // This overflows if the timestamp (as nanoseconds) exceeds `i64` range, but
+    // that'd be 500 years or so from now...
+    sql_tx.execute(
+        "INSERT OR REPLACE INTO blocks (height, timestamp, hash, app_hash, num_txs)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            block.height,
+            block.timestamp.into_nanos() as i64,
+            block.hash.to_string(),
+            app_hash.to_string(),
+            txs.len(),
+        ],
+    )?;
+
+    write_events(sql_tx, block.height, None, None, events)?;
+
+    for (idx, (tx, tx_hash, outcome)) in txs.into_iter().enumerate() {
+        let tx_hash = tx_hash.to_string();
+
+        sql_tx.execute(
+            "INSERT OR REPLACE INTO txs (hash, height, idx, sender, tx, outcome)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                tx_hash,
+                block.height,
+                idx,
+                tx.sender.to_string(),
+                tx.to_json_string()?,
+                outcome.to_json_string()?,
+            ],
+        )?;
+
+        write_events(sql_tx, block.height, Some(&tx_hash), None, outcome.events)?;
+    }
+
+    Ok(())
+}
+
+// Record the events, as well as the transfers and contract creations they
+// represent, in depth-first order.
+fn write_events(
+    sql_tx: &Transaction,
+    height: u64,
+    tx_hash: Option<&str>,
+    parent_id: Option<i64>,
+    events: Vec<Event>,
+) -> IndexerResult<()> {
+    for mut event in events {
+        let children = mem::take(&mut event.children);
+
+        sql_tx.execute(
+            "INSERT INTO events (parent_id, height, tx_hash, type, contract, event)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                parent_id,
+                height,
+                tx_hash,
+                event.r#type,
+                event.contract.map(|contract| contract.to_string()),
+                event.to_json_string()?,
+            ],
+        )?;
+
+        let id = sql_tx.last_insert_rowid();
+
+        match event.r#type.as_str() {
+            // The bank is called with the transfer as the message.
+            "bank_execute" => {
+                if let Some(msg) = event.msg {
+                    let msg: BankMsg = msg.deserialize_json()?;
+
+                    sql_tx.execute(
+                        "INSERT INTO transfers (height, tx_hash, sender, recipient, coins)
+                         VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![
+                            height,
+                            tx_hash,
+                            msg.from.to_string(),
+                            msg.to.to_string(),
+                            msg.coins.to_json_string()?,
+                        ],
+                    )?;
+                }
+            },
+            "create_contract" => {
+                let address: Addr = find_attribute(&event, "address")?.parse()?;
+                let code_hash: Hash256 = find_attribute(&event, "code_hash")?.parse()?;
+                let creator: Addr = find_attribute(&event, "creator")?.parse()?;
+                let admin = find_attribute(&event, "admin")
+                    .ok()
+                    .map(|admin| admin.parse::<Addr>())
+                    .transpose()?;
+
+                sql_tx.execute(
+                    "INSERT OR REPLACE INTO contracts (address, code_hash, creator, admin, height, tx_hash)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        address.to_string(),
+                        code_hash.to_string(),
+                        creator.to_string(),
+                        admin.map(|admin| admin.to_string()),
+                        height,
+                        tx_hash,
+                    ],
+                )?;
+            },
+            _ => (),
+        }
+
+        write_events(sql_tx, height, tx_hash, Some(id), children)?;
+    }
+
+    Ok(())
+}
+
+fn find_attribute<'a>(event: &'a Event, key: &'static str) -> IndexerResult<&'a str> {
+    event
+        .attributes
+        .iter()
+        .find(|attr| attr.key == key)
+        .map(|attr| attr.value.as_str())
+        .ok_or_else(|| IndexerError::MissingAttribute {
+            ty: event.r#type.clone(),
+            key,
+        })
+}
+
+// ----------------------------------- tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        grug_types::{json, Coins, ContractEvent, GenericResult, Json, Message, Timestamp},
+    };
+
+    const SENDER: Addr = Addr::mock(1);
+    const RECIPIENT: Addr = Addr::mock(2);
+    const CONTRACT: Addr = Addr::mock(3);
+    const BANK: Addr = Addr::mock(4);
+    const CODE_HASH: Hash256 = Hash256::from_array([1; 32]);
+
+    const TX_HASH: Hash256 = Hash256::from_array([4; 32]);
+
+    fn mock_block() -> (BlockInfo, Vec<(Tx, Hash256)>, BlockOutcome) {
+        let block = BlockInfo {
+            height: 1,
+            timestamp: Timestamp::from_seconds(100),
+            hash: Hash256::from_array([2; 32]),
+        };
+
+        let coins = Coins::one("uusdc", 100).unwrap();
+
+        let tx = Tx {
+            sender: SENDER,
+            gas_limit: 1_000_000,
+            msgs: vec![Message::execute(CONTRACT, &json!({ "ping": {} }), coins.clone()).unwrap()],
+            data: Json::Null,
+            credential: Json::Null,
+        };
+
+        let events = vec![
+            Event::new("create_contract")
+                .add_attribute("address", CONTRACT)
+                .add_attribute("code_hash", CODE_HASH)
+                .add_attribute("creator", SENDER),
+            Event {
+                r#type: "execute".to_string(),
+                contract: Some(CONTRACT),
+                msg: Some(json!({ "ping": {} })),
+                contract_events: vec![ContractEvent::new("pinged", &json!({})).unwrap()],
+                children: vec![Event {
+                    r#type: "bank_execute".to_string(),
+                    contract: Some(BANK),
+                    msg: Some(
+                        BankMsg {
+                            from: CONTRACT,
+                            to: RECIPIENT,
+                            coins,
+                        }
+                        .to_json_value()
+                        .unwrap(),
+                    ),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        ];
+
+        let outcome = BlockOutcome {
+            app_hash: Hash256::from_array([3; 32]),
+            cron_outcomes: vec![],
+            tx_outcomes: vec![TxOutcome {
+                gas_limit: 1_000_000,
+                gas_used: 12_345,
+                events,
+                result: GenericResult::Ok(()),
+            }],
+        };
+
+        (block, vec![(tx, TX_HASH)], outcome)
+    }
+
+    #[test]
+    fn indexing_works() {
+        let indexer = SqlIndexer::open_in_memory().unwrap();
+        let (block, txs, outcome) = mock_block();
+
+        indexer.index_block(&block, &txs, &outcome).unwrap();
+
+        // Nothing is written until the block is committed.
+        assert_eq!(indexer.query_block(1).unwrap(), None);
+
+        indexer.commit().unwrap();
+
+        let indexed_block = indexer.query_block(1).unwrap().unwrap();
+        assert_eq!(indexed_block.info, block);
+        assert_eq!(indexed_block.app_hash, outcome.app_hash);
+        assert_eq!(indexed_block.num_txs, 1);
+
+        let indexed_txs = indexer.query_txs_by_sender(SENDER, None, None).unwrap();
+        assert_eq!(indexed_txs.len(), 1);
+        assert_eq!(indexed_txs[0].tx, txs[0].0);
+        assert_eq!(indexed_txs[0].outcome, outcome.tx_outcomes[0]);
+
+        // The transaction is identified by the given hash.
+        let tx_hash = indexed_txs[0].hash;
+        assert_eq!(tx_hash, TX_HASH);
+        assert_eq!(indexer.query_tx(tx_hash).unwrap().unwrap(), indexed_txs[0]);
+
+        // Events are stored without their children, which instead reference
+        // their parents.
+        let events = indexer.query_events_by_tx(tx_hash).unwrap();
+        let summary = events
+            .iter()
+            .map(|event| (event.event.r#type.as_str(), event.parent_id))
+            .collect::<Vec<_>>();
+        assert_eq!(summary, [
+            ("create_contract", None),
+            ("execute", None),
+            ("bank_execute", Some(events[1].id)),
+        ]);
+        assert!(events[1].event.children.is_empty());
+
+        let contract_events = indexer
+            .query_events_by_contract(CONTRACT, None, None)
+            .unwrap();
+        assert_eq!(contract_events, [events[1].clone()]);
+
+        // The transfer is found by both its sender and recipient.
+        for address in [CONTRACT, RECIPIENT] {
+            let transfers = indexer
+                .query_transfers_by_address(address, None, None)
+                .unwrap();
+            assert_eq!(transfers.len(), 1);
+            assert_eq!(transfers[0].from, CONTRACT);
+            assert_eq!(transfers[0].to, RECIPIENT);
+            assert_eq!(transfers[0].tx_hash, Some(tx_hash));
+        }
+
+        assert!(indexer
+            .query_transfers_by_address(SENDER, None, None)
+            .unwrap()
+            .is_empty());
+
+        let contract = indexer.query_contract(CONTRACT).unwrap().unwrap();
+        assert_eq!(contract.code_hash, CODE_HASH);
+        assert_eq!(contract.creator, SENDER);
+        assert_eq!(contract.admin, None);
+        assert_eq!(
+            indexer
+                .query_contracts_by_code_hash(CODE_HASH, None, None)
+                .unwrap(),
+            [contract]
+        );
+    }
+
+    #[test]
+    fn pagination_works() {
+        let indexer = SqlIndexer::open_in_memory().unwrap();
+        let (mut block, mut txs, outcome) = mock_block();
+
+        // Index the same transaction in three blocks, with different hashes.
+        for height in 1..=3 {
+            block.height = height;
+            txs[0].1 = Hash256::from_array([height as u8; 32]);
+            indexer.index_block(&block, &txs, &outcome).unwrap();
+            indexer.commit().unwrap();
+        }
+
+        let page1 = indexer.query_txs_by_sender(SENDER, None, Some(2)).unwrap();
+        assert_eq!(page1.iter().map(|tx| tx.height).collect::<Vec<_>>(), [1, 2]);
+
+        let page2 = indexer
+            .query_txs_by_sender(SENDER, Some(page1[1].id), Some(2))
+            .unwrap();
+        assert_eq!(page2.iter().map(|tx| tx.height).collect::<Vec<_>>(), [3]);
+    }
+
+    #[test]
+    fn committing_without_indexing_fails() {
+        let indexer = SqlIndexer::open_in_memory().unwrap();
+
+        assert!(matches!(
+            indexer.commit(),
+            Err(IndexerError::PendingBlockNotSet)
+        ));
+    }
+
+    #[test]
+    fn indexing_again_replaces_pending_block() {
+        let indexer = SqlIndexer::open_in_memory().unwrap();
+        let (block, txs, outcome) = mock_block();
+
+        indexer.index_block(&block, &txs, &outcome).unwrap();
+        indexer
+            .index_block(&block, &[], &BlockOutcome {
+                tx_outcomes: vec![],
+                ..outcome
+            })
+            .unwrap();
+        indexer.commit().unwrap();
+
+        assert_eq!(indexer.query_block(1).unwrap().unwrap().num_txs, 0);
+        assert_eq!(indexer.query_tx(TX_HASH).unwrap(), None);
+    }
+
+    #[test]
+    fn replaying_block_is_idempotent() {
+        let indexer = SqlIndexer::open_in_memory().unwrap();
+        let (block, txs, outcome) = mock_block();
+
+        // Commit the same block twice, as if the node replays it after a crash.
+        for _ in 0..2 {
+            indexer.index_block(&block, &txs, &outcome).unwrap();
+            indexer.commit().unwrap();
+        }
+
+        assert_eq!(indexer.query_events_by_tx(TX_HASH).unwrap().len(), 3);
+        assert_eq!(
+            indexer
+                .query_transfers_by_address(RECIPIENT, None, None)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            indexer
+                .query_txs_by_sender(SENDER, None, None)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn indexing_genesis_works() {
+        let indexer = SqlIndexer::open_in_memory().unwrap();
+        let (mut block, _, outcome) = mock_block();
+
+        block.height = 0;
+
+        let events = vec![Event::new("create_contract")
+            .add_attribute("address", CONTRACT)
+            .add_attribute("code_hash", CODE_HASH)
+            .add_attribute("creator", SENDER)];
+
+        // Genesis is written right away, without calling `commit`.
+        indexer
+            .index_genesis_block(&block, outcome.app_hash, &events)
+            .unwrap();
+
+        let indexed_block = indexer.query_block(0).unwrap().unwrap();
+        assert_eq!(indexed_block.info, block);
+        assert_eq!(indexed_block.num_txs, 0);
+
+        let contract = indexer.query_contract(CONTRACT).unwrap().unwrap();
+        assert_eq!(contract.height, 0);
+        assert_eq!(contract.tx_hash, None);
+    }
+
+    #[test]
+    fn indexed_data_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.sqlite");
+        let (block, txs, outcome) = mock_block();
+
+        {
+            let indexer = SqlIndexer::open(&path).unwrap();
+            indexer.index_block(&block, &txs, &outcome).unwrap();
+            indexer.commit().unwrap();
+        }
+
+        let indexer = SqlIndexer::open(&path).unwrap();
+        assert_eq!(indexer.query_block(1).unwrap().unwrap().info, block);
+    }
+}
@@ -0,0 +1,6 @@
+mod error;
+mod indexer;
+mod query;
+mod types;
+
+pub use {error::*, indexer::*, query::*, types::*};
@@ -0,0 +1,284 @@
+use {
+    crate::{
+        IndexedBlock, IndexedContract, IndexedEvent, IndexedTransfer, IndexedTx, IndexerResult,
+        SqlIndexer,
+    },
+    grug_types::{Addr, BlockInfo, Hash256, JsonDeExt, Timestamp},
+    rusqlite::{params, OptionalExtension},
+};
+
+pub const DEFAULT_PAGE_LIMIT: u32 = 30;
+
+impl SqlIndexer {
+    /// Query a block by height.
+    pub fn query_block(&self, height: u64) -> IndexerResult<Option<IndexedBlock>> {
+        let conn = self.conn.lock()?;
+
+        conn.query_row(
+            "SELECT timestamp, hash, app_hash, num_txs FROM blocks WHERE height = ?1",
+            params![height],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, u32>(3)?,
+                ))
+            },
+        )
+        .optional()?
+        .map(|(timestamp, hash, app_hash, num_txs)| -> IndexerResult<_> {
+            Ok(IndexedBlock {
+                info: BlockInfo {
+                    height,
+                    timestamp: Timestamp::from_nanos(timestamp as u128),
+                    hash: hash.parse()?,
+                },
+                app_hash: app_hash.parse()?,
+                num_txs,
+            })
+        })
+        .transpose()
+    }
+
+    /// Query a transaction by hash.
+    pub fn query_tx(&self, hash: Hash256) -> IndexerResult<Option<IndexedTx>> {
+        let conn = self.conn.lock()?;
+
+        conn.query_row(
+            "SELECT id, hash, height, idx, tx, outcome FROM txs WHERE hash = ?1",
+            params![hash.to_string()],
+            tx_from_row,
+        )
+        .optional()?
+        .map(into_indexed_tx)
+        .transpose()
+    }
+
+    /// Query transactions sent by the given account, oldest first.
+    pub fn query_txs_by_sender(
+        &self,
+        sender: Addr,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> IndexerResult<Vec<IndexedTx>> {
+        let conn = self.conn.lock()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, hash, height, idx, tx, outcome FROM txs
+             WHERE sender = ?1 AND id > ?2 ORDER BY id LIMIT ?3",
+        )?;
+
+        stmt.query_map(
+            params![
+                sender.to_string(),
+                start_after.unwrap_or(0),
+                limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+            ],
+            tx_from_row,
+        )?
+        .map(|row| into_indexed_tx(row?))
+        .collect()
+    }
+
+    /// Query the events emitted by the given transaction, in the order they
+    /// were emitted.
+    pub fn query_events_by_tx(&self, tx_hash: Hash256) -> IndexerResult<Vec<IndexedEvent>> {
+        let conn = self.conn.lock()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, parent_id, height, tx_hash, event FROM events
+             WHERE tx_hash = ?1 ORDER BY id",
+        )?;
+
+        stmt.query_map(params![tx_hash.to_string()], event_from_row)?
+            .map(|row| into_indexed_event(row?))
+            .collect()
+    }
+
+    /// Query the events emitted by the given contract, oldest first.
+    pub fn query_events_by_contract(
+        &self,
+        contract: Addr,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> IndexerResult<Vec<IndexedEvent>> {
+        let conn = self.conn.lock()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, parent_id, height, tx_hash, event FROM events
+             WHERE contract = ?1 AND id > ?2 ORDER BY id LIMIT ?3",
+        )?;
+
+        stmt.query_map(
+            params![
+                contract.to_string(),
+                start_after.unwrap_or(0),
+                limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+            ],
+            event_from_row,
+        )?
+        .map(|row| into_indexed_event(row?))
+        .collect()
+    }
+
+    /// Query the transfers sent or received by the given account, oldest first.
+    pub fn query_transfers_by_address(
+        &self,
+        address: Addr,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> IndexerResult<Vec<IndexedTransfer>> {
+        let conn = self.conn.lock()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, height, tx_hash, sender, recipient, coins FROM transfers
+             WHERE (sender = ?1 OR recipient = ?1) AND id > ?2 ORDER BY id LIMIT ?3",
+        )?;
+
+        stmt.query_map(
+            params![
+                address.to_string(),
+                start_after.unwrap_or(0),
+                limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, u64>(0)?,
+                    row.get::<_, u64>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                ))
+            },
+        )?
+        .map(|row| -> IndexerResult<_> {
+            let (id, height, tx_hash, from, to, coins) = row?;
+
+            Ok(IndexedTransfer {
+                id,
+                height,
+                tx_hash: tx_hash.map(|hash| hash.parse()).transpose()?,
+                from: from.parse()?,
+                to: to.parse()?,
+                coins: coins.deserialize_json()?,
+            })
+        })
+        .collect()
+    }
+
+    /// Query a contract's creation by address.
+    pub fn query_contract(&self, address: Addr) -> IndexerResult<Option<IndexedContract>> {
+        let conn = self.conn.lock()?;
+
+        conn.query_row(
+            "SELECT address, code_hash, creator, admin, height, tx_hash FROM contracts
+             WHERE address = ?1",
+            params![address.to_string()],
+            contract_from_row,
+        )
+        .optional()?
+        .map(into_indexed_contract)
+        .transpose()
+    }
+
+    /// Query the contracts running the given code, ordered by address.
+    pub fn query_contracts_by_code_hash(
+        &self,
+        code_hash: Hash256,
+        start_after: Option<Addr>,
+        limit: Option<u32>,
+    ) -> IndexerResult<Vec<IndexedContract>> {
+        let conn = self.conn.lock()?;
+        let mut stmt = conn.prepare(
+            "SELECT address, code_hash, creator, admin, height, tx_hash FROM contracts
+             WHERE code_hash = ?1 AND address > ?2 ORDER BY address LIMIT ?3",
+        )?;
+
+        stmt.query_map(
+            params![
+                code_hash.to_string(),
+                start_after
+                    .map(|address| address.to_string())
+                    .unwrap_or_default(),
+                limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+            ],
+            contract_from_row,
+        )?
+        .map(|row| into_indexed_contract(row?))
+        .collect()
+    }
+}
+
+// ---------------------------------- helpers ----------------------------------
+
+type TxRow = (u64, String, u64, u32, String, String);
+
+fn tx_from_row(row: &rusqlite::Row) -> rusqlite::Result<TxRow> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+    ))
+}
+
+fn into_indexed_tx((id, hash, height, index, tx, outcome): TxRow) -> IndexerResult<IndexedTx> {
+    Ok(IndexedTx {
+        id,
+        hash: hash.parse()?,
+        height,
+        index,
+        tx: tx.deserialize_json()?,
+        outcome: outcome.deserialize_json()?,
+    })
+}
+
+type EventRow = (u64, Option<u64>, u64, Option<String>, String);
+
+fn event_from_row(row: &rusqlite::Row) -> rusqlite::Result<EventRow> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+    ))
+}
+
+fn into_indexed_event(
+    (id, parent_id, height, tx_hash, event): EventRow,
+) -> IndexerResult<IndexedEvent> {
+    Ok(IndexedEvent {
+        id,
+        parent_id,
+        height,
+        tx_hash: tx_hash.map(|hash| hash.parse()).transpose()?,
+        event: event.deserialize_json()?,
+    })
+}
+
+type ContractRow = (String, String, String, Option<String>, u64, Option<String>);
+
+fn contract_from_row(row: &rusqlite::Row) -> rusqlite::Result<ContractRow> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+    ))
+}
+
+fn into_indexed_contract(
+    (address, code_hash, creator, admin, height, tx_hash): ContractRow,
+) -> IndexerResult<IndexedContract> {
+    Ok(IndexedContract {
+        address: address.parse()?,
+        code_hash: code_hash.parse()?,
+        creator: creator.parse()?,
+        admin: admin.map(|admin| admin.parse()).transpose()?,
+        height,
+        tx_hash: tx_hash.map(|hash| hash.parse()).transpose()?,
+    })
+}
@@ -0,0 +1,74 @@
+use {
+    grug_types::{Addr, BlockInfo, Coins, Event, Hash256, Tx, TxOutcome},
+    serde::{Deserialize, Serialize},
+};
+
+/// A block, as recorded by the indexer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IndexedBlock {
+    pub info: BlockInfo,
+    /// The Merkle root hash after executing this block.
+    pub app_hash: Hash256,
+    pub num_txs: u32,
+}
+
+/// A transaction and its outcome, as recorded by the indexer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IndexedTx {
+    /// Sequence number of the transaction among all indexed ones, used for
+    /// pagination.
+    pub id: u64,
+    /// SHA-256 hash of the transaction's JSON encoding, which is how it's
+    /// broadcasted to CometBFT.
+    pub hash: Hash256,
+    pub height: u64,
+    /// Position of the transaction in the block.
+    pub index: u32,
+    pub tx: Tx,
+    pub outcome: TxOutcome,
+}
+
+/// An event, as recorded by the indexer.
+///
+/// Events are recorded individually, such that they can be queried by the
+/// emitting contract. The tree structure is kept through the parent ID.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IndexedEvent {
+    /// Sequence number of the event among all indexed ones, used for
+    /// pagination.
+    pub id: u64,
+    /// The event whose children this event is one of, if any.
+    pub parent_id: Option<u64>,
+    pub height: u64,
+    /// The transaction that emitted this event. `None` if emitted by a cronjob.
+    pub tx_hash: Option<Hash256>,
+    /// The event, without its children.
+    pub event: Event,
+}
+
+/// A transfer of coins, as recorded by the indexer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IndexedTransfer {
+    /// Sequence number of the transfer among all indexed ones, used for
+    /// pagination.
+    pub id: u64,
+    pub height: u64,
+    /// The transaction that made this transfer. `None` if made by a cronjob.
+    pub tx_hash: Option<Hash256>,
+    pub from: Addr,
+    pub to: Addr,
+    pub coins: Coins,
+}
+
+/// A contract creation, as recorded by the indexer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IndexedContract {
+    pub address: Addr,
+    pub code_hash: Hash256,
+    pub creator: Addr,
+    pub admin: Option<Addr>,
+    pub height: u64,
+    /// The transaction that created this contract. `None` if created by a
+    /// cronjob or a genesis message.
+    pub tx_hash: Option<Hash256>,
+}
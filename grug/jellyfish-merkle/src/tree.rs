@@ -4,7 +4,8 @@ use {
         ProofNode,
     },
     grug_storage::{Map, PrefixBound, Set},
-    grug_types::{Batch, Hash256, HashExt, Op, Order, StdResult, Storage},
+    grug_types::{Batch, BorshDeExt, Hash256, HashExt, Op, Order, Record, StdResult, Storage},
+    std::iter,
 };
 
 // Default storage namespaces
@@ -554,6 +555,59 @@ impl<'a> MerkleTree<'a> {
         Ok(())
     }
 
+    /// Iterate the nodes that make up the tree at the given version.
+    ///
+    /// The nodes are traversed depth-first, parent before children, left child
+    /// before right child. As such, leaf nodes are yielded in ascending order
+    /// of their key hashes.
+    ///
+    /// Each item consists of the node's raw storage key and value, so that it
+    /// can be written as-is into another storage (e.g. when restoring the tree
+    /// from a state sync snapshot), as well as the node itself.
+    pub fn iter_nodes<'b>(
+        &'b self,
+        storage: &'b dyn Storage,
+        version: u64,
+    ) -> Box<dyn Iterator<Item = StdResult<(Record, Node)>> + 'b> {
+        // If the tree is empty at this version, there isn't a root node to
+        // start from.
+        let mut stack = if self.nodes.has(storage, (version, &ROOT_BITS)) {
+            vec![(version, ROOT_BITS)]
+        } else {
+            vec![]
+        };
+
+        Box::new(iter::from_fn(move || {
+            let (version, bits) = stack.pop()?;
+            let path = self.nodes.path((version, &bits));
+            let path = path.as_path();
+
+            let res = path.load_raw(storage).and_then(|value| {
+                let node = value.deserialize_borsh::<Node>()?;
+
+                // Push the right child first, so that the left child is popped
+                // (and thus visited) first.
+                if let Node::Internal(InternalNode {
+                    left_child,
+                    right_child,
+                }) = &node
+                {
+                    if let Some(child) = right_child {
+                        stack.push((child.version, bits.extend_one_bit(false)));
+                    }
+
+                    if let Some(child) = left_child {
+                        stack.push((child.version, bits.extend_one_bit(true)));
+                    }
+                }
+
+                Ok(((path.storage_key().to_vec(), value), node))
+            });
+
+            Some(res)
+        }))
+    }
+
     #[inline]
     fn save_node(
         &self,
@@ -794,6 +848,30 @@ mod tests {
             .is_none());
     }
 
+    #[test]
+    fn iterating_nodes() {
+        let (storage, _) = build_test_case().unwrap();
+        let nodes = TREE
+            .iter_nodes(&storage, 0)
+            .map(|res| res.map(|(_, node)| node.hash()))
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+
+        // Nodes should be visited parent first, left before right.
+        assert_eq!(nodes, [
+            HASH_ROOT, HASH_0, HASH_01, HASH_010, HASH_011, HASH_0110, HASH_0111, HASH_1
+        ]);
+
+        // The raw records should be identical to those in the storage.
+        for res in TREE.iter_nodes(&storage, 0) {
+            let ((key, value), _) = res.unwrap();
+            assert_eq!(storage.read(&key), Some(value));
+        }
+
+        // There's no node at version 1, so nothing should be yielded.
+        assert!(TREE.iter_nodes(&storage, 1).next().is_none());
+    }
+
     // Delete the leaves 010 and 0110. this should cause the leaf 0111 be moved
     // up to bit path `0`. the result tree is:
     //
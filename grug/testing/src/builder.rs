@@ -5,9 +5,9 @@ use {
     grug_db_memory::MemDb,
     grug_math::Udec128,
     grug_types::{
-        Addr, Binary, BlockInfo, Coins, Config, Defined, Denom, Duration, GenesisState, HashExt,
-        Json, JsonSerExt, MaybeDefined, Message, Permission, Permissions, Timestamp, Undefined,
-        GENESIS_BLOCK_HASH, GENESIS_BLOCK_HEIGHT, GENESIS_SENDER,
+        Addr, Binary, BlockInfo, Coins, Config, Defined, Denom, Duration, GasCosts, GenesisState,
+        HashExt, Json, JsonSerExt, MaybeDefined, Message, Permission, Permissions, Timestamp,
+        Undefined, WasmLimits, GENESIS_BLOCK_HASH, GENESIS_BLOCK_HEIGHT, GENESIS_SENDER,
     },
     grug_vm_rust::RustVm,
     serde::Serialize,
@@ -565,12 +565,14 @@ where
                 upload: Permission::Everybody,
                 instantiate: Permission::Everybody,
             },
+            wasm_limits: WasmLimits::default(),
         };
 
         let genesis_state = GenesisState {
             config,
             msgs,
             app_configs: self.app_configs,
+            gas_costs: GasCosts::default(),
         };
 
         let suite = TestSuite::new_with_vm(
@@ -5,9 +5,10 @@ use {
     grug_db_memory::MemDb,
     grug_math::Uint128,
     grug_types::{
-        Addr, Addressable, Binary, BlockInfo, BlockOutcome, Coins, Config, ConfigUpdates,
-        ContractInfo, Denom, Duration, GenesisState, Hash256, Json, JsonDeExt, JsonSerExt, Message,
-        Op, Outcome, Query, QueryRequest, ResultExt, Signer, StdError, Tx, TxOutcome, UnsignedTx,
+        Addr, Addressable, Binary, BlockInfo, BlockOutcome, CallTrace, Coins, Config,
+        ConfigUpdates, ContractInfo, Denom, Duration, GasCosts, GasProfile, GenesisState, Hash256,
+        Json, JsonDeExt, JsonSerExt, Message, Op, Outcome, Query, QueryRequest, ResultExt, Signer,
+        StdError, Tx, TxOutcome, UnsignedTx,
     },
     grug_vm_rust::RustVm,
     serde::{de::DeserializeOwned, ser::Serialize},
@@ -116,6 +117,28 @@ where
         Ok(self.app.do_simulate(unsigned_tx, 0, false)?)
     }
 
+    /// Simulate an unsigned transaction, and additionally return a breakdown
+    /// of the gas consumed, by contract call frame and by operation.
+    pub fn simulate_tx_with_gas_profile(
+        &self,
+        unsigned_tx: UnsignedTx,
+    ) -> anyhow::Result<(TxOutcome, GasProfile)> {
+        Ok(self
+            .app
+            .do_simulate_with_gas_profile(unsigned_tx, 0, false)?)
+    }
+
+    /// Simulate an unsigned transaction, and additionally return a trace of the
+    /// contract calls made, including the nested ones.
+    pub fn simulate_tx_with_call_traces(
+        &self,
+        unsigned_tx: UnsignedTx,
+    ) -> anyhow::Result<(TxOutcome, Vec<CallTrace>)> {
+        Ok(self
+            .app
+            .do_simulate_with_call_traces(unsigned_tx, 0, false)?)
+    }
+
     /// Perform ABCI `CheckTx` call of a transaction.
     pub fn check_tx(&self, tx: Tx) -> anyhow::Result<Outcome> {
         Ok(self.app.do_check_tx(tx)?)
@@ -134,6 +157,16 @@ where
         self.block.height += 1;
         self.block.timestamp = self.block.timestamp + self.block_time;
 
+        // Pair each transaction with its hash, which CometBFT computes as the
+        // SHA-256 hash of the raw transaction bytes, i.e. the JSON encoding.
+        let txs = txs
+            .into_iter()
+            .map(|tx| {
+                let tx_hash = Hash256::from_array(sha2_256(&tx.to_json_vec()?));
+                Ok((tx, tx_hash))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
         // Call ABCI `FinalizeBlock` method
         let block_outcome = self.app.do_finalize_block(self.block, txs)?;
 
@@ -474,6 +507,13 @@ where
             .map_err(Into::into)
     }
 
+    pub fn query_gas_costs(&self) -> anyhow::Result<GasCosts> {
+        self.app
+            .do_query_app(Query::GasCosts {}, 0, false)
+            .map(|val| val.as_gas_costs())
+            .map_err(Into::into)
+    }
+
     pub fn query_app_config(&self, key: &str) -> anyhow::Result<Json> {
         self.app
             .do_query_app(
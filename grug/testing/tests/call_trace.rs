@@ -0,0 +1,175 @@
+use {
+    grug_testing::TestBuilder,
+    grug_types::{
+        json, Addr, BankMsg, CallTrace, Coins, Empty, Json, JsonSerExt, Message, ResultExt,
+        UnsignedTx,
+    },
+    grug_vm_rust::ContractBuilder,
+};
+
+/// A contract that, when executed, queries its own balance, and then sends the
+/// funds it has received back to the sender. Fails if instructed to.
+mod refunder {
+    use {
+        grug_types::{Denom, Empty, Message, MutableCtx, Response, StdError, StdResult},
+        std::str::FromStr,
+    };
+
+    pub fn instantiate(_ctx: MutableCtx, _msg: Empty) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    pub fn execute(ctx: MutableCtx, fail: bool) -> StdResult<Response> {
+        if fail {
+            return Err(StdError::generic_err("refund refused"));
+        }
+
+        ctx.querier
+            .query_balance(ctx.contract, Denom::from_str("uusdc")?)?;
+
+        Ok(Response::new().add_message(Message::transfer(ctx.sender, ctx.funds)?))
+    }
+}
+
+/// Find the trace among the given ones that represents a call to the given
+/// contract's given entry point.
+fn find_trace<'a>(traces: &'a [CallTrace], callee: Addr, entry_point: &str) -> &'a CallTrace {
+    traces
+        .iter()
+        .find(|trace| trace.callee == callee && trace.entry_point == entry_point)
+        .unwrap()
+}
+
+#[test]
+fn call_tracing_works() {
+    let (mut suite, mut accounts) = TestBuilder::new()
+        .add_account("larry", Coins::one("uusdc", 123).unwrap())
+        .unwrap()
+        .set_owner("larry")
+        .unwrap()
+        .build()
+        .unwrap();
+
+    let larry = accounts["larry"].address;
+    let bank = suite.query_config().unwrap().bank;
+
+    let refunder_code = ContractBuilder::new(Box::new(refunder::instantiate))
+        .with_execute(Box::new(refunder::execute))
+        .build();
+
+    let (_, refunder) = suite
+        .upload_and_instantiate(
+            accounts.get_mut("larry").unwrap(),
+            refunder_code,
+            "refunder",
+            &Empty {},
+            Coins::new(),
+        )
+        .unwrap();
+
+    let funds = Coins::one("uusdc", 100).unwrap();
+
+    let unsigned_tx = UnsignedTx {
+        sender: larry,
+        msgs: vec![Message::execute(refunder, &false, funds.clone()).unwrap()],
+        data: Json::Null,
+    };
+
+    let (outcome, traces) = suite
+        .simulate_tx_with_call_traces(unsigned_tx.clone())
+        .unwrap();
+
+    // Tracing shouldn't change the outcome.
+    assert_eq!(outcome, suite.simulate_tx(unsigned_tx).unwrap());
+    outcome.result.should_succeed();
+
+    // The sender is authenticated by the chain, not by another contract.
+    let authenticate = find_trace(&traces, larry, "authenticate");
+    assert_eq!(authenticate.caller, None);
+    assert_eq!(authenticate.funds, None);
+    authenticate.result.clone().should_succeed();
+
+    // The contract is executed by the sender, with the funds attached.
+    let execute = find_trace(&traces, refunder, "execute");
+    assert_eq!(execute.caller, Some(larry));
+    assert_eq!(execute.funds, Some(funds.clone()));
+    assert_eq!(execute.input, json!(false));
+    assert!(execute.gas_used > 0);
+    execute.result.clone().should_succeed();
+
+    // The query and the submessage made by the contract should be nested under
+    // the contract's call, in the order they were made.
+    let nested = execute
+        .children
+        .iter()
+        .map(|trace| (trace.callee, trace.entry_point.as_str()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(nested[..2], [(bank, "bank_query"), (bank, "bank_execute")]);
+
+    // The refund is carried out by the bank, which is called by the chain.
+    let refund = &execute.children[1];
+    assert_eq!(refund.caller, None);
+    assert_eq!(
+        refund.input,
+        BankMsg {
+            from: refunder,
+            to: larry,
+            coins: funds,
+        }
+        .to_json_value()
+        .unwrap()
+    );
+
+    // Nested calls are part of the call that made them, so can't use more gas.
+    let nested_gas_used = execute
+        .children
+        .iter()
+        .map(|trace| trace.gas_used)
+        .sum::<u64>();
+
+    assert!(nested_gas_used <= execute.gas_used);
+}
+
+#[test]
+fn call_tracing_records_errors() {
+    let (mut suite, mut accounts) = TestBuilder::new()
+        .add_account("larry", Coins::one("uusdc", 123).unwrap())
+        .unwrap()
+        .set_owner("larry")
+        .unwrap()
+        .build()
+        .unwrap();
+
+    let refunder_code = ContractBuilder::new(Box::new(refunder::instantiate))
+        .with_execute(Box::new(refunder::execute))
+        .build();
+
+    let (_, refunder) = suite
+        .upload_and_instantiate(
+            accounts.get_mut("larry").unwrap(),
+            refunder_code,
+            "refunder",
+            &Empty {},
+            Coins::new(),
+        )
+        .unwrap();
+
+    let unsigned_tx = UnsignedTx {
+        sender: accounts["larry"].address,
+        msgs: vec![Message::execute(refunder, &true, Coins::new()).unwrap()],
+        data: Json::Null,
+    };
+
+    let (outcome, traces) = suite.simulate_tx_with_call_traces(unsigned_tx).unwrap();
+
+    // The transaction fails, but the failed call is still traced.
+    outcome.result.should_fail_with_error("refund refused");
+
+    let execute = find_trace(&traces, refunder, "execute");
+    assert!(execute.children.is_empty());
+    execute
+        .result
+        .clone()
+        .should_fail_with_error("refund refused");
+}
@@ -0,0 +1,178 @@
+use {
+    grug_testing::TestBuilder,
+    grug_types::{
+        json, Addr, Attribute, Coins, ContractEvent, Empty, Event, Message, ResultExt,
+        CONTRACT_ADDRESS_KEY,
+    },
+    grug_vm_rust::ContractBuilder,
+};
+
+/// A contract that, when executed, emits an attribute and a structured event,
+/// and optionally executes another contract in the same way.
+mod emitter {
+    use grug_types::{json, Addr, Coins, Empty, Message, MutableCtx, Response, StdResult};
+
+    pub fn instantiate(_ctx: MutableCtx, _msg: Empty) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    pub fn execute(ctx: MutableCtx, next: Option<Addr>) -> StdResult<Response> {
+        let next_msg = next
+            .map(|next| Message::execute(next, &None::<Addr>, Coins::new()))
+            .transpose()?;
+
+        Response::new()
+            .add_attribute("action", "emit")
+            .may_add_message(next_msg)
+            .add_event("emitted", &json!({ "sender": ctx.sender }))
+    }
+}
+
+/// A contract that, when executed, emits an attribute that impersonates one of
+/// the state machine's.
+mod impersonator {
+    use grug_types::{Empty, MutableCtx, Response, StdResult, CONTRACT_ADDRESS_KEY};
+
+    pub fn instantiate(_ctx: MutableCtx, _msg: Empty) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    pub fn execute(ctx: MutableCtx, _msg: Empty) -> StdResult<Response> {
+        Ok(Response::new().add_attribute(CONTRACT_ADDRESS_KEY, ctx.sender))
+    }
+}
+
+/// Find the event among the given ones that is emitted by the given contract's
+/// `execute` entry point.
+fn find_execute_event(events: &[Event], contract: Addr) -> &Event {
+    events
+        .iter()
+        .find(|event| event.r#type == "execute" && event.contract == Some(contract))
+        .unwrap()
+}
+
+#[test]
+fn events_are_nested() {
+    let (mut suite, mut accounts) = TestBuilder::new()
+        .add_account("larry", Coins::one("uusdc", 123).unwrap())
+        .unwrap()
+        .set_owner("larry")
+        .unwrap()
+        .build()
+        .unwrap();
+
+    let larry = accounts["larry"].address;
+
+    let emitter_code = ContractBuilder::new(Box::new(emitter::instantiate))
+        .with_execute(Box::new(emitter::execute))
+        .build();
+
+    let (code_hash, emitter1) = suite
+        .upload_and_instantiate(
+            accounts.get_mut("larry").unwrap(),
+            emitter_code,
+            "emitter1",
+            &Empty {},
+            Coins::new(),
+        )
+        .unwrap();
+
+    let emitter2 = suite
+        .instantiate(
+            accounts.get_mut("larry").unwrap(),
+            code_hash,
+            "emitter2",
+            &Empty {},
+            Coins::new(),
+        )
+        .unwrap();
+
+    let outcome = suite
+        .send_message(
+            accounts.get_mut("larry").unwrap(),
+            Message::execute(emitter1, &Some(emitter2), Coins::new()).unwrap(),
+        )
+        .unwrap();
+
+    let events = outcome.result.should_succeed();
+
+    // The event records the emitting contract and the message that caused it,
+    // along with what the contract emitted.
+    let event1 = find_execute_event(&events, emitter1);
+    assert_eq!(event1.msg, Some(json!(emitter2)));
+    assert_eq!(event1.attributes, [Attribute::new("action", "emit")]);
+    assert_eq!(event1.contract_events, [ContractEvent {
+        r#type: "emitted".to_string(),
+        data: json!({ "sender": larry }),
+    }]);
+
+    // The event emitted by the submessage is nested under the event of the
+    // contract that emitted the submessage.
+    let event2 = find_execute_event(&event1.children, emitter2);
+    assert_eq!(event2.msg, Some(json!(null)));
+    assert_eq!(event2.contract_events, [ContractEvent {
+        r#type: "emitted".to_string(),
+        data: json!({ "sender": emitter1 }),
+    }]);
+    assert!(event2.children.is_empty());
+
+    // No information is lost when converting to the format of CometBFT.
+    assert_eq!(
+        Event::unflatten(Event::flatten(events.clone())).unwrap(),
+        events
+    );
+}
+
+#[test]
+fn reserved_attribute_keys_are_escaped() {
+    let (mut suite, mut accounts) = TestBuilder::new()
+        .add_account("larry", Coins::one("uusdc", 123).unwrap())
+        .unwrap()
+        .set_owner("larry")
+        .unwrap()
+        .build()
+        .unwrap();
+
+    let larry = accounts["larry"].address;
+
+    let impersonator_code = ContractBuilder::new(Box::new(impersonator::instantiate))
+        .with_execute(Box::new(impersonator::execute))
+        .build();
+
+    let (_, impersonator) = suite
+        .upload_and_instantiate(
+            accounts.get_mut("larry").unwrap(),
+            impersonator_code,
+            "impersonator",
+            &Empty {},
+            Coins::new(),
+        )
+        .unwrap();
+
+    // Emitting an attribute with a reserved key doesn't fail the call.
+    let events = suite
+        .send_message(
+            accounts.get_mut("larry").unwrap(),
+            Message::execute(impersonator, &Empty {}, Coins::new()).unwrap(),
+        )
+        .unwrap()
+        .result
+        .should_succeed();
+
+    let event = find_execute_event(&events, impersonator);
+    assert_eq!(event.attributes, [Attribute::new(
+        CONTRACT_ADDRESS_KEY,
+        larry
+    )]);
+
+    // When flattened, the attribute's key is escaped, so it can't be mistaken
+    // for the one recording the emitting contract.
+    let flat_events = Event::flatten([event.clone()]);
+    let attributes = &flat_events[0].attributes;
+    assert!(attributes.contains(&Attribute::new(CONTRACT_ADDRESS_KEY, impersonator)));
+    assert!(attributes.contains(&Attribute::new(format!("_{CONTRACT_ADDRESS_KEY}"), larry)));
+    assert!(!attributes.contains(&Attribute::new(CONTRACT_ADDRESS_KEY, larry)));
+
+    // Unflattening recovers the original key.
+    assert_eq!(Event::unflatten(flat_events).unwrap(), [event.clone()]);
+}
@@ -0,0 +1,150 @@
+use {
+    grug_testing::TestBuilder,
+    grug_types::{ConfigUpdates, GasCosts, LinearGasCost, Message, ResultExt, Signer, TxOutcome},
+    std::collections::BTreeMap,
+};
+
+const GAS_LIMIT: u64 = 1_000_000;
+
+/// Return the amount of gas used by a transaction that is expected to succeed.
+fn gas_used(outcome: TxOutcome) -> u64 {
+    outcome.result.should_succeed();
+    outcome.gas_used
+}
+
+#[test]
+fn updating_gas_costs_works() {
+    let (mut suite, mut accounts) = TestBuilder::new()
+        .add_account("larry", [("ugrug", 100)])
+        .unwrap()
+        .set_owner("larry")
+        .unwrap()
+        .build()
+        .unwrap();
+
+    // Without being specified in the genesis state, the default costs are used.
+    let old_costs = suite.query_gas_costs().unwrap();
+    assert_eq!(old_costs, GasCosts::default());
+
+    let new_costs = GasCosts {
+        db_write: LinearGasCost::new(old_costs.db_write.base * 2, old_costs.db_write.per_item * 2),
+        ..old_costs.clone()
+    };
+
+    // Upload a code under the old costs, for reference.
+    let outcome = suite
+        .send_message(
+            accounts.get_mut("larry").unwrap(),
+            Message::upload(b"jake".to_vec()),
+        )
+        .unwrap();
+    let old_gas_used = gas_used(outcome);
+
+    // Update the costs, and upload a code of the same size in the same block.
+    // The new costs shouldn't take effect until the next block.
+    let larry = accounts.get_mut("larry").unwrap();
+    let configure_tx = larry
+        .sign_transaction(
+            vec![Message::configure(
+                ConfigUpdates {
+                    gas_costs: Some(Box::new(new_costs.clone())),
+                    ..Default::default()
+                },
+                BTreeMap::new(),
+            )],
+            &suite.chain_id,
+            GAS_LIMIT,
+        )
+        .unwrap();
+    let upload_tx = larry
+        .sign_transaction(
+            vec![Message::upload(b"mike".to_vec())],
+            &suite.chain_id,
+            GAS_LIMIT,
+        )
+        .unwrap();
+
+    let mut outcomes = suite
+        .make_block(vec![configure_tx, upload_tx])
+        .unwrap()
+        .tx_outcomes;
+    let upload_outcome = outcomes.pop().unwrap();
+    outcomes.pop().unwrap().result.should_succeed();
+    assert_eq!(gas_used(upload_outcome), old_gas_used);
+
+    // The new costs can be queried.
+    assert_eq!(suite.query_gas_costs().unwrap(), new_costs);
+
+    // Starting from the next block, uploading costs more gas.
+    let outcome = suite
+        .send_message(
+            accounts.get_mut("larry").unwrap(),
+            Message::upload(b"pump".to_vec()),
+        )
+        .unwrap();
+    assert!(gas_used(outcome) > old_gas_used);
+}
+
+#[test]
+fn updating_gas_costs_by_non_owner_fails() {
+    let (mut suite, mut accounts) = TestBuilder::new()
+        .add_account("larry", [("ugrug", 100)])
+        .unwrap()
+        .add_account("jake", [("ugrug", 100)])
+        .unwrap()
+        .set_owner("larry")
+        .unwrap()
+        .build()
+        .unwrap();
+
+    suite
+        .send_message(
+            accounts.get_mut("jake").unwrap(),
+            Message::configure(
+                ConfigUpdates {
+                    gas_costs: Some(Box::new(GasCosts {
+                        db_read: LinearGasCost::new(0, 0),
+                        ..GasCosts::default()
+                    })),
+                    ..Default::default()
+                },
+                BTreeMap::new(),
+            ),
+        )
+        .unwrap()
+        .result
+        .should_fail();
+
+    assert_eq!(suite.query_gas_costs().unwrap(), GasCosts::default());
+}
+
+#[test]
+fn updating_gas_costs_to_overflowing_values_fails() {
+    let (mut suite, mut accounts) = TestBuilder::new()
+        .add_account("larry", [("ugrug", 100)])
+        .unwrap()
+        .set_owner("larry")
+        .unwrap()
+        .build()
+        .unwrap();
+
+    suite
+        .send_message(
+            accounts.get_mut("larry").unwrap(),
+            Message::configure(
+                ConfigUpdates {
+                    gas_costs: Some(Box::new(GasCosts {
+                        sha2_256: LinearGasCost::new(0, u64::MAX / 2),
+                        ..GasCosts::default()
+                    })),
+                    ..Default::default()
+                },
+                BTreeMap::new(),
+            ),
+        )
+        .unwrap()
+        .result
+        .should_fail_with_error("gas cost `sha2_256` overflows");
+
+    assert_eq!(suite.query_gas_costs().unwrap(), GasCosts::default());
+}
@@ -0,0 +1,133 @@
+use {
+    grug_testing::TestBuilder,
+    grug_types::{Coins, Empty, GasProfile, Json, Message, ResultExt, UnsignedTx},
+    grug_vm_rust::ContractBuilder,
+};
+
+/// A contract that, when executed, queries its own balance, and then sends the
+/// funds it has received back to the sender.
+mod refunder {
+    use {
+        grug_types::{Denom, Empty, Message, MutableCtx, Response, StdResult},
+        std::str::FromStr,
+    };
+
+    pub fn instantiate(_ctx: MutableCtx, _msg: Empty) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    pub fn execute(ctx: MutableCtx, _msg: Empty) -> StdResult<Response> {
+        ctx.querier
+            .query_balance(ctx.contract, Denom::from_str("uusdc")?)?;
+
+        Ok(Response::new().add_message(Message::transfer(ctx.sender, ctx.funds)?))
+    }
+}
+
+/// Find the first frame in the profile, in depth-first order, that matches the
+/// given predicate.
+fn find_frame<'a, F>(profile: &'a GasProfile, predicate: &F) -> Option<&'a GasProfile>
+where
+    F: Fn(&GasProfile) -> bool,
+{
+    if predicate(profile) {
+        return Some(profile);
+    }
+
+    profile
+        .children
+        .iter()
+        .find_map(|child| find_frame(child, predicate))
+}
+
+/// Assert that the gas used in each frame equals the sum of the gas used
+/// directly by it and by its children.
+fn assert_consistent(profile: &GasProfile) {
+    let direct = profile.operations.values().sum::<u64>();
+    let children = profile
+        .children
+        .iter()
+        .map(|child| child.gas_used)
+        .sum::<u64>();
+
+    assert_eq!(profile.gas_used, direct + children);
+
+    for child in &profile.children {
+        assert_consistent(child);
+    }
+}
+
+#[test]
+fn gas_profiling_works() {
+    let (mut suite, mut accounts) = TestBuilder::new()
+        .add_account("larry", Coins::one("uusdc", 123).unwrap())
+        .unwrap()
+        .set_owner("larry")
+        .unwrap()
+        .build()
+        .unwrap();
+
+    let bank = suite.query_config().unwrap().bank;
+
+    let refunder_code = ContractBuilder::new(Box::new(refunder::instantiate))
+        .with_execute(Box::new(refunder::execute))
+        .build();
+
+    let (_, refunder) = suite
+        .upload_and_instantiate(
+            accounts.get_mut("larry").unwrap(),
+            refunder_code,
+            "refunder",
+            &Empty {},
+            Coins::new(),
+        )
+        .unwrap();
+
+    let unsigned_tx = UnsignedTx {
+        sender: accounts["larry"].address,
+        msgs: vec![
+            Message::execute(refunder, &Empty {}, Coins::one("uusdc", 100).unwrap()).unwrap(),
+        ],
+        data: Json::Null,
+    };
+
+    let (outcome, profile) = suite
+        .simulate_tx_with_gas_profile(unsigned_tx.clone())
+        .unwrap();
+
+    // Profiling shouldn't change the outcome.
+    assert_eq!(outcome, suite.simulate_tx(unsigned_tx).unwrap());
+    outcome.result.should_succeed();
+
+    // The root frame represents the transaction.
+    assert_eq!(profile.contract, None);
+    assert_eq!(profile.entry_point, None);
+    assert_eq!(profile.gas_used, outcome.gas_used);
+    assert_consistent(&profile);
+
+    // The sender is authenticated in its own frame.
+    assert!(profile.children.iter().any(|frame| {
+        frame.contract == Some(accounts["larry"].address)
+            && frame.entry_point.as_deref() == Some("authenticate")
+    }));
+
+    // The query and the submessage made by the contract should be nested under
+    // the contract's frame, in the order they were made.
+    let execute_frame = find_frame(&profile, &|frame: &GasProfile| {
+        frame.contract == Some(refunder) && frame.entry_point.as_deref() == Some("execute")
+    })
+    .unwrap();
+
+    let nested = execute_frame
+        .children
+        .iter()
+        .map(|frame| {
+            (
+                frame.contract.unwrap(),
+                frame.entry_point.as_deref().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(nested[..2], [(bank, "bank_query"), (bank, "bank_execute")]);
+}
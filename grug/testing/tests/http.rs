@@ -0,0 +1,172 @@
+use {
+    futures::StreamExt,
+    grug_app::{
+        App, AppError, BlockStream, CommittedBlock, Db, Indexer, QueryAppRequest,
+        QueryStoreRequest, QueryStoreResponse, SimulateRequest, Vm,
+    },
+    grug_testing::TestBuilder,
+    grug_types::{
+        Addr, BlockInfo, Coins, Hash256, Json, JsonDeExt, Message, Query, QueryResponse, TxOutcome,
+        UnsignedTx,
+    },
+    reqwest::{Client, StatusCode},
+    std::{net::SocketAddr, num::NonZeroUsize},
+    tokio::net::TcpListener,
+    tokio_tungstenite::connect_async,
+};
+
+// Start the HTTP server on a local socket, with a port assigned by the OS.
+async fn start_server<DB, VM, ID>(app: App<DB, VM, ID>, blocks: BlockStream) -> SocketAddr
+where
+    DB: Db + Clone + Send + Sync + 'static,
+    VM: Vm + Clone + Send + Sync + 'static,
+    ID: Indexer + Clone + Send + Sync + 'static,
+    AppError: From<DB::Error> + From<VM::Error>,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(app.start_http_server(listener, blocks));
+
+    addr
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_queries_work() {
+    let (suite, accounts) = TestBuilder::new()
+        .add_account("larry", Coins::one("uusdc", 123).unwrap())
+        .unwrap()
+        .set_owner("larry")
+        .unwrap()
+        .build()
+        .unwrap();
+
+    let blocks = BlockStream::new(NonZeroUsize::new(10).unwrap());
+    let addr = start_server(suite.app.clone(), blocks).await;
+    let client = Client::new();
+
+    // Query the app.
+    let res = client
+        .post(format!("http://{addr}/query/app"))
+        .json(&QueryAppRequest {
+            query: Query::Config {},
+            height: 0,
+            prove: false,
+        })
+        .send()
+        .await
+        .unwrap()
+        .json::<QueryResponse>()
+        .await
+        .unwrap();
+
+    assert_eq!(res.as_config(), suite.query_config().unwrap());
+
+    // Query the store, with a proof.
+    let res = client
+        .post(format!("http://{addr}/query/store"))
+        .json(&QueryStoreRequest {
+            key: b"chain_id".to_vec().into(),
+            height: 0,
+            prove: true,
+        })
+        .send()
+        .await
+        .unwrap()
+        .json::<QueryStoreResponse>()
+        .await
+        .unwrap();
+
+    let (value, proof) = suite.app.do_query_store(b"chain_id", 0, true).unwrap();
+    assert_eq!(res.value, value.map(Into::into));
+    assert_eq!(res.proof, proof.map(Into::into));
+
+    // Simulate a transaction.
+    let unsigned_tx = UnsignedTx {
+        sender: accounts["larry"].address,
+        msgs: vec![Message::transfer(Addr::mock(1), Coins::one("uusdc", 100).unwrap()).unwrap()],
+        data: Json::Null,
+    };
+
+    let res = client
+        .post(format!("http://{addr}/simulate"))
+        .json(&SimulateRequest {
+            tx: unsigned_tx.clone(),
+            height: 0,
+            prove: false,
+        })
+        .send()
+        .await
+        .unwrap()
+        .json::<TxOutcome>()
+        .await
+        .unwrap();
+
+    assert_eq!(res, suite.simulate_tx(unsigned_tx).unwrap());
+
+    // Errors are returned as the response body.
+    let res = client
+        .post(format!("http://{addr}/query/app"))
+        .json(&QueryAppRequest {
+            query: Query::Config {},
+            height: 0,
+            prove: true,
+        })
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert!(res
+        .text()
+        .await
+        .unwrap()
+        .contains("Merkle proof is not supported"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn http_block_subscription_works() {
+    let (suite, _) = TestBuilder::new()
+        .add_account("larry", Coins::one("uusdc", 123).unwrap())
+        .unwrap()
+        .set_owner("larry")
+        .unwrap()
+        .build()
+        .unwrap();
+
+    let blocks = BlockStream::new(NonZeroUsize::new(10).unwrap());
+    let app = suite.app.clone().with_indexer(blocks.clone());
+    let addr = start_server(app.clone(), blocks).await;
+
+    let (mut ws, _) = connect_async(format!("ws://{addr}/subscribe/blocks"))
+        .await
+        .unwrap();
+
+    let mut block = suite.block;
+
+    for _ in 0..2 {
+        block = BlockInfo {
+            height: block.height + 1,
+            timestamp: block.timestamp + suite.block_time,
+            hash: Hash256::ZERO,
+        };
+
+        let outcome = app.do_finalize_block(block, vec![]).unwrap();
+        app.do_commit().unwrap();
+
+        let committed: CommittedBlock = ws
+            .next()
+            .await
+            .unwrap()
+            .unwrap()
+            .into_text()
+            .unwrap()
+            .deserialize_json()
+            .unwrap();
+
+        assert_eq!(committed, CommittedBlock {
+            info: block,
+            outcome,
+        });
+    }
+}
@@ -0,0 +1,206 @@
+use {
+    grug_app::ExecutionMode,
+    grug_math::Udec128,
+    grug_mock_account::PublicKey,
+    grug_testing::{TestAccount, TestSuite, TestVm},
+    grug_types::{
+        Addr, BlockInfo, Coins, Config, Denom, Duration, GasCosts, GenesisState, HashExt, Message,
+        Permission, Permissions, Signer, Timestamp, Tx, WasmLimits, GENESIS_BLOCK_HASH,
+        GENESIS_BLOCK_HEIGHT, GENESIS_SENDER,
+    },
+    grug_vm_rust::RustVm,
+    k256::ecdsa::SigningKey,
+    std::{collections::BTreeMap, num::NonZeroUsize, str::FromStr},
+};
+
+const NUM_ACCOUNTS: u8 = 8;
+
+/// Create a test suite, as well as the accounts in it.
+///
+/// Unlike `TestBuilder`, which generates random keys, here the accounts use
+/// deterministic keys, such that we can create two chains with identical
+/// states, and feed them the same signed transactions.
+fn setup_suite(execution_mode: ExecutionMode) -> (TestSuite, Vec<TestAccount>) {
+    let account_code = RustVm::default_account_code();
+    let bank_code = RustVm::default_bank_code();
+    let taxman_code = RustVm::default_taxman_code();
+
+    let accounts = (1..=NUM_ACCOUNTS)
+        .map(|i| {
+            let salt = format!("account-{i}");
+            let sk = SigningKey::from_slice(&[i; 32]).unwrap();
+            let pk: [u8; 33] = sk
+                .verifying_key()
+                .to_encoded_point(true)
+                .to_bytes()
+                .as_ref()
+                .try_into()
+                .unwrap();
+
+            TestAccount {
+                address: Addr::compute(GENESIS_SENDER, account_code.hash256(), salt.as_bytes()),
+                sk,
+                pk: PublicKey::from_inner(pk),
+                sequence: 0,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let initial_balances = accounts
+        .iter()
+        .map(|account| (account.address, Coins::one("uatom", 100).unwrap()))
+        .collect();
+
+    let mut msgs = vec![
+        Message::upload(account_code.clone()),
+        Message::upload(bank_code.clone()),
+        Message::upload(taxman_code.clone()),
+        Message::instantiate(
+            bank_code.hash256(),
+            &grug_mock_bank::InstantiateMsg { initial_balances },
+            "bank",
+            Coins::new(),
+            None,
+        )
+        .unwrap(),
+        Message::instantiate(
+            taxman_code.hash256(),
+            &grug_mock_taxman::InstantiateMsg {
+                config: grug_mock_taxman::Config {
+                    fee_denom: Denom::from_str("ugrug").unwrap(),
+                    fee_rate: Udec128::ZERO,
+                },
+            },
+            "taxman",
+            Coins::new(),
+            None,
+        )
+        .unwrap(),
+    ];
+
+    for (i, account) in (1..=NUM_ACCOUNTS).zip(&accounts) {
+        msgs.push(
+            Message::instantiate(
+                account_code.hash256(),
+                &grug_mock_account::InstantiateMsg {
+                    public_key: account.pk,
+                },
+                format!("account-{i}"),
+                Coins::new(),
+                Some(account.address),
+            )
+            .unwrap(),
+        );
+    }
+
+    let genesis_state = GenesisState {
+        config: Config {
+            owner: accounts[0].address,
+            bank: Addr::compute(GENESIS_SENDER, bank_code.hash256(), b"bank"),
+            taxman: Addr::compute(GENESIS_SENDER, taxman_code.hash256(), b"taxman"),
+            cronjobs: BTreeMap::new(),
+            permissions: Permissions {
+                upload: Permission::Everybody,
+                instantiate: Permission::Everybody,
+            },
+            wasm_limits: WasmLimits::default(),
+        },
+        app_configs: BTreeMap::new(),
+        gas_costs: GasCosts::default(),
+        msgs,
+    };
+
+    let genesis_block = BlockInfo {
+        height: GENESIS_BLOCK_HEIGHT,
+        timestamp: Timestamp::from_nanos(0),
+        hash: GENESIS_BLOCK_HASH,
+    };
+
+    let mut suite = TestSuite::new(
+        "dev-1".to_string(),
+        Duration::from_seconds(1),
+        1_000_000,
+        genesis_block,
+        genesis_state,
+    )
+    .unwrap();
+
+    suite.app.set_execution_mode(execution_mode);
+
+    (suite, accounts)
+}
+
+fn transfer(sender: &mut TestAccount, to: Addr, amount: u128) -> Tx {
+    sender
+        .sign_transaction(
+            vec![Message::transfer(to, Coins::one("uatom", amount).unwrap()).unwrap()],
+            "dev-1",
+            1_000_000,
+        )
+        .unwrap()
+}
+
+#[test]
+fn parallel_execution_matches_sequential() {
+    let (mut seq_suite, mut accounts) = setup_suite(ExecutionMode::Sequential);
+    let (mut par_suite, _) = setup_suite(ExecutionMode::Parallel {
+        workers: NonZeroUsize::new(4).unwrap(),
+    });
+
+    for round in 0..3 {
+        let mut txs = vec![];
+
+        // Each account sends tokens to the next one. Every tx reads the balance
+        // written by the previous one, so there will be plenty of conflicts.
+        for i in 0..accounts.len() {
+            let next = (i + 1) % accounts.len();
+            let to = accounts[next].address;
+            txs.push(transfer(&mut accounts[i], to, 10 + round));
+        }
+
+        // The first account sends a second tx in the same block. It needs the
+        // sequence number incremented by its previous tx.
+        let to = accounts[2].address;
+        txs.push(transfer(&mut accounts[0], to, 5));
+
+        // A tx that fails because the sender has insufficient balance.
+        let to = accounts[4].address;
+        txs.push(transfer(&mut accounts[3], to, 1_000_000));
+
+        // A tx that fails authentication, because it has an incorrect sequence.
+        let tx = accounts[5]
+            .sign_transaction_with_sequence(
+                vec![
+                    Message::transfer(accounts[6].address, Coins::one("uatom", 1).unwrap())
+                        .unwrap(),
+                ],
+                "dev-1",
+                999,
+                1_000_000,
+            )
+            .unwrap();
+        txs.push(tx);
+
+        let seq_outcome = seq_suite.make_block(txs.clone()).unwrap();
+        let par_outcome = par_suite.make_block(txs).unwrap();
+
+        assert_eq!(seq_outcome.app_hash, par_outcome.app_hash);
+        assert_eq!(seq_outcome.cron_outcomes, par_outcome.cron_outcomes);
+        assert_eq!(seq_outcome.tx_outcomes, par_outcome.tx_outcomes);
+
+        // Make sure the txs actually did something, and some of them failed.
+        let num_failed = seq_outcome
+            .tx_outcomes
+            .iter()
+            .filter(|outcome| outcome.result.is_err())
+            .count();
+        assert_eq!(num_failed, 2);
+    }
+
+    for account in &accounts {
+        assert_eq!(
+            seq_suite.query_balances(account).unwrap(),
+            par_suite.query_balances(account).unwrap()
+        );
+    }
+}
@@ -1,10 +1,16 @@
 use {
-    crate::{Addr, Duration, Event, GenericResult, Hash256, Json, Message, Timestamp},
+    crate::{
+        Addr, Duration, Event, GasCosts, GenericResult, Hash256, Json, Message, StdError,
+        StdResult, Timestamp,
+    },
     borsh::{BorshDeserialize, BorshSerialize},
     hex_literal::hex,
     serde::{Deserialize, Serialize},
     serde_with::skip_serializing_none,
-    std::collections::{BTreeMap, BTreeSet},
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        io::{self, Read},
+    },
 };
 
 /// The mock up sender address used for executing genesis messages.
@@ -43,6 +49,9 @@ pub struct GenesisState {
     pub config: Config,
     /// App-specific configurations.
     pub app_configs: BTreeMap<String, Json>,
+    /// Gas costs of host operations. Uses the default costs if not provided.
+    #[serde(default)]
+    pub gas_costs: GasCosts,
     /// Messages to be executed in order during genesis.
     pub msgs: Vec<Message>,
 }
@@ -62,6 +71,29 @@ pub struct Config {
     pub cronjobs: BTreeMap<Addr, Duration>,
     /// Permissions for certain gated actions.
     pub permissions: Permissions,
+    /// Limits on the size and complexity of Wasm modules that can be uploaded.
+    /// Uses the default limits if not provided.
+    ///
+    /// Configs stored before this field was introduced don't include it, in
+    /// which case the default limits are used as well.
+    #[serde(default)]
+    #[borsh(deserialize_with = "deserialize_wasm_limits")]
+    pub wasm_limits: WasmLimits,
+}
+
+/// Deserialize the Wasm limits, which is the last field of the config, or use
+/// the default ones if the input has already been exhausted.
+fn deserialize_wasm_limits<R>(reader: &mut R) -> io::Result<WasmLimits>
+where
+    R: io::Read,
+{
+    let mut first_byte = Vec::with_capacity(1);
+
+    if reader.take(1).read_to_end(&mut first_byte)? == 0 {
+        return Ok(WasmLimits::default());
+    }
+
+    BorshDeserialize::deserialize_reader(&mut first_byte.as_slice().chain(reader))
 }
 
 /// Set of updates to be made to the config.
@@ -79,6 +111,15 @@ pub struct ConfigUpdates {
     pub taxman: Option<Addr>,
     pub cronjobs: Option<BTreeMap<Addr, Duration>>,
     pub permissions: Option<Permissions>,
+    /// New gas costs of host operations. Takes effect starting from the next
+    /// block.
+    ///
+    /// Boxed, because `GasCosts` is much bigger than the other fields, and
+    /// would otherwise make `Message` much bigger.
+    pub gas_costs: Option<Box<GasCosts>>,
+    /// New limits on Wasm modules. Only applies to code uploaded after the
+    /// update; code that has already been uploaded remains usable.
+    pub wasm_limits: Option<WasmLimits>,
 }
 
 #[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
@@ -100,6 +141,69 @@ pub enum Permission {
     Somebodies(BTreeSet<Addr>),
 }
 
+/// Limits on the size and complexity of Wasm modules, enforced when code is
+/// uploaded.
+///
+/// Compiling a module takes time roughly linear to its size and complexity, so
+/// these prevent uploaders from making every node spend a long time compiling.
+#[derive(
+    Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq,
+)]
+#[serde(deny_unknown_fields)]
+pub struct WasmLimits {
+    /// Maximum size of a module, in bytes.
+    pub max_code_size: u32,
+    /// Maximum number of functions defined in a module, not including the
+    /// imported ones.
+    pub max_functions: u32,
+    /// Maximum number of locals declared in a single function, not including
+    /// its parameters.
+    pub max_function_locals: u32,
+    /// Maximum number of elements in a table.
+    pub max_table_size: u32,
+    /// Maximum number of imports of a module.
+    pub max_imports: u32,
+}
+
+impl Default for WasmLimits {
+    /// The limits a chain starts with, unless otherwise specified in the
+    /// genesis state.
+    ///
+    /// Where applicable, these are consistent with CosmWasm.
+    fn default() -> Self {
+        Self {
+            max_code_size: 3 * 1024 * 1024,
+            max_functions: 20_000,
+            max_function_locals: 10_000,
+            max_table_size: 2_500,
+            max_imports: 100,
+        }
+    }
+}
+
+impl WasmLimits {
+    /// Ensure none of the limits is zero, which would reject every module.
+    ///
+    /// Uploading can be disabled with the upload permission instead.
+    pub fn validate(&self) -> StdResult<()> {
+        for (name, limit) in [
+            ("max_code_size", self.max_code_size),
+            ("max_functions", self.max_functions),
+            ("max_function_locals", self.max_function_locals),
+            ("max_table_size", self.max_table_size),
+            ("max_imports", self.max_imports),
+        ] {
+            if limit == 0 {
+                return Err(StdError::generic_err(format!(
+                    "wasm limit `{name}` can't be zero"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(
     Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq,
 )]
@@ -155,8 +259,8 @@ pub struct TxOutcome {
     pub result: GenericResult<()>,
 }
 
-#[derive(Debug)]
 /// Outcome of executing a block.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct BlockOutcome {
     /// The Merkle root hash after executing this block.
     pub app_hash: Hash256,
@@ -165,3 +269,79 @@ pub struct BlockOutcome {
     /// Results of executing the transactions.
     pub tx_outcomes: Vec<TxOutcome>,
 }
+
+// ----------------------------------- tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{json, BorshDeExt, BorshSerExt, JsonDeExt},
+    };
+
+    /// `Config` as it was encoded before Wasm limits were added.
+    #[derive(BorshSerialize)]
+    struct LegacyConfig {
+        owner: Addr,
+        bank: Addr,
+        taxman: Addr,
+        cronjobs: BTreeMap<Addr, Duration>,
+        permissions: Permissions,
+    }
+
+    #[test]
+    fn decoding_config_without_wasm_limits() {
+        let permissions = Permissions {
+            upload: Permission::Nobody,
+            instantiate: Permission::Everybody,
+        };
+
+        let mut config = Config {
+            owner: Addr::mock(1),
+            bank: Addr::mock(2),
+            taxman: Addr::mock(3),
+            cronjobs: BTreeMap::new(),
+            permissions: permissions.clone(),
+            wasm_limits: WasmLimits::default(),
+        };
+
+        // Configs stored before the limits were added use the default ones.
+        let legacy = LegacyConfig {
+            owner: Addr::mock(1),
+            bank: Addr::mock(2),
+            taxman: Addr::mock(3),
+            cronjobs: BTreeMap::new(),
+            permissions,
+        }
+        .to_borsh_vec()
+        .unwrap();
+
+        assert_eq!(legacy.deserialize_borsh::<Config>().unwrap(), config);
+
+        // So do genesis states that don't specify them.
+        let json = json!({
+            "owner": Addr::mock(1),
+            "bank": Addr::mock(2),
+            "taxman": Addr::mock(3),
+            "cronjobs": {},
+            "permissions": {
+                "upload": "nobody",
+                "instantiate": "everybody",
+            },
+        });
+
+        assert_eq!(json.deserialize_json::<Config>().unwrap(), config);
+
+        // Configs with limits are encoded and decoded as normal.
+        config.wasm_limits.max_functions = 10;
+
+        assert_eq!(
+            config
+                .to_borsh_vec()
+                .unwrap()
+                .deserialize_borsh::<Config>()
+                .unwrap(),
+            config
+        );
+    }
+}
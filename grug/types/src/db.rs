@@ -1,5 +1,5 @@
 use {
-    crate::{StdError, StdResult},
+    crate::{Hash256, StdError, StdResult},
     borsh::{BorshDeserialize, BorshSerialize},
     serde::{Deserialize, Serialize},
     std::collections::BTreeMap,
@@ -52,6 +52,23 @@ impl<V> Op<V> {
     }
 }
 
+/// Metadata of a snapshot of the state at a given version, which a node can
+/// use to restore the state without replaying blocks from genesis, a process
+/// known as "state sync".
+///
+/// The snapshot's data is split into chunks, which are addressed by their
+/// hashes.
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// The version at which the snapshot was taken.
+    pub version: u64,
+    /// The encoding format of the chunks. Nodes that don't recognize the
+    /// format will reject the snapshot.
+    pub format: u32,
+    /// Hashes of the chunks, in the order they are to be applied.
+    pub chunks: Vec<Hash256>,
+}
+
 /// Describing iteration order.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Order {
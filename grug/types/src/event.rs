@@ -1,14 +1,61 @@
 use {
+    crate::{Addr, Json, JsonDeExt, JsonSerExt, StdError, StdResult},
     borsh::{BorshDeserialize, BorshSerialize},
     serde::{Deserialize, Serialize},
+    serde_with::skip_serializing_none,
 };
 
+// Event attribute keys emitted by the state machine are prefixed by an
+// underscore. Keys of attributes emitted by contracts that are similarly
+// prefixed are escaped with another underscore when flattened. This prevents
+// malicious contracts from emitting an attribute that impersonates state machine
+// attributes in order to fool indexers.
+pub const RESERVED_KEY_PREFIX: &str = "_";
+
+/// Prefix of the keys of contract-emitted attributes that have been escaped.
+/// No reserved key starts with it.
+const ESCAPED_KEY_PREFIX: &str = "__";
+
+/// Attribute key of the flattened event under which the emitting contract's
+/// address is recorded.
+pub const CONTRACT_ADDRESS_KEY: &str = "_contract_address";
+
+/// Attribute key of the flattened event under which the message the contract
+/// was called with is recorded.
+pub const MSG_KEY: &str = "_msg";
+
+/// Attribute key of the flattened event under which each contract event is
+/// recorded.
+pub const CONTRACT_EVENT_KEY: &str = "_event";
+
+/// Attribute key of the flattened event under which its depth in the event
+/// tree is recorded.
+pub const DEPTH_KEY: &str = "_depth";
+
+/// An event emitted during the processing of a transaction or cronjob.
+///
+/// Events form a tree: the events emitted while handling a contract call's
+/// submessages, including the replies, are the children of that call's event.
+#[skip_serializing_none]
 #[derive(
     Serialize, Deserialize, BorshSerialize, BorshDeserialize, Default, Debug, Clone, PartialEq, Eq,
 )]
 pub struct Event {
     pub r#type: String,
+    /// The contract that emitted this event. `None` if it's emitted by the
+    /// state machine itself, e.g. when uploading a code.
+    pub contract: Option<Addr>,
+    /// The message the contract was called with, if any.
+    ///
+    /// Not recorded for calls that take the transaction itself, such as
+    /// `authenticate`, as the transaction is already included in the block,
+    /// and its credentials don't belong in events.
+    pub msg: Option<Json>,
     pub attributes: Vec<Attribute>,
+    /// Events the contract emitted through its [`Response`](crate::Response).
+    pub contract_events: Vec<ContractEvent>,
+    /// Events emitted by the submessages and replies of this call.
+    pub children: Vec<Event>,
 }
 
 impl Event {
@@ -18,7 +65,7 @@ impl Event {
     {
         Self {
             r#type: ty.to_string(),
-            attributes: vec![],
+            ..Default::default()
         }
     }
 
@@ -38,6 +85,147 @@ impl Event {
         self.attributes.extend(attrs);
         self
     }
+
+    /// Flatten events into the format of CometBFT, which consists of string
+    /// attributes and doesn't support nesting, in depth-first order.
+    ///
+    /// Information that doesn't fit into this format, such as the emitting
+    /// contract or the nesting, is recorded under reserved attribute keys, such
+    /// that the events can be recovered using [`Event::unflatten`].
+    pub fn flatten<I>(events: I) -> Vec<FlatEvent>
+    where
+        I: IntoIterator<Item = Event>,
+    {
+        let mut flat_events = vec![];
+
+        for event in events {
+            event.flatten_into(0, &mut flat_events);
+        }
+
+        flat_events
+    }
+
+    /// Recover events from the format of CometBFT. The inverse of [`Event::flatten`].
+    pub fn unflatten<I>(flat_events: I) -> StdResult<Vec<Event>>
+    where
+        I: IntoIterator<Item = FlatEvent>,
+    {
+        let mut events = vec![];
+        // Events whose children may still follow, outermost first.
+        let mut stack: Vec<Event> = vec![];
+
+        for flat_event in flat_events {
+            let (depth, event) = Event::from_flat(flat_event)?;
+
+            if depth > stack.len() {
+                return Err(StdError::generic_err(format!(
+                    "event depth skips a level! expecting at most: {}, actual: {depth}",
+                    stack.len()
+                )));
+            }
+
+            while stack.len() > depth {
+                let child = stack.pop().unwrap();
+                push_child(&mut stack, &mut events, child);
+            }
+
+            stack.push(event);
+        }
+
+        while let Some(child) = stack.pop() {
+            push_child(&mut stack, &mut events, child);
+        }
+
+        Ok(events)
+    }
+
+    fn flatten_into(self, depth: usize, flat_events: &mut Vec<FlatEvent>) {
+        let mut attributes = vec![Attribute::new(DEPTH_KEY, depth)];
+
+        if let Some(contract) = self.contract {
+            attributes.push(Attribute::new(CONTRACT_ADDRESS_KEY, contract));
+        }
+
+        if let Some(msg) = self.msg {
+            attributes.push(Attribute::new(MSG_KEY, msg));
+        }
+
+        attributes.extend(self.attributes.into_iter().map(|mut attr| {
+            if attr.key.starts_with(RESERVED_KEY_PREFIX) {
+                attr.key.insert_str(0, RESERVED_KEY_PREFIX);
+            }
+            attr
+        }));
+
+        for contract_event in &self.contract_events {
+            // Serializing a struct consisting of a string and a JSON value
+            // can't fail.
+            let value = contract_event.to_json_string().unwrap();
+            attributes.push(Attribute::new(CONTRACT_EVENT_KEY, value));
+        }
+
+        flat_events.push(FlatEvent {
+            r#type: self.r#type,
+            attributes,
+        });
+
+        for child in self.children {
+            child.flatten_into(depth + 1, flat_events);
+        }
+    }
+
+    fn from_flat(flat_event: FlatEvent) -> StdResult<(usize, Self)> {
+        let mut depth = None;
+        let mut event = Event::new(flat_event.r#type);
+
+        for attr in flat_event.attributes {
+            match attr.key.as_str() {
+                DEPTH_KEY => {
+                    depth = Some(
+                        attr.value
+                            .parse()
+                            .map_err(|err| StdError::deserialize::<usize, _>("string", err))?,
+                    );
+                },
+                CONTRACT_ADDRESS_KEY => {
+                    event.contract = Some(attr.value.parse()?);
+                },
+                MSG_KEY => {
+                    event.msg = Some(attr.value.deserialize_json()?);
+                },
+                CONTRACT_EVENT_KEY => {
+                    event.contract_events.push(attr.value.deserialize_json()?);
+                },
+                key if key.starts_with(ESCAPED_KEY_PREFIX) => {
+                    let key = key[RESERVED_KEY_PREFIX.len()..].to_string();
+                    event.attributes.push(Attribute { key, ..attr });
+                },
+                key if key.starts_with(RESERVED_KEY_PREFIX) => {
+                    return Err(StdError::generic_err(format!(
+                        "unknown reserved event attribute key: {key}"
+                    )));
+                },
+                _ => {
+                    event.attributes.push(attr);
+                },
+            }
+        }
+
+        let depth = depth.ok_or_else(|| {
+            StdError::generic_err(format!("event attribute `{DEPTH_KEY}` is missing"))
+        })?;
+
+        Ok((depth, event))
+    }
+}
+
+// Append an event to its parent, which is the last one in the stack, or to the
+// top-level events if the stack is empty.
+fn push_child(stack: &mut [Event], events: &mut Vec<Event>, child: Event) {
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(child),
+        None => events.push(child),
+    }
 }
 
 #[derive(
@@ -60,3 +248,141 @@ impl Attribute {
         }
     }
 }
+
+/// A structured event emitted by a contract through its [`Response`](crate::Response),
+/// with a JSON payload.
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ContractEvent {
+    pub r#type: String,
+    pub data: Json,
+}
+
+impl ContractEvent {
+    pub fn new<T, D>(ty: T, data: &D) -> StdResult<Self>
+    where
+        T: ToString,
+        D: Serialize,
+    {
+        Ok(Self {
+            r#type: ty.to_string(),
+            data: data.to_json_value()?,
+        })
+    }
+}
+
+/// An event in the format of CometBFT. See [`Event::flatten`].
+#[derive(
+    Serialize, Deserialize, BorshSerialize, BorshDeserialize, Default, Debug, Clone, PartialEq, Eq,
+)]
+pub struct FlatEvent {
+    pub r#type: String,
+    pub attributes: Vec<Attribute>,
+}
+
+// ----------------------------------- tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::json};
+
+    const CONTRACT1: Addr = Addr::mock(1);
+    const CONTRACT2: Addr = Addr::mock(2);
+
+    fn mock_events() -> Vec<Event> {
+        vec![
+            Event::new("upload").add_attribute("code_hash", "abcd"),
+            Event {
+                r#type: "execute".to_string(),
+                contract: Some(CONTRACT1),
+                msg: Some(json!({ "swap": { "route": [1, 2] } })),
+                attributes: vec![
+                    Attribute::new("method", "swap"),
+                    // Keys colliding with reserved ones are escaped.
+                    Attribute::new(CONTRACT_ADDRESS_KEY, CONTRACT2),
+                    Attribute::new("__foo", "bar"),
+                ],
+                contract_events: vec![ContractEvent {
+                    r#type: "swapped".to_string(),
+                    data: json!({ "input": "100", "output": "99" }),
+                }],
+                children: vec![
+                    Event {
+                        r#type: "execute".to_string(),
+                        contract: Some(CONTRACT2),
+                        msg: Some(json!("hello")),
+                        children: vec![Event {
+                            r#type: "bank_execute".to_string(),
+                            contract: Some(CONTRACT1),
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    },
+                    Event {
+                        r#type: "reply".to_string(),
+                        contract: Some(CONTRACT1),
+                        ..Default::default()
+                    },
+                ],
+            },
+            Event::new("configure").add_attribute("sender", CONTRACT2),
+        ]
+    }
+
+    #[test]
+    fn flattening_works() {
+        let flat_events = Event::flatten(mock_events());
+
+        let summary = flat_events
+            .iter()
+            .map(|event| {
+                let depth = event
+                    .attributes
+                    .iter()
+                    .find(|attr| attr.key == DEPTH_KEY)
+                    .unwrap();
+                (event.r#type.as_str(), depth.value.as_str())
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(summary, [
+            ("upload", "0"),
+            ("execute", "0"),
+            ("execute", "1"),
+            ("bank_execute", "2"),
+            ("reply", "1"),
+            ("configure", "0"),
+        ]);
+    }
+
+    #[test]
+    fn unflattening_is_lossless() {
+        let events = mock_events();
+        let flat_events = Event::flatten(events.clone());
+
+        assert_eq!(Event::unflatten(flat_events).unwrap(), events);
+    }
+
+    #[test]
+    fn unflattening_rejects_malformed_events() {
+        // Missing depth.
+        assert!(Event::unflatten([FlatEvent {
+            r#type: "execute".to_string(),
+            attributes: vec![],
+        }])
+        .is_err());
+
+        // A child without a parent.
+        assert!(Event::unflatten([FlatEvent {
+            r#type: "execute".to_string(),
+            attributes: vec![Attribute::new(DEPTH_KEY, 1)],
+        }])
+        .is_err());
+
+        // Unknown reserved key.
+        assert!(Event::unflatten([FlatEvent {
+            r#type: "execute".to_string(),
+            attributes: vec![Attribute::new(DEPTH_KEY, 0), Attribute::new("_foo", "bar")],
+        }])
+        .is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use {
+    crate::{Addr, StdError, StdResult},
+    borsh::{BorshDeserialize, BorshSerialize},
+    serde::{Deserialize, Serialize},
+    serde_with::skip_serializing_none,
+    std::collections::BTreeMap,
+};
+
+/// Gas costs of operations performed by the host, on top of the gas consumed
+/// by executing Wasm instructions.
+///
+/// This is an on-chain parameter, which the chain owner can update through
+/// [`Message::Configure`](crate::Message::Configure). The update takes effect
+/// starting from the next block.
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct GasCosts {
+    // Storage
+    pub db_read: LinearGasCost,
+    pub db_scan: u64,
+    pub db_next: u64,
+    pub db_write: LinearGasCost,
+    pub db_remove: u64,
+    // Signature verifiers
+    pub secp256r1_verify: u64,
+    pub secp256k1_verify: u64,
+    pub secp256k1_pubkey_recover: u64,
+    pub ed25519_verify: u64,
+    pub ed25519_batch_verify: LinearGasCost,
+    // Hashers
+    pub sha2_256: LinearGasCost,
+    pub sha2_512: LinearGasCost,
+    pub sha2_512_truncated: LinearGasCost,
+    pub sha3_256: LinearGasCost,
+    pub sha3_512: LinearGasCost,
+    pub sha3_512_truncated: LinearGasCost,
+    pub keccak256: LinearGasCost,
+    pub blake2s_256: LinearGasCost,
+    pub blake2b_512: LinearGasCost,
+    pub blake3: LinearGasCost,
+}
+
+impl Default for GasCosts {
+    /// The gas costs a chain starts with, unless otherwise specified in the
+    /// genesis state.
+    fn default() -> Self {
+        Self {
+            // Storage.
+            //
+            // For storage, we take the values from Cosmos SDK:
+            // https://github.com/cosmos/cosmos-sdk/blob/v0.50.7/store/types/gas.go#L232-L242
+            //
+            // Following the conversion:
+            // - 1 Cosmos SDK gas = 100 CosmWasm gas
+            // - 170 CosmWasm gas = 1 Wasmer point
+            // - 1 Wasmer point = 1 Grug gas
+            // This means: 1 Cosmos SDK gas = 0.588 Grug gas
+            db_read: LinearGasCost::new(588, 2),
+            db_scan: 588,
+            db_next: 18,
+            db_write: LinearGasCost::new(1176, 18),
+            db_remove: 588,
+            // Verifiers
+            //
+            // For batch verification, there's a flat setup cost, and a cost per signature.
+            secp256r1_verify: 1_880_000,
+            secp256k1_verify: 770_000,
+            secp256k1_pubkey_recover: 1_580_000,
+            ed25519_verify: 410_000,
+            ed25519_batch_verify: LinearGasCost::new(1_340_000, 188_000),
+            // Hashers.
+            //
+            // For hashers, `per_item` means per byte.
+            // The truncated versions have the same cost as the untruncated counterparts.
+            sha2_256: LinearGasCost::new(0, 27),
+            sha2_512: LinearGasCost::new(0, 16),
+            sha2_512_truncated: LinearGasCost::new(0, 16),
+            sha3_256: LinearGasCost::new(0, 15),
+            sha3_512: LinearGasCost::new(0, 28),
+            sha3_512_truncated: LinearGasCost::new(0, 28),
+            keccak256: LinearGasCost::new(0, 15),
+            blake2s_256: LinearGasCost::new(0, 15),
+            blake2b_512: LinearGasCost::new(0, 9),
+            blake3: LinearGasCost::new(0, 5),
+        }
+    }
+}
+
+impl GasCosts {
+    /// Make sure none of the linear costs can overflow.
+    ///
+    /// The number of items a host function is charged for (e.g. the number of
+    /// bytes to be hashed) is bounded by the size of the Wasm memory, which is
+    /// addressed by 32 bits. So it suffices to check the cost of `u32::MAX`
+    /// items.
+    pub fn validate(&self) -> StdResult<()> {
+        for (name, cost) in [
+            ("db_read", self.db_read),
+            ("db_write", self.db_write),
+            ("ed25519_batch_verify", self.ed25519_batch_verify),
+            ("sha2_256", self.sha2_256),
+            ("sha2_512", self.sha2_512),
+            ("sha2_512_truncated", self.sha2_512_truncated),
+            ("sha3_256", self.sha3_256),
+            ("sha3_512", self.sha3_512),
+            ("sha3_512_truncated", self.sha3_512_truncated),
+            ("keccak256", self.keccak256),
+            ("blake2s_256", self.blake2s_256),
+            ("blake2b_512", self.blake2b_512),
+            ("blake3", self.blake3),
+        ] {
+            if cost.checked_cost(u32::MAX as u64).is_none() {
+                return Err(StdError::generic_err(format!(
+                    "gas cost `{name}` overflows for {} items",
+                    u32::MAX
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq,
+)]
+#[serde(deny_unknown_fields)]
+pub struct LinearGasCost {
+    /// The flat part of the cost, charged once per batch.
+    pub base: u64,
+    /// The cost per item, on top of the flat part.
+    pub per_item: u64,
+}
+
+impl LinearGasCost {
+    pub const fn new(base: u64, per_item: u64) -> Self {
+        Self { base, per_item }
+    }
+
+    /// Compute the cost of the given number of items.
+    ///
+    /// Saturates at `u64::MAX` instead of overflowing, which makes the call
+    /// run out of gas.
+    pub fn cost(&self, items: usize) -> u64 {
+        self.checked_cost(items as u64).unwrap_or(u64::MAX)
+    }
+
+    fn checked_cost(&self, items: u64) -> Option<u64> {
+        self.per_item.checked_mul(items)?.checked_add(self.base)
+    }
+}
+
+/// A breakdown of the gas consumed by a transaction, as a tree of contract call
+/// frames.
+///
+/// The root frame represents the transaction itself. Each child frame is a
+/// call to a contract's entry point, made either by the app (e.g. to execute a
+/// message) or by a contract in a parent frame (e.g. a query, or a submessage).
+#[skip_serializing_none]
+#[derive(
+    Serialize, Deserialize, BorshSerialize, BorshDeserialize, Default, Debug, Clone, PartialEq, Eq,
+)]
+#[serde(deny_unknown_fields)]
+pub struct GasProfile {
+    /// The contract being called. `None` for the root frame.
+    pub contract: Option<Addr>,
+    /// The entry point being called, e.g. `execute`. `None` for the root frame.
+    pub entry_point: Option<String>,
+    /// Total gas consumed in this frame, including its children.
+    pub gas_used: u64,
+    /// Gas consumed directly in this frame, excluding its children, by the
+    /// operations that consumed it.
+    ///
+    /// The keys are the comments under which the gas was consumed, e.g. names
+    /// of host functions such as `db_write` or `secp256k1_verify`, or
+    /// `wasm_metering` for the execution of Wasm instructions.
+    pub operations: BTreeMap<String, u64>,
+    /// Contract calls made from within this frame, in the order they were made.
+    pub children: Vec<GasProfile>,
+}
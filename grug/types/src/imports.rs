@@ -11,8 +11,8 @@
 
 use {
     crate::{
-        Addr, Batch, Binary, Coins, Config, ContractInfo, Denom, Hash256, Json, JsonDeExt,
-        JsonSerExt, Op, Order, Query, QueryRequest, QueryResponse, Record, StdResult,
+        Addr, Batch, Binary, Coins, Config, ContractInfo, Denom, GasCosts, Hash256, Json,
+        JsonDeExt, JsonSerExt, Op, Order, Query, QueryRequest, QueryResponse, Record, StdResult,
     },
     dyn_clone::DynClone,
     grug_math::Uint128,
@@ -305,6 +305,12 @@ impl<'a> QuerierWrapper<'a> {
             .map(|res| res.as_app_configs())
     }
 
+    pub fn query_gas_costs(&self) -> StdResult<GasCosts> {
+        self.inner
+            .query_chain(Query::GasCosts {})
+            .map(|res| res.as_gas_costs())
+    }
+
     pub fn query_balance(&self, address: Addr, denom: Denom) -> StdResult<Uint128> {
         self.inner
             .query_chain(Query::Balance { address, denom })
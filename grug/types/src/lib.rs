@@ -14,6 +14,7 @@ mod denom;
 mod empty;
 mod error;
 mod event;
+mod gas;
 mod hash;
 mod hashers;
 mod imports;
@@ -26,15 +27,16 @@ mod result;
 mod serializers;
 mod signer;
 mod time;
+mod trace;
 mod tx;
 mod unique_vec;
 mod utils;
 
 pub use {
     address::*, app::*, bank::*, bound::*, builder::*, bytes::*, changeset::*, coin::*,
-    coin_pair::*, coins::*, context::*, db::*, denom::*, empty::*, error::*, event::*, hash::*,
-    hashers::*, imports::*, non_empty::*, non_zero::*, query::*, response::*, result::*,
-    serializers::*, signer::*, time::*, tx::*, unique_vec::*, utils::*,
+    coin_pair::*, coins::*, context::*, db::*, denom::*, empty::*, error::*, event::*, gas::*,
+    hash::*, hashers::*, imports::*, non_empty::*, non_zero::*, query::*, response::*, result::*,
+    serializers::*, signer::*, time::*, trace::*, tx::*, unique_vec::*, utils::*,
 };
 
 // ---------------------------------- testing ----------------------------------
@@ -1,7 +1,7 @@
 use {
     crate::{
-        Addr, Binary, Coin, Coins, Config, ContractInfo, Denom, Hash256, Json, JsonSerExt,
-        StdResult,
+        Addr, Binary, Coin, Coins, Config, ContractInfo, Denom, GasCosts, Hash256, Json,
+        JsonSerExt, StdResult,
     },
     borsh::{BorshDeserialize, BorshSerialize},
     paste::paste,
@@ -89,6 +89,13 @@ pub enum Query {
     /// Perform multiple queries at once.
     /// Returns: `Vec<QueryResponse>`.
     Multi(Vec<Query>),
+    /// The gas costs of host operations.
+    ///
+    /// This is the last variant, so that adding it doesn't change the Borsh
+    /// encoding of the others.
+    ///
+    /// Returns: `GasCosts`
+    GasCosts {},
 }
 
 impl Query {
@@ -139,6 +146,7 @@ pub enum QueryResponse {
     WasmRaw(Option<Binary>),
     WasmSmart(Json),
     Multi(Vec<QueryResponse>),
+    GasCosts(GasCosts),
 }
 
 macro_rules! generate_downcast {
@@ -175,5 +183,6 @@ impl QueryResponse {
         WasmRaw    => Option<Binary>,
         WasmSmart  => Json,
         Multi      => Vec<QueryResponse>,
+        GasCosts   => GasCosts,
     }
 }
@@ -1,15 +1,20 @@
 use {
-    crate::{Attribute, Json, JsonSerExt, Message, StdResult},
+    crate::{Attribute, ContractEvent, Json, JsonSerExt, Message, StdResult},
     borsh::{BorshDeserialize, BorshSerialize},
     serde::{Deserialize, Serialize},
+    std::io::{self, Read},
 };
 
-#[derive(
-    Serialize, Deserialize, BorshSerialize, BorshDeserialize, Default, Debug, Clone, PartialEq, Eq,
-)]
+/// The Borsh encoding of `Response` is the fields in order, except that
+/// `events` may be omitted, in which case it's decoded as empty. This is
+/// because `events` was added later: contracts built before then don't emit it,
+/// and must still be understood by the host.
+#[derive(Serialize, Deserialize, BorshSerialize, Default, Debug, Clone, PartialEq, Eq)]
 pub struct Response {
     pub submsgs: Vec<SubMessage>,
     pub attributes: Vec<Attribute>,
+    #[serde(default)]
+    pub events: Vec<ContractEvent>,
 }
 
 impl Response {
@@ -66,15 +71,25 @@ impl Response {
         self.attributes.push(Attribute::new(key, value));
         self
     }
+
+    pub fn add_event<T, D>(mut self, ty: T, data: &D) -> StdResult<Self>
+    where
+        T: ToString,
+        D: Serialize,
+    {
+        self.events.push(ContractEvent::new(ty, data)?);
+        Ok(self)
+    }
 }
 
 /// A special response emitted by the account contract at the end of the
 /// `authenticate` method call. In addition to the usual [`Response`](crate::Response),
 /// this also includes a boolean specifying whether the account requests a
 /// backrun call.
-#[derive(
-    Serialize, Deserialize, BorshSerialize, BorshDeserialize, Default, Debug, Clone, PartialEq, Eq,
-)]
+///
+/// For the same reason as with `Response`, the response's `events` come last in
+/// the Borsh encoding, after `request_backrun`, and may be omitted.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 pub struct AuthResponse {
     pub response: Response,
     pub request_backrun: bool,
@@ -134,6 +149,77 @@ impl AuthResponse {
         self.response = self.response.add_attribute(key, value);
         self
     }
+
+    pub fn add_event<T, D>(mut self, ty: T, data: &D) -> StdResult<Self>
+    where
+        T: ToString,
+        D: Serialize,
+    {
+        self.response = self.response.add_event(ty, data)?;
+        Ok(self)
+    }
+}
+
+impl BorshDeserialize for Response {
+    fn deserialize_reader<R>(reader: &mut R) -> io::Result<Self>
+    where
+        R: io::Read,
+    {
+        Ok(Self {
+            submsgs: BorshDeserialize::deserialize_reader(reader)?,
+            attributes: BorshDeserialize::deserialize_reader(reader)?,
+            events: deserialize_trailing_events(reader)?,
+        })
+    }
+}
+
+impl BorshSerialize for AuthResponse {
+    fn serialize<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        BorshSerialize::serialize(&self.response.submsgs, writer)?;
+        BorshSerialize::serialize(&self.response.attributes, writer)?;
+        BorshSerialize::serialize(&self.request_backrun, writer)?;
+        BorshSerialize::serialize(&self.response.events, writer)
+    }
+}
+
+impl BorshDeserialize for AuthResponse {
+    fn deserialize_reader<R>(reader: &mut R) -> io::Result<Self>
+    where
+        R: io::Read,
+    {
+        let submsgs = BorshDeserialize::deserialize_reader(reader)?;
+        let attributes = BorshDeserialize::deserialize_reader(reader)?;
+        let request_backrun = BorshDeserialize::deserialize_reader(reader)?;
+        let events = deserialize_trailing_events(reader)?;
+
+        Ok(Self {
+            response: Response {
+                submsgs,
+                attributes,
+                events,
+            },
+            request_backrun,
+        })
+    }
+}
+
+/// Deserialize the events at the end of a response, or return an empty vector
+/// if the reader is exhausted, i.e. the response was encoded before events
+/// were added.
+fn deserialize_trailing_events<R>(reader: &mut R) -> io::Result<Vec<ContractEvent>>
+where
+    R: io::Read,
+{
+    let mut first_byte = [0];
+
+    if reader.read(&mut first_byte)? == 0 {
+        return Ok(Vec::new());
+    }
+
+    BorshDeserialize::deserialize_reader(&mut first_byte.as_slice().chain(reader))
 }
 
 /// Indicates that after a submessage has been executed, whether the host should
@@ -200,3 +286,82 @@ impl SubMessage {
         })
     }
 }
+
+// ----------------------------------- tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{json, BorshDeExt, BorshSerExt},
+    };
+
+    /// `Response` as it was encoded before events were added.
+    #[derive(BorshSerialize)]
+    struct LegacyResponse {
+        submsgs: Vec<SubMessage>,
+        attributes: Vec<Attribute>,
+    }
+
+    /// `AuthResponse` as it was encoded before events were added.
+    #[derive(BorshSerialize)]
+    struct LegacyAuthResponse {
+        response: LegacyResponse,
+        request_backrun: bool,
+    }
+
+    fn mock_response() -> Response {
+        Response::new()
+            .add_message(Message::upload(b"jake".to_vec()))
+            .add_attribute("foo", "bar")
+    }
+
+    #[test]
+    fn borsh_round_trip() {
+        let response = mock_response()
+            .add_event("swapped", &json!({ "input": "100", "output": "99" }))
+            .unwrap();
+
+        let bytes = response.to_borsh_vec().unwrap();
+        assert_eq!(bytes.deserialize_borsh::<Response>().unwrap(), response);
+
+        let auth_response = AuthResponse {
+            response,
+            request_backrun: true,
+        };
+
+        let bytes = auth_response.to_borsh_vec().unwrap();
+        assert_eq!(
+            bytes.deserialize_borsh::<AuthResponse>().unwrap(),
+            auth_response
+        );
+    }
+
+    #[test]
+    fn decoding_responses_without_events() {
+        let legacy = LegacyResponse {
+            submsgs: mock_response().submsgs,
+            attributes: mock_response().attributes,
+        };
+
+        let bytes = legacy.to_borsh_vec().unwrap();
+        assert_eq!(
+            bytes.deserialize_borsh::<Response>().unwrap(),
+            mock_response()
+        );
+
+        let legacy = LegacyAuthResponse {
+            response: legacy,
+            request_backrun: true,
+        };
+
+        let bytes = legacy.to_borsh_vec().unwrap();
+        assert_eq!(
+            bytes.deserialize_borsh::<AuthResponse>().unwrap(),
+            AuthResponse {
+                response: mock_response(),
+                request_backrun: true,
+            }
+        );
+    }
+}
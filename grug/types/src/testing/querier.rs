@@ -1,7 +1,7 @@
 use {
     crate::{
-        Addr, Binary, Coin, Config, ContractInfo, Denom, GenericResult, Hash256, HashExt, Json,
-        JsonSerExt, MockStorage, Querier, Query, QueryResponse, StdError, StdResult, Storage,
+        Addr, Binary, Coin, Config, ContractInfo, Denom, GasCosts, GenericResult, Hash256, HashExt,
+        Json, JsonSerExt, MockStorage, Querier, Query, QueryResponse, StdError, StdResult, Storage,
     },
     grug_math::{NumberConst, Uint128},
     serde::Serialize,
@@ -19,6 +19,7 @@ type SmartQueryHandler = Box<dyn Fn(Addr, Json) -> GenericResult<Json>>;
 pub struct MockQuerier {
     config: Option<Config>,
     app_configs: BTreeMap<String, Json>,
+    gas_costs: GasCosts,
     balances: BTreeMap<Addr, BTreeMap<Denom, Uint128>>,
     supplies: BTreeMap<Denom, Uint128>,
     codes: BTreeMap<Hash256, Binary>,
@@ -49,6 +50,11 @@ impl MockQuerier {
         Ok(self)
     }
 
+    pub fn with_gas_costs(mut self, gas_costs: GasCosts) -> Self {
+        self.gas_costs = gas_costs;
+        self
+    }
+
     pub fn with_balance<D, A>(mut self, address: Addr, denom: D, amount: A) -> StdResult<Self>
     where
         D: TryInto<Denom>,
@@ -148,6 +154,7 @@ impl Querier for MockQuerier {
                     .collect();
                 Ok(QueryResponse::AppConfigs(entries))
             },
+            Query::GasCosts {} => Ok(QueryResponse::GasCosts(self.gas_costs.clone())),
             Query::Balance { address, denom } => {
                 let amount = self
                     .balances
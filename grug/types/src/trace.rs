@@ -0,0 +1,40 @@
+use {
+    crate::{Addr, Coins, GenericResult, Json},
+    borsh::{BorshDeserialize, BorshSerialize},
+    serde::{Deserialize, Serialize},
+    serde_with::skip_serializing_none,
+};
+
+/// A call to a contract's entry point, made during the processing of a
+/// transaction, and the calls it has led to.
+///
+/// Similar to the call frames returned by Ethereum's `debug_traceTransaction`.
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct CallTrace {
+    /// The account that made the call, as seen by the callee. `None` if the
+    /// call is made by the chain itself, e.g. `authenticate` or `bank_execute`.
+    pub caller: Option<Addr>,
+    /// The contract being called.
+    pub callee: Addr,
+    /// The entry point being called, e.g. `execute`.
+    pub entry_point: String,
+    /// The input given to the entry point, e.g. the execute message. If the
+    /// entry point takes more than one input, this is an array of them. `null`
+    /// if it takes none.
+    pub input: Json,
+    /// The funds sent along with the call, if any.
+    pub funds: Option<Coins>,
+    /// The amount of gas used by the call, including its child calls.
+    pub gas_used: u64,
+    /// The output of the entry point, or the error if it failed.
+    ///
+    /// This only concerns the entry point itself. If the entry point succeeds
+    /// but a submessage it has emitted fails, it's reflected in the child call.
+    pub result: GenericResult<Json>,
+    /// Calls made during this call, in the order they were made. These include
+    /// queries made by the contract, submessages it has emitted, as well as
+    /// `reply` calls to it.
+    pub children: Vec<CallTrace>,
+}
@@ -1,7 +1,7 @@
 use {
     crate::{get_contract_impl, ContractWrapper, VmError, VmResult},
     grug_app::{GasTracker, Instance, QuerierProvider, StorageProvider, Vm},
-    grug_types::{BorshDeExt, BorshSerExt, Context, Hash256, MockApi},
+    grug_types::{BorshDeExt, BorshSerExt, Context, Hash256, MockApi, WasmLimits},
 };
 
 /// Names of export functions supported by Grug.
@@ -59,6 +59,12 @@ impl Vm for RustVm {
             wrapper: ContractWrapper::from_bytes(code),
         })
     }
+
+    // Rust VM contracts are compiled into the binary along with the app, so
+    // there's nothing to validate.
+    fn validate_code(&mut self, _code: &[u8], _limits: &WasmLimits) -> VmResult<()> {
+        Ok(())
+    }
 }
 
 pub struct RustInstance {
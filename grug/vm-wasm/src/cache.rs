@@ -3,7 +3,12 @@ use {
     clru::CLruCache,
     grug_app::Shared,
     grug_types::Hash256,
-    std::num::NonZeroUsize,
+    std::{
+        collections::{BTreeMap, HashMap},
+        num::NonZeroUsize,
+        sync::{Arc, OnceLock},
+        time::{Duration, Instant},
+    },
     wasmer::{Engine, Module},
 };
 
@@ -38,15 +43,103 @@ impl Metrics {
     }
 }
 
+/// Statistics about a single module in the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryMetrics {
+    /// Whether the module is pinned.
+    pub pinned: bool,
+    /// Number of times the module has been fetched from the cache.
+    pub hits: usize,
+    /// Time it took to build the module, that is, to compile it or to load it
+    /// from the disk cache.
+    pub build_time: Duration,
+    /// Estimated memory used by the module, in bytes.
+    pub size: usize,
+}
+
+/// A snapshot of the statistics of a cache instance.
+#[derive(Debug, Clone)]
+pub struct CacheMetrics {
+    /// Maximum number of modules in the LRU cache, not including pinned ones.
+    pub capacity: usize,
+    pub hits: usize,
+    pub misses: usize,
+    pub entries: BTreeMap<Hash256, EntryMetrics>,
+}
+
+impl CacheMetrics {
+    /// Number of modules in the LRU cache, not including pinned ones.
+    pub fn len(&self) -> usize {
+        self.entries.values().filter(|entry| !entry.pinned).count()
+    }
+
+    /// Return whether there is no module in the LRU cache.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of pinned modules.
+    pub fn pinned(&self) -> usize {
+        self.entries.values().filter(|entry| entry.pinned).count()
+    }
+
+    /// Estimated memory used by all modules, including pinned ones, in bytes.
+    pub fn size(&self) -> usize {
+        self.entries.values().map(|entry| entry.size).sum()
+    }
+}
+
+struct Entry {
+    data: Data,
+    pinned: bool,
+    hits: usize,
+    build_time: Duration,
+    /// Estimated memory used by the module, computed when metrics are first
+    /// requested, as estimating it is costly. It's shared, such that it can be
+    /// computed without holding the cache's lock.
+    size: Arc<OnceLock<usize>>,
+}
+
+impl Entry {
+    /// Build the module using the given builder method, and record the time it
+    /// takes.
+    fn build_with<B>(builder: B) -> VmResult<Self>
+    where
+        B: FnOnce() -> VmResult<Data>,
+    {
+        let start = Instant::now();
+        let data = builder()?;
+        let build_time = start.elapsed();
+
+        Ok(Self {
+            data,
+            pinned: false,
+            hits: 0,
+            build_time,
+            size: Arc::new(OnceLock::new()),
+        })
+    }
+
+    /// Record a hit and return a clone of the data.
+    fn hit(&mut self) -> Data {
+        self.hits = self.hits.saturating_add(1);
+        self.data.clone()
+    }
+}
+
 /// An in-memory cache for wasm modules, so that they don't need to be re-built
 /// every time the same contract is called.
+///
+/// Modules of frequently used contracts can be pinned, in which case they are
+/// kept outside of the LRU cache and never evicted.
 #[derive(Clone)]
 pub struct Cache {
     inner: Shared<CacheInner>,
 }
 
 struct CacheInner {
-    lru_cache: CLruCache<Hash256, Data>,
+    pinned: HashMap<Hash256, Entry>,
+    lru_cache: CLruCache<Hash256, Entry>,
     metrics: Metrics,
 }
 
@@ -55,6 +148,7 @@ impl Cache {
     pub fn new(capacity: NonZeroUsize) -> Self {
         Self {
             inner: Shared::new(CacheInner {
+                pinned: HashMap::new(),
                 lru_cache: CLruCache::new(capacity),
                 metrics: Metrics::new(),
             }),
@@ -69,9 +163,17 @@ impl Cache {
         B: FnOnce() -> VmResult<Data>,
     {
         self.inner.write_with(|mut inner| {
-            match inner.lru_cache.get(&code_hash).cloned() {
+            if let Some(entry) = inner.pinned.get_mut(&code_hash) {
+                let data = entry.hit();
+                inner.metrics.increment_hits();
+
+                return Ok(data);
+            }
+
+            match inner.lru_cache.get_mut(&code_hash) {
                 // Cache hit - simply clone the cached data and return.
-                Some(data) => {
+                Some(entry) => {
+                    let data = entry.hit();
                     inner.metrics.increment_hits();
 
                     Ok(data)
@@ -79,9 +181,10 @@ impl Cache {
                 // Cache miss - build the module using the given builder method;
                 // insert both the module and engine to the cache.
                 None => {
-                    let data = builder()?;
+                    let entry = Entry::build_with(builder)?;
+                    let data = entry.data.clone();
 
-                    inner.lru_cache.put(code_hash, data.clone());
+                    inner.lru_cache.put(code_hash, entry);
                     inner.metrics.increment_misses();
 
                     Ok(data)
@@ -89,6 +192,124 @@ impl Cache {
             }
         })
     }
+
+    /// Build a module using the given method, even if it's already cached, and
+    /// insert it into the cache, replacing the existing one if any.
+    ///
+    /// Used when the module must be built for its side effects, e.g. to check
+    /// that it compiles under the current rules. A pinned module isn't
+    /// replaced, but the builder is still called.
+    pub fn build_and_insert_with<B>(&self, code_hash: Hash256, builder: B) -> VmResult<Data>
+    where
+        B: FnOnce() -> VmResult<Data>,
+    {
+        self.inner.write_with(|mut inner| {
+            let entry = Entry::build_with(builder)?;
+            let data = entry.data.clone();
+
+            if !inner.pinned.contains_key(&code_hash) {
+                inner.lru_cache.put(code_hash, entry);
+            }
+
+            inner.metrics.increment_misses();
+
+            Ok(data)
+        })
+    }
+
+    /// Pin a module, such that it's never evicted from the cache.
+    ///
+    /// If the module is already in the LRU cache, it's moved out of it.
+    /// Otherwise, it's built using the given method. Either way, it doesn't
+    /// count towards the capacity of the cache.
+    pub fn pin_with<B>(&self, code_hash: Hash256, builder: B) -> VmResult<()>
+    where
+        B: FnOnce() -> VmResult<Data>,
+    {
+        self.inner.write_with(|mut inner| {
+            if inner.pinned.contains_key(&code_hash) {
+                return Ok(());
+            }
+
+            let mut entry = match inner.lru_cache.pop(&code_hash) {
+                Some(entry) => entry,
+                None => Entry::build_with(builder)?,
+            };
+
+            entry.pinned = true;
+            inner.pinned.insert(code_hash, entry);
+
+            Ok(())
+        })
+    }
+
+    /// Unpin a module. It's moved to the LRU cache, which may cause the least
+    /// recently used module to be evicted.
+    pub fn unpin(&self, code_hash: Hash256) {
+        self.inner.write_with(|mut inner| {
+            if let Some(mut entry) = inner.pinned.remove(&code_hash) {
+                entry.pinned = false;
+                inner.lru_cache.put(code_hash, entry);
+            }
+        });
+    }
+
+    /// Return a snapshot of the cache's statistics.
+    ///
+    /// The first time this is called for a module, its size is estimated,
+    /// which is costly. This is done after releasing the cache's lock, so it
+    /// doesn't block contract calls.
+    pub fn metrics(&self) -> CacheMetrics {
+        let (capacity, hits, misses, entries) = self.inner.read_with(|inner| {
+            let entries = inner
+                .pinned
+                .iter()
+                .chain(inner.lru_cache.iter())
+                .map(|(code_hash, entry)| {
+                    (
+                        *code_hash,
+                        entry.pinned,
+                        entry.hits,
+                        entry.build_time,
+                        entry.data.0.clone(),
+                        entry.size.clone(),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            (
+                inner.lru_cache.capacity(),
+                inner.metrics.hits,
+                inner.metrics.misses,
+                entries,
+            )
+        });
+
+        CacheMetrics {
+            capacity,
+            hits,
+            misses,
+            entries: entries
+                .into_iter()
+                .map(|(code_hash, pinned, hits, build_time, module, size)| {
+                    (code_hash, EntryMetrics {
+                        pinned,
+                        hits,
+                        build_time,
+                        size: *size.get_or_init(|| estimate_size(&module)),
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Estimate the memory used by a compiled module.
+///
+/// Wasmer doesn't provide a way to measure it directly, so we use the size of
+/// the serialized module, which consists mostly of the compiled machine code.
+fn estimate_size(module: &Module) -> usize {
+    module.serialize().map(|bytes| bytes.len()).unwrap_or(0)
 }
 
 // ----------------------------------- tests -----------------------------------
@@ -98,7 +319,7 @@ mod tests {
     use {
         crate::{Cache, VmResult},
         grug_types::HashExt,
-        std::num::NonZeroUsize,
+        std::{cell::Cell, num::NonZeroUsize},
         wasmer::{Engine, Module, Singlepass},
     };
 
@@ -152,4 +373,104 @@ mod tests {
             assert_eq!(inner.metrics.misses, 1);
         });
     }
+
+    #[test]
+    fn build_and_insert() {
+        let cache = Cache::new(NonZeroUsize::new(2).unwrap());
+        let hash = CONTRACT.hash256();
+        let builds = Cell::new(0);
+        let counting_builder = || {
+            builds.set(builds.get() + 1);
+            builder()
+        };
+
+        // The module is built even though it's already cached.
+        cache.get_or_build_with(hash, counting_builder).unwrap();
+        cache.build_and_insert_with(hash, counting_builder).unwrap();
+
+        assert_eq!(builds.get(), 2);
+
+        cache.inner.read_with(|inner| {
+            assert!(inner.lru_cache.contains(&hash));
+            assert_eq!(inner.lru_cache.len(), 1);
+            assert_eq!(inner.metrics.hits, 0);
+            assert_eq!(inner.metrics.misses, 2);
+        });
+    }
+
+    #[test]
+    fn pinned() {
+        let cache = Cache::new(NonZeroUsize::new(1).unwrap());
+
+        // Build and cache the 1st contract, then pin it.
+        let hash1 = CONTRACT.hash256();
+        cache.get_or_build_with(hash1, builder).unwrap();
+        cache.pin_with(hash1, builder).unwrap();
+
+        // Build the 2nd contract. It's inserted into the LRU cache, while the
+        // pinned contract isn't evicted.
+        let hash2 = b"jake".hash256();
+        cache.get_or_build_with(hash2, builder).unwrap();
+        cache.get_or_build_with(hash1, builder).unwrap();
+
+        cache.inner.read_with(|inner| {
+            assert!(inner.pinned.contains_key(&hash1));
+            assert!(!inner.lru_cache.contains(&hash1));
+            assert!(inner.lru_cache.contains(&hash2));
+            assert_eq!(inner.lru_cache.len(), 1);
+            assert_eq!(inner.metrics.hits, 1);
+            assert_eq!(inner.metrics.misses, 2);
+        });
+
+        // Unpin the 1st contract. It's moved back to the LRU cache, evicting
+        // the 2nd contract.
+        cache.unpin(hash1);
+
+        cache.inner.read_with(|inner| {
+            assert!(inner.pinned.is_empty());
+            assert!(inner.lru_cache.contains(&hash1));
+            assert!(!inner.lru_cache.contains(&hash2));
+        });
+    }
+
+    #[test]
+    fn metrics() {
+        let cache = Cache::new(NonZeroUsize::new(2).unwrap());
+
+        let hash1 = CONTRACT.hash256();
+        let hash2 = b"jake".hash256();
+
+        // Pin the 1st contract, which builds it. Then fetch it twice.
+        cache.pin_with(hash1, builder).unwrap();
+        cache.get_or_build_with(hash1, builder).unwrap();
+        cache.get_or_build_with(hash1, builder).unwrap();
+
+        // Build the 2nd contract without pinning it.
+        cache.get_or_build_with(hash2, builder).unwrap();
+
+        // Sizes aren't estimated until the metrics are requested.
+        cache.inner.read_with(|inner| {
+            assert!(inner.pinned[&hash1].size.get().is_none());
+            assert!(inner.lru_cache.peek(&hash2).unwrap().size.get().is_none());
+        });
+
+        let metrics = cache.metrics();
+
+        assert_eq!(metrics.capacity, 2);
+        assert_eq!(metrics.hits, 2);
+        assert_eq!(metrics.misses, 1);
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics.pinned(), 1);
+
+        let entry1 = metrics.entries[&hash1];
+        assert!(entry1.pinned);
+        assert_eq!(entry1.hits, 2);
+        assert!(entry1.size > 0);
+
+        let entry2 = metrics.entries[&hash2];
+        assert!(!entry2.pinned);
+        assert_eq!(entry2.hits, 0);
+
+        assert_eq!(metrics.size(), entry1.size + entry2.size);
+    }
 }
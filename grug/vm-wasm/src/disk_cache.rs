@@ -0,0 +1,215 @@
+use {
+    crate::{Compiler, VmError, VmResult},
+    grug_types::{Hash256, HashExt},
+    std::{
+        fs, io,
+        path::{Path, PathBuf},
+    },
+    wasmer::{Engine, Module, Target},
+};
+
+/// Version of the format in which compiled modules are stored on disk.
+///
+/// Must be bumped whenever a change is made to how modules are compiled (e.g.
+/// the middlewares, the gas cost per operation, or the memory limit), so that
+/// modules compiled under the old rules are not loaded.
+pub const MODULE_SERIALIZATION_VERSION: u32 = 3;
+
+/// An on-disk cache of compiled Wasm modules, so that they don't need to be
+/// re-compiled after the node restarts.
+///
+/// Modules are stored in a subdirectory named after the serialization format
+/// version, the Wasmer version, the compiler, and the target platform. A module
+/// compiled under any different one of these may not be compatible, so when
+/// either of them changes, the node starts with an empty cache.
+///
+/// Each file is prefixed with the SHA-256 checksum of the serialized module,
+/// which is checked before the module is loaded.
+#[derive(Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    /// Open the cache under the given directory, creating it if it doesn't
+    /// exist. Modules compiled under different versions, or by a different
+    /// compiler, are deleted.
+    pub fn open<P>(base_dir: P, compiler: Compiler) -> VmResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let base_dir = base_dir.as_ref();
+        let tag = version_tag(compiler);
+
+        fs::create_dir_all(base_dir.join(&tag))?;
+
+        for entry in fs::read_dir(base_dir)? {
+            let entry = entry?;
+
+            if entry.file_type()?.is_dir() && entry.file_name() != tag.as_str() {
+                fs::remove_dir_all(entry.path())?;
+            }
+        }
+
+        Ok(Self {
+            dir: base_dir.join(tag),
+        })
+    }
+
+    /// Return whether a module of the given code hash exists in the cache.
+    ///
+    /// Note that this doesn't check the module's integrity, which is only done
+    /// when loading it.
+    pub fn contains(&self, code_hash: Hash256) -> bool {
+        self.path(code_hash).is_file()
+    }
+
+    /// Load a module from the cache. Return `None` if not found.
+    ///
+    /// The engine must be configured the same way as the one the module was
+    /// compiled with.
+    pub fn load(&self, code_hash: Hash256, engine: &Engine) -> VmResult<Option<Module>> {
+        let mut bytes = match fs::read(self.path(code_hash)) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        if bytes.len() < Hash256::LENGTH {
+            return Err(VmError::CorruptedModule { code_hash });
+        }
+
+        let module = bytes.split_off(Hash256::LENGTH);
+
+        if bytes != module.hash256().as_ref() {
+            return Err(VmError::CorruptedModule { code_hash });
+        }
+
+        // Safety: deserializing a module is unsafe, because the bytes include
+        // machine code that will be executed as-is. We have checked above that
+        // the bytes are exactly those we had written.
+        let module = unsafe { Module::deserialize(engine, module) }
+            .map_err(|err| VmError::Deserialize(err.to_string()))?;
+
+        Ok(Some(module))
+    }
+
+    /// Save a module to the cache, overwriting the existing one if any.
+    pub fn store(&self, code_hash: Hash256, module: &Module) -> VmResult<()> {
+        let module = module
+            .serialize()
+            .map_err(|err| VmError::Serialize(err.to_string()))?;
+
+        let mut bytes = Vec::with_capacity(Hash256::LENGTH + module.len());
+        bytes.extend_from_slice(module.hash256().as_ref());
+        bytes.extend_from_slice(&module);
+
+        // Write to a temporary file first, then rename it. This way, another
+        // process loading the module never sees a partially written file.
+        let path = self.path(code_hash);
+        let tmp_path = path.with_extension("tmp");
+
+        fs::write(&tmp_path, bytes)?;
+        fs::rename(tmp_path, path)?;
+
+        Ok(())
+    }
+
+    fn path(&self, code_hash: Hash256) -> PathBuf {
+        self.dir.join(format!("{code_hash}.module"))
+    }
+}
+
+fn version_tag(compiler: Compiler) -> String {
+    format!(
+        "v{}-wasmer-{}-{}-{}",
+        MODULE_SERIALIZATION_VERSION,
+        wasmer::VERSION,
+        compiler,
+        Target::default().triple()
+    )
+}
+
+// ----------------------------------- tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::fs,
+        tempfile::TempDir,
+        wasmer::{Engine, Module, Singlepass},
+    };
+
+    const CONTRACT: &[u8] = br#"(module)"#;
+
+    fn compile() -> (Module, Engine) {
+        let engine = Engine::from(Singlepass::new());
+        let module = Module::new(&engine, CONTRACT).unwrap();
+        (module, engine)
+    }
+
+    #[test]
+    fn storing_and_loading() {
+        let dir = TempDir::new().unwrap();
+        let cache = DiskCache::open(dir.path(), Compiler::Singlepass).unwrap();
+        let hash = CONTRACT.hash256();
+
+        // Nothing is cached yet.
+        assert!(!cache.contains(hash));
+        assert!(cache.load(hash, &compile().1).unwrap().is_none());
+
+        let (module, _) = compile();
+        cache.store(hash, &module).unwrap();
+
+        // The module can be loaded, including by another instance of the cache
+        // opened under the same directory, e.g. after a restart.
+        let cache = DiskCache::open(dir.path(), Compiler::Singlepass).unwrap();
+        assert!(cache.contains(hash));
+        assert!(cache.load(hash, &compile().1).unwrap().is_some());
+    }
+
+    #[test]
+    fn rejecting_corrupted_module() {
+        let dir = TempDir::new().unwrap();
+        let cache = DiskCache::open(dir.path(), Compiler::Singlepass).unwrap();
+        let hash = CONTRACT.hash256();
+
+        let (module, engine) = compile();
+        cache.store(hash, &module).unwrap();
+
+        // Flip the last byte of the file.
+        let path = cache.path(hash);
+        let mut bytes = fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        fs::write(&path, bytes).unwrap();
+
+        assert!(matches!(
+            cache.load(hash, &engine),
+            Err(VmError::CorruptedModule { code_hash }) if code_hash == hash
+        ));
+
+        // A truncated file is also rejected.
+        fs::write(&path, b"jake").unwrap();
+
+        assert!(matches!(
+            cache.load(hash, &engine),
+            Err(VmError::CorruptedModule { code_hash }) if code_hash == hash
+        ));
+    }
+
+    #[test]
+    fn removing_other_versions() {
+        let dir = TempDir::new().unwrap();
+
+        // Create a directory as if it's created by a different Wasmer version.
+        let stale_dir = dir.path().join("v0-wasmer-0.0.0-singlepass-unknown");
+        fs::create_dir_all(&stale_dir).unwrap();
+        fs::write(stale_dir.join("foo.module"), b"bar").unwrap();
+
+        DiskCache::open(dir.path(), Compiler::Singlepass).unwrap();
+
+        assert!(!stale_dir.exists());
+        assert!(dir.path().join(version_tag(Compiler::Singlepass)).is_dir());
+    }
+}
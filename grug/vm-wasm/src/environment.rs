@@ -1,5 +1,5 @@
 use {
-    crate::{Iterator, VmError, VmResult, WasmVm},
+    crate::{Iterator, VmError, VmResult, WasmVm, WASM_METERING},
     grug_app::{GasTracker, QuerierProvider, StorageProvider},
     grug_types::{Record, StdError},
     std::{collections::HashMap, ptr::NonNull},
@@ -205,7 +205,7 @@ impl Environment {
             // return the result as-is.
             (result, MeteringPoints::Remaining(remaining)) => {
                 let consumed = self.gas_checkpoint - remaining;
-                self.gas_tracker.consume(consumed, name)?;
+                self.gas_tracker.consume(consumed, WASM_METERING)?;
                 self.gas_checkpoint = remaining;
 
                 Ok(result?)
@@ -224,7 +224,8 @@ impl Environment {
                 // all `u64::MAX` gas units have been depleted) this would
                 // overflow. However this should never happen in practice (the
                 // call would run an exceedingly long time to start with).
-                self.gas_tracker.consume(self.gas_checkpoint, name)?;
+                self.gas_tracker
+                    .consume(self.gas_checkpoint, WASM_METERING)?;
                 self.gas_checkpoint = 0;
 
                 Err(StdError::OutOfGas {
@@ -254,10 +255,18 @@ impl Environment {
         let instance = self.get_wasmer_instance()?;
         match get_remaining_points(store, instance) {
             MeteringPoints::Remaining(remaining) => {
-                // gas_checkpoint can't be less than remaining
-                // compute consumed equals to the gas consumed since the last update + external gas
-                let consumed = self.gas_checkpoint - remaining + external;
-                self.gas_tracker.consume(consumed, comment)?;
+                // gas_checkpoint can't be less than remaining.
+                //
+                // The gas consumed by Wasm execution since the last update and
+                // the external gas are consumed as a single amount, but the
+                // profiler records them separately.
+                self.gas_tracker.consume_parts(
+                    &[
+                        (self.gas_checkpoint - remaining, WASM_METERING),
+                        (external, comment),
+                    ],
+                    comment,
+                )?;
 
                 // If there is a limit on gas_tracker, update the remaining points in the store
                 if let Some(remaining) = self.gas_tracker.remaining() {
@@ -1,7 +1,7 @@
 use {
     grug_app::AppError,
-    grug_types::StdError,
-    std::string::FromUtf8Error,
+    grug_types::{Hash256, StdError},
+    std::{io, string::FromUtf8Error},
     thiserror::Error,
     wasmer::{CompileError, ExportError, InstantiationError, MemoryAccessError, RuntimeError},
 };
@@ -14,6 +14,9 @@ pub enum VmError {
     #[error(transparent)]
     FromUtf8(#[from] FromUtf8Error),
 
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
     #[error(transparent)]
     Export(#[from] ExportError),
 
@@ -26,9 +29,37 @@ pub enum VmError {
     // The wasmer `CompileError` and `InstantiateError` are big (56 and 128 bytes,
     // respectively). We get a clippy warning if we wrap them directly here in
     // VmError (result_large_err). To avoid this, we cast them to strings instead.
+    #[error("failed to compile Wasm module: {0}")]
+    Compile(String),
+
     #[error("failed to instantiate Wasm module: {0}")]
     Instantiation(String),
 
+    #[error("failed to parse Wasm module: {0}")]
+    Parse(String),
+
+    #[error("Wasm module exceeds the `{limit}` limit! value: {value}, max: {max}")]
+    LimitExceeded {
+        limit: &'static str,
+        value: u64,
+        max: u64,
+    },
+
+    #[error("Wasm module doesn't have the required export `{name}`")]
+    MissingExport { name: &'static str },
+
+    #[error("unsupported interface version! found: {found}, supported: {supported}")]
+    UnsupportedInterfaceVersion { found: String, supported: u32 },
+
+    #[error("failed to serialize Wasm module: {0}")]
+    Serialize(String),
+
+    #[error("failed to deserialize Wasm module: {0}")]
+    Deserialize(String),
+
+    #[error("cached Wasm module with hash `{code_hash}` is corrupted")]
+    CorruptedModule { code_hash: Hash256 },
+
     #[error("Wasmer memory not set in Environment")]
     WasmerMemoryNotSet,
 
@@ -79,7 +110,7 @@ pub enum VmError {
 
 impl From<CompileError> for VmError {
     fn from(err: CompileError) -> Self {
-        Self::Instantiation(err.to_string())
+        Self::Compile(err.to_string())
     }
 }
 
@@ -1,6 +1,5 @@
 use {
     crate::{read_from_memory, write_to_memory, Environment, Iterator, VmError, VmResult},
-    grug_app::GAS_COSTS,
     grug_types::{decode_sections, Addr, BorshDeExt, BorshSerExt, Query, Record, Storage},
     tracing::info,
     wasmer::FunctionEnvMut,
@@ -15,13 +14,17 @@ pub fn db_read(mut fe: FunctionEnvMut<Environment>, key_ptr: u32) -> VmResult<u3
         Some(value) => {
             env.consume_external_gas(
                 &mut store,
-                GAS_COSTS.db_read.cost(value.len()),
+                env.gas_tracker.costs().db_read.cost(value.len()),
                 "db_read/found",
             )?;
             write_to_memory(env, &mut store, &value)
         },
         None => {
-            env.consume_external_gas(&mut store, GAS_COSTS.db_read.cost(0), "db_read/not_found")?;
+            env.consume_external_gas(
+                &mut store,
+                env.gas_tracker.costs().db_read.cost(0),
+                "db_read/not_found",
+            )?;
             // If the record doesn't exist, return a zero pointer.
             Ok(0)
         },
@@ -50,7 +53,7 @@ pub fn db_scan(
     let order = order.try_into()?;
     let iterator = Iterator::new(min, max, order);
 
-    env.consume_external_gas(&mut store, GAS_COSTS.db_scan, "db_scan")?;
+    env.consume_external_gas(&mut store, env.gas_tracker.costs().db_scan, "db_scan")?;
 
     Ok(env.add_iterator(iterator))
 }
@@ -62,14 +65,23 @@ pub fn db_next(mut fe: FunctionEnvMut<Environment>, iterator_id: i32) -> VmResul
         Some((key, value)) => {
             env.consume_external_gas(
                 &mut store,
-                GAS_COSTS.db_next + GAS_COSTS.db_read.cost(key.len() + value.len()),
+                env.gas_tracker.costs().db_next
+                    + env
+                        .gas_tracker
+                        .costs()
+                        .db_read
+                        .cost(key.len() + value.len()),
                 "db_next/found",
             )?;
 
             write_to_memory(env, &mut store, &encode_record((key, value)))
         },
         None => {
-            env.consume_external_gas(&mut store, GAS_COSTS.db_next, "db_next/not_found")?;
+            env.consume_external_gas(
+                &mut store,
+                env.gas_tracker.costs().db_next,
+                "db_next/not_found",
+            )?;
 
             Ok(0)
         },
@@ -83,14 +95,18 @@ pub fn db_next_key(mut fe: FunctionEnvMut<Environment>, iterator_id: i32) -> VmR
         Some((key, _)) => {
             env.consume_external_gas(
                 &mut store,
-                GAS_COSTS.db_next + GAS_COSTS.db_read.cost(key.len()),
+                env.gas_tracker.costs().db_next + env.gas_tracker.costs().db_read.cost(key.len()),
                 "db_next_key/found",
             )?;
 
             write_to_memory(env, &mut store, &key)
         },
         None => {
-            env.consume_external_gas(&mut store, GAS_COSTS.db_next, "db_next_key/not_found")?;
+            env.consume_external_gas(
+                &mut store,
+                env.gas_tracker.costs().db_next,
+                "db_next_key/not_found",
+            )?;
 
             Ok(0)
         },
@@ -104,14 +120,18 @@ pub fn db_next_value(mut fe: FunctionEnvMut<Environment>, iterator_id: i32) -> V
         Some((_, value)) => {
             env.consume_external_gas(
                 &mut store,
-                GAS_COSTS.db_next + GAS_COSTS.db_read.cost(value.len()),
+                env.gas_tracker.costs().db_next + env.gas_tracker.costs().db_read.cost(value.len()),
                 "db_next_value/found",
             )?;
 
             write_to_memory(env, &mut store, &value)
         },
         None => {
-            env.consume_external_gas(&mut store, GAS_COSTS.db_next, "db_next_value/not_found")?;
+            env.consume_external_gas(
+                &mut store,
+                env.gas_tracker.costs().db_next,
+                "db_next_value/not_found",
+            )?;
 
             Ok(0)
         },
@@ -133,7 +153,9 @@ pub fn db_write(mut fe: FunctionEnvMut<Environment>, key_ptr: u32, value_ptr: u3
     let key = read_from_memory(env, &store, key_ptr)?;
     let value = read_from_memory(env, &store, value_ptr)?;
 
-    let gas_cost = GAS_COSTS
+    let gas_cost = env
+        .gas_tracker
+        .costs()
         .db_write
         .cost(env.storage.namespace().len() + key.len() + value.len());
 
@@ -177,7 +199,11 @@ pub fn db_remove(mut fe: FunctionEnvMut<Environment>, key_ptr: u32) -> VmResult<
 
     env.storage.remove(&key);
     env.clear_iterators();
-    env.consume_external_gas(&mut store, GAS_COSTS.db_remove, "storage_remove")
+    env.consume_external_gas(
+        &mut store,
+        env.gas_tracker.costs().db_remove,
+        "storage_remove",
+    )
 }
 
 pub fn db_remove_range(
@@ -204,7 +230,11 @@ pub fn db_remove_range(
 
     env.storage.remove_range(min.as_deref(), max.as_deref());
     env.clear_iterators();
-    env.consume_external_gas(&mut store, GAS_COSTS.db_remove, "storage_remove_range")
+    env.consume_external_gas(
+        &mut store,
+        env.gas_tracker.costs().db_remove,
+        "storage_remove_range",
+    )
 }
 
 pub fn debug(mut fe: FunctionEnvMut<Environment>, addr_ptr: u32, msg_ptr: u32) -> VmResult<()> {
@@ -250,7 +280,11 @@ pub fn secp256k1_verify(
     let sig = read_from_memory(env, &store, sig_ptr)?;
     let pk = read_from_memory(env, &store, pk_ptr)?;
 
-    env.consume_external_gas(&mut store, GAS_COSTS.secp256k1_verify, "secp256k1_verify")?;
+    env.consume_external_gas(
+        &mut store,
+        env.gas_tracker.costs().secp256k1_verify,
+        "secp256k1_verify",
+    )?;
 
     match grug_crypto::secp256k1_verify(&msg_hash, &sig, &pk) {
         Ok(()) => Ok(0),
@@ -270,7 +304,11 @@ pub fn secp256r1_verify(
     let sig = read_from_memory(env, &store, sig_ptr)?;
     let pk = read_from_memory(env, &store, pk_ptr)?;
 
-    env.consume_external_gas(&mut store, GAS_COSTS.secp256k1_verify, "secp256r1_verify")?;
+    env.consume_external_gas(
+        &mut store,
+        env.gas_tracker.costs().secp256k1_verify,
+        "secp256r1_verify",
+    )?;
 
     match grug_crypto::secp256r1_verify(&msg_hash, &sig, &pk) {
         Ok(()) => Ok(0),
@@ -298,7 +336,7 @@ pub fn secp256k1_pubkey_recover(
 
     env.consume_external_gas(
         &mut store,
-        GAS_COSTS.secp256k1_pubkey_recover,
+        env.gas_tracker.costs().secp256k1_pubkey_recover,
         "secp256k1_pubkey_recover",
     )?;
 
@@ -328,7 +366,11 @@ pub fn ed25519_verify(
     let sig = read_from_memory(env, &store, sig_ptr)?;
     let pk = read_from_memory(env, &store, pk_ptr)?;
 
-    env.consume_external_gas(&mut store, GAS_COSTS.ed25519_verify, "ed25519_verify")?;
+    env.consume_external_gas(
+        &mut store,
+        env.gas_tracker.costs().ed25519_verify,
+        "ed25519_verify",
+    )?;
 
     match grug_crypto::ed25519_verify(&msg_hash, &sig, &pk) {
         Ok(()) => Ok(0),
@@ -354,7 +396,10 @@ pub fn ed25519_batch_verify(
 
     env.consume_external_gas(
         &mut store,
-        GAS_COSTS.ed25519_batch_verify.cost(prehash_msgs.len()),
+        env.gas_tracker
+            .costs()
+            .ed25519_batch_verify
+            .cost(prehash_msgs.len()),
         "ed25519_batch_verify",
     )?;
 
@@ -372,7 +417,7 @@ macro_rules! impl_hash_method {
             let data = read_from_memory(env, &store, data_ptr)?;
             let hash = grug_crypto::$hasher(&data);
 
-            env.consume_external_gas(&mut store, GAS_COSTS.$hasher.cost(data.len()), $name)?;
+            env.consume_external_gas(&mut store, env.gas_tracker.costs().$hasher.cost(data.len()), $name)?;
 
             write_to_memory(env, &mut store, &hash)
         }
@@ -414,7 +459,7 @@ mod tests {
             db_read, db_remove, db_remove_range, db_scan, db_write, debug, read_from_memory,
             write_to_memory, Environment, VmResult, WasmVm, GAS_PER_OPERATION,
         },
-        grug_app::{GasTracker, QuerierProvider, Shared, StorageProvider, APP_CONFIGS, GAS_COSTS},
+        grug_app::{GasTracker, QuerierProvider, Shared, StorageProvider, APP_CONFIGS},
         grug_crypto::{Identity256, Identity512},
         grug_types::{
             encode_sections, json, Addr, BlockInfo, BorshDeExt, BorshSerExt, GenericResult,
@@ -754,7 +799,10 @@ mod tests {
 
         let gas_consumed = suite.env_mut().gas_tracker.used() - gas_pre;
 
-        let cost = GAS_COSTS
+        let cost = suite
+            .env_mut()
+            .gas_tracker
+            .costs()
             .db_write
             .cost(NAMESPACE_CONTRACT.len() + k.len() + v.len());
 
@@ -785,7 +833,7 @@ mod tests {
 
         let gas_consumed = suite.env_mut().gas_tracker.used() - gas_pre;
 
-        assert_eq!(gas_consumed, GAS_COSTS.db_remove);
+        assert_eq!(gas_consumed, suite.env_mut().gas_tracker.costs().db_remove);
     }
 
     // ---------------------------- db_remove_range ----------------------------
@@ -847,7 +895,7 @@ mod tests {
 
         let gas_consumed = suite.env_mut().gas_tracker.used() - gas_pre;
 
-        assert_eq!(gas_consumed, GAS_COSTS.db_remove);
+        assert_eq!(gas_consumed, suite.env_mut().gas_tracker.costs().db_remove);
     }
 
     // -------------------------------- debug ----------------------------------
@@ -1,17 +1,21 @@
 mod cache;
+mod disk_cache;
 mod environment;
 mod error;
 mod gatekeeper;
 mod imports;
 mod iterator;
+mod limiter;
 mod memory;
 mod region;
+mod stack_limiter;
 #[cfg(feature = "testing")]
 mod testing;
 mod tunables;
+mod validation;
 mod vm;
 
 pub use {
-    cache::*, environment::*, error::*, gatekeeper::*, imports::*, iterator::*, memory::*,
-    region::*, tunables::*, vm::*,
+    cache::*, disk_cache::*, environment::*, error::*, gatekeeper::*, imports::*, iterator::*,
+    limiter::*, memory::*, region::*, stack_limiter::*, tunables::*, validation::*, vm::*,
 };
@@ -0,0 +1,177 @@
+use {
+    crate::VmError,
+    grug_types::WasmLimits,
+    wasmer::{
+        wasmparser::Operator, FunctionMiddleware, LocalFunctionIndex, MiddlewareError,
+        MiddlewareReaderState, ModuleMiddleware,
+    },
+    wasmer_types::ModuleInfo,
+};
+
+/// The name used in errors
+const MIDDLEWARE_NAME: &str = "Limiter";
+
+// ----------------------------- module middleware -----------------------------
+
+/// A middleware that rejects modules exceeding the given limits on the number
+/// of functions, the number of imports, or the sizes of tables.
+///
+/// These are checked on the module info, which is parsed the same way
+/// regardless of the compiler. The code size and the number of locals aren't
+/// available to middlewares, and are checked by [`validate_wasm`](crate::validate_wasm)
+/// instead.
+#[derive(Debug)]
+pub struct Limiter {
+    limits: WasmLimits,
+}
+
+impl Limiter {
+    pub fn new(limits: WasmLimits) -> Self {
+        Self { limits }
+    }
+}
+
+impl ModuleMiddleware for Limiter {
+    /// Generates a `FunctionMiddleware` for a given function.
+    fn generate_function_middleware(&self, _: LocalFunctionIndex) -> Box<dyn FunctionMiddleware> {
+        Box::new(FunctionLimiter)
+    }
+
+    /// Checks the module info against the limits.
+    fn transform_module_info(&self, info: &mut ModuleInfo) -> Result<(), MiddlewareError> {
+        check_limit(
+            "max_functions",
+            info.functions.len() - info.num_imported_functions,
+            self.limits.max_functions,
+        )?;
+
+        check_limit("max_imports", info.imports.len(), self.limits.max_imports)?;
+
+        // Tables can't grow at runtime, because the "reference types" feature,
+        // which introduces the `table.grow` operator, is disabled by the
+        // `Gatekeeper`. So we only need to check their initial sizes.
+        for table in info.tables.values() {
+            check_limit(
+                "max_table_size",
+                table.minimum as usize,
+                self.limits.max_table_size,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn check_limit(limit: &'static str, value: usize, max: u32) -> Result<(), MiddlewareError> {
+    if value > max as usize {
+        return Err(MiddlewareError::new(
+            MIDDLEWARE_NAME,
+            VmError::LimitExceeded {
+                limit,
+                value: value as u64,
+                max: max as u64,
+            }
+            .to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// ---------------------------- function middleware ----------------------------
+
+/// The limits are all on the module level, so the function middleware simply
+/// passes operators through.
+#[derive(Debug)]
+struct FunctionLimiter;
+
+impl FunctionMiddleware for FunctionLimiter {
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        state.push_operator(operator);
+
+        Ok(())
+    }
+}
+
+// ----------------------------------- tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::sync::Arc,
+        test_case::test_case,
+        wasmer::{CompileError, CompilerConfig, Module, Singlepass, Store},
+    };
+
+    const LIMITS: WasmLimits = WasmLimits {
+        max_code_size: 1024,
+        max_functions: 2,
+        max_function_locals: 10,
+        max_table_size: 10,
+        max_imports: 1,
+    };
+
+    #[test_case(
+        br#"
+          (module
+            (import "env" "foo" (func))
+            (table 10 funcref)
+            (func)
+            (func))
+        "#,
+        |result| {
+            assert!(result.is_ok());
+        };
+        "within limits"
+    )]
+    #[test_case(
+        br#"
+          (module
+            (func)
+            (func)
+            (func))
+        "#,
+        |result| {
+            assert!(result.unwrap_err().to_string().contains("max_functions"));
+        };
+        "too many functions"
+    )]
+    #[test_case(
+        br#"
+          (module
+            (import "env" "foo" (func))
+            (import "env" "bar" (func)))
+        "#,
+        |result| {
+            assert!(result.unwrap_err().to_string().contains("max_imports"));
+        };
+        "too many imports"
+    )]
+    #[test_case(
+        br#"
+          (module
+            (table 11 funcref))
+        "#,
+        |result| {
+            assert!(result.unwrap_err().to_string().contains("max_table_size"));
+        };
+        "table too large"
+    )]
+    fn limiter<T>(wat: &[u8], callback: T)
+    where
+        T: Fn(Result<Module, CompileError>),
+    {
+        let mut compiler = Singlepass::new();
+        compiler.push_middleware(Arc::new(Limiter::new(LIMITS)));
+
+        let store = Store::new(compiler);
+        let result = Module::new(&store, wat);
+
+        callback(result);
+    }
+}
@@ -0,0 +1,350 @@
+use {
+    crate::{count_locals, VmError, VmResult, MAX_QUERY_DEPTH},
+    std::sync::{Arc, Mutex},
+    wasmer::{
+        wasmparser::{
+            BlockType, FuncValidator, FunctionBody, Operator, Parser, ValidPayload, Validator,
+            ValidatorResources,
+        },
+        FunctionMiddleware, LocalFunctionIndex, MiddlewareError, MiddlewareReaderState,
+        ModuleMiddleware,
+    },
+    wasmer_types::{GlobalIndex, GlobalInit, GlobalType, ModuleInfo, Mutability, Type},
+};
+
+/// The name used in errors
+const MIDDLEWARE_NAME: &str = "StackLimiter";
+
+/// Maximum total cost of the frames on the native stack, across all instances
+/// running on it.
+///
+/// The cost of a frame is an estimate of the number of 8-byte values it holds,
+/// so this corresponds to 512 KiB, which is half of the native stack Wasmer
+/// runs Wasm code on. The other half is left for the host functions that run
+/// between nested instances, and for whatever the compiler stores in a frame
+/// beyond this estimate.
+const MAX_TOTAL_STACK_COST: u64 = 64 * 1024;
+
+/// Maximum total cost of the frames on the call stack of a single instance.
+///
+/// When a contract makes a query, the contract being queried runs in a new
+/// instance, on the same native stack, and tracks its own cost starting from
+/// zero. As such, the total budget is split evenly between the largest number
+/// of instances that can be nested this way.
+pub const MAX_STACK_COST: u64 = MAX_TOTAL_STACK_COST / (MAX_QUERY_DEPTH as u64 + 1);
+
+/// Cost of a frame in addition to the function's parameters, locals, and
+/// operand stack, accounting for the return address and saved registers.
+const FRAME_OVERHEAD: u64 = 16;
+
+// ----------------------------- module middleware -----------------------------
+
+/// A middleware that limits the depth of recursion deterministically.
+///
+/// Without it, recursion is only limited by the native stack, which overflows
+/// at a depth that depends on how large the compiler makes each frame. Nodes
+/// using different compilers would disagree on whether a call succeeds, and
+/// on how much gas it consumes.
+///
+/// Instead, the cost of the frames on the call stack, which only depends on
+/// the number of parameters, locals, and the largest operand stack height of
+/// each function, is tracked in a global, and a call that would make it exceed
+/// [`MAX_STACK_COST`] traps.
+///
+/// A trap leaves the cost of the frames it unwinds in the global. This is fine,
+/// as an instance isn't used again after it traps.
+///
+/// Must be pushed after the `Metering` middleware, such that the operators it
+/// inserts don't consume gas, and be used for a single module only.
+#[derive(Debug)]
+pub struct StackLimiter {
+    /// The number of locals, plus the largest operand stack height, of each
+    /// function defined in the module. Doesn't include their parameters, which
+    /// are only known from the module info.
+    body_costs: Vec<u64>,
+    /// The global that tracks the cost, and the cost of each function's frame.
+    /// Set once the module info is known.
+    state: Mutex<Option<Arc<StackState>>>,
+}
+
+#[derive(Debug)]
+struct StackState {
+    global_index: GlobalIndex,
+    /// The cost of each function's frame, including imported ones.
+    frame_costs: Vec<u64>,
+    /// The largest cost among all functions, used for indirect calls, as
+    /// their callee isn't known until runtime.
+    max_frame_cost: u64,
+}
+
+impl StackLimiter {
+    /// Create the middleware for the given module code.
+    pub fn new(code: &[u8]) -> VmResult<Self> {
+        let mut body_costs = Vec::new();
+        let mut validator = Validator::new();
+
+        for payload in Parser::new(0).parse_all(code) {
+            let payload = payload.map_err(|err| VmError::Parse(err.to_string()))?;
+
+            if let ValidPayload::Func(func, body) = validator
+                .payload(&payload)
+                .map_err(|err| VmError::Parse(err.to_string()))?
+            {
+                let func = func.into_validator(Default::default());
+                let height = max_operand_stack_height(func, &body)?;
+
+                body_costs.push(count_locals(&body)? + height);
+            }
+        }
+
+        Ok(Self {
+            body_costs,
+            state: Mutex::new(None),
+        })
+    }
+}
+
+/// Find the largest number of values on a function's operand stack at any
+/// point of its execution.
+///
+/// This depends on the types of the operators, so we use the validator, which
+/// keeps track of the operand stack as it checks each operator.
+fn max_operand_stack_height(
+    mut func: FuncValidator<ValidatorResources>,
+    body: &FunctionBody,
+) -> VmResult<u64> {
+    let mut reader = body.get_binary_reader();
+    let mut max_height = 0;
+
+    func.read_locals(&mut reader)
+        .map_err(|err| VmError::Parse(err.to_string()))?;
+
+    while !reader.eof() {
+        let offset = reader.original_position();
+        let operator = reader
+            .read_operator()
+            .map_err(|err| VmError::Parse(err.to_string()))?;
+
+        func.op(offset, &operator)
+            .map_err(|err| VmError::Parse(err.to_string()))?;
+
+        max_height = max_height.max(func.operand_stack_height());
+    }
+
+    Ok(max_height as u64)
+}
+
+impl ModuleMiddleware for StackLimiter {
+    /// Generates a `FunctionMiddleware` for a given function.
+    fn generate_function_middleware(&self, _: LocalFunctionIndex) -> Box<dyn FunctionMiddleware> {
+        Box::new(FunctionStackLimiter {
+            state: self.state.lock().unwrap().clone().unwrap(),
+        })
+    }
+
+    /// Adds the global that tracks the cost, and computes each function's
+    /// frame cost.
+    fn transform_module_info(&self, info: &mut ModuleInfo) -> Result<(), MiddlewareError> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.is_some() {
+            return Err(MiddlewareError::new(
+                MIDDLEWARE_NAME,
+                "the middleware can't be used for multiple modules",
+            ));
+        }
+
+        let global_index = info
+            .globals
+            .push(GlobalType::new(Type::I64, Mutability::Var));
+        info.global_initializers.push(GlobalInit::I64Const(0));
+
+        let frame_costs = info
+            .functions
+            .iter()
+            .map(|(function_index, signature_index)| {
+                let params = info.signatures[*signature_index].params().len() as u64;
+                let body = info
+                    .local_func_index(function_index)
+                    .and_then(|index| self.body_costs.get(index.as_u32() as usize))
+                    .copied()
+                    .unwrap_or(0);
+
+                FRAME_OVERHEAD + params + body
+            })
+            .collect::<Vec<_>>();
+
+        let max_frame_cost = frame_costs.iter().copied().max().unwrap_or(FRAME_OVERHEAD);
+
+        *state = Some(Arc::new(StackState {
+            global_index,
+            frame_costs,
+            max_frame_cost,
+        }));
+
+        Ok(())
+    }
+}
+
+// ---------------------------- function middleware ----------------------------
+
+#[derive(Debug)]
+struct FunctionStackLimiter {
+    state: Arc<StackState>,
+}
+
+impl FunctionMiddleware for FunctionStackLimiter {
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        let cost = match &operator {
+            Operator::Call { function_index } => self
+                .state
+                .frame_costs
+                .get(*function_index as usize)
+                .copied()
+                .unwrap_or(self.state.max_frame_cost),
+            Operator::CallIndirect { .. } => self.state.max_frame_cost,
+            _ => {
+                state.push_operator(operator);
+
+                return Ok(());
+            },
+        };
+
+        let global_index = self.state.global_index.as_u32();
+
+        // Add the callee's frame cost before the call, and trap if it exceeds
+        // the maximum.
+        state.extend(&[
+            Operator::GlobalGet { global_index },
+            Operator::I64Const { value: cost as i64 },
+            Operator::I64Add,
+            Operator::GlobalSet { global_index },
+            Operator::GlobalGet { global_index },
+            Operator::I64Const {
+                value: MAX_STACK_COST as i64,
+            },
+            Operator::I64GtU,
+            Operator::If {
+                blockty: BlockType::Empty,
+            },
+            Operator::Unreachable,
+            Operator::End,
+        ]);
+
+        state.push_operator(operator);
+
+        // Subtract it once the callee returns.
+        state.extend(&[
+            Operator::GlobalGet { global_index },
+            Operator::I64Const { value: cost as i64 },
+            Operator::I64Sub,
+            Operator::GlobalSet { global_index },
+        ]);
+
+        Ok(())
+    }
+}
+
+// ----------------------------------- tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "cranelift")]
+    use wasmer::Cranelift;
+    use {
+        super::*,
+        test_case::test_case,
+        wasmer::{imports, wat2wasm, CompilerConfig, Engine, Instance, Module, Singlepass, Store},
+    };
+
+    /// A function that recurses `n` times.
+    const RECURSE: &[u8] = br#"
+      (module
+        (func $recurse (export "recurse") (param $n i64) (result i64)
+          local.get $n
+          i64.eqz
+          if (result i64)
+            i64.const 0
+          else
+            local.get $n
+            i64.const 1
+            i64.sub
+            call $recurse
+            i64.const 1
+            i64.add
+          end))
+    "#;
+
+    /// The maximum number of times the function can recurse. Each call costs
+    /// the overhead, plus one for the parameter, plus two for the operand
+    /// stack, which holds at most two values at once.
+    const MAX_DEPTH: i64 = (MAX_STACK_COST / (FRAME_OVERHEAD + 1 + 2)) as i64;
+
+    fn recurse<C>(mut compiler: C, depths: &[i64]) -> Vec<Result<i64, String>>
+    where
+        C: CompilerConfig,
+        Engine: From<C>,
+    {
+        let code = wat2wasm(RECURSE).unwrap();
+
+        compiler.push_middleware(Arc::new(StackLimiter::new(&code).unwrap()));
+
+        let mut store = Store::new(compiler);
+        let module = Module::new(&store, &code).unwrap();
+        let instance = Instance::new(&mut store, &module, &imports! {}).unwrap();
+        let function = instance
+            .exports
+            .get_typed_function::<i64, i64>(&store, "recurse")
+            .unwrap();
+
+        depths
+            .iter()
+            .map(|depth| {
+                function
+                    .call(&mut store, *depth)
+                    .map_err(|err| err.message())
+            })
+            .collect()
+    }
+
+    #[test_case(Singlepass::new(); "singlepass")]
+    #[cfg_attr(feature = "cranelift", test_case(Cranelift::new(); "cranelift"))]
+    fn recursing_within_limit<C>(compiler: C)
+    where
+        C: CompilerConfig,
+        Engine: From<C>,
+    {
+        // Recursing up to the maximum depth works. The cost is released once
+        // the calls return, so it can be done again.
+        assert_eq!(recurse(compiler, &[MAX_DEPTH, MAX_DEPTH]), [
+            Ok(MAX_DEPTH),
+            Ok(MAX_DEPTH)
+        ]);
+    }
+
+    #[test_case(Singlepass::new(), MAX_DEPTH + 1; "singlepass, just over the limit")]
+    #[test_case(Singlepass::new(), 1_000_000; "singlepass, far over the limit")]
+    #[cfg_attr(
+        feature = "cranelift",
+        test_case(Cranelift::new(), MAX_DEPTH + 1; "cranelift, just over the limit")
+    )]
+    #[cfg_attr(
+        feature = "cranelift",
+        test_case(Cranelift::new(), 1_000_000; "cranelift, far over the limit")
+    )]
+    fn exceeding_recursion_limit<C>(compiler: C, depth: i64)
+    where
+        C: CompilerConfig,
+        Engine: From<C>,
+    {
+        // Recursing deeper traps at the limit, rather than overflowing the
+        // native stack, however deep the recursion would go.
+        let results = recurse(compiler, &[depth]);
+
+        assert!(results[0].as_ref().unwrap_err().contains("unreachable"));
+    }
+}
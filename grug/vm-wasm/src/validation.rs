@@ -0,0 +1,187 @@
+use {
+    crate::{VmError, VmResult},
+    grug_types::WasmLimits,
+    wasmer::wasmparser::{ExternalKind, FunctionBody, Parser, Payload},
+};
+
+/// Version of the interface between the host and Wasm modules, i.e. the set of
+/// imports and exports, that the host supports.
+///
+/// A Wasm module signals the version it's built against by exporting an empty
+/// function named `interface_version_{version}`.
+pub const INTERFACE_VERSION: u32 = 1;
+
+/// Prefix of the name of the export that signals a module's interface version.
+const INTERFACE_VERSION_PREFIX: &str = "interface_version_";
+
+/// Exports that every Wasm module must have, other than the interface version
+/// marker:
+///
+/// - `memory`, the linear memory used to pass data between host and module;
+/// - `allocate` and `deallocate`, used to manage the memory;
+/// - `instantiate`, the entry point called when creating a contract, without
+///   which the code is useless.
+const REQUIRED_EXPORTS: [(&str, ExternalKind); 4] = [
+    ("memory", ExternalKind::Memory),
+    ("allocate", ExternalKind::Func),
+    ("deallocate", ExternalKind::Func),
+    ("instantiate", ExternalKind::Func),
+];
+
+/// Perform static checks on a Wasm module without compiling it.
+///
+/// These are cheap compared to compiling, so they're done first, such that a
+/// module that is obviously bad is rejected without spending time compiling.
+///
+/// Of the given limits, only the code size and the number of locals per
+/// function are checked here. The rest are checked by the [`Limiter`](crate::Limiter)
+/// during compilation.
+pub fn validate_wasm(code: &[u8], limits: &WasmLimits) -> VmResult<()> {
+    // Compiling a module takes time roughly linear to its size, so we check
+    // the size before anything else.
+    check_limit("max_code_size", code.len() as u64, limits.max_code_size)?;
+
+    let mut exports = Vec::new();
+
+    for payload in Parser::new(0).parse_all(code) {
+        match payload.map_err(|err| VmError::Parse(err.to_string()))? {
+            Payload::ExportSection(reader) => {
+                for export in reader {
+                    let export = export.map_err(|err| VmError::Parse(err.to_string()))?;
+                    exports.push((export.name, export.kind));
+                }
+            },
+            Payload::CodeSectionEntry(body) => {
+                let locals = count_locals(&body)?;
+
+                check_limit("max_function_locals", locals, limits.max_function_locals)?;
+            },
+            _ => (),
+        }
+    }
+
+    for (name, kind) in REQUIRED_EXPORTS {
+        if !exports.contains(&(name, kind)) {
+            return Err(VmError::MissingExport { name });
+        }
+    }
+
+    // Contracts built before the interface version marker was introduced don't
+    // export it. These are compatible with version 1, so we accept them.
+    let versions = exports
+        .iter()
+        .filter_map(|(name, _)| name.strip_prefix(INTERFACE_VERSION_PREFIX))
+        .collect::<Vec<_>>();
+
+    match versions.as_slice() {
+        [] => (),
+        [version] if *version == INTERFACE_VERSION.to_string() => (),
+        _ => {
+            return Err(VmError::UnsupportedInterfaceVersion {
+                found: versions.join(", "),
+                supported: INTERFACE_VERSION,
+            });
+        },
+    }
+
+    Ok(())
+}
+
+/// Count the locals declared by a function, not including its parameters.
+pub(crate) fn count_locals(body: &FunctionBody) -> VmResult<u64> {
+    let mut locals = 0;
+
+    for local in body
+        .get_locals_reader()
+        .map_err(|err| VmError::Parse(err.to_string()))?
+    {
+        let (count, _) = local.map_err(|err| VmError::Parse(err.to_string()))?;
+        locals += count as u64;
+    }
+
+    Ok(locals)
+}
+
+fn check_limit(limit: &'static str, value: u64, max: u32) -> VmResult<()> {
+    if value > max as u64 {
+        return Err(VmError::LimitExceeded {
+            limit,
+            value,
+            max: max as u64,
+        });
+    }
+
+    Ok(())
+}
+
+// ----------------------------------- tests -----------------------------------
+
+#[cfg(test)]
+mod tests {
+    use {super::*, test_case::test_case, wasmer::wat2wasm};
+
+    /// The smallest valid Wasm module: just the magic number and version.
+    const EMPTY_MODULE: &[u8] = b"\0asm\x01\0\0\0";
+
+    /// A module with a function that declares 11 locals, not including its
+    /// parameter.
+    const MANY_LOCALS: &[u8] = br#"
+      (module
+        (func (param i32) (local i32 i64) (local f32 f32 f32 f32 f32 f32 f32 f32 f32)))
+    "#;
+
+    #[test_case(
+        include_bytes!("../testdata/grug_tester.wasm"),
+        |res| res.is_ok();
+        "valid contract"
+    )]
+    #[test_case(
+        b"not a wasm module",
+        |res| matches!(res, Err(VmError::Parse(_)));
+        "not wasm"
+    )]
+    #[test_case(
+        EMPTY_MODULE,
+        |res| matches!(res, Err(VmError::MissingExport { name: "memory" }));
+        "missing exports"
+    )]
+    #[test_case(
+        &vec![0; WasmLimits::default().max_code_size as usize + 1],
+        |res| matches!(res, Err(VmError::LimitExceeded { limit: "max_code_size", .. }));
+        "too large"
+    )]
+    fn validating(code: &[u8], predicate: fn(VmResult<()>) -> bool) {
+        assert!(predicate(validate_wasm(code, &WasmLimits::default())));
+    }
+
+    #[test]
+    fn limiting_locals() {
+        let code = wat2wasm(MANY_LOCALS).unwrap();
+
+        let limits = WasmLimits {
+            max_function_locals: 10,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            validate_wasm(&code, &limits),
+            Err(VmError::LimitExceeded {
+                limit: "max_function_locals",
+                value: 11,
+                max: 10,
+            })
+        ));
+
+        // With the limit raised, the module passes the check on locals, and is
+        // instead rejected for not having the required exports.
+        let limits = WasmLimits {
+            max_function_locals: 11,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            validate_wasm(&code, &limits),
+            Err(VmError::MissingExport { name: "memory" })
+        ));
+    }
+}
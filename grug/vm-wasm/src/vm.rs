@@ -1,14 +1,21 @@
+#[cfg(feature = "cranelift")]
+use wasmer::Cranelift;
 use {
     crate::{
         blake2b_512, blake2s_256, blake3, db_next, db_next_key, db_next_value, db_read, db_remove,
         db_remove_range, db_scan, db_write, debug, ed25519_batch_verify, ed25519_verify, keccak256,
         query_chain, read_then_wipe, secp256k1_pubkey_recover, secp256k1_verify, secp256r1_verify,
         sha2_256, sha2_512, sha2_512_truncated, sha3_256, sha3_512, sha3_512_truncated,
-        write_to_memory, Cache, Environment, Gatekeeper, LimitingTunables, VmError, VmResult,
+        validate_wasm, write_to_memory, Cache, CacheMetrics, DiskCache, Environment, Gatekeeper,
+        Limiter, LimitingTunables, StackLimiter, VmError, VmResult,
     },
-    grug_app::{GasTracker, Instance, QuerierProvider, StorageProvider, Vm},
-    grug_types::{BorshSerExt, Context, Hash256},
-    std::{num::NonZeroUsize, sync::Arc},
+    grug_app::{
+        GasTracker, Instance, QuerierProvider, StorageProvider, Vm, CODES, CONFIG, CONTRACTS,
+    },
+    grug_types::{
+        Addr, BorshSerExt, Context, Hash256, HashExt, Order, StdResult, Storage, WasmLimits,
+    },
+    std::{collections::BTreeSet, fmt, num::NonZeroUsize, path::Path, sync::Arc},
     wasmer::{
         imports, sys::BaseTunables, CompilerConfig, Engine, Function, FunctionEnv, Module,
         NativeEngineExt, Singlepass, Store, StoreMut, Target, WASM_PAGE_SIZE,
@@ -19,6 +26,10 @@ use {
 /// Gas cost per Wasmer operation.
 pub const GAS_PER_OPERATION: u64 = 1;
 
+/// The comment under which gas consumed by executing Wasm instructions is
+/// recorded in the gas tracker, as opposed to the names of host functions.
+pub const WASM_METERING: &str = "wasm_metering";
+
 /// Maximum number of chained queries.
 ///
 /// E.g. contract A queries contract B; when handling this query, contract B
@@ -50,17 +61,177 @@ pub const MAX_MEMORY_PAGES: u32 = (MAX_MEMORY_MEBI * 1024 * 1024 / WASM_PAGE_SIZ
 // max memory that Wasmer can support.
 const _: () = assert!(MAX_MEMORY_PAGES < wasmer::WASM_MAX_PAGES);
 
+// --------------------------------- compiler ----------------------------------
+
+/// The compiler that turns Wasm modules into machine code.
+///
+/// Regardless of the compiler, modules are compiled with the same middlewares,
+/// NaN canonicalization, and memory limit, so they consume the same amount of
+/// gas and produce the same outputs. In particular, the depth of recursion is
+/// limited by the `StackLimiter` rather than by the native stack, which each
+/// compiler uses differently. Choosing a compiler is a trade-off between
+/// compilation speed and execution speed, and is up to each node.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compiler {
+    /// Compiles in linear time, but produces slow code.
+    #[default]
+    Singlepass,
+    /// Compiles slower than Singlepass, but produces much faster code.
+    #[cfg(feature = "cranelift")]
+    Cranelift,
+}
+
+impl fmt::Display for Compiler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Compiler::Singlepass => f.write_str("singlepass"),
+            #[cfg(feature = "cranelift")]
+            Compiler::Cranelift => f.write_str("cranelift"),
+        }
+    }
+}
+
 // ------------------------------------ vm -------------------------------------
 
 #[derive(Clone)]
 pub struct WasmVm {
+    compiler: Compiler,
     cache: Option<Cache>,
+    disk_cache: Option<DiskCache>,
 }
 
 impl WasmVm {
     pub fn new(cache_capacity: usize) -> Self {
+        Self::new_with_compiler(cache_capacity, Compiler::default())
+    }
+
+    pub fn new_with_compiler(cache_capacity: usize, compiler: Compiler) -> Self {
         Self {
+            compiler,
             cache: NonZeroUsize::new(cache_capacity).map(Cache::new),
+            disk_cache: None,
+        }
+    }
+
+    /// Persist compiled modules in the given directory, so that they don't
+    /// need to be re-compiled after a restart.
+    pub fn with_disk_cache<P>(mut self, dir: P) -> VmResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        self.disk_cache = Some(DiskCache::open(dir, self.compiler)?);
+
+        Ok(self)
+    }
+
+    /// Prepare the caches for the codes that exist in the given storage, so
+    /// that the first blocks after the node starts aren't slowed down by
+    /// compiling modules.
+    ///
+    /// - Codes that aren't in the disk cache yet are compiled and stored there.
+    /// - Codes of the bank, the taxman, the given contracts (e.g. the account
+    ///   factory), and the given code hashes (e.g. those of user accounts) are
+    ///   loaded into the memory cache and pinned, so that they are never
+    ///   evicted.
+    ///
+    /// A code that fails to compile is skipped, and will fail the same way when
+    /// it's called.
+    pub fn warm_up(
+        &self,
+        storage: &dyn Storage,
+        pinned_contracts: &[Addr],
+        pinned_codes: &[Hash256],
+    ) -> VmResult<()> {
+        // The chain hasn't been initialized yet. There is nothing to warm up.
+        let Some(config) = CONFIG.may_load(storage)? else {
+            return Ok(());
+        };
+
+        let mut pinned = [config.bank, config.taxman]
+            .iter()
+            .chain(pinned_contracts)
+            .map(|address| CONTRACTS.load(storage, *address).map(|info| info.code_hash))
+            .collect::<StdResult<BTreeSet<_>>>()?;
+
+        pinned.extend(pinned_codes);
+
+        for res in CODES.range(storage, None, None, Order::Ascending) {
+            let (code_hash, code) = res?;
+
+            let res = match (&self.cache, &self.disk_cache) {
+                (Some(cache), _) if pinned.contains(&code_hash) => {
+                    cache.pin_with(code_hash, || self.load_or_compile(code_hash, &code))
+                },
+                (_, Some(disk_cache)) if !disk_cache.contains(code_hash) => {
+                    self.load_or_compile(code_hash, &code).map(|_| ())
+                },
+                _ => Ok(()),
+            };
+
+            if let Err(err) = res {
+                tracing::warn!(
+                    code_hash = code_hash.to_string(),
+                    err = err.to_string(),
+                    "Failed to warm up Wasm module"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Return a snapshot of the memory cache's statistics, or `None` if the
+    /// memory cache isn't used.
+    pub fn cache_metrics(&self) -> Option<CacheMetrics> {
+        self.cache.as_ref().map(Cache::metrics)
+    }
+
+    /// Load a module from the disk cache if it's there. Otherwise, compile it
+    /// and store it in the disk cache.
+    ///
+    /// Failing to read or write the disk cache isn't an error; we simply fall
+    /// back to compiling.
+    fn load_or_compile(&self, code_hash: Hash256, code: &[u8]) -> VmResult<(Module, Engine)> {
+        let Some(disk_cache) = &self.disk_cache else {
+            return compile_wasmer(self.compiler, None, code);
+        };
+
+        let engine = make_engine(self.compiler, None, code)?;
+
+        match disk_cache.load(code_hash, &engine) {
+            Ok(Some(module)) => return Ok((module, engine)),
+            Ok(None) => (),
+            Err(err) => {
+                tracing::warn!(
+                    code_hash = code_hash.to_string(),
+                    err = err.to_string(),
+                    "Failed to load Wasm module from disk cache; re-compiling"
+                );
+            },
+        }
+
+        let module = Module::new(&engine, code)?;
+
+        self.store_on_disk(code_hash, &module);
+
+        Ok((module, engine))
+    }
+
+    /// Store a module in the disk cache, if there is one.
+    ///
+    /// Failing to write the disk cache isn't an error, as the module can always
+    /// be re-compiled.
+    fn store_on_disk(&self, code_hash: Hash256, module: &Module) {
+        let Some(disk_cache) = &self.disk_cache else {
+            return;
+        };
+
+        if let Err(err) = disk_cache.store(code_hash, module) {
+            tracing::warn!(
+                code_hash = code_hash.to_string(),
+                err = err.to_string(),
+                "Failed to store Wasm module in disk cache"
+            );
         }
     }
 }
@@ -86,9 +257,9 @@ impl Vm for WasmVm {
         let (module, engine) = if let Some(cache) = &self.cache {
             // Attempt to fetch a pre-built Wasmer module from the cache.
             // If not found, build it and insert it into the cache.
-            cache.get_or_build_with(code_hash, || compile_wasmer(code))?
+            cache.get_or_build_with(code_hash, || self.load_or_compile(code_hash, code))?
         } else {
-            compile_wasmer(code)?
+            self.load_or_compile(code_hash, code)?
         };
 
         // Compute the amount of gas left for this call. This will be used as
@@ -166,11 +337,104 @@ impl Vm for WasmVm {
             fe,
         })
     }
+
+    fn validate_code(&mut self, code: &[u8], limits: &WasmLimits) -> VmResult<()> {
+        // Perform the static checks first, as they are much cheaper than
+        // compiling the module.
+        validate_wasm(code, limits)?;
+
+        // Compile the module, with the same middlewares used for executing it,
+        // plus the `Limiter`. This rejects code that doesn't compile, uses
+        // operators forbidden by the `Gatekeeper`, or exceeds the limits.
+        //
+        // The module is always compiled with Singlepass, regardless of the
+        // compiler this node uses, and never taken from the cache, so that
+        // whether the code is accepted doesn't depend on the node's config or
+        // its cache. Otherwise, nodes could disagree.
+        let (module, engine) = compile_wasmer(Compiler::Singlepass, Some(*limits), code)?;
+
+        // The compiled module is likely to be used soon, when the code is
+        // instantiated, so we insert it into the caches. If the node uses a
+        // different compiler, the module is compiled again with it.
+        //
+        // Failing to do so isn't an error: the code has been accepted, and the
+        // module will be compiled again when it's instantiated.
+        let code_hash = code.hash256();
+        let compile = || -> VmResult<_> {
+            let (module, engine) = if self.compiler == Compiler::Singlepass {
+                (module, engine)
+            } else {
+                compile_wasmer(self.compiler, None, code)?
+            };
+            self.store_on_disk(code_hash, &module);
+            Ok((module, engine))
+        };
+
+        let res = if let Some(cache) = &self.cache {
+            cache.build_and_insert_with(code_hash, compile).map(|_| ())
+        } else {
+            compile().map(|_| ())
+        };
+
+        if let Err(err) = res {
+            tracing::warn!(
+                code_hash = code_hash.to_string(),
+                compiler = self.compiler.to_string(),
+                err = err.to_string(),
+                "Failed to cache Wasm module after validating it"
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn compile_wasmer(
+    compiler: Compiler,
+    limits: Option<WasmLimits>,
+    code: &[u8],
+) -> VmResult<(Module, Engine)> {
+    let engine = make_engine(compiler, limits, code)?;
+    let module = Module::new(&engine, code)?;
+
+    Ok((module, engine))
 }
 
-fn compile_wasmer(code: &[u8]) -> VmResult<(Module, Engine)> {
-    let mut compiler = Singlepass::new();
+/// Create the engine with which modules are compiled, or loaded from the disk
+/// cache.
+///
+/// If limits are given, the engine rejects modules that exceed them. This is
+/// only done when the code is uploaded; modules compiled afterwards, e.g. to
+/// be executed, aren't subject to limits changed since.
+///
+/// The engine can only be used for the given code, as the `StackLimiter` it's
+/// set up with is specific to it.
+fn make_engine(compiler: Compiler, limits: Option<WasmLimits>, code: &[u8]) -> VmResult<Engine> {
+    let mut engine = match compiler {
+        Compiler::Singlepass => make_compiling_engine(Singlepass::new(), limits, code)?,
+        #[cfg(feature = "cranelift")]
+        Compiler::Cranelift => make_compiling_engine(Cranelift::new(), limits, code)?,
+    };
 
+    // Set memory limit for Wasm instances.
+    let base = BaseTunables::for_target(&Target::default());
+    let tunables = LimitingTunables::new(base, MAX_MEMORY_PAGES);
+    engine.set_tunables(tunables);
+
+    Ok(engine)
+}
+
+/// Create an engine from the given compiler, with the middlewares that every
+/// compiler must use.
+fn make_compiling_engine<C>(
+    mut compiler: C,
+    limits: Option<WasmLimits>,
+    code: &[u8],
+) -> VmResult<Engine>
+where
+    C: CompilerConfig,
+    Engine: From<C>,
+{
     // Set up the gas metering middleware.
     //
     // Set `initial_points` as zero for now, because this engine will be
@@ -189,19 +453,20 @@ fn compile_wasmer(code: &[u8]) -> VmResult<(Module, Engine)> {
     // may cause non-determinism.
     compiler.push_middleware(Arc::new(Gatekeeper::default()));
 
-    // Ensure determinism related to floating point numbers.
-    compiler.canonicalize_nans(true);
-
-    let mut engine = Engine::from(compiler);
+    // Set up the `Limiter`, if limits are given. This rejects modules that
+    // are too complex.
+    if let Some(limits) = limits {
+        compiler.push_middleware(Arc::new(Limiter::new(limits)));
+    }
 
-    // Set memory limit for Wasm instances.
-    let base = BaseTunables::for_target(&Target::default());
-    let tunables = LimitingTunables::new(base, MAX_MEMORY_PAGES);
-    engine.set_tunables(tunables);
+    // Set up the `StackLimiter`. This limits the depth of recursion, such that
+    // it doesn't depend on the compiler.
+    compiler.push_middleware(Arc::new(StackLimiter::new(code)?));
 
-    let module = Module::new(&engine, code)?;
+    // Ensure determinism related to floating point numbers.
+    compiler.canonicalize_nans(true);
 
-    Ok((module, engine))
+    Ok(Engine::from(compiler))
 }
 
 // --------------------------------- instance ----------------------------------
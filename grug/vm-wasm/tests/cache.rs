@@ -0,0 +1,101 @@
+use {
+    grug_app::{CODES, CONFIG, CONTRACTS},
+    grug_types::{
+        Addr, Binary, Config, ContractInfo, HashExt, MockStorage, Permission, Permissions,
+        WasmLimits,
+    },
+    grug_vm_wasm::{Compiler, DiskCache, WasmVm},
+    std::{collections::BTreeMap, fs},
+    tempfile::TempDir,
+};
+
+const WASM_CACHE_CAPACITY: usize = 10;
+
+fn read_wasm_file(filename: &str) -> Binary {
+    let path = format!("{}/testdata/{filename}", env!("CARGO_MANIFEST_DIR"));
+    fs::read(path).unwrap().into()
+}
+
+#[test]
+fn warming_up() {
+    let bank_code = read_wasm_file("grug_mock_bank.wasm");
+    let taxman_code = read_wasm_file("grug_mock_taxman.wasm");
+    let account_code = read_wasm_file("grug_mock_account.wasm");
+    let tester_code = read_wasm_file("grug_tester.wasm");
+
+    let bank = Addr::mock(1);
+    let taxman = Addr::mock(2);
+
+    // Set up the storage as if the chain has been running with the bank and
+    // taxman instantiated, and the other codes uploaded.
+    let mut storage = MockStorage::new();
+
+    CONFIG
+        .save(&mut storage, &Config {
+            owner: Addr::mock(0),
+            bank,
+            taxman,
+            cronjobs: BTreeMap::new(),
+            permissions: Permissions {
+                upload: Permission::Everybody,
+                instantiate: Permission::Everybody,
+            },
+            wasm_limits: WasmLimits::default(),
+        })
+        .unwrap();
+
+    for (address, code) in [(bank, &bank_code), (taxman, &taxman_code)] {
+        CONTRACTS
+            .save(&mut storage, address, &ContractInfo {
+                code_hash: code.hash256(),
+                admin: None,
+            })
+            .unwrap();
+    }
+
+    for code in [&bank_code, &taxman_code, &account_code, &tester_code] {
+        CODES.save(&mut storage, code.hash256(), code).unwrap();
+    }
+
+    let dir = TempDir::new().unwrap();
+    let vm = WasmVm::new(WASM_CACHE_CAPACITY)
+        .with_disk_cache(dir.path())
+        .unwrap();
+
+    vm.warm_up(&storage, &[], &[account_code.hash256()])
+        .unwrap();
+
+    // The bank, taxman, and account codes should have been pinned. The tester
+    // code should not have been loaded into memory.
+    let metrics = vm.cache_metrics().unwrap();
+
+    assert_eq!(metrics.pinned(), 3);
+    assert!(metrics.is_empty());
+    assert_eq!(metrics.hits, 0);
+    assert_eq!(metrics.misses, 0);
+
+    for code in [&bank_code, &taxman_code, &account_code] {
+        let entry = metrics.entries[&code.hash256()];
+        assert!(entry.pinned);
+        assert!(entry.size > 0);
+    }
+
+    assert!(!metrics.entries.contains_key(&tester_code.hash256()));
+
+    // All codes should have been written to the disk cache.
+    let disk_cache = DiskCache::open(dir.path(), Compiler::Singlepass).unwrap();
+
+    for code in [&bank_code, &taxman_code, &account_code, &tester_code] {
+        assert!(disk_cache.contains(code.hash256()));
+    }
+
+    // A node that restarts should be able to pin the modules by loading them
+    // from the disk cache.
+    let vm = WasmVm::new(WASM_CACHE_CAPACITY)
+        .with_disk_cache(dir.path())
+        .unwrap();
+
+    vm.warm_up(&storage, &[], &[]).unwrap();
+
+    assert_eq!(vm.cache_metrics().unwrap().pinned(), 2);
+}
@@ -0,0 +1,321 @@
+//! Differential tests that run the same contracts on different compilers, and
+//! check that they consume the same amount of gas and produce the same outputs.
+//!
+//! A discrepancy would mean nodes using different compilers can't agree on the
+//! result of a block, so the chain halts.
+
+use {
+    grug_crypto::{sha2_256, Identity256},
+    grug_db_memory::MemDb,
+    grug_math::Udec128,
+    grug_tester::QueryVerifySecp256k1Request,
+    grug_testing::{TestAccounts, TestBuilder, TestSuite},
+    grug_types::{Addr, Binary, Coins, Denom, Empty, HashExt, Message, TxOutcome},
+    grug_vm_wasm::{Compiler, WasmVm, MAX_STACK_COST},
+    k256::ecdsa::{signature::DigestSigner, Signature, SigningKey, VerifyingKey},
+    rand::rngs::OsRng,
+    std::{fs, str::FromStr, sync::LazyLock},
+    wasmer::wat2wasm,
+};
+
+const WASM_CACHE_CAPACITY: usize = 10;
+
+const FEE_RATE: Udec128 = Udec128::new_percent(10);
+
+static DENOM: LazyLock<Denom> = LazyLock::new(|| Denom::from_str("ugrug").unwrap());
+
+const COMPILERS: [Compiler; 2] = [Compiler::Singlepass, Compiler::Cranelift];
+
+fn read_wasm_file(filename: &str) -> Binary {
+    let path = format!("{}/testdata/{filename}", env!("CARGO_MANIFEST_DIR"));
+    fs::read(path).unwrap().into()
+}
+
+/// A contract that, when queried, recurses the given number of times, then
+/// queries itself with the same message, and returns the result as its own.
+///
+/// The queries nest until the max query depth is exceeded, such that as many
+/// instances as possible recurse at once, on the same native stack.
+fn nested_recursion_code(depth: u64) -> Binary {
+    let wat = format!(
+        r#"
+      (module
+        (import "env" "query_chain" (func $query_chain (param i32) (result i32)))
+        (memory (export "memory") 1)
+        (global $heap (mut i32) (i32.const 1024))
+        (global $ctx (mut i32) (i32.const 0))
+        (global $msg (mut i32) (i32.const 0))
+
+        ;; Reserve a region, and return a pointer to its descriptor, which
+        ;; consists of the offset, capacity, and length. Memory is never freed.
+        (func $allocate (export "allocate") (param $capacity i32) (result i32)
+          (local $region i32)
+          (local.set $region (global.get $heap))
+          (i32.store (local.get $region) (i32.add (local.get $region) (i32.const 12)))
+          (i32.store offset=4 (local.get $region) (local.get $capacity))
+          (i32.store offset=8 (local.get $region) (i32.const 0))
+          (global.set $heap
+            (i32.add (i32.add (local.get $region) (i32.const 12)) (local.get $capacity)))
+          (local.get $region))
+
+        (func (export "deallocate") (param i32))
+
+        ;; Copy bytes to the end of a region's data.
+        (func $append (param $region i32) (param $src i32) (param $len i32)
+          (local $dst i32)
+          (local $i i32)
+          (local.set $dst
+            (i32.add (i32.load (local.get $region)) (i32.load offset=8 (local.get $region))))
+          (block $done
+            (loop $copy
+              (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+              (i32.store8
+                (i32.add (local.get $dst) (local.get $i))
+                (i32.load8_u (i32.add (local.get $src) (local.get $i))))
+              (local.set $i (i32.add (local.get $i) (i32.const 1)))
+              (br $copy)))
+          (i32.store offset=8
+            (local.get $region)
+            (i32.add (i32.load offset=8 (local.get $region)) (local.get $len))))
+
+        ;; Return `Ok(Response::default())`: the variant index, followed by
+        ;; three empty vectors, all zeros.
+        (func (export "instantiate") (param i32 i32) (result i32)
+          (local $res i32)
+          (local.set $res (call $allocate (i32.const 13)))
+          (i32.store offset=8 (local.get $res) (i32.const 13))
+          (local.get $res))
+
+        ;; Query the contract itself with the same message. In the context,
+        ;; the contract's address follows the chain ID and the block info, the
+        ;; latter being 60 bytes. The request is `Query::WasmSmart`, i.e. the
+        ;; variant index 12, followed by the address and the message.
+        (func $forward (result i32)
+          (local $data i32)
+          (local $req i32)
+          (local.set $data (i32.load (global.get $ctx)))
+          (local.set $req
+            (call $allocate (i32.add (i32.const 25) (i32.load offset=8 (global.get $msg)))))
+          (i32.store8 (i32.load (local.get $req)) (i32.const 12))
+          (i32.store offset=8 (local.get $req) (i32.const 1))
+          (call $append
+            (local.get $req)
+            (i32.add (i32.add (local.get $data) (i32.const 64)) (i32.load (local.get $data)))
+            (i32.const 24))
+          (call $append
+            (local.get $req)
+            (i32.load (global.get $msg))
+            (i32.load offset=8 (global.get $msg)))
+          (call $query_chain (local.get $req)))
+
+        (func $recurse (param $n i64) (result i32)
+          (if (result i32) (i64.eqz (local.get $n))
+            (then (call $forward))
+            (else (call $recurse (i64.sub (local.get $n) (i64.const 1))))))
+
+        (func (export "query") (param $ctx i32) (param $msg i32) (result i32)
+          (global.set $ctx (local.get $ctx))
+          (global.set $msg (local.get $msg))
+          (call $recurse (i64.const {depth}))))
+    "#
+    );
+
+    wat2wasm(wat.as_bytes()).unwrap().to_vec().into()
+}
+
+fn setup_test(compiler: Compiler) -> (TestSuite<MemDb, WasmVm>, TestAccounts) {
+    TestBuilder::new_with_vm(WasmVm::new_with_compiler(WASM_CACHE_CAPACITY, compiler))
+        .add_account("owner", Coins::new())
+        .unwrap()
+        .add_account("sender", Coins::one(DENOM.clone(), 100_000_000).unwrap())
+        .unwrap()
+        .add_account("receiver", Coins::new())
+        .unwrap()
+        .set_owner("owner")
+        .unwrap()
+        .set_fee_denom(DENOM.clone())
+        .set_fee_rate(FEE_RATE)
+        .build()
+        .unwrap()
+}
+
+/// Run the same transactions on the given compiler, and return the outcome of
+/// each of them.
+fn run_transactions(compiler: Compiler, tester_code: &Binary) -> Vec<TxOutcome> {
+    let (mut suite, mut accounts) = setup_test(compiler);
+    let receiver = accounts["receiver"].address;
+    let code_hash = tester_code.hash256();
+    let tester = Addr::compute(accounts["sender"].address, code_hash, b"tester");
+    let sender = accounts.get_mut("sender").unwrap();
+
+    let mut outcomes = Vec::new();
+    let mut send = |gas_limit, msg| {
+        let outcome = suite.send_message_with_gas(sender, gas_limit, msg).unwrap();
+        outcomes.push(outcome);
+    };
+
+    // Upload and instantiate the tester contract.
+    send(320_000_000, Message::upload(tester_code.clone()));
+    send(
+        10_000_000,
+        Message::instantiate(
+            code_hash,
+            &grug_tester::InstantiateMsg {},
+            "tester",
+            Coins::new(),
+            None,
+        )
+        .unwrap(),
+    );
+
+    // Transfer tokens, which calls the bank and taxman contracts.
+    send(
+        2_500_000,
+        Message::transfer(receiver, Coins::one(DENOM.clone(), 123).unwrap()).unwrap(),
+    );
+
+    // Run out of gas in an infinite loop. The gas used should be exactly the
+    // limit under any compiler.
+    send(
+        1_000_000,
+        Message::execute(
+            tester,
+            &grug_tester::ExecuteMsg::InfiniteLoop {},
+            Coins::new(),
+        )
+        .unwrap(),
+    );
+
+    // Fail with a VM error.
+    send(
+        1_000_000,
+        Message::execute(
+            tester,
+            &grug_tester::ExecuteMsg::ForceWriteOnQuery {
+                key: "larry".to_string(),
+                value: "engineer".to_string(),
+            },
+            Coins::new(),
+        )
+        .unwrap(),
+    );
+
+    // Fail by exceeding the max message depth.
+    send(
+        10_000_000,
+        Message::execute(
+            tester,
+            &grug_tester::ExecuteMsg::StackOverflow {},
+            Coins::new(),
+        )
+        .unwrap(),
+    );
+
+    outcomes
+}
+
+#[test]
+fn same_outcomes_across_compilers() {
+    let tester_code = read_wasm_file("grug_tester.wasm");
+
+    let [expected, actual] = COMPILERS.map(|compiler| run_transactions(compiler, &tester_code));
+
+    for (i, (expected, actual)) in expected.into_iter().zip(actual).enumerate() {
+        assert_eq!(
+            expected, actual,
+            "outcome of transaction {i} differs between {} and {}",
+            COMPILERS[0], COMPILERS[1]
+        );
+    }
+}
+
+#[test]
+fn same_query_responses_across_compilers() {
+    let tester_code = read_wasm_file("grug_tester.wasm");
+
+    // Generate the request once, so that it's the same for both compilers.
+    let sk = SigningKey::random(&mut OsRng);
+    let vk = VerifyingKey::from(&sk);
+    let msg_hash = Identity256::from(sha2_256(b"finger but hole"));
+    let sig: Signature = sk.sign_digest(msg_hash.clone());
+
+    let verify_request = QueryVerifySecp256k1Request {
+        pk: vk.to_sec1_bytes().to_vec().into(),
+        sig: sig.to_bytes().to_vec().into(),
+        msg_hash: msg_hash.into_bytes().into(),
+    };
+
+    let [expected, actual] = COMPILERS.map(|compiler| {
+        let (mut suite, mut accounts) = setup_test(compiler);
+
+        let (_, tester) = suite
+            .upload_and_instantiate_with_gas(
+                accounts.get_mut("sender").unwrap(),
+                320_000_000,
+                tester_code.clone(),
+                "tester",
+                &grug_tester::InstantiateMsg {},
+                Coins::new(),
+            )
+            .unwrap();
+
+        (
+            suite
+                .query_wasm_smart(tester, verify_request.clone())
+                .map_err(|err| err.to_string()),
+            suite
+                .query_wasm_smart(tester, grug_tester::QueryLoopRequest { iterations: 1000 })
+                .map_err(|err| err.to_string()),
+            suite
+                .query_wasm_smart(tester, grug_tester::QueryStackOverflowRequest {})
+                .map_err(|err| err.to_string()),
+        )
+    });
+
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn same_nested_recursion_outcomes_across_compilers() {
+    // Each call to `recurse` costs 19: the frame overhead of 16, plus one for
+    // its parameter, plus two for its operand stack.
+    let max_depth = MAX_STACK_COST / 19;
+
+    for (depth, expect) in [
+        // Recurse close to the limit in each of the nested instances. This
+        // must not overflow the native stack. The innermost query fails for
+        // exceeding the max query depth, and the error is passed outwards.
+        (max_depth - 10, "max query depth exceeded"),
+        // Recursing past the limit traps in the outermost instance.
+        (max_depth, "unreachable"),
+    ] {
+        let code = nested_recursion_code(depth);
+
+        let [expected, actual] = COMPILERS.map(|compiler| {
+            let (mut suite, mut accounts) = setup_test(compiler);
+
+            let (_, contract) = suite
+                .upload_and_instantiate_with_gas(
+                    accounts.get_mut("sender").unwrap(),
+                    50_000_000,
+                    code.clone(),
+                    "recurser",
+                    &Empty {},
+                    Coins::new(),
+                )
+                .unwrap();
+
+            // The message doesn't matter, as the contract forwards it as is.
+            suite
+                .query_wasm_smart(contract, grug_tester::QueryStackOverflowRequest {})
+                .unwrap_err()
+                .to_string()
+        });
+
+        assert_eq!(expected, actual);
+        assert!(
+            actual.contains(expect),
+            "recursing {depth} times: expecting error containing `{expect}`, got `{actual}`"
+        );
+    }
+}
@@ -7,15 +7,15 @@ use {
         QueryRecoverSepc256k1Request, QueryVerifyEd25519BatchRequest, QueryVerifyEd25519Request,
         QueryVerifySecp256k1Request, QueryVerifySecp256r1Request,
     },
-    grug_testing::{TestAccounts, TestBuilder, TestSuite},
+    grug_testing::{TestAccount, TestAccounts, TestBuilder, TestSuite},
     grug_types::{
-        Addr, Binary, Coins, Denom, GenericResult, JsonSerExt, Message, QueryRequest, ResultExt,
-        VerificationError,
+        Addr, Binary, Coins, ConfigUpdates, Denom, GenericResult, JsonSerExt, Message,
+        QueryRequest, ResultExt, VerificationError, WasmLimits,
     },
     grug_vm_wasm::{VmError, WasmVm},
     rand::rngs::OsRng,
     serde::{de::DeserializeOwned, Serialize},
-    std::{fmt::Debug, fs, str::FromStr, sync::LazyLock, vec},
+    std::{collections::BTreeMap, fmt::Debug, fs, str::FromStr, sync::LazyLock, vec},
     test_case::test_case,
 };
 
@@ -155,6 +155,132 @@ fn message_stack_overflow() {
         .should_fail_with_error(AppError::ExceedMaxMessageDepth);
 }
 
+#[test]
+fn uploading_invalid_code() {
+    let (mut suite, mut accounts, _) = setup_test();
+
+    // Attempt to upload something that isn't a Wasm module. Should fail.
+    suite
+        .send_message_with_gas(
+            accounts.get_mut("sender").unwrap(),
+            10_000_000,
+            Message::upload(b"jake".to_vec()),
+        )
+        .unwrap()
+        .result
+        .should_fail_with_error("failed to parse Wasm module");
+
+    // Attempt to upload a Wasm module that doesn't have the required exports.
+    // Should fail.
+    suite
+        .send_message_with_gas(
+            accounts.get_mut("sender").unwrap(),
+            10_000_000,
+            Message::upload(b"\0asm\x01\0\0\0".to_vec()),
+        )
+        .unwrap()
+        .result
+        .should_fail_with_error(VmError::MissingExport { name: "memory" });
+}
+
+#[test]
+fn uploading_code_exceeding_limits() {
+    // No fee, so that the owner can send transactions without tokens.
+    let (mut suite, mut accounts) = TestBuilder::new_with_vm(WasmVm::new(WASM_CACHE_CAPACITY))
+        .add_account("owner", Coins::new())
+        .unwrap()
+        .set_owner("owner")
+        .unwrap()
+        .build()
+        .unwrap();
+
+    let owner = accounts.get_mut("owner").unwrap();
+    let tester_code = read_wasm_file("grug_tester.wasm");
+    let account_code = read_wasm_file("grug_mock_account.wasm");
+
+    let set_limits = |suite: &mut TestSuite<MemDb, WasmVm>, owner: &mut TestAccount, limits| {
+        suite
+            .configure(
+                owner,
+                ConfigUpdates {
+                    wasm_limits: Some(limits),
+                    ..Default::default()
+                },
+                BTreeMap::new(),
+            )
+            .unwrap();
+    };
+
+    // Upload the tester contract under the default limits.
+    let tester_hash = suite
+        .upload_with_gas(owner, 320_000_000, tester_code)
+        .unwrap();
+
+    // Lower the limits, such that the contracts have too many functions.
+    // Uploading should fail, naming the limit that is exceeded.
+    set_limits(&mut suite, owner, WasmLimits {
+        max_functions: 10,
+        ..Default::default()
+    });
+
+    suite
+        .send_message_with_gas(owner, 320_000_000, Message::upload(account_code.clone()))
+        .unwrap()
+        .result
+        .should_fail_with_error("max_functions");
+
+    // Same with the code size.
+    set_limits(&mut suite, owner, WasmLimits {
+        max_code_size: 1024,
+        ..Default::default()
+    });
+
+    suite
+        .send_message_with_gas(owner, 320_000_000, Message::upload(account_code.clone()))
+        .unwrap()
+        .result
+        .should_fail_with_error("max_code_size");
+
+    // Codes uploaded before the limits were lowered can still be instantiated.
+    suite
+        .instantiate_with_gas(
+            owner,
+            10_000_000,
+            tester_hash,
+            "tester",
+            &grug_tester::InstantiateMsg {},
+            Coins::new(),
+        )
+        .unwrap();
+
+    // Limits can't be set to zero.
+    suite
+        .send_message(
+            owner,
+            Message::configure(
+                ConfigUpdates {
+                    wasm_limits: Some(WasmLimits {
+                        max_imports: 0,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                BTreeMap::new(),
+            ),
+        )
+        .unwrap()
+        .result
+        .should_fail_with_error("wasm limit `max_imports` can't be zero");
+
+    // Restore the default limits. The code that failed to upload can now be
+    // uploaded.
+    set_limits(&mut suite, owner, WasmLimits::default());
+
+    suite
+        .upload_with_gas(owner, 320_000_000, account_code)
+        .unwrap();
+}
+
 // ------------------------------- crypto tests --------------------------------
 
 const MSG: &[u8] = b"finger but hole";
@@ -14,6 +14,7 @@ import { type ExecuteReturnType, execute } from "~/actions/user/execute";
 export type SafeAccountProposeParameters = {
   sender: Address;
   account: Address;
+  proposer: string;
   title: string;
   description?: string;
   messages: Message[];
@@ -27,6 +28,7 @@ export type SafeAccountProposeReturnType = ExecuteReturnType;
  * @param parameters
  * @param parameters.sender The sender of the proposal.
  * @param parameters.account The safe account address.
+ * @param parameters.proposer The username of the member creating the proposal.
  * @param parameters.title The title of the proposal.
  * @param parameters.description The description of the proposal.
  * @param parameters.messages The messages to execute.
@@ -51,6 +53,7 @@ export async function safeAccountPropose<chain extends Chain | undefined, signer
     type: [{ name: "propose", type: "SafePropose" }],
     extraTypes: {
       SafePropose: [
+        { name: "proposer", type: "string" },
         { name: "title", type: "string" },
         { name: "description", type: "string" },
         { name: "messages", type: "Message[]" },
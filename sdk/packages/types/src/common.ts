@@ -24,3 +24,14 @@ export type SomebodiesPermission = { somebodies: Address[] };
  * Permissions for uploading code or instantiating contracts.
  */
 export type Permission = NobodyPermission | EverybodyPermission | SomebodiesPermission;
+
+/**
+ * Limits on the size and complexity of uploaded Wasm modules.
+ */
+export type WasmLimits = {
+  maxCodeSize: number;
+  maxFunctions: number;
+  maxFunctionLocals: number;
+  maxTableSize: number;
+  maxImports: number;
+};
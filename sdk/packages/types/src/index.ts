@@ -185,6 +185,7 @@ export type {
   ProposalStatus,
   Power,
   Safe,
+  Allowance,
 } from "./safe";
 
 export type {
@@ -1,6 +1,6 @@
 import type { Address } from "./address";
 import type { Coin, Coins } from "./coin";
-import type { Duration, Permission } from "./common";
+import type { Duration, Permission, WasmLimits } from "./common";
 import type { Metadata } from "./credential";
 import type { Hex, Json } from "./encoding";
 import type { Message } from "./tx";
@@ -25,6 +25,7 @@ export type ChainConfigResponse = {
     upload: Permission;
     instantiate: Permission;
   };
+  wasmLimits: WasmLimits;
 };
 
 export type SimulateRequest = {
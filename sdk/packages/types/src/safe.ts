@@ -1,4 +1,6 @@
 import type { Username } from "./account";
+import type { Address } from "./address";
+import type { Denom } from "./coin";
 import type { Duration, Timestamp } from "./common";
 import type { Message } from "./tx";
 
@@ -13,6 +15,11 @@ export type Power = number;
 export type ProposalId = number;
 
 export type Proposal = {
+  /**
+   * The member who created this proposal. Absent for proposals created
+   * before proposers were recorded.
+   */
+  proposer?: Username;
   title: string;
   description?: string;
   messages: Message[];
@@ -30,6 +37,22 @@ export type Safe = {
   threshold: Power;
   /** The minimum delay after a proposal is passed before it can be executed. */
   timelock?: Duration;
+  /** Members who can send tokens from the Safe without a proposal, within the
+   * limits of their respective allowances.
+   */
+  allowances: Record<Username, Allowance>;
+};
+
+/** Tokens a Safe member can send without a proposal. */
+export type Allowance = {
+  /** The only denom that can be sent. */
+  denom: Denom;
+  /** The maximum amount that can be sent within each period. */
+  amount: string;
+  /** The length of each period. */
+  period: Duration;
+  /** Recipients of transfers, or contracts to be executed, that are allowed. */
+  targets: Address[];
 };
 
 export type ProposalStatus = VotingStatus | PassedStatus | "failed" | "executed";
@@ -1,6 +1,6 @@
 import type { Address } from "./address";
 import type { Coins, Funds } from "./coin";
-import type { Duration, Permission } from "./common";
+import type { Duration, Permission, WasmLimits } from "./common";
 import type { Credential, Metadata } from "./credential";
 import type { Base64, Hex, Json } from "./encoding";
 
@@ -70,4 +70,5 @@ export type ConfigUpdate = {
     upload: Permission;
     instantiate: Permission;
   };
+  wasmLimits?: WasmLimits;
 };